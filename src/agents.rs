@@ -3,13 +3,25 @@
 //! This module handles the creation, management, and coordination of AI agents
 //! within the Chimera platform.
 
+use crate::cache::CacheMode;
+use crate::events::{EventBus, EventPayload};
+use crate::guardrails::GuardrailPolicy;
+use crate::platform::service::ServiceRegistration;
+use crate::prompts::ChatFormat;
+use crate::sessions::MemoryPolicy;
+use crate::storage::AgentRepository;
+use anyhow::Result;
 use parking_lot::RwLock;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+use tracing::warn;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+const AGENT_SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub enum AgentType {
     General,
     CodeGeneration,
@@ -25,6 +37,8 @@ pub enum AgentStatus {
     Idle,
     Error,
     Maintenance,
+    /// Its process has drained and shut down.
+    Offline,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,10 +48,41 @@ pub struct Agent {
     pub agent_type: AgentType,
     pub status: AgentStatus,
     pub capabilities: Vec<String>,
+    /// Tenant that owns the agent; shared by every tenant when `None`.
+    #[serde(default)]
+    pub tenant: Option<String>,
     pub config: AgentConfig,
     pub metrics: AgentMetrics,
 }
 
+impl Agent {
+    /// An idle agent with fresh metrics.
+    pub fn from_config(id: String, config: AgentConfig) -> Self {
+        Self {
+            id,
+            name: config.agent_name.clone(),
+            agent_type: config.agent_type.clone(),
+            status: AgentStatus::Idle,
+            capabilities: config.capabilities.clone(),
+            tenant: config.tenant.clone(),
+            config,
+            metrics: AgentMetrics::default(),
+        }
+    }
+
+    pub fn serves(&self, tenant: &str) -> bool {
+        self.tenant.as_deref().is_none_or(|owner| owner == tenant)
+    }
+
+    /// Whether requests and tasks may be routed to the agent.
+    pub fn accepts_work(&self) -> bool {
+        !matches!(
+            self.status,
+            AgentStatus::Error | AgentStatus::Maintenance | AgentStatus::Offline
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentConfig {
     pub model_path: String,
@@ -48,6 +93,26 @@ pub struct AgentConfig {
     pub max_concurrent_requests: usize,
     pub capabilities: Vec<String>,
     pub agent_type: AgentType,
+    /// Base URL of the agent's HTTP API, when it serves requests remotely.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub memory: MemoryPolicy,
+    /// Named template from `[prompts.templates]`; the agent type's built-in otherwise.
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+    #[serde(default)]
+    pub prompt_variables: HashMap<String, String>,
+    #[serde(default)]
+    pub chat_format: ChatFormat,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub guardrail: GuardrailPolicy,
+    #[serde(default)]
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub cache: CacheMode,
 }
 
 impl Default for AgentConfig {
@@ -61,6 +126,15 @@ impl Default for AgentConfig {
             max_concurrent_requests: 4,
             capabilities: vec!["text_generation".to_string()],
             agent_type: AgentType::General,
+            endpoint: None,
+            memory: MemoryPolicy::default(),
+            prompt_template: None,
+            prompt_variables: HashMap::new(),
+            chat_format: ChatFormat::default(),
+            tools: Vec::new(),
+            guardrail: GuardrailPolicy::default(),
+            tenant: None,
+            cache: CacheMode::default(),
         }
     }
 }
@@ -87,27 +161,27 @@ impl Default for AgentMetrics {
 #[derive(Clone, Default)]
 pub struct AgentRegistry {
     agents: Arc<RwLock<HashMap<String, Agent>>>,
+    events: Option<EventBus>,
 }
 
 impl AgentRegistry {
     pub fn new() -> Self {
         Self {
             agents: Arc::new(RwLock::new(HashMap::new())),
+            events: None,
         }
     }
 
+    /// Publishes `agent.status` whenever an agent's status changes.
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
     pub fn from_catalog(catalog: HashMap<String, AgentConfig>) -> Self {
         let registry = Self::new();
         for (_name, config) in catalog {
-            registry.register_agent(Agent {
-                id: uuid::Uuid::new_v4().to_string(),
-                name: config.agent_name.clone(),
-                agent_type: config.agent_type.clone(),
-                status: AgentStatus::Idle,
-                capabilities: config.capabilities.clone(),
-                config: config.clone(),
-                metrics: AgentMetrics::default(),
-            });
+            registry.register_from_config(config);
         }
         registry
     }
@@ -115,15 +189,24 @@ impl AgentRegistry {
     pub fn register_agent(&self, agent: Agent) {
         let mut agents = self.agents.write();
         agents.insert(agent.id.clone(), agent);
-    pub fn update_activity(&mut self, id: &str) {
-        if let Some(agent) = self.agents.get_mut(id) {
-            agent.metrics.last_activity = SystemTime::now();
-            agent.metrics.requests_processed += 1;
-        }
     }
 
-    pub fn get_agent(&self, id: &str) -> Option<&Agent> {
-        self.agents.get(id)
+    pub fn register_from_config(&self, config: AgentConfig) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.register_agent(Agent::from_config(id.clone(), config));
+        id
+    }
+
+    pub fn contains_name(&self, name: &str) -> bool {
+        let agents = self.agents.read();
+        agents.values().any(|agent| agent.name == name)
+    }
+
+    pub fn remove_agents_by_name(&self, name: &str) -> usize {
+        let mut agents = self.agents.write();
+        let before = agents.len();
+        agents.retain(|_, agent| agent.name != name);
+        before - agents.len()
     }
 
     pub fn update_activity(&self, id: &str) {
@@ -137,20 +220,80 @@ impl AgentRegistry {
     pub fn get_agent(&self, id: &str) -> Option<Agent> {
         let agents = self.agents.read();
         agents.get(id).cloned()
-    pub fn get_agents_by_type(&self, agent_type: &AgentType) -> Vec<&Agent> {
-        self.agents
+    }
+
+    /// Agents whose id or name is `key`.
+    pub fn find(&self, key: &str) -> Vec<Agent> {
+        let agents = self.agents.read();
+        agents
             .values()
-            .filter(|agent| {
-                std::mem::discriminant(&agent.agent_type) == std::mem::discriminant(agent_type)
-            })
+            .filter(|agent| agent.id == key || agent.name == key)
+            .cloned()
             .collect()
     }
 
+    /// Sets the status of every agent matching `key` by id or name, returning them.
+    pub fn set_status(&self, key: &str, status: AgentStatus) -> Vec<Agent> {
+        let mut changes = Vec::new();
+        let updated = {
+            let mut agents = self.agents.write();
+            agents
+                .values_mut()
+                .filter(|agent| agent.id == key || agent.name == key)
+                .map(|agent| {
+                    let previous = std::mem::replace(&mut agent.status, status.clone());
+                    if previous != status {
+                        changes.push((agent.clone(), previous));
+                    }
+                    agent.clone()
+                })
+                .collect()
+        };
+
+        if let Some(events) = &self.events {
+            for (agent, from) in changes {
+                events.publish(
+                    agent.tenant.clone(),
+                    EventPayload::AgentStatusChanged {
+                        agent_id: agent.id,
+                        name: agent.name,
+                        from,
+                        to: status.clone(),
+                    },
+                );
+            }
+        }
+        updated
+    }
+
     pub fn list_agents(&self) -> Vec<Agent> {
         let agents = self.agents.read();
         agents.values().cloned().collect()
     }
 
+    /// Agents owned by `tenant` plus the shared ones.
+    pub fn list_agents_for(&self, tenant: &str) -> Vec<Agent> {
+        let agents = self.agents.read();
+        agents
+            .values()
+            .filter(|agent| agent.serves(tenant))
+            .cloned()
+            .collect()
+    }
+
+    /// Least-used reachable agent advertising `capability` that serves `tenant`.
+    pub fn select_for_capability(&self, capability: &str, tenant: &str) -> Option<Agent> {
+        let agents = self.agents.read();
+        agents
+            .values()
+            .filter(|agent| agent.serves(tenant))
+            .filter(|agent| agent.config.endpoint.is_some())
+            .filter(|agent| agent.accepts_work())
+            .filter(|agent| agent.capabilities.iter().any(|c| c == capability))
+            .min_by_key(|agent| agent.metrics.requests_processed)
+            .cloned()
+    }
+
     pub fn get_agents_by_type(&self, agent_type: AgentType) -> Vec<Agent> {
         let agents = self.agents.read();
         agents
@@ -161,6 +304,103 @@ impl AgentRegistry {
     }
 }
 
+/// Saves every registered agent and forgets stored agents whose name has left
+/// `catalog`. Other processes sharing the database register agents this one
+/// doesn't, so a stored agent missing from `registry` alone is kept.
+async fn sync_agents(
+    registry: &AgentRegistry,
+    repository: &dyn AgentRepository,
+    catalog: &HashMap<String, AgentConfig>,
+) -> Result<()> {
+    let current = registry.list_agents();
+    for agent in &current {
+        repository.save_agent(agent).await?;
+    }
+    for stored in repository.load_agents().await? {
+        let configured = catalog.contains_key(&stored.name);
+        if !configured && !current.iter().any(|agent| agent.id == stored.id) {
+            repository.delete_agent(&stored.id).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Registers every agent of `catalog` not yet in `registry`, keeping the id
+/// and metrics `stored` for its name. An agent an operator paused stays in
+/// maintenance; other statuses only described the process that saved them.
+fn hydrate_agents(
+    registry: &AgentRegistry,
+    stored: &[Agent],
+    catalog: HashMap<String, AgentConfig>,
+) {
+    for (name, config) in catalog {
+        if registry.contains_name(&name) {
+            continue;
+        }
+        match stored.iter().find(|agent| agent.name == name) {
+            Some(previous) => {
+                let mut agent = Agent {
+                    metrics: previous.metrics.clone(),
+                    ..Agent::from_config(previous.id.clone(), config)
+                };
+                if previous.status == AgentStatus::Maintenance {
+                    agent.status = AgentStatus::Maintenance;
+                }
+                registry.register_agent(agent);
+            }
+            None => {
+                registry.register_from_config(config);
+            }
+        }
+    }
+}
+
+/// Hydrates the platform's agent registry from the configured catalog.
+///
+/// Agents keep the id, metrics and maintenance status stored for their name
+/// by an earlier run;
+/// the registry is written back to storage periodically and on shutdown.
+/// Other services declare a dependency on `"agents"` so they only start once
+/// every configured agent is registered.
+pub fn agent_loader_service() -> ServiceRegistration {
+    ServiceRegistration::new(
+        "agents",
+        Arc::new(|context, token| {
+            tokio::spawn(async move {
+                let registry = context.agents();
+                let repository = context.storage().agents;
+                let stored = repository.load_agents().await?;
+                hydrate_agents(&registry, &stored, context.config().agent_catalog());
+
+                let mut interval = tokio::time::interval(AGENT_SYNC_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = token.cancelled() => break,
+                        _ = interval.tick() => {}
+                    }
+                    let catalog = context.config().agent_catalog();
+                    if let Err(err) = sync_agents(&registry, repository.as_ref(), &catalog).await {
+                        warn!(error = %format!("{:#}", err), "failed to persist agents");
+                    }
+                }
+                let catalog = context.config().agent_catalog();
+                sync_agents(&registry, repository.as_ref(), &catalog).await
+            })
+        }),
+    )
+    .with_readiness(Arc::new(|context| {
+        Box::pin(async move {
+            let registry = context.agents();
+            let names: Vec<String> = context.config().agent_catalog().into_keys().collect();
+            while !names.iter().all(|name| registry.contains_name(name)) {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            Ok(())
+        })
+    }))
+    .critical()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +415,7 @@ mod tests {
             agent_type: AgentType::General,
             status: AgentStatus::Idle,
             capabilities: vec!["text_generation".to_string()],
+            tenant: None,
             config: AgentConfig {
                 model_path: "models/test".to_string(),
                 max_tokens: 512,
@@ -184,14 +425,9 @@ mod tests {
                 max_concurrent_requests: 4,
                 capabilities: vec!["text_generation".to_string()],
                 agent_type: AgentType::General,
+                ..AgentConfig::default()
             },
             metrics: AgentMetrics::default(),
-            metrics: AgentMetrics {
-                requests_processed: 0,
-                average_response_time_ms: 0.0,
-                success_rate: 1.0,
-                last_activity: SystemTime::now(),
-            },
         };
 
         assert_eq!(agent.name, "test_agent");
@@ -208,6 +444,7 @@ mod tests {
             agent_type: AgentType::CodeGeneration,
             status: AgentStatus::Active,
             capabilities: vec!["code_gen".to_string()],
+            tenant: None,
             config: AgentConfig {
                 model_path: "models/codellama".to_string(),
                 max_tokens: 1024,
@@ -217,14 +454,9 @@ mod tests {
                 max_concurrent_requests: 2,
                 capabilities: vec!["code_gen".to_string()],
                 agent_type: AgentType::CodeGeneration,
+                ..AgentConfig::default()
             },
             metrics: AgentMetrics::default(),
-            metrics: AgentMetrics {
-                requests_processed: 0,
-                average_response_time_ms: 0.0,
-                success_rate: 1.0,
-                last_activity: SystemTime::now(),
-            },
         };
 
         manager.register_agent(agent);
@@ -241,4 +473,125 @@ mod tests {
             .duration_since(UNIX_EPOCH)
             .is_ok());
     }
+
+    #[test]
+    fn capability_routing_prefers_least_used_reachable_agent() {
+        let registry = AgentRegistry::new();
+        let remote = |name: &str| AgentConfig {
+            agent_name: name.to_string(),
+            capabilities: vec!["code_gen".to_string()],
+            endpoint: Some(format!("http://{}.local", name)),
+            ..AgentConfig::default()
+        };
+
+        let busy = registry.register_from_config(remote("busy"));
+        registry.register_from_config(remote("fresh"));
+        registry.register_from_config(AgentConfig {
+            endpoint: None,
+            ..remote("local-only")
+        });
+        registry.update_activity(&busy);
+        registry.register_from_config(AgentConfig {
+            tenant: Some("search".to_string()),
+            ..remote("dedicated")
+        });
+        assert_eq!(registry.list_agents_for("default").len(), 3);
+        assert_eq!(registry.list_agents_for("search").len(), 4);
+
+        let selected = registry
+            .select_for_capability("code_gen", "default")
+            .unwrap();
+        assert_eq!(selected.name, "fresh");
+        assert!(registry
+            .select_for_capability("vision", "default")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn sync_keeps_agents_registered_by_other_processes() {
+        let repository = crate::storage::InMemoryRepository::new();
+        let config = |name: &str| AgentConfig {
+            agent_name: name.to_string(),
+            ..AgentConfig::default()
+        };
+        let catalog: HashMap<String, AgentConfig> = ["support", "billing"]
+            .into_iter()
+            .map(|name| (name.to_string(), config(name)))
+            .collect();
+        repository
+            .save_agent(&Agent::from_config(
+                "other-process".into(),
+                config("billing"),
+            ))
+            .await
+            .unwrap();
+        repository
+            .save_agent(&Agent::from_config("stale".into(), config("retired")))
+            .await
+            .unwrap();
+        let registry = AgentRegistry::new();
+        registry.register_from_config(config("support"));
+
+        sync_agents(&registry, &repository, &catalog).await.unwrap();
+
+        let mut names: Vec<String> = repository
+            .load_agents()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|agent| agent.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["billing", "support"]);
+    }
+
+    #[test]
+    fn hydration_keeps_paused_agents_in_maintenance() {
+        let config = |name: &str| AgentConfig {
+            agent_name: name.to_string(),
+            ..AgentConfig::default()
+        };
+        let stored: Vec<Agent> = [
+            ("support", AgentStatus::Maintenance),
+            ("billing", AgentStatus::Busy),
+            ("search", AgentStatus::Offline),
+        ]
+        .into_iter()
+        .map(|(name, status)| Agent {
+            status,
+            ..Agent::from_config(format!("{}-id", name), config(name))
+        })
+        .collect();
+        let catalog: HashMap<String, AgentConfig> = ["support", "billing", "search", "new"]
+            .into_iter()
+            .map(|name| (name.to_string(), config(name)))
+            .collect();
+        let registry = AgentRegistry::new();
+
+        hydrate_agents(&registry, &stored, catalog);
+
+        let mut statuses: Vec<(String, String, AgentStatus)> = registry
+            .list_agents()
+            .into_iter()
+            .map(|agent| (agent.name, agent.id, agent.status))
+            .collect();
+        statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            statuses[0],
+            ("billing".into(), "billing-id".into(), AgentStatus::Idle)
+        );
+        assert_eq!(statuses[1].2, AgentStatus::Idle);
+        assert_eq!(
+            statuses[2],
+            ("search".into(), "search-id".into(), AgentStatus::Idle)
+        );
+        assert_eq!(
+            statuses[3],
+            (
+                "support".into(),
+                "support-id".into(),
+                AgentStatus::Maintenance
+            )
+        );
+    }
 }
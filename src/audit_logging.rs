@@ -1,11 +1,17 @@
 use serde::{Deserialize, Serialize};
 use std::fs::{File, OpenOptions};
-use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+use crate::events::{EventBus, EventPayload};
+use crate::utils::Redactor;
+
+/// How far back from the end of the log [`AuditLogger::recent`] reads.
+const TAIL_WINDOW_BYTES: u64 = 4 * 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuditEvent {
     pub id: String,
@@ -17,11 +23,13 @@ pub struct AuditEvent {
     pub result: String,
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
+    #[serde(default)]
+    pub tenant: Option<String>,
     pub metadata: std::collections::HashMap<String, String>,
     pub severity: AuditSeverity,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AuditSeverity {
     Low,
     Medium,
@@ -32,7 +40,11 @@ pub enum AuditSeverity {
 #[derive(Clone)]
 pub struct AuditLogger {
     log_file: Arc<Mutex<BufWriter<File>>>,
+    log_path: PathBuf,
     _retention_days: u32,
+    redactor: Option<Arc<Redactor>>,
+    events: Option<EventBus>,
+    tenant: Option<String>,
 }
 
 impl AuditLogger {
@@ -50,10 +62,60 @@ impl AuditLogger {
 
         Ok(Self {
             log_file: Arc::new(Mutex::new(BufWriter::new(file))),
+            log_path: PathBuf::from(log_path),
             _retention_days: retention_days,
+            redactor: None,
+            events: None,
+            tenant: None,
         })
     }
 
+    /// Redacts PII from event metadata before it is written.
+    pub fn with_redactor(mut self, redactor: Arc<Redactor>) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Publishes high and critical events on `events` as `audit.alert`.
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Logger that stamps `tenant` on every event it writes.
+    pub fn for_tenant(&self, tenant: &str) -> Self {
+        Self {
+            tenant: Some(tenant.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// The newest `limit` events written after `since` (unix seconds), oldest first.
+    pub fn recent(
+        &self,
+        limit: usize,
+        since: Option<u64>,
+    ) -> Result<Vec<AuditEvent>, Box<dyn std::error::Error>> {
+        let mut file = File::open(&self.log_path)?;
+        let length = file.metadata()?.len();
+        let start = length.saturating_sub(TAIL_WINDOW_BYTES);
+        file.seek(SeekFrom::Start(start))?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let mut lines = contents.lines();
+        if start > 0 {
+            // The window starts mid-line.
+            lines.next();
+        }
+        let events: Vec<AuditEvent> = lines
+            .filter_map(|line| serde_json::from_str::<AuditEvent>(line).ok())
+            .filter(|event| since.is_none_or(|since| event.timestamp > since))
+            .collect();
+        let skip = events.len().saturating_sub(limit);
+        Ok(events.into_iter().skip(skip).collect())
+    }
+
     pub fn log_event(&self, mut event: AuditEvent) -> Result<(), Box<dyn std::error::Error>> {
         // Set timestamp if not already set
         if event.timestamp == 0 {
@@ -65,6 +127,16 @@ impl AuditLogger {
             event.id = Uuid::new_v4().to_string();
         }
 
+        if event.tenant.is_none() {
+            event.tenant = self.tenant.clone();
+        }
+
+        if let Some(redactor) = self.redactor.as_ref().filter(|r| r.settings().audit) {
+            for value in event.metadata.values_mut() {
+                *value = redactor.redact(value).text;
+            }
+        }
+
         // Serialize and write event
         let event_json = serde_json::to_string(&event)? + "\n";
         let mut writer = self.log_file.lock().unwrap();
@@ -76,11 +148,23 @@ impl AuditLogger {
             event.severity,
             AuditSeverity::Critical | AuditSeverity::High
         ) {
-        if matches!(event.severity, AuditSeverity::Critical | AuditSeverity::High) {
             eprintln!(
                 "AUDIT [{:?}]: {} - {}",
                 event.severity, event.event_type, event.action
             );
+            if let Some(events) = &self.events {
+                events.publish(
+                    event.tenant.clone(),
+                    EventPayload::AuditAlert {
+                        audit_id: event.id.clone(),
+                        event_type: event.event_type.clone(),
+                        action: event.action.clone(),
+                        resource: event.resource.clone(),
+                        user_id: event.user_id.clone(),
+                        severity: event.severity.clone(),
+                    },
+                );
+            }
         }
 
         Ok(())
@@ -102,6 +186,7 @@ impl AuditLogger {
             result: if success { "success" } else { "failure" }.to_string(),
             ip_address,
             user_agent: None,
+            tenant: None,
             metadata: std::collections::HashMap::new(),
             severity: if success {
                 AuditSeverity::Low
@@ -131,6 +216,7 @@ impl AuditLogger {
             result: status_code.to_string(),
             ip_address,
             user_agent: None,
+            tenant: None,
             metadata: {
                 let mut map = std::collections::HashMap::new();
                 map.insert("status_code".to_string(), status_code.to_string());
@@ -164,6 +250,7 @@ impl AuditLogger {
             result: "success".to_string(),
             ip_address,
             user_agent: None,
+            tenant: None,
             metadata: std::collections::HashMap::new(),
             severity: AuditSeverity::Medium,
         };
@@ -171,6 +258,82 @@ impl AuditLogger {
         self.log_event(event)
     }
 
+    pub fn log_guardrail(
+        &self,
+        user_id: Option<String>,
+        agent: &str,
+        decision: &str,
+        score: f32,
+        rules: &[&str],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("score".to_string(), format!("{:.2}", score));
+        metadata.insert("rules".to_string(), rules.join(","));
+
+        let event = AuditEvent {
+            id: String::new(),
+            timestamp: 0,
+            event_type: "guardrail".to_string(),
+            user_id,
+            resource: agent.to_string(),
+            action: "screen_prompt".to_string(),
+            result: decision.to_string(),
+            ip_address: None,
+            user_agent: None,
+            tenant: None,
+            metadata,
+            severity: if decision == "block" {
+                AuditSeverity::High
+            } else {
+                AuditSeverity::Medium
+            },
+        };
+
+        self.log_event(event)
+    }
+
+    pub fn log_tool_call(
+        &self,
+        user_id: Option<String>,
+        agent: &str,
+        tool: &str,
+        outcome: Result<(), &str>,
+        duration_ms: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("agent".to_string(), agent.to_string());
+        metadata.insert("duration_ms".to_string(), duration_ms.to_string());
+        if let Err(error) = outcome {
+            metadata.insert("error".to_string(), error.to_string());
+        }
+
+        let event = AuditEvent {
+            id: String::new(),
+            timestamp: 0,
+            event_type: "tool_call".to_string(),
+            user_id,
+            resource: tool.to_string(),
+            action: "invoke".to_string(),
+            result: if outcome.is_ok() {
+                "success"
+            } else {
+                "failure"
+            }
+            .to_string(),
+            ip_address: None,
+            user_agent: None,
+            tenant: None,
+            metadata,
+            severity: if outcome.is_ok() {
+                AuditSeverity::Low
+            } else {
+                AuditSeverity::Medium
+            },
+        };
+
+        self.log_event(event)
+    }
+
     pub fn log_admin_action(
         &self,
         admin_user_id: &str,
@@ -188,6 +351,7 @@ impl AuditLogger {
             result: "success".to_string(),
             ip_address,
             user_agent: None,
+            tenant: None,
             metadata: std::collections::HashMap::new(),
             severity: AuditSeverity::High,
         };
@@ -216,10 +380,76 @@ mod tests {
             result: "success".to_string(),
             ip_address: Some("127.0.0.1".to_string()),
             user_agent: None,
+            tenant: None,
             metadata: std::collections::HashMap::new(),
             severity: AuditSeverity::Low,
         };
 
         assert!(logger.log_event(event).is_ok());
     }
+
+    #[test]
+    fn metadata_is_redacted_before_writing() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let redactor = Redactor::from_settings(
+            &crate::utils::RedactionSettings::default(),
+            crate::utils::RedactionKey::generate(),
+        )
+        .unwrap();
+        let logger = AuditLogger::new(temp_file.path().to_str().unwrap(), 7)
+            .unwrap()
+            .with_redactor(Arc::new(redactor));
+
+        logger
+            .log_tool_call(
+                None,
+                "support",
+                "kv_lookup",
+                Err("no entry for jane@example.com"),
+                3,
+            )
+            .unwrap();
+
+        let written = std::fs::read_to_string(temp_file.path()).unwrap();
+        assert!(!written.contains("jane@example.com"));
+        assert!(written.contains("[REDACTED:email]"));
+    }
+
+    #[test]
+    fn tenant_scoped_logger_stamps_events() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let logger = AuditLogger::new(temp_file.path().to_str().unwrap(), 7).unwrap();
+
+        logger
+            .for_tenant("search")
+            .log_api_access(Some("key:ab12".into()), "/predict", "POST", 200, None)
+            .unwrap();
+        logger
+            .log_api_access(None, "/predict", "POST", 200, None)
+            .unwrap();
+
+        let written = std::fs::read_to_string(temp_file.path()).unwrap();
+        let events: Vec<AuditEvent> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events[0].tenant.as_deref(), Some("search"));
+        assert_eq!(events[1].tenant, None);
+    }
+
+    #[test]
+    fn recent_returns_the_newest_events() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let logger = AuditLogger::new(temp_file.path().to_str().unwrap(), 7).unwrap();
+        for endpoint in ["/a", "/b", "/c"] {
+            logger
+                .log_api_access(None, endpoint, "GET", 200, None)
+                .unwrap();
+        }
+
+        let events = logger.recent(2, None).unwrap();
+        let resources: Vec<&str> = events.iter().map(|e| e.resource.as_str()).collect();
+        assert_eq!(resources, ["/b", "/c"]);
+        assert!(logger.recent(10, Some(u64::MAX)).unwrap().is_empty());
+    }
 }
@@ -0,0 +1,347 @@
+use super::{AuthError, JwtSettings, Role};
+use crate::tenancy::DEFAULT_TENANT;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use ring::{hmac, signature};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Identity carried by a verified token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenClaims {
+    pub subject: String,
+    pub role: Role,
+    pub tenant: String,
+    pub expires_at: u64,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    alg: String,
+}
+
+/// Verifies HS256 and RS256 tokens against locally held keys. No key
+/// discovery is attempted; an algorithm without a configured key is refused.
+pub struct JwtVerifier {
+    hs256: Option<hmac::Key>,
+    /// PKCS#1 `RSAPublicKey` DER, the form ring expects.
+    rs256: Option<Vec<u8>>,
+    settings: JwtSettings,
+}
+
+impl JwtVerifier {
+    pub fn from_settings(settings: &JwtSettings) -> Result<Self> {
+        let hs256 = std::env::var(&settings.hs256_secret_env)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()));
+        let rs256 = settings
+            .rs256_public_key_path
+            .as_ref()
+            .map(|path| {
+                let pem = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read JWT public key {}", path))?;
+                rsa_public_key_der(&pem).with_context(|| format!("invalid JWT public key {}", path))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            hs256,
+            rs256,
+            settings: settings.clone(),
+        })
+    }
+
+    /// True when at least one algorithm has a key.
+    pub fn is_configured(&self) -> bool {
+        self.hs256.is_some() || self.rs256.is_some()
+    }
+
+    pub fn verify(&self, token: &str, now: u64) -> Result<TokenClaims, AuthError> {
+        let invalid = |reason: &str| AuthError::InvalidToken(reason.to_string());
+
+        let mut parts = token.splitn(3, '.');
+        let (Some(header), Some(payload), Some(signature)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid("expected three dot-separated segments"));
+        };
+        let header: Header = decode_segment(header).ok_or_else(|| invalid("malformed header"))?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| invalid("malformed signature"))?;
+        let signed = &token[..header_payload_len(token)];
+
+        let verified = match header.alg.as_str() {
+            "HS256" => {
+                let key = self
+                    .hs256
+                    .as_ref()
+                    .ok_or_else(|| invalid("HS256 is not enabled"))?;
+                hmac::verify(key, signed.as_bytes(), &signature).is_ok()
+            }
+            "RS256" => {
+                let key = self
+                    .rs256
+                    .as_ref()
+                    .ok_or_else(|| invalid("RS256 is not enabled"))?;
+                signature::UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, key)
+                    .verify(signed.as_bytes(), &signature)
+                    .is_ok()
+            }
+            other => {
+                return Err(AuthError::InvalidToken(format!(
+                    "unsupported alg {}",
+                    other
+                )))
+            }
+        };
+        if !verified {
+            return Err(invalid("signature mismatch"));
+        }
+
+        let claims: Value = decode_segment(payload).ok_or_else(|| invalid("malformed payload"))?;
+        self.check_claims(&claims, now)
+    }
+
+    fn check_claims(&self, claims: &Value, now: u64) -> Result<TokenClaims, AuthError> {
+        let invalid = |reason: String| AuthError::InvalidToken(reason);
+        let leeway = self.settings.leeway_seconds;
+
+        let expires_at = claims["exp"]
+            .as_u64()
+            .ok_or_else(|| invalid("missing exp claim".into()))?;
+        if now > expires_at + leeway {
+            return Err(AuthError::Expired);
+        }
+        if claims["nbf"].as_u64().is_some_and(|nbf| now + leeway < nbf) {
+            return Err(invalid("token is not valid yet".into()));
+        }
+
+        if let Some(issuer) = &self.settings.issuer {
+            if claims["iss"].as_str() != Some(issuer.as_str()) {
+                return Err(invalid(format!("issuer must be {}", issuer)));
+            }
+        }
+        if let Some(audience) = &self.settings.audience {
+            let matches = match &claims["aud"] {
+                Value::String(aud) => aud == audience,
+                Value::Array(auds) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !matches {
+                return Err(invalid(format!("audience must include {}", audience)));
+            }
+        }
+
+        let subject = claims["sub"]
+            .as_str()
+            .filter(|sub| !sub.is_empty())
+            .ok_or_else(|| invalid("missing sub claim".into()))?;
+        let role = claims[self.settings.role_claim.as_str()]
+            .as_str()
+            .ok_or_else(|| invalid(format!("missing {} claim", self.settings.role_claim)))?
+            .parse()
+            .map_err(|err: anyhow::Error| invalid(err.to_string()))?;
+
+        let tenant = claims[self.settings.tenant_claim.as_str()]
+            .as_str()
+            .unwrap_or(DEFAULT_TENANT);
+
+        Ok(TokenClaims {
+            subject: subject.to_string(),
+            role,
+            tenant: tenant.to_string(),
+            expires_at,
+        })
+    }
+}
+
+fn header_payload_len(token: &str) -> usize {
+    token.rfind('.').unwrap_or(token.len())
+}
+
+fn decode_segment<T: serde::de::DeserializeOwned>(segment: &str) -> Option<T> {
+    let bytes = URL_SAFE_NO_PAD.decode(segment).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Accepts `PUBLIC KEY` (SPKI) or `RSA PUBLIC KEY` (PKCS#1) PEM and returns
+/// the PKCS#1 DER.
+fn rsa_public_key_der(pem: &str) -> Result<Vec<u8>> {
+    let label = pem
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("-----BEGIN "))
+        .and_then(|rest| rest.strip_suffix("-----"))
+        .ok_or_else(|| anyhow!("not a PEM file"))?;
+    let body: String = pem
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    let der = STANDARD.decode(body).context("PEM body is not base64")?;
+
+    match label {
+        "RSA PUBLIC KEY" => Ok(der),
+        "PUBLIC KEY" => spki_subject_key(&der),
+        other => bail!("expected a public key, found {}", other),
+    }
+}
+
+/// Pulls the key bit string out of `SubjectPublicKeyInfo ::= SEQUENCE {
+/// algorithm AlgorithmIdentifier, subjectPublicKey BIT STRING }`.
+fn spki_subject_key(der: &[u8]) -> Result<Vec<u8>> {
+    let (info, _) = der_element(der, 0x30)?;
+    let (_, rest) = der_element(info, 0x30)?;
+    let (bits, _) = der_element(rest, 0x03)?;
+    match bits.split_first() {
+        Some((0, key)) => Ok(key.to_vec()),
+        _ => bail!("unexpected padding in public key bit string"),
+    }
+}
+
+/// Splits one DER element with the expected tag into (contents, remainder).
+fn der_element(input: &[u8], tag: u8) -> Result<(&[u8], &[u8])> {
+    let truncated = || anyhow!("truncated DER");
+    let (&found, rest) = input.split_first().ok_or_else(truncated)?;
+    if found != tag {
+        bail!("expected DER tag {:#04x}, found {:#04x}", tag, found);
+    }
+    let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let octets = (first & 0x7f) as usize;
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            bail!("unsupported DER length");
+        }
+        let len = rest[..octets]
+            .iter()
+            .fold(0usize, |len, &b| (len << 8) | b as usize);
+        rest = &rest[octets..];
+        len
+    };
+    if rest.len() < len {
+        return Err(truncated());
+    }
+    Ok(rest.split_at(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use serde_json::json;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn encode(header: Value, claims: &Value) -> String {
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    fn sign_hs256(secret: &str, claims: &Value) -> String {
+        let signed = encode(json!({ "alg": "HS256", "typ": "JWT" }), claims);
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+        let tag = hmac::sign(&key, signed.as_bytes());
+        format!("{}.{}", signed, URL_SAFE_NO_PAD.encode(tag.as_ref()))
+    }
+
+    fn sign_rs256(claims: &Value) -> String {
+        let pem = include_str!("../../tests/fixtures/jwt_rs256_private.pem");
+        let body: String = pem.lines().filter(|l| !l.starts_with("-----")).collect();
+        let key_pair = signature::RsaKeyPair::from_pkcs8(&STANDARD.decode(body).unwrap()).unwrap();
+
+        let signed = encode(json!({ "alg": "RS256", "typ": "JWT" }), claims);
+        let mut sig = vec![0; key_pair.public().modulus_len()];
+        key_pair
+            .sign(
+                &signature::RSA_PKCS1_SHA256,
+                &SystemRandom::new(),
+                signed.as_bytes(),
+                &mut sig,
+            )
+            .unwrap();
+        format!("{}.{}", signed, URL_SAFE_NO_PAD.encode(sig))
+    }
+
+    fn verifier(secret_env: &str) -> JwtVerifier {
+        JwtVerifier::from_settings(&JwtSettings {
+            hs256_secret_env: secret_env.to_string(),
+            rs256_public_key_path: Some(
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/jwt_rs256_public.pem"
+                )
+                .into(),
+            ),
+            issuer: Some("https://idp.example".into()),
+            audience: Some("chimera".into()),
+            ..JwtSettings::default()
+        })
+        .unwrap()
+    }
+
+    fn claims(exp: u64) -> Value {
+        json!({
+            "sub": "alice",
+            "role": "operator",
+            "iss": "https://idp.example",
+            "aud": ["chimera", "other"],
+            "exp": exp,
+        })
+    }
+
+    #[test]
+    fn hs256_tokens_are_checked_against_the_shared_secret() {
+        std::env::set_var("CHIMERA_TEST_JWT_SECRET_HS", "s3cret");
+        let verifier = verifier("CHIMERA_TEST_JWT_SECRET_HS");
+
+        let token = sign_hs256("s3cret", &claims(NOW + 60));
+        let verified = verifier.verify(&token, NOW).unwrap();
+        assert_eq!(verified.subject, "alice");
+        assert_eq!(verified.role, Role::Operator);
+        assert_eq!(verified.tenant, DEFAULT_TENANT);
+
+        let forged = sign_hs256("guess", &claims(NOW + 60));
+        assert!(matches!(
+            verifier.verify(&forged, NOW),
+            Err(AuthError::InvalidToken(_))
+        ));
+        // Within the default 60s leeway, then expired.
+        assert!(verifier.verify(&token, NOW + 100).is_ok());
+        assert!(matches!(
+            verifier.verify(&token, NOW + 200),
+            Err(AuthError::Expired)
+        ));
+    }
+
+    #[test]
+    fn rs256_tokens_verify_with_the_public_key() {
+        let verifier = verifier("CHIMERA_TEST_JWT_SECRET_UNSET");
+        let token = sign_rs256(&claims(NOW + 60));
+        assert_eq!(verifier.verify(&token, NOW).unwrap().subject, "alice");
+
+        let mut wrong_audience = claims(NOW + 60);
+        wrong_audience["aud"] = json!("someone-else");
+        let err = verifier
+            .verify(&sign_rs256(&wrong_audience), NOW)
+            .unwrap_err();
+        assert!(err.to_string().contains("audience"));
+
+        // HS256 has no secret here, so an HS256 token is refused outright.
+        let hs = sign_hs256("anything", &claims(NOW + 60));
+        assert!(verifier.verify(&hs, NOW).is_err());
+    }
+
+    #[test]
+    fn unsigned_tokens_are_rejected() {
+        let verifier = verifier("CHIMERA_TEST_JWT_SECRET_UNSET");
+        let token = format!("{}.", encode(json!({ "alg": "none" }), &claims(NOW + 60)));
+        assert!(verifier.verify(&token, NOW).is_err());
+    }
+}
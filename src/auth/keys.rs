@@ -0,0 +1,330 @@
+use super::{AuthError, Role};
+use crate::tenancy::DEFAULT_TENANT;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use parking_lot::Mutex;
+use ring::rand::{SecureRandom, SystemRandom};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const KEY_PREFIX: &str = "chk_";
+
+/// Stored form of an API key; the secret itself is only kept as a hash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub name: String,
+    pub role: Role,
+    #[serde(default = "default_tenant")]
+    pub tenant: String,
+    /// Hex SHA-256 of the secret part of the key.
+    pub hash: String,
+    pub created_at: u64,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+pub trait ApiKeyStore: Send + Sync {
+    fn find(&self, id: &str) -> Result<Option<ApiKeyRecord>>;
+    fn insert(&self, record: &ApiKeyRecord) -> Result<()>;
+    fn list(&self) -> Result<Vec<ApiKeyRecord>>;
+    /// Marks `id` revoked; false when no such key exists.
+    fn revoke(&self, id: &str) -> Result<bool>;
+}
+
+/// Creates a key for `name` and stores its hash. The returned plaintext is
+/// shown once and cannot be recovered later.
+pub fn issue_key(
+    store: &dyn ApiKeyStore,
+    name: &str,
+    role: Role,
+    tenant: &str,
+) -> Result<(ApiKeyRecord, String)> {
+    let rng = SystemRandom::new();
+    let mut id = [0u8; 6];
+    let mut secret = [0u8; 32];
+    rng.fill(&mut id)
+        .and_then(|_| rng.fill(&mut secret))
+        .map_err(|_| anyhow::anyhow!("system random number generator failed"))?;
+
+    let id: String = id.iter().map(|b| format!("{:02x}", b)).collect();
+    let secret = URL_SAFE_NO_PAD.encode(secret);
+    let record = ApiKeyRecord {
+        id: id.clone(),
+        name: name.to_string(),
+        role,
+        tenant: tenant.to_string(),
+        hash: hash_secret(&secret),
+        created_at: crate::utils::timestamp_now(),
+        revoked: false,
+    };
+    store.insert(&record)?;
+    Ok((record, format!("{}{}.{}", KEY_PREFIX, id, secret)))
+}
+
+/// Looks up and checks a presented key.
+pub fn verify_key(store: &dyn ApiKeyStore, presented: &str) -> Result<ApiKeyRecord, AuthError> {
+    let (id, secret) = presented
+        .strip_prefix(KEY_PREFIX)
+        .and_then(|rest| rest.split_once('.'))
+        .ok_or(AuthError::InvalidApiKey)?;
+    let record = store
+        .find(id)
+        .map_err(|err| AuthError::Unavailable(err.to_string()))?
+        .ok_or(AuthError::InvalidApiKey)?;
+
+    if !constant_time_eq(hash_secret(secret).as_bytes(), record.hash.as_bytes()) {
+        return Err(AuthError::InvalidApiKey);
+    }
+    if record.revoked {
+        return Err(AuthError::RevokedKey);
+    }
+    Ok(record)
+}
+
+fn hash_secret(secret: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, secret.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Keys kept as a JSON array in a local file, re-read on every lookup so
+/// edits from `chimera keys` apply without a restart.
+pub struct FileKeyStore {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileKeyStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn read(&self) -> Result<Vec<ApiKeyRecord>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) if content.trim().is_empty() => Ok(Vec::new()),
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("corrupt key file {}", self.path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err).with_context(|| format!("failed to read {}", self.path.display())),
+        }
+    }
+
+    fn write(&self, records: &[ApiKeyRecord]) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(records)?)?;
+        restrict_permissions(&tmp)?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+impl ApiKeyStore for FileKeyStore {
+    fn find(&self, id: &str) -> Result<Option<ApiKeyRecord>> {
+        Ok(self.read()?.into_iter().find(|record| record.id == id))
+    }
+
+    fn insert(&self, record: &ApiKeyRecord) -> Result<()> {
+        let _guard = self.lock.lock();
+        let mut records = self.read()?;
+        records.push(record.clone());
+        self.write(&records)
+    }
+
+    fn list(&self) -> Result<Vec<ApiKeyRecord>> {
+        self.read()
+    }
+
+    fn revoke(&self, id: &str) -> Result<bool> {
+        let _guard = self.lock.lock();
+        let mut records = self.read()?;
+        let Some(record) = records.iter_mut().find(|record| record.id == id) else {
+            return Ok(false);
+        };
+        record.revoked = true;
+        self.write(&records)?;
+        Ok(true)
+    }
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    role TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    revoked INTEGER NOT NULL DEFAULT 0,
+    tenant TEXT NOT NULL DEFAULT 'default'
+);
+";
+
+pub struct SqliteKeyStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteKeyStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open key database {}", path.display()))?;
+        conn.execute_batch(SCHEMA)
+            .context("failed to create api_keys table")?;
+        // Tables created before tenants existed lack the column.
+        let has_tenant = conn
+            .prepare("SELECT 1 FROM pragma_table_info('api_keys') WHERE name = 'tenant'")?
+            .exists([])?;
+        if !has_tenant {
+            conn.execute_batch(
+                "ALTER TABLE api_keys ADD COLUMN tenant TEXT NOT NULL DEFAULT 'default'",
+            )?;
+        }
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+fn record_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<(ApiKeyRecord, String)> {
+    Ok((
+        ApiKeyRecord {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            role: Role::ReadOnly,
+            tenant: row.get(6)?,
+            hash: row.get(3)?,
+            created_at: row.get::<_, i64>(4)? as u64,
+            revoked: row.get(5)?,
+        },
+        row.get(2)?,
+    ))
+}
+
+fn with_role((mut record, role): (ApiKeyRecord, String)) -> Result<ApiKeyRecord> {
+    record.role = role.parse()?;
+    Ok(record)
+}
+
+impl ApiKeyStore for SqliteKeyStore {
+    fn find(&self, id: &str) -> Result<Option<ApiKeyRecord>> {
+        let row = self
+            .conn
+            .lock()
+            .query_row(
+                "SELECT id, name, role, hash, created_at, revoked, tenant FROM api_keys WHERE id = ?1",
+                params![id],
+                record_from_row,
+            )
+            .optional()?;
+        row.map(with_role).transpose()
+    }
+
+    fn insert(&self, record: &ApiKeyRecord) -> Result<()> {
+        self.conn.lock().execute(
+            "INSERT INTO api_keys (id, name, role, hash, created_at, revoked, tenant)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.id,
+                record.name,
+                record.role.as_str(),
+                record.hash,
+                record.created_at as i64,
+                record.revoked,
+                record.tenant,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<ApiKeyRecord>> {
+        let conn = self.conn.lock();
+        let mut statement = conn.prepare(
+            "SELECT id, name, role, hash, created_at, revoked, tenant FROM api_keys ORDER BY created_at",
+        )?;
+        let rows = statement.query_map([], record_from_row)?;
+        rows.map(|row| with_role(row?)).collect()
+    }
+
+    fn revoke(&self, id: &str) -> Result<bool> {
+        Ok(self
+            .conn
+            .lock()
+            .execute("UPDATE api_keys SET revoked = 1 WHERE id = ?1", params![id])?
+            > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(store: &dyn ApiKeyStore) {
+        let (record, key) = issue_key(store, "ci", Role::Client, "search").unwrap();
+        assert!(!serde_json::to_string(&store.list().unwrap())
+            .unwrap()
+            .contains(key.split_once('.').unwrap().1));
+
+        let verified = verify_key(store, &key).unwrap();
+        assert_eq!(
+            (verified.role, verified.tenant.as_str()),
+            (Role::Client, "search")
+        );
+        let forged = format!("{}x", key);
+        assert!(matches!(
+            verify_key(store, &forged),
+            Err(AuthError::InvalidApiKey)
+        ));
+
+        assert!(store.revoke(&record.id).unwrap());
+        assert!(matches!(
+            verify_key(store, &key),
+            Err(AuthError::RevokedKey)
+        ));
+    }
+
+    #[test]
+    fn file_store_hashes_and_revokes_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        round_trip(&FileKeyStore::new(dir.path().join("keys.json")));
+    }
+
+    #[test]
+    fn sqlite_store_hashes_and_revokes_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        round_trip(&SqliteKeyStore::open(dir.path().join("auth.db")).unwrap());
+    }
+}
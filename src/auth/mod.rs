@@ -0,0 +1,522 @@
+//! Authentication and role-based access control for the HTTP surface
+//!
+//! Callers present either an API key (`X-API-Key` or `Authorization: Bearer
+//! chk_...`) or a JWT signed with a locally configured HS256 secret or RS256
+//! public key. Either way they resolve to a [`Principal`] whose [`Role`]
+//! decides which route [`Permission`]s it holds.
+
+mod jwt;
+mod keys;
+
+pub use jwt::{JwtVerifier, TokenClaims};
+pub use keys::{issue_key, verify_key, ApiKeyRecord, ApiKeyStore, FileKeyStore, SqliteKeyStore};
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::platform::PlatformContext;
+use crate::rate_limiting::ClientId;
+use crate::tenancy::DEFAULT_TENANT;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Operator,
+    Client,
+    ReadOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Run inference and post session messages.
+    Infer,
+    /// Read session history.
+    ReadSessions,
+    /// Status, readiness details and metrics.
+    ViewStatus,
+    /// Day-to-day operations such as reloads and agent control.
+    Operate,
+    /// Key management and anything else reserved to administrators.
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Admin, Role::Operator, Role::Client, Role::ReadOnly];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Operator => "operator",
+            Role::Client => "client",
+            Role::ReadOnly => "read_only",
+        }
+    }
+
+    pub fn permissions(self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Admin => &[Infer, ReadSessions, ViewStatus, Operate, Admin],
+            Role::Operator => &[Infer, ReadSessions, ViewStatus, Operate],
+            Role::Client => &[Infer, ReadSessions],
+            Role::ReadOnly => &[ReadSessions, ViewStatus],
+        }
+    }
+
+    pub fn allows(self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.as_str() == value)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "unknown role {:?} (expected admin, operator, client or read_only)",
+                    value
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    Anonymous,
+    ApiKey,
+    Jwt,
+}
+
+/// The authenticated caller, attached to each request as an extension.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Principal {
+    pub id: String,
+    pub role: Role,
+    pub method: AuthMethod,
+    pub tenant: String,
+    pub ip: Option<IpAddr>,
+}
+
+impl Principal {
+    pub fn anonymous(role: Role, ip: Option<IpAddr>) -> Self {
+        Self {
+            id: "anonymous".to_string(),
+            role,
+            method: AuthMethod::Anonymous,
+            tenant: DEFAULT_TENANT.to_string(),
+            ip,
+        }
+    }
+
+    pub fn is_anonymous(&self) -> bool {
+        self.method == AuthMethod::Anonymous
+    }
+
+    /// Identifier recorded in audit events; `None` for anonymous callers.
+    pub fn user_id(&self) -> Option<String> {
+        match self.method {
+            AuthMethod::Anonymous => None,
+            AuthMethod::ApiKey => Some(format!("key:{}", self.id)),
+            AuthMethod::Jwt => Some(format!("jwt:{}", self.id)),
+        }
+    }
+
+    /// Rate-limit bucket: per principal when authenticated, per address otherwise.
+    pub fn client_id(&self) -> ClientId {
+        match self.user_id() {
+            Some(id) => ClientId::Principal(id),
+            None => ClientId::Ip(self.ip.unwrap_or(IpAddr::from([127, 0, 0, 1]))),
+        }
+    }
+
+    pub fn ip_string(&self) -> Option<String> {
+        self.ip.map(|ip| ip.to_string())
+    }
+
+    /// Admins act across tenants; everyone else only within their own.
+    pub fn can_access_tenant(&self, tenant: &str) -> bool {
+        self.role == Role::Admin || self.tenant == tenant
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AuthError {
+    MissingCredentials,
+    InvalidApiKey,
+    RevokedKey,
+    InvalidToken(String),
+    Expired,
+    Forbidden { role: Role, permission: Permission },
+    Unavailable(String),
+}
+
+impl AuthError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthError::Forbidden { .. } => StatusCode::FORBIDDEN,
+            AuthError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthError::MissingCredentials => write!(f, "missing API key or bearer token"),
+            AuthError::InvalidApiKey => write!(f, "invalid API key"),
+            AuthError::RevokedKey => write!(f, "API key has been revoked"),
+            AuthError::InvalidToken(reason) => write!(f, "invalid token: {}", reason),
+            AuthError::Expired => write!(f, "token has expired"),
+            AuthError::Forbidden { role, permission } => {
+                write!(f, "role {} lacks the {:?} permission", role, permission)
+            }
+            AuthError::Unavailable(reason) => {
+                write!(f, "authentication unavailable: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl IntoResponse for AuthError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body = Json(serde_json::json!({ "error": self.to_string() }));
+        if status == StatusCode::UNAUTHORIZED {
+            (status, [(header::WWW_AUTHENTICATE, "Bearer")], body).into_response()
+        } else {
+            (status, body).into_response()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyBackend {
+    File,
+    Sqlite,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthSettings {
+    /// When false every request runs as an anonymous admin.
+    pub enabled: bool,
+    pub backend: KeyBackend,
+    /// Key file used when `backend = "file"`.
+    pub keys_path: String,
+    /// Database file used when `backend = "sqlite"`.
+    pub sqlite_path: String,
+    pub jwt: JwtSettings,
+    /// Paths served without credentials.
+    pub public_paths: Vec<String>,
+}
+
+impl Default for AuthSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: KeyBackend::File,
+            keys_path: "data/api_keys.json".to_string(),
+            sqlite_path: "data/auth.db".to_string(),
+            jwt: JwtSettings::default(),
+            public_paths: vec!["/health".to_string(), "/ready".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtSettings {
+    /// Environment variable holding the HS256 secret; HS256 is off when unset.
+    pub hs256_secret_env: String,
+    /// PEM public key for RS256; RS256 is off when absent.
+    pub rs256_public_key_path: Option<String>,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    /// Clock skew tolerated on `exp` and `nbf`.
+    pub leeway_seconds: u64,
+    /// Claim holding the caller's role.
+    pub role_claim: String,
+    /// Claim holding the caller's tenant; `default` when absent.
+    pub tenant_claim: String,
+}
+
+impl Default for JwtSettings {
+    fn default() -> Self {
+        Self {
+            hs256_secret_env: "CHIMERA_JWT_SECRET".to_string(),
+            rs256_public_key_path: None,
+            issuer: None,
+            audience: None,
+            leeway_seconds: 60,
+            role_claim: "role".to_string(),
+            tenant_claim: "tenant".to_string(),
+        }
+    }
+}
+
+/// Opens the key store selected by `settings.backend`.
+pub fn open_key_store(settings: &AuthSettings) -> Result<Arc<dyn ApiKeyStore>> {
+    Ok(match settings.backend {
+        KeyBackend::File => Arc::new(FileKeyStore::new(&settings.keys_path)),
+        KeyBackend::Sqlite => Arc::new(SqliteKeyStore::open(&settings.sqlite_path)?),
+    })
+}
+
+/// Resolves request credentials to a [`Principal`].
+pub struct Authenticator {
+    settings: AuthSettings,
+    keys: Option<Arc<dyn ApiKeyStore>>,
+    jwt: Option<JwtVerifier>,
+}
+
+impl Authenticator {
+    pub fn from_settings(settings: &AuthSettings) -> Result<Self> {
+        if !settings.enabled {
+            return Ok(Self::disabled());
+        }
+        let jwt = JwtVerifier::from_settings(&settings.jwt)?;
+        Ok(Self {
+            settings: settings.clone(),
+            keys: Some(open_key_store(settings)?),
+            jwt: jwt.is_configured().then_some(jwt),
+        })
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            settings: AuthSettings::default(),
+            keys: None,
+            jwt: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    pub fn is_public(&self, path: &str) -> bool {
+        self.settings
+            .public_paths
+            .iter()
+            .any(|public| public == path)
+    }
+
+    pub fn authenticate(
+        &self,
+        headers: &HeaderMap,
+        ip: Option<IpAddr>,
+    ) -> Result<Principal, AuthError> {
+        if !self.is_enabled() {
+            return Ok(Principal::anonymous(Role::Admin, ip));
+        }
+
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        let credential = header("x-api-key").or_else(|| {
+            header(header::AUTHORIZATION.as_str())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .map(str::trim)
+        });
+        let Some(credential) = credential.filter(|c| !c.is_empty()) else {
+            return Err(AuthError::MissingCredentials);
+        };
+
+        if credential.starts_with("chk_") {
+            let keys = self.keys.as_ref().ok_or(AuthError::InvalidApiKey)?;
+            let record = verify_key(keys.as_ref(), credential)?;
+            return Ok(Principal {
+                id: record.id,
+                role: record.role,
+                method: AuthMethod::ApiKey,
+                tenant: record.tenant,
+                ip,
+            });
+        }
+
+        let jwt = self.jwt.as_ref().ok_or_else(|| {
+            AuthError::InvalidToken("JWT authentication is not configured".into())
+        })?;
+        let claims = jwt.verify(credential, crate::utils::timestamp_now())?;
+        Ok(Principal {
+            id: claims.subject,
+            role: claims.role,
+            method: AuthMethod::Jwt,
+            tenant: claims.tenant,
+            ip,
+        })
+    }
+}
+
+/// Middleware that authenticates every request and attaches its [`Principal`].
+/// Public paths without credentials run as an anonymous read-only caller.
+pub async fn authenticate(
+    State(ctx): State<PlatformContext>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let auth = ctx.auth();
+
+    let principal = match auth.authenticate(request.headers(), ip) {
+        Ok(principal) => principal,
+        Err(AuthError::MissingCredentials) if auth.is_public(request.uri().path()) => {
+            Principal::anonymous(Role::ReadOnly, ip)
+        }
+        Err(err) => {
+            warn!(path = %request.uri().path(), error = %err, "request rejected by authentication");
+            if let Err(audit_err) =
+                ctx.audit_logger()
+                    .log_authentication("unknown", false, ip.map(|ip| ip.to_string()))
+            {
+                error!(?audit_err, "failed to record audit log for authentication");
+            }
+            return err.into_response();
+        }
+    };
+
+    request.extensions_mut().insert(principal);
+    next.run(request).await
+}
+
+/// Route layer refusing callers whose role lacks `permission`:
+/// `route_layer(from_fn_with_state(Permission::Infer, auth::require))`.
+pub async fn require(
+    State(permission): State<Permission>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match request.extensions().get::<Principal>() {
+        Some(principal) if principal.role.allows(permission) => next.run(request).await,
+        Some(principal) => AuthError::Forbidden {
+            role: principal.role,
+            permission,
+        }
+        .into_response(),
+        None => AuthError::MissingCredentials.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::test_support::test_context_with;
+    use crate::PlatformConfig;
+    use axum::body::Body;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::Service;
+
+    #[test]
+    fn roles_map_to_route_permissions() {
+        assert!(Role::Admin.allows(Permission::Admin));
+        assert!(!Role::Operator.allows(Permission::Admin));
+        assert!(Role::Client.allows(Permission::Infer));
+        assert!(!Role::Client.allows(Permission::ViewStatus));
+        assert!(!Role::ReadOnly.allows(Permission::Infer));
+        assert_eq!("read_only".parse::<Role>().unwrap(), Role::ReadOnly);
+    }
+
+    async fn status(app: &Router, path: &str, key: Option<&str>) -> StatusCode {
+        let mut request = axum::http::Request::builder().uri(path);
+        if let Some(key) = key {
+            request = request.header("x-api-key", key);
+        }
+        // Router is always ready, so `call` can skip `poll_ready`.
+        app.clone()
+            .call(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn middleware_enforces_roles_per_route() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = PlatformConfig::default();
+        config.auth.enabled = true;
+        config.auth.keys_path = dir.path().join("keys.json").display().to_string();
+        let (ctx, _audit) = test_context_with(config.clone());
+
+        let store = open_key_store(&config.auth).unwrap();
+        let (_, client) = issue_key(store.as_ref(), "app", Role::Client, DEFAULT_TENANT).unwrap();
+        let (_, viewer) =
+            issue_key(store.as_ref(), "dash", Role::ReadOnly, DEFAULT_TENANT).unwrap();
+
+        let guarded = |permission| from_fn_with_state(permission, require);
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route(
+                "/predict",
+                get(|| async { "ok" }).route_layer(guarded(Permission::Infer)),
+            )
+            .route(
+                "/status",
+                get(|| async { "ok" }).route_layer(guarded(Permission::ViewStatus)),
+            )
+            .layer(from_fn_with_state(ctx, authenticate));
+
+        assert_eq!(status(&app, "/health", None).await, StatusCode::OK);
+        assert_eq!(
+            status(&app, "/predict", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "/predict", Some("chk_nope.x")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(&app, "/predict", Some(&client)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&app, "/status", Some(&client)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status(&app, "/status", Some(&viewer)).await, StatusCode::OK);
+        assert_eq!(
+            status(&app, "/predict", Some(&viewer)).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn disabled_auth_runs_as_anonymous_admin() {
+        let (ctx, _audit) = test_context_with(PlatformConfig::default());
+        let app = Router::new()
+            .route(
+                "/admin",
+                get(|| async { "ok" }).route_layer(from_fn_with_state(Permission::Admin, require)),
+            )
+            .layer(from_fn_with_state(ctx, authenticate));
+        assert_eq!(status(&app, "/admin", None).await, StatusCode::OK);
+    }
+}
@@ -1,15 +1,24 @@
+use axum::extract::FromRef;
+use axum::middleware::from_fn_with_state;
 use axum::response::IntoResponse;
+use chimera_core::agents::AgentConfig;
+use chimera_core::auth::{self, Permission, Principal};
+use chimera_core::guardrails::{self, GuardDecision};
+use chimera_core::inference::{InferenceEngine, InferenceError, InferenceRequest, ResponseFormat};
+use chimera_core::metering::UsageTotals;
+use chimera_core::platform::drain;
+use chimera_core::prompts::PromptLibrary;
+use chimera_core::sessions::{ContextWindow, Message, Role};
+use chimera_core::tenancy;
+use chimera_core::tools::{AgentLoopOutcome, ToolExecutor};
 use chimera_core::{Platform, PlatformConfig, PlatformContext};
 use clap::Parser;
 use dotenvy::dotenv;
+use serde::Deserialize;
 use std::net::SocketAddr;
 use std::path::PathBuf;
-use chimera_core::*;
-use clap::Parser;
-use dotenvy::dotenv;
-use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -25,6 +34,10 @@ struct Args {
     /// Agent name
     #[arg(short, long)]
     name: String,
+
+    /// Serve on this inherited listening socket instead of binding `--port`
+    #[arg(long)]
+    listen_fd: Option<i32>,
 }
 
 #[tokio::main]
@@ -33,65 +46,176 @@ async fn main() -> anyhow::Result<()> {
     let args = Args::parse();
 
     info!(agent = %args.name, "starting Chimera agent");
-    // Initialize tracing
-    tracing_subscriber::fmt().with_env_filter("info").init();
-
-    info!("Starting Chimera Agent: {}", args.name);
 
     let config = PlatformConfig::load_from_path(Some(args.config.clone()))?;
-    let platform = Platform::new(config);
+    if args.listen_fd.is_some() {
+        drain::mark_handoff();
+    }
+    let mut platform = Platform::new(config);
+    platform.watch_config(args.config.clone());
     let runtime = platform.start().await?;
     let context = runtime.context();
 
-    let app = build_router(context.clone());
-    // Initialize platform
-    let platform = Arc::new(init_platform(config).await?);
+    let agent = context
+        .config()
+        .agent_catalog()
+        .remove(&args.name)
+        .unwrap_or_else(|| AgentConfig {
+            agent_name: args.name.clone(),
+            ..AgentConfig::default()
+        });
+    let mut engine = InferenceEngine::new()
+        .with_tokenizer(context.config().metering.tokenizer.build())
+        .with_cache(context.response_cache(), agent.cache);
+    engine
+        .load_model(&agent.model_path)
+        .map_err(|err| anyhow::anyhow!("failed to load model {}: {}", agent.model_path, err))?;
 
-    // Start HTTP server
-    let app = axum::Router::new()
-        .route("/health", axum::routing::get(health_check))
-        .route("/predict", axum::routing::post(predict))
-        .route("/status", axum::routing::get(agent_status))
-        .with_state(platform.clone());
+    let app = build_router(AppState {
+        platform: context.clone(),
+        agent: Arc::new(HostedAgent {
+            config: agent,
+            engine,
+        }),
+    });
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], args.port));
-    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let listener = match args.listen_fd.or_else(drain::listen_fd_from_env) {
+        Some(fd) => drain::inherit(fd)?,
+        None => drain::bind(
+            SocketAddr::from(([0, 0, 0, 0], args.port)),
+            context.config().drain.reuse_port,
+        )?,
+    };
 
-    info!(%addr, "agent listening");
-    axum::serve(listener, app).await?;
+    info!(addr = %listener.local_addr()?, "agent listening");
+    let shutdown = context.shutdown_token();
+    let server = tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await
+    });
 
-    runtime.shutdown().await?;
+    // Keep serving (and answering 503 to new work) while draining, so
+    // requests already routed here are not dropped.
+    tokio::select! {
+        _ = shutdown_signal() => runtime.drain().await?,
+        _ = runtime.cancelled() => runtime.shutdown().await?,
+    }
+    server.await??;
     Ok(())
 }
 
-fn build_router(context: PlatformContext) -> axum::Router {
+/// Resolves on SIGTERM or Ctrl-C.
+async fn shutdown_signal() {
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .expect("failed to install SIGTERM handler");
+    tokio::select! {
+        _ = terminate.recv() => info!("received SIGTERM"),
+        _ = tokio::signal::ctrl_c() => info!("received Ctrl-C"),
+    }
+}
+
+/// The agent this process serves, resolved from the catalog by `--name`.
+struct HostedAgent {
+    config: AgentConfig,
+    engine: InferenceEngine,
+}
+
+#[derive(Clone, FromRef)]
+struct AppState {
+    platform: PlatformContext,
+    agent: Arc<HostedAgent>,
+}
+
+fn build_router(state: AppState) -> axum::Router {
+    let guard = |permission: Permission| from_fn_with_state(permission, auth::require);
     axum::Router::new()
         .route("/health", axum::routing::get(health_check))
-        .route("/predict", axum::routing::post(predict))
-        .route("/status", axum::routing::get(agent_status))
-        .with_state(context)
+        .route("/ready", axum::routing::get(readiness_check))
+        .route(
+            "/predict",
+            axum::routing::post(predict).route_layer(guard(Permission::Infer)),
+        )
+        .route(
+            "/status",
+            axum::routing::get(agent_status).route_layer(guard(Permission::ViewStatus)),
+        )
+        .route(
+            "/sessions/:id/messages",
+            axum::routing::get(session_history)
+                .route_layer(guard(Permission::ReadSessions))
+                .merge(axum::routing::post(session_message).route_layer(guard(Permission::Infer))),
+        )
+        .layer(from_fn_with_state(
+            state.platform.clone(),
+            auth::authenticate,
+        ))
+        .layer(from_fn_with_state(
+            state.platform.clone(),
+            drain::track_in_flight,
+        ))
+        .with_state(state)
 }
 
 async fn health_check() -> impl axum::response::IntoResponse {
     axum::Json(serde_json::json!({
         "status": "healthy",
         "timestamp": chimera_core::utils::timestamp_now(),
-        "timestamp": utils::timestamp_now(),
     }))
 }
 
+async fn readiness_check(
+    axum::extract::State(platform): axum::extract::State<PlatformContext>,
+) -> axum::response::Response {
+    let services = platform.services();
+    let ready = services.is_ready() && !platform.is_draining();
+    let status = if ready {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        axum::Json(serde_json::json!({
+            "ready": ready,
+            "draining": platform.is_draining(),
+            "services": services.list(),
+        })),
+    )
+        .into_response()
+}
+
 async fn predict(
     axum::extract::State(platform): axum::extract::State<PlatformContext>,
+    axum::Extension(principal): axum::Extension<Principal>,
     axum::extract::Json(payload): axum::extract::Json<serde_json::Value>,
 ) -> axum::response::Response {
-    info!("received prediction request: {:?}", payload);
+    // Prompts may only be logged once redacted.
+    let redactor = platform.redactor();
+    if redactor.settings().prompts {
+        info!(request = %redactor.redact_json(&payload), "received prediction request");
+    } else {
+        info!("received prediction request");
+    }
 
-    let client_ip = std::net::IpAddr::from([127, 0, 0, 1]);
-    if let Err(e) = platform
-        .rate_limiter()
-        .check_rate_limit(client_ip, "/predict")
-    {
-        error!("rate limit exceeded for {}: {:?}", client_ip, e);
+    if platform.is_draining() {
+        return draining_response();
+    }
+
+    if let Err(e) = platform.rate_limiter().check_tenant_rate_limit(
+        principal.client_id(),
+        &principal.tenant,
+        "/predict",
+    ) {
+        error!(
+            "rate limit exceeded for {:?}: {:?}",
+            principal.client_id(),
+            e
+        );
         return (
             axum::http::StatusCode::TOO_MANY_REQUESTS,
             axum::Json(serde_json::json!({ "error": "Rate limit exceeded" })),
@@ -99,48 +223,28 @@ async fn predict(
             .into_response();
     }
 
+    if let Err(err) = platform.tenants().admit_request(&principal.tenant) {
+        return error_response(axum::http::StatusCode::TOO_MANY_REQUESTS, err.to_string());
+    }
+
     if let Err(errors) = chimera_core::utils::validate_request_payload(&payload) {
         return (axum::http::StatusCode::BAD_REQUEST, axum::Json(errors)).into_response();
     }
 
     if let Err(err) = platform
         .audit_logger()
-        .log_api_access(None, "/predict", "POST", 200, None)
+        .for_tenant(&principal.tenant)
+        .log_api_access(
+            principal.user_id(),
+            "/predict",
+            "POST",
+            200,
+            principal.ip_string(),
+        )
     {
         error!(?err, "failed to record audit log for predict request");
     }
 
-    axum::extract::State(platform): axum::extract::State<Arc<Platform>>,
-    axum::extract::Json(payload): axum::extract::Json<serde_json::Value>,
-) -> axum::response::Response {
-    info!("Received prediction request: {:?}", payload);
-
-    let client_ip = std::net::IpAddr::from([127, 0, 0, 1]);
-    if let Err(e) = platform
-        .rate_limiter
-        .check_rate_limit(client_ip, "/predict")
-    {
-        error!("Rate limit exceeded for {}: {:?}", client_ip, e);
-        return (
-            axum::http::StatusCode::TOO_MANY_REQUESTS,
-            axum::Json(serde_json::json!({ "error": "Rate limit exceeded" })),
-        )
-            .into_response();
-    }
-
-    if let Err(errors) = utils::validate_request_payload(&payload) {
-        return (
-            axum::http::StatusCode::BAD_REQUEST,
-            axum::Json(errors),
-        )
-            .into_response();
-    }
-
-    platform
-        .audit_logger
-        .log_api_access(None, "/predict", "POST", 200, None)
-        .ok();
-
     let response = serde_json::json!({
         "result": "Prediction completed",
         "confidence": 0.95,
@@ -152,7 +256,6 @@ async fn predict(
 
 async fn agent_status(
     axum::extract::State(_platform): axum::extract::State<PlatformContext>,
-    axum::extract::State(_platform): axum::extract::State<Arc<Platform>>,
 ) -> impl axum::response::IntoResponse {
     axum::Json(serde_json::json!({
         "name": "chimera-agent",
@@ -162,3 +265,332 @@ async fn agent_status(
         "average_response_time_ms": 145
     }))
 }
+
+#[derive(Deserialize)]
+struct SessionMessage {
+    content: String,
+    #[serde(default = "default_role")]
+    role: Role,
+    /// Requires the reply to be JSON matching this schema.
+    #[serde(default)]
+    response_format: Option<ResponseFormat>,
+}
+
+fn default_role() -> Role {
+    Role::User
+}
+
+fn error_response(status: axum::http::StatusCode, message: String) -> axum::response::Response {
+    (status, axum::Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+fn draining_response() -> axum::response::Response {
+    error_response(
+        axum::http::StatusCode::SERVICE_UNAVAILABLE,
+        "agent is draining and not accepting new requests".to_string(),
+    )
+}
+
+/// Refuses callers from other tenants when the hosted agent belongs to one.
+fn tenant_denied(agent: &HostedAgent, principal: &Principal) -> Option<axum::response::Response> {
+    match &agent.config.tenant {
+        Some(owner) if !principal.can_access_tenant(owner) => Some(error_response(
+            axum::http::StatusCode::FORBIDDEN,
+            format!(
+                "agent {} belongs to another tenant",
+                agent.config.agent_name
+            ),
+        )),
+        _ => None,
+    }
+}
+
+async fn session_history(
+    axum::extract::State(platform): axum::extract::State<PlatformContext>,
+    axum::extract::State(agent): axum::extract::State<Arc<HostedAgent>>,
+    axum::Extension(principal): axum::Extension<Principal>,
+    axum::extract::Path(session_id): axum::extract::Path<String>,
+) -> axum::response::Response {
+    if let Some(response) = tenant_denied(&agent, &principal) {
+        return response;
+    }
+
+    // Sessions are stored under a tenant-scoped key so ids never collide across tenants.
+    let key = tenancy::scoped_key(&principal.tenant, &session_id);
+    match platform.sessions().get(&key, &agent.config.memory).await {
+        Ok(Some(mut session)) if session.agent == agent.config.agent_name => {
+            session.id = session_id;
+            axum::Json(session).into_response()
+        }
+        Ok(_) => error_response(
+            axum::http::StatusCode::NOT_FOUND,
+            format!("session {} not found", session_id),
+        ),
+        Err(err) => error_response(
+            axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+            err.to_string(),
+        ),
+    }
+}
+
+async fn session_message(
+    axum::extract::State(platform): axum::extract::State<PlatformContext>,
+    axum::extract::State(agent): axum::extract::State<Arc<HostedAgent>>,
+    axum::Extension(principal): axum::Extension<Principal>,
+    axum::extract::Path(session_id): axum::extract::Path<String>,
+    axum::extract::Json(body): axum::extract::Json<SessionMessage>,
+) -> axum::response::Response {
+    if platform.is_draining() {
+        return draining_response();
+    }
+    if body.content.trim().is_empty() {
+        return error_response(
+            axum::http::StatusCode::BAD_REQUEST,
+            "content must not be empty".to_string(),
+        );
+    }
+    if matches!(body.role, Role::System | Role::Assistant) {
+        return error_response(
+            axum::http::StatusCode::BAD_REQUEST,
+            "only user and tool messages can be posted".to_string(),
+        );
+    }
+
+    if let Some(response) = tenant_denied(&agent, &principal) {
+        return response;
+    }
+
+    if body.response_format.is_some() && !agent.config.tools.is_empty() {
+        return error_response(
+            axum::http::StatusCode::BAD_REQUEST,
+            "response_format is not supported for agents with tools".to_string(),
+        );
+    }
+
+    let endpoint = "/sessions/messages";
+    if platform
+        .rate_limiter()
+        .check_tenant_rate_limit(principal.client_id(), &principal.tenant, endpoint)
+        .is_err()
+    {
+        return error_response(
+            axum::http::StatusCode::TOO_MANY_REQUESTS,
+            "Rate limit exceeded".to_string(),
+        );
+    }
+    let tenants = platform.tenants();
+    if let Err(err) = tenants.admit_request(&principal.tenant) {
+        return error_response(axum::http::StatusCode::TOO_MANY_REQUESTS, err.to_string());
+    }
+    let audit = platform.audit_logger().for_tenant(&principal.tenant);
+
+    let platform_config = platform.config();
+    let config = &agent.config;
+    let system_prompt = match PromptLibrary::from_settings(&platform_config.prompts)
+        .map_err(|errors| errors[0].1.clone())
+        .and_then(|library| library.system_prompt(config))
+    {
+        Ok(prompt) => prompt,
+        Err(err) => {
+            return error_response(
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                format!("failed to render system prompt: {}", err),
+            )
+        }
+    };
+
+    let verdict = guardrails::check(&body.content, &agent.config.guardrail);
+    if verdict.decision != GuardDecision::Allow {
+        warn!(
+            session = %session_id,
+            decision = ?verdict.decision,
+            score = verdict.score,
+            rules = ?verdict.rule_ids(),
+            "guardrail matched session message"
+        );
+        let decision = match verdict.decision {
+            GuardDecision::Block => "block",
+            _ => "flag",
+        };
+        if let Err(err) = audit.log_guardrail(
+            principal.user_id(),
+            &agent.config.agent_name,
+            decision,
+            verdict.score,
+            &verdict.rule_ids(),
+        ) {
+            error!(?err, "failed to record audit log for guardrail verdict");
+        }
+    }
+    if verdict.decision == GuardDecision::Block {
+        return (
+            axum::http::StatusCode::FORBIDDEN,
+            axum::Json(serde_json::json!({
+                "error": "message rejected by prompt guardrail",
+                "guardrail": verdict,
+            })),
+        )
+            .into_response();
+    }
+
+    // Redacted before storage, so neither the model nor the session store sees raw PII.
+    let redactor = platform.redactor();
+    let mut content = body.content;
+    if redactor.settings().prompts {
+        let redacted = redactor.redact(&content);
+        content = redacted.text;
+        info!(
+            session = %session_id,
+            prompt = %content,
+            redactions = redacted.findings.len(),
+            "session message received"
+        );
+    }
+
+    let sessions = platform.sessions();
+    let session_key = tenancy::scoped_key(&principal.tenant, &session_id);
+    // The session stays locked for the whole turn, and the message is only
+    // stored together with the reply it gets.
+    let mut turn = match sessions
+        .begin_turn(&session_key, &config.agent_name, &config.memory)
+        .await
+    {
+        Ok(turn) => turn,
+        Err(err) => return error_response(axum::http::StatusCode::CONFLICT, err.to_string()),
+    };
+    turn.push(Message::new(body.role, content));
+
+    // Agents with tools get their descriptions appended to the system prompt and
+    // run the tool loop; only the final answer is kept in the session.
+    let tools = (!config.tools.is_empty()).then(|| {
+        ToolExecutor::new(
+            platform.tools(),
+            config.tools.clone(),
+            &config.agent_name,
+            &platform_config.tools,
+            Arc::new(audit.clone()),
+        )
+        .on_behalf_of(principal.user_id())
+    });
+    let mut system_text = system_prompt.text.clone();
+    if let Some(tools) = &tools {
+        system_text = format!("{}\n\n{}", system_text, tools.instructions());
+    }
+
+    let window =
+        ContextWindow::assemble(turn.session(), &system_text, config.memory.context_tokens);
+    let meter = platform.usage();
+    let usage = std::sync::Mutex::new(UsageTotals::default());
+    let structured = std::sync::Mutex::new(None);
+    let generate = |messages: Vec<Message>| {
+        let request = InferenceRequest {
+            prompt: config.chat_format.render(&messages),
+            max_tokens: config.max_tokens,
+            temperature: config.temperature,
+            top_p: platform_config.inference.top_p,
+            repetition_penalty: platform_config.inference.repetition_penalty,
+            template: Some(system_prompt.template.clone()),
+            response_format: body.response_format.clone(),
+            tenant: Some(principal.tenant.clone()),
+        };
+        let engine = &agent.engine;
+        let (meter, usage, principal) = (&meter, &usage, &principal);
+        let structured = &structured;
+        async move {
+            let response = engine.generate(request).await?;
+            // Each call is metered on its own, so tool-loop steps show up as separate records.
+            let record = meter.record(
+                &config.agent_name,
+                &response.model,
+                &principal.tenant,
+                principal.user_id(),
+                response.usage,
+            );
+            usage.lock().unwrap().add(&record);
+            *structured.lock().unwrap() = response.structured;
+            Ok(response.text)
+        }
+    };
+    let outcome = match &tools {
+        Some(tools) => tools.run(window.messages.clone(), generate).await,
+        None => generate(window.messages.clone())
+            .await
+            .map(|answer| AgentLoopOutcome {
+                answer,
+                steps: 1,
+                calls: Vec::new(),
+            }),
+    };
+    let mut reply = match outcome {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            if let Some(InferenceError::StructuredOutput { errors, .. }) = err.downcast_ref() {
+                return (
+                    axum::http::StatusCode::UNPROCESSABLE_ENTITY,
+                    axum::Json(serde_json::json!({ "error": err.to_string(), "details": errors })),
+                )
+                    .into_response();
+            }
+            return error_response(
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                err.to_string(),
+            );
+        }
+    };
+    let mut structured = structured.into_inner().unwrap();
+    if redactor.settings().completions {
+        reply.answer = redactor.redact(&reply.answer).text;
+        structured = structured.map(|value| redactor.redact_json(&value));
+    }
+    let usage = usage.into_inner().unwrap();
+    let tokens_used = usage.prompt_tokens + usage.completion_tokens;
+    tenants.record_tokens(&principal.tenant, tokens_used);
+    info!(
+        session = %session_id,
+        template = %system_prompt.template.name,
+        version = %system_prompt.template.version,
+        tokens = tokens_used,
+        steps = reply.steps,
+        tool_calls = reply.calls.len(),
+        "inference completed"
+    );
+
+    turn.push(Message::new(Role::Assistant, reply.answer.clone()));
+    let session = match turn.save().await {
+        Ok(session) => session,
+        Err(err) => {
+            return error_response(
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                err.to_string(),
+            )
+        }
+    };
+
+    if let Err(err) = audit.log_api_access(
+        principal.user_id(),
+        endpoint,
+        "POST",
+        200,
+        principal.ip_string(),
+    ) {
+        error!(?err, "failed to record audit log for session message");
+    }
+
+    axum::Json(serde_json::json!({
+        "session_id": session_id,
+        "reply": reply.answer,
+        "tokens_used": tokens_used,
+        "usage": usage,
+        "template": system_prompt.template,
+        "tool_calls": reply.calls,
+        "structured": structured,
+        "context": {
+            "messages": window.messages.len(),
+            "tokens": window.tokens,
+            "truncated": window.truncated,
+        },
+        "history_length": session.messages.len(),
+        "guardrail": verdict,
+    }))
+    .into_response()
+}
@@ -0,0 +1,579 @@
+use chimera_core::auth::{self, Role};
+use chimera_core::platform::config::SessionBackend;
+use chimera_core::storage::{migrations, StorageBackend};
+use chimera_core::PlatformConfig;
+use clap::{Parser, Subcommand};
+use dotenvy::dotenv;
+use reqwest::Method;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+
+const DEFAULT_ADMIN_URL: &str = "http://localhost:9090";
+
+#[derive(Parser)]
+#[command(author, version, about = "Chimera platform administration", long_about = None)]
+struct Args {
+    /// Telemetry address of a running process; defaults to $CHIMERA_ADMIN_URL
+    #[arg(long, global = true)]
+    url: Option<String>,
+
+    /// API key for the admin API; defaults to $CHIMERA_API_KEY
+    #[arg(long, global = true)]
+    api_key: Option<String>,
+
+    /// Print responses as JSON instead of tables
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Inspect and validate platform configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Create, list and revoke API keys
+    #[command(subcommand)]
+    Keys(KeysCommand),
+    /// Check and apply database schema migrations
+    #[command(subcommand)]
+    Db(DbCommand),
+    /// List, inspect, pause and resume agents
+    #[command(subcommand)]
+    Agents(AgentsCommand),
+    /// List, cancel and retry orchestrator tasks
+    #[command(subcommand)]
+    Tasks(TasksCommand),
+    /// Read the audit log
+    #[command(subcommand)]
+    Audit(AuditCommand),
+    /// Show request counts per client identity
+    RateLimits,
+    /// Reload the configuration file the process was started with
+    Reload,
+    /// Stop the process from taking new work
+    Drain,
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Show the schema version of the `[storage]` and `[sessions]` databases
+    Status {
+        /// Configuration file path
+        #[arg(short, long, default_value = "configs/platform.toml")]
+        config: PathBuf,
+    },
+    /// Apply pending migrations
+    Migrate {
+        /// Configuration file path
+        #[arg(short, long, default_value = "configs/platform.toml")]
+        config: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum AgentsCommand {
+    List,
+    /// Show agents by id or name
+    Show {
+        agent: String,
+    },
+    /// Put agents into maintenance so they take no new work
+    Pause {
+        agent: String,
+    },
+    Resume {
+        agent: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TasksCommand {
+    List,
+    Cancel {
+        id: String,
+    },
+    /// Requeue a failed or cancelled task
+    Retry {
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditCommand {
+    /// Print the most recent events
+    Tail {
+        /// Number of events
+        #[arg(short = 'n', long, default_value_t = 20)]
+        lines: usize,
+
+        /// Keep polling for new events
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Seconds between polls with --follow
+        #[arg(long, default_value_t = 2)]
+        interval: u64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Strictly validate a configuration file
+    Validate {
+        /// Configuration file path
+        #[arg(short, long, default_value = "configs/platform.toml")]
+        config: PathBuf,
+    },
+    /// Print the configuration with defaults filled in
+    Print {
+        /// Configuration file path
+        #[arg(short, long, default_value = "configs/platform.toml")]
+        config: PathBuf,
+
+        /// Apply CHIMERA__* environment overrides before printing
+        #[arg(long)]
+        effective: bool,
+    },
+    /// Emit the JSON Schema for platform.toml
+    Schema,
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// Issue a new key; the plaintext is printed once
+    Create {
+        /// Configuration file path
+        #[arg(short, long, default_value = "configs/platform.toml")]
+        config: PathBuf,
+
+        /// Human-readable label for the key
+        #[arg(long)]
+        name: String,
+
+        /// admin, operator, client or read_only
+        #[arg(long, default_value = "client")]
+        role: Role,
+
+        /// Tenant the key's requests are accounted to
+        #[arg(long, default_value = chimera_core::tenancy::DEFAULT_TENANT)]
+        tenant: String,
+    },
+    /// List issued keys without their secrets
+    List {
+        /// Configuration file path
+        #[arg(short, long, default_value = "configs/platform.toml")]
+        config: PathBuf,
+    },
+    /// Revoke a key by id
+    Revoke {
+        /// Configuration file path
+        #[arg(short, long, default_value = "configs/platform.toml")]
+        config: PathBuf,
+
+        /// Key id, as shown by `keys list`
+        id: String,
+    },
+}
+
+fn main() -> ExitCode {
+    dotenv().ok();
+    let args = Args::parse();
+
+    let outcome = match args.command {
+        Command::Config(command) => run_config(command),
+        Command::Keys(command) => run_keys(command),
+        Command::Db(command) => run_db(command),
+        command => {
+            let client = AdminClient::new(args.url, args.api_key, args.json);
+            tokio::runtime::Runtime::new()
+                .map_err(anyhow::Error::from)
+                .and_then(|runtime| runtime.block_on(run_admin(&client, command)))
+        }
+    };
+
+    match outcome {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_config(command: ConfigCommand) -> anyhow::Result<()> {
+    match command {
+        ConfigCommand::Validate { config } => {
+            let parsed = PlatformConfig::from_file(&config)?;
+            println!(
+                "{}: ok ({} agents configured)",
+                config.display(),
+                parsed.agents.len()
+            );
+        }
+        ConfigCommand::Print { config, effective } => {
+            let parsed = if effective {
+                PlatformConfig::load_from_path(Some(&config))?
+            } else {
+                PlatformConfig::from_file(&config)?
+            };
+            print!("{}", toml::to_string_pretty(&parsed)?);
+        }
+        ConfigCommand::Schema => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&PlatformConfig::json_schema())?
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn run_keys(command: KeysCommand) -> anyhow::Result<()> {
+    let open = |config: &PathBuf| -> anyhow::Result<_> {
+        let parsed = PlatformConfig::load_from_path(Some(config))?;
+        auth::open_key_store(&parsed.auth)
+    };
+
+    match command {
+        KeysCommand::Create {
+            config,
+            name,
+            role,
+            tenant,
+        } => {
+            let store = open(&config)?;
+            let (record, key) = auth::issue_key(store.as_ref(), &name, role, &tenant)?;
+            println!("id:     {}", record.id);
+            println!("role:   {}", record.role);
+            println!("tenant: {}", record.tenant);
+            println!("key:    {}", key);
+            eprintln!("store this key now; it cannot be shown again");
+        }
+        KeysCommand::List { config } => {
+            for record in open(&config)?.list()? {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    record.id,
+                    record.role,
+                    record.tenant,
+                    if record.revoked { "revoked" } else { "active" },
+                    record.name
+                );
+            }
+        }
+        KeysCommand::Revoke { config, id } => {
+            if !open(&config)?.revoke(&id)? {
+                anyhow::bail!("no key with id {}", id);
+            }
+            println!("revoked {}", id);
+        }
+    }
+
+    Ok(())
+}
+
+fn run_db(command: DbCommand) -> anyhow::Result<()> {
+    let (DbCommand::Status { config } | DbCommand::Migrate { config }) = &command;
+    let config = PlatformConfig::load_from_path(Some(config))?;
+    // The session store may keep its own file with the same schema.
+    let mut paths = Vec::new();
+    if config.storage.backend == StorageBackend::Sqlite {
+        paths.push(PathBuf::from(&config.storage.sqlite_path));
+    }
+    if config.sessions.backend == SessionBackend::Sqlite {
+        let path = PathBuf::from(&config.sessions.sqlite_path);
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        println!("storage and sessions are kept in memory; there is no database to migrate");
+        return Ok(());
+    }
+
+    for path in paths {
+        if !path.exists() {
+            if let DbCommand::Status { .. } = command {
+                println!("{}: not created yet", path.display());
+                continue;
+            }
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let mut conn = rusqlite::Connection::open(&path)?;
+        if let DbCommand::Migrate { .. } = command {
+            for version in migrations::migrate(&mut conn)? {
+                println!("{}: applied migration {}", path.display(), version);
+            }
+        }
+        let status = migrations::status(&conn)?;
+        println!(
+            "{}: schema version {} of {}",
+            path.display(),
+            status.current,
+            status.latest
+        );
+        for migration in status.pending() {
+            println!("pending: {} {}", migration.version, migration.name);
+        }
+    }
+    Ok(())
+}
+
+/// Client for the `/admin` API on a process's telemetry port.
+struct AdminClient {
+    http: reqwest::Client,
+    base: String,
+    api_key: Option<String>,
+    json: bool,
+}
+
+impl AdminClient {
+    fn new(url: Option<String>, api_key: Option<String>, json: bool) -> Self {
+        let base = url
+            .or_else(|| std::env::var("CHIMERA_ADMIN_URL").ok())
+            .unwrap_or_else(|| DEFAULT_ADMIN_URL.to_string());
+        Self {
+            http: reqwest::Client::new(),
+            base: base.trim_end_matches('/').to_string(),
+            api_key: api_key.or_else(|| std::env::var("CHIMERA_API_KEY").ok()),
+            json,
+        }
+    }
+
+    async fn send(&self, method: Method, path: &str) -> anyhow::Result<Value> {
+        let url = format!("{}/admin{}", self.base, path);
+        let mut request = self
+            .http
+            .request(method, &url)
+            .timeout(Duration::from_secs(30));
+        if let Some(key) = &self.api_key {
+            request = request.header("x-api-key", key);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|err| anyhow::anyhow!("{}: {}", url, err))?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            let message = body["error"].as_str().unwrap_or("request failed");
+            anyhow::bail!("{} ({})", message, status);
+        }
+        Ok(body)
+    }
+
+    async fn get(&self, path: &str) -> anyhow::Result<Value> {
+        self.send(Method::GET, path).await
+    }
+
+    async fn post(&self, path: &str) -> anyhow::Result<Value> {
+        self.send(Method::POST, path).await
+    }
+
+    /// Prints `body` as JSON, or as a table of `columns` (header, field) otherwise.
+    fn print_rows(&self, body: &Value, columns: &[(&str, &str)]) -> anyhow::Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(body)?);
+            return Ok(());
+        }
+        let rows: Vec<Vec<String>> = match body {
+            Value::Array(items) => items.iter().map(|item| row(item, columns)).collect(),
+            item => vec![row(item, columns)],
+        };
+        let headers: Vec<&str> = columns.iter().map(|(header, _)| *header).collect();
+        print_table(&headers, &rows);
+        Ok(())
+    }
+
+    fn print_object(&self, body: &Value) -> anyhow::Result<()> {
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(body)?);
+        } else if let Value::Object(fields) = body {
+            for (key, value) in fields {
+                println!("{}: {}", key, cell(value));
+            }
+        }
+        Ok(())
+    }
+}
+
+const AGENT_COLUMNS: &[(&str, &str)] = &[
+    ("ID", "id"),
+    ("NAME", "name"),
+    ("TYPE", "agent_type"),
+    ("STATUS", "status"),
+    ("TENANT", "tenant"),
+    ("REQUESTS", "metrics.requests_processed"),
+];
+
+const TASK_COLUMNS: &[(&str, &str)] = &[
+    ("ID", "id"),
+    ("TYPE", "task_type"),
+    ("TENANT", "tenant"),
+    ("STATUS", "status"),
+    ("AGENT", "assigned_agent"),
+    ("ERROR", "error"),
+];
+
+const AUDIT_COLUMNS: &[(&str, &str)] = &[
+    ("TIME", "timestamp"),
+    ("USER", "user_id"),
+    ("TENANT", "tenant"),
+    ("ACTION", "action"),
+    ("RESOURCE", "resource"),
+    ("RESULT", "result"),
+];
+
+async fn run_admin(client: &AdminClient, command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Agents(command) => {
+            let body = match command {
+                AgentsCommand::List => client.get("/agents").await?,
+                AgentsCommand::Show { agent } => {
+                    let body = client.get(&format!("/agents/{}", agent)).await?;
+                    if client.json {
+                        return client.print_object(&body);
+                    }
+                    for agent in body.as_array().into_iter().flatten() {
+                        client.print_object(agent)?;
+                        println!();
+                    }
+                    return Ok(());
+                }
+                AgentsCommand::Pause { agent } => {
+                    client.post(&format!("/agents/{}/pause", agent)).await?
+                }
+                AgentsCommand::Resume { agent } => {
+                    client.post(&format!("/agents/{}/resume", agent)).await?
+                }
+            };
+            client.print_rows(&body, AGENT_COLUMNS)
+        }
+        Command::Tasks(command) => {
+            let body = match command {
+                TasksCommand::List => client.get("/tasks").await?,
+                TasksCommand::Cancel { id } => {
+                    client.post(&format!("/tasks/{}/cancel", id)).await?
+                }
+                TasksCommand::Retry { id } => client.post(&format!("/tasks/{}/retry", id)).await?,
+            };
+            client.print_rows(&body, TASK_COLUMNS)
+        }
+        Command::Audit(AuditCommand::Tail {
+            lines,
+            follow,
+            interval,
+        }) => tail_audit(client, lines, follow, interval).await,
+        Command::RateLimits => {
+            let body = client.get("/rate-limits").await?;
+            client.print_rows(
+                &body,
+                &[
+                    ("CLIENT", "client"),
+                    ("REQUESTS", "requests"),
+                    ("LAST SEEN (S AGO)", "last_request_secs_ago"),
+                ],
+            )
+        }
+        Command::Reload => client.print_object(&client.post("/reload").await?),
+        Command::Drain => client.print_object(&client.post("/drain").await?),
+        Command::Config(_) | Command::Keys(_) | Command::Db(_) => {
+            unreachable!("handled locally")
+        }
+    }
+}
+
+/// Polls with `since` one second before the newest event seen, since
+/// timestamps have second resolution, and skips ids already printed.
+async fn tail_audit(
+    client: &AdminClient,
+    lines: usize,
+    follow: bool,
+    interval: u64,
+) -> anyhow::Result<()> {
+    let mut body = client.get(&format!("/audit?limit={}", lines)).await?;
+    let mut seen = HashSet::new();
+    let mut newest = 0;
+    loop {
+        let events: Vec<Value> = body
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|event| seen.insert(event["id"].as_str().unwrap_or_default().to_string()))
+            .cloned()
+            .collect();
+        if let Some(timestamp) = events.iter().filter_map(|e| e["timestamp"].as_u64()).max() {
+            newest = newest.max(timestamp);
+        }
+        if client.json {
+            for event in &events {
+                println!("{}", event);
+            }
+        } else if !events.is_empty() {
+            client.print_rows(&Value::Array(events), AUDIT_COLUMNS)?;
+        }
+        if !follow {
+            return Ok(());
+        }
+
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        body = client
+            .get(&format!(
+                "/audit?limit=1000&since={}",
+                newest.saturating_sub(1)
+            ))
+            .await?;
+    }
+}
+
+/// Reads a dotted `path` out of `item` for a table cell.
+fn row(item: &Value, columns: &[(&str, &str)]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|(_, path)| {
+            let value = path
+                .split('.')
+                .try_fold(item, |value, key| value.get(key))
+                .unwrap_or(&Value::Null);
+            cell(value)
+        })
+        .collect()
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(headers.to_vec());
+    for row in rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
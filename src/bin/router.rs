@@ -1,108 +1,106 @@
+use axum::response::IntoResponse;
+use chimera_core::routing::{router_service, RouterOptions};
+use chimera_core::{Platform, PlatformConfig, PlatformContext};
 use clap::Parser;
 use dotenvy::dotenv;
-use redis::{AsyncCommands, Client};
-use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::info;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// Configuration file path
+    #[arg(short, long, default_value = "configs/platform.toml")]
+    config: PathBuf,
+
     /// Redis URL
     #[arg(short, long, default_value = "redis://localhost:6379")]
     redis_url: String,
 
-    /// Router port
+    /// Port for the router's health endpoints
     #[arg(short, long, default_value = "8081")]
     port: u16,
 
-    /// Number of worker agents
+    /// Number of consumers reading the request stream
     #[arg(short, long, default_value = "3")]
     workers: usize,
+
+    /// Seconds a response stays in Redis before it expires
+    #[arg(long, default_value = "300")]
+    response_ttl: u64,
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Load environment variables
+async fn main() -> anyhow::Result<()> {
     dotenv().ok();
-
-    // Parse command line arguments
     let args = Args::parse();
 
-    // Initialize tracing
-    tracing_subscriber::fmt().with_env_filter("info").init();
-
-    info!("Starting Chimera Router");
-
-    // Connect to Redis
-    let redis_client = Client::open(args.redis_url)?;
-    let mut redis_conn = redis_client.get_async_connection().await?;
-
-    // Test Redis connection
-    let _: () = redis::cmd("PING").query_async(&mut redis_conn).await?;
-    info!("Connected to Redis successfully");
-
-    // Create request stream
-    let stream_key = "chimera:requests";
-
-    // Start request processing loop
-    process_requests(redis_conn, stream_key).await?;
+    let config = PlatformConfig::load_from_path(Some(args.config.clone()))?;
+    let mut platform = Platform::new(config);
+    platform.watch_config(args.config.clone());
+    let mut options = RouterOptions::new(args.redis_url)
+        .with_workers(args.workers)
+        .with_response_ttl(Duration::from_secs(args.response_ttl));
+    // Read from the environment rather than a flag so the key stays out of `ps`.
+    if let Ok(key) = std::env::var("CHIMERA_ROUTER_API_KEY") {
+        options = options.with_api_key(key);
+    }
+    platform.register_service(router_service(options));
+    let runtime = platform.start().await?;
+    let context = runtime.context();
+
+    let app = axum::Router::new()
+        .route("/health", axum::routing::get(health_check))
+        .route("/ready", axum::routing::get(readiness_check))
+        .with_state(context.clone());
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], args.port));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    info!(%addr, workers = args.workers, "router listening");
+
+    let shutdown = context.shutdown_token();
+    tokio::select! {
+        served = axum::serve(listener, app)
+            .with_graceful_shutdown(async move { shutdown.cancelled().await }) => served?,
+        _ = tokio::signal::ctrl_c() => info!("shutdown requested"),
+    }
 
+    runtime.shutdown().await?;
     Ok(())
 }
 
-async fn process_requests(
-    mut redis_conn: redis::aio::Connection,
-    stream_key: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    info!("Starting request processing loop");
-
-    loop {
-        // Read from Redis stream
-        let results: Vec<(String, HashMap<String, redis::Value>)> =
-            redis_conn.xread(&[stream_key], &[0]).await?;
-
-        for (_id, fields) in results {
-            // Process each request
-            if let (Some(request_id), Some(request_data)) =
-                (fields.get("request_id"), fields.get("data"))
-            {
-                if let (
-                    redis::Value::Data(request_id_bytes),
-                    redis::Value::Data(request_data_bytes),
-                ) = (request_id, request_data)
-                {
-                    let request_id = String::from_utf8_lossy(&request_id_bytes);
-                    let _request_data = String::from_utf8_lossy(&request_data_bytes);
-
-                    info!("Processing request: {}", request_id);
-
-                    // TODO: Implement actual request routing logic
-                    // This would route requests to appropriate agents based on type/capability
-
-                    // Simulate processing
-                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-
-                    // Send response back via Redis
-                    let response_key = format!("chimera:response:{}", request_id);
-                    let response = format!(
-                        "{{\"result\": \"processed\", \"request_id\": \"{}\"}}",
-                        request_id
-                    );
-
-                    let _: () = redis_conn.set(&response_key, response).await?;
-                    info!("Response sent for request: {}", request_id);
-                }
-            }
-        }
+async fn health_check() -> impl IntoResponse {
+    axum::Json(serde_json::json!({
+        "status": "healthy",
+        "timestamp": chimera_core::utils::timestamp_now(),
+    }))
+}
 
-        // Small delay to prevent busy waiting
-        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-    }
+async fn readiness_check(
+    axum::extract::State(platform): axum::extract::State<PlatformContext>,
+) -> axum::response::Response {
+    let services = platform.services();
+    let status = if services.is_ready() {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        axum::Json(serde_json::json!({
+            "ready": services.is_ready(),
+            "services": services.list(),
+        })),
+    )
+        .into_response()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use redis::Client;
 
     #[tokio::test]
     async fn test_redis_connection() {
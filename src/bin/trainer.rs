@@ -1,3 +1,5 @@
+use chimera_core::events::EventPayload;
+use chimera_core::training::{gpu_hours_today, ModelArtifact, TrainingJob};
 use chimera_core::{Platform, PlatformConfig, PlatformContext};
 use clap::Parser;
 use dotenvy::dotenv;
@@ -35,6 +37,10 @@ struct Args {
     #[arg(short, long, default_value = "500")]
     save_steps: usize,
 
+    /// Tenant the run is accounted to
+    #[arg(long, default_value = chimera_core::tenancy::DEFAULT_TENANT)]
+    tenant: String,
+
     /// Platform configuration file
     #[arg(short = 'c', long, default_value = "configs/platform.toml")]
     config: PathBuf,
@@ -55,14 +61,73 @@ async fn main() -> anyhow::Result<()> {
     info!("Starting Chimera Trainer");
     info!(model = %args.model, dataset = %args.dataset, output = %args.output);
 
-    simulate_training(&args, context.clone()).await?;
+    let tenants = context.tenants();
+    let storage = context.storage();
+    let used_today = gpu_hours_today(storage.training_jobs.as_ref(), &args.tenant).await?;
+    tenants.admit_training(&args.tenant, used_today)?;
+    let mut job = TrainingJob::new(
+        &args.tenant,
+        serde_json::json!({
+            "model": args.model,
+            "dataset": args.dataset,
+            "output": args.output,
+            "learning_rate": args.learning_rate,
+            "epochs": args.epochs,
+            "batch_size": args.batch_size,
+            "save_steps": args.save_steps,
+        }),
+    );
+    job.start();
+    storage.training_jobs.save_training_job(&job).await?;
+    let started = std::time::Instant::now();
+
+    let outcome = simulate_training(&args, context.clone()).await;
+
+    let gpu_hours = started.elapsed().as_secs_f64() / 3600.0;
+    tenants.record_gpu_hours(&args.tenant, gpu_hours);
+    job.record_gpu_hours(gpu_hours);
+    info!(tenant = %args.tenant, gpu_hours, "training usage recorded");
+
+    let adapter_path = match outcome {
+        Ok(path) => path,
+        Err(err) => {
+            job.fail(format!("{:#}", err));
+            storage.training_jobs.save_training_job(&job).await?;
+            publish_finished(&context, &job);
+            runtime.shutdown().await?;
+            return Err(err);
+        }
+    };
+    let mut artifact = ModelArtifact::from_file(&args.model, "lora_adapter", &adapter_path).await?;
+    artifact.metadata = Some(serde_json::json!({ "training_job": job.id }));
+    storage.artifacts.save_artifact(&artifact).await?;
+    job.complete(
+        &artifact.id,
+        serde_json::json!({ "epochs": args.epochs, "gpu_hours": gpu_hours }),
+    );
+    storage.training_jobs.save_training_job(&job).await?;
+    publish_finished(&context, &job);
+    info!(job = %job.id, artifact = %artifact.id, checksum = %artifact.checksum, "model artifact recorded");
 
     info!("Training completed successfully!");
     runtime.shutdown().await?;
     Ok(())
 }
 
-async fn simulate_training(args: &Args, context: PlatformContext) -> anyhow::Result<()> {
+/// Announces the outcome on `training.finished`; webhooks are delivered during shutdown.
+fn publish_finished(context: &PlatformContext, job: &TrainingJob) {
+    context.events().publish(
+        Some(job.tenant.clone()),
+        EventPayload::TrainingFinished {
+            job_id: job.id.clone(),
+            status: job.status,
+            model_id: job.model_id.clone(),
+            error: job.error_message.clone(),
+        },
+    );
+}
+
+async fn simulate_training(args: &Args, context: PlatformContext) -> anyhow::Result<PathBuf> {
     use std::time::Duration;
     use tokio::io::AsyncWriteExt;
     use tokio::time::sleep;
@@ -101,12 +166,13 @@ async fn simulate_training(args: &Args, context: PlatformContext) -> anyhow::Res
     info!("Training completed! Saving adapter to: {}", args.output);
 
     tokio::fs::create_dir_all(&args.output).await?;
-    let adapter_path = format!("{}/adapter.safetensors", args.output);
+    let adapter_path = PathBuf::from(&args.output).join("adapter.safetensors");
     let mut file = tokio::fs::File::create(&adapter_path).await?;
     file.write_all(b"simulated_adapter_data").await?;
 
     context
         .audit_logger()
+        .for_tenant(&args.tenant)
         .log_admin_action(
             "trainer_system",
             "training_completed",
@@ -115,5 +181,5 @@ async fn simulate_training(args: &Args, context: PlatformContext) -> anyhow::Res
         )
         .map_err(|err| anyhow::anyhow!(err.to_string()))?;
 
-    Ok(())
+    Ok(adapter_path)
 }
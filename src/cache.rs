@@ -0,0 +1,480 @@
+//! Response cache in front of the inference engine
+//!
+//! Two tiers: an exact match on a SHA-256 of everything that determines the
+//! output (tenant, model, template, sampling parameters, response format and
+//! prompt), and an optional similarity tier that compares prompt embeddings
+//! among entries whose other inputs match exactly. Only deterministic
+//! requests (temperature 0) are cached unless the agent forces it.
+
+use parking_lot::{Mutex, RwLock};
+use ring::digest;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::inference::{InferenceRequest, InferenceResponse};
+
+/// Per-agent cache behaviour.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheMode {
+    /// Cache only requests with temperature 0.
+    #[default]
+    Auto,
+    Off,
+    /// Cache regardless of temperature.
+    Force,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSettings {
+    pub enabled: bool,
+    pub ttl_seconds: u64,
+    /// Oldest entries are evicted beyond this many.
+    pub max_entries: usize,
+    pub similarity: SimilaritySettings,
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_seconds: 3_600,
+            max_entries: 10_000,
+            similarity: SimilaritySettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimilaritySettings {
+    pub enabled: bool,
+    /// Minimum cosine similarity between prompt embeddings for a hit.
+    pub threshold: f32,
+    /// Size of the built-in hashing embedding.
+    pub dimensions: usize,
+}
+
+impl Default for SimilaritySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0.95,
+            dimensions: 512,
+        }
+    }
+}
+
+/// Turns a prompt into a vector for the similarity tier.
+pub trait Embedder: Send + Sync + fmt::Debug {
+    fn embed(&self, text: &str) -> Vec<f32>;
+}
+
+/// Local embedding without a model: lowercase words and character trigrams
+/// are hashed into a fixed number of buckets and L2-normalized.
+#[derive(Debug, Clone, Copy)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    fn bucket(&self, feature: &str) -> usize {
+        let hash = digest::digest(&digest::SHA256, feature.as_bytes());
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash.as_ref()[..8]);
+        (u64::from_le_bytes(bytes) % self.dimensions as u64) as usize
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        let text = text.to_lowercase();
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+        {
+            vector[self.bucket(word)] += 1.0;
+            let chars: Vec<char> = format!(" {} ", word).chars().collect();
+            for trigram in chars.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                vector[self.bucket(&trigram)] += 0.5;
+            }
+        }
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms =
+        a.iter().map(|v| v * v).sum::<f32>().sqrt() * b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheTier {
+    Exact,
+    Similar,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub exact_hits: u64,
+    pub similar_hits: u64,
+    pub misses: u64,
+    /// Requests not eligible for caching (disabled, opted out or sampled).
+    pub bypassed: u64,
+    pub evictions: u64,
+    pub entries: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    exact_hits: AtomicU64,
+    similar_hits: AtomicU64,
+    misses: AtomicU64,
+    bypassed: AtomicU64,
+    evictions: AtomicU64,
+}
+
+struct Entry {
+    /// Hash of every input except the prompt; similarity only compares within it.
+    context: String,
+    embedding: Option<Vec<f32>>,
+    response: InferenceResponse,
+    stored_at: Instant,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<String, Entry>,
+    order: VecDeque<String>,
+}
+
+/// Shared by every engine in the process; entries are isolated per tenant.
+#[derive(Clone)]
+pub struct ResponseCache {
+    settings: Arc<RwLock<CacheSettings>>,
+    embedder: Arc<dyn Embedder>,
+    entries: Arc<Mutex<Entries>>,
+    counters: Arc<Counters>,
+}
+
+impl fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("settings", &*self.settings.read())
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(CacheSettings::default())
+    }
+}
+
+impl ResponseCache {
+    pub fn new(settings: CacheSettings) -> Self {
+        let embedder = Arc::new(HashingEmbedder::new(settings.similarity.dimensions));
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            embedder,
+            entries: Arc::new(Mutex::new(Entries::default())),
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// Swap in new settings; entries past the new TTL or size are dropped lazily.
+    pub fn reconfigure(&self, settings: CacheSettings) {
+        *self.settings.write() = settings;
+    }
+
+    /// Whether `request` may be served from or stored in the cache.
+    pub fn is_eligible(&self, mode: CacheMode, request: &InferenceRequest) -> bool {
+        let eligible = self.settings.read().enabled
+            && match mode {
+                CacheMode::Off => false,
+                CacheMode::Auto => request.temperature == 0.0,
+                CacheMode::Force => true,
+            };
+        if !eligible {
+            self.counters.bypassed.fetch_add(1, Ordering::Relaxed);
+        }
+        eligible
+    }
+
+    pub fn lookup(&self, model: &str, request: &InferenceRequest) -> Option<InferenceResponse> {
+        let settings = self.settings.read().clone();
+        let ttl = Duration::from_secs(settings.ttl_seconds);
+        let (context, key) = cache_keys(model, request);
+        let mut entries = self.entries.lock();
+
+        let mut hit = match entries.by_key.get(&key) {
+            Some(entry) if entry.stored_at.elapsed() < ttl => {
+                Some((CacheTier::Exact, entry.response.clone()))
+            }
+            _ => None,
+        };
+        if hit.is_none() && settings.similarity.enabled {
+            let embedding = self.embedder.embed(&request.prompt);
+            hit = entries
+                .by_key
+                .values()
+                .filter(|entry| entry.context == context && entry.stored_at.elapsed() < ttl)
+                .filter_map(|entry| {
+                    let score = cosine_similarity(&embedding, entry.embedding.as_deref()?);
+                    (score >= settings.similarity.threshold).then_some((score, entry))
+                })
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, entry)| (CacheTier::Similar, entry.response.clone()));
+        }
+        self.evict(&mut entries, &settings);
+
+        let counter = match hit {
+            Some((CacheTier::Exact, _)) => &self.counters.exact_hits,
+            Some((CacheTier::Similar, _)) => &self.counters.similar_hits,
+            None => &self.counters.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        hit.map(|(tier, mut response)| {
+            // A hit costs no model tokens.
+            response.cache_hit = Some(tier);
+            response.usage = Default::default();
+            response.tokens_used = 0;
+            response.processing_time_ms = 0;
+            response
+        })
+    }
+
+    pub fn store(&self, model: &str, request: &InferenceRequest, response: &InferenceResponse) {
+        let settings = self.settings.read().clone();
+        let (context, key) = cache_keys(model, request);
+        let embedding = settings
+            .similarity
+            .enabled
+            .then(|| self.embedder.embed(&request.prompt));
+        let entry = Entry {
+            context,
+            embedding,
+            response: response.clone(),
+            stored_at: Instant::now(),
+        };
+
+        let mut entries = self.entries.lock();
+        if entries.by_key.insert(key.clone(), entry).is_some() {
+            entries.order.retain(|existing| existing != &key);
+        }
+        entries.order.push_back(key);
+        self.evict(&mut entries, &settings);
+    }
+
+    /// Drops expired entries from the front, then the oldest beyond `max_entries`.
+    fn evict(&self, entries: &mut Entries, settings: &CacheSettings) {
+        let ttl = Duration::from_secs(settings.ttl_seconds);
+        while let Some(oldest) = entries.order.front() {
+            let expired = entries
+                .by_key
+                .get(oldest)
+                .is_none_or(|entry| entry.stored_at.elapsed() >= ttl);
+            if !expired && entries.by_key.len() <= settings.max_entries {
+                break;
+            }
+            let oldest = entries.order.pop_front().expect("front exists");
+            entries.by_key.remove(&oldest);
+            self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            exact_hits: self.counters.exact_hits.load(Ordering::Relaxed),
+            similar_hits: self.counters.similar_hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            bypassed: self.counters.bypassed.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            entries: self.entries.lock().by_key.len() as u64,
+        }
+    }
+
+    /// Hit, miss and size series in Prometheus text format.
+    pub fn export_prometheus(&self) -> String {
+        let stats = self.stats();
+        format!(
+            "# TYPE chimera_cache_hits_total counter\n\
+             chimera_cache_hits_total{{tier=\"exact\"}} {}\n\
+             chimera_cache_hits_total{{tier=\"similar\"}} {}\n\
+             # TYPE chimera_cache_misses_total counter\n\
+             chimera_cache_misses_total {}\n\
+             # TYPE chimera_cache_bypassed_total counter\n\
+             chimera_cache_bypassed_total {}\n\
+             # TYPE chimera_cache_evictions_total counter\n\
+             chimera_cache_evictions_total {}\n\
+             # TYPE chimera_cache_entries gauge\n\
+             chimera_cache_entries {}\n",
+            stats.exact_hits,
+            stats.similar_hits,
+            stats.misses,
+            stats.bypassed,
+            stats.evictions,
+            stats.entries
+        )
+    }
+}
+
+/// Returns the context hash (every input but the prompt) and the exact key.
+fn cache_keys(model: &str, request: &InferenceRequest) -> (String, String) {
+    let context = serde_json::json!({
+        "tenant": request.tenant,
+        "model": model,
+        "template": request.template,
+        "max_tokens": request.max_tokens,
+        "temperature": request.temperature,
+        "top_p": request.top_p,
+        "repetition_penalty": request.repetition_penalty,
+        "response_format": request.response_format,
+    })
+    .to_string();
+    let context = hex(digest::digest(&digest::SHA256, context.as_bytes()).as_ref());
+
+    let mut exact = digest::Context::new(&digest::SHA256);
+    exact.update(context.as_bytes());
+    exact.update(request.prompt.as_bytes());
+    let key = hex(exact.finish().as_ref());
+    (context, key)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(prompt: &str, temperature: f32) -> InferenceRequest {
+        InferenceRequest {
+            prompt: prompt.to_string(),
+            max_tokens: 64,
+            temperature,
+            top_p: 1.0,
+            repetition_penalty: 1.0,
+            template: None,
+            response_format: None,
+            tenant: Some("search".to_string()),
+        }
+    }
+
+    fn response(text: &str) -> InferenceResponse {
+        InferenceResponse {
+            text: text.to_string(),
+            tokens_used: 10,
+            usage: Default::default(),
+            model: "base".to_string(),
+            processing_time_ms: 5,
+            confidence: 0.9,
+            template: None,
+            structured: None,
+            attempts: 1,
+            cache_hit: None,
+        }
+    }
+
+    #[test]
+    fn exact_hits_respect_tenant_and_eligibility() {
+        let cache = ResponseCache::default();
+        let faq = request("How do I reset my password?", 0.0);
+        assert!(cache.is_eligible(CacheMode::Auto, &faq));
+        assert!(!cache.is_eligible(CacheMode::Auto, &request("hi", 0.7)));
+        assert!(cache.is_eligible(CacheMode::Force, &request("hi", 0.7)));
+        assert!(!cache.is_eligible(CacheMode::Off, &faq));
+
+        assert!(cache.lookup("base", &faq).is_none());
+        cache.store("base", &faq, &response("Use the reset link."));
+        let hit = cache.lookup("base", &faq).unwrap();
+        assert_eq!(hit.cache_hit, Some(CacheTier::Exact));
+        assert_eq!(hit.tokens_used, 0);
+
+        let other_tenant = InferenceRequest {
+            tenant: Some("ads".to_string()),
+            ..faq.clone()
+        };
+        assert!(cache.lookup("base", &other_tenant).is_none());
+        assert!(cache.lookup("other-model", &faq).is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.exact_hits, stats.misses, stats.bypassed), (1, 3, 2));
+    }
+
+    #[test]
+    fn similar_prompts_hit_above_the_threshold() {
+        let cache = ResponseCache::new(CacheSettings {
+            similarity: SimilaritySettings {
+                enabled: true,
+                threshold: 0.8,
+                ..SimilaritySettings::default()
+            },
+            ..CacheSettings::default()
+        });
+        cache.store(
+            "base",
+            &request("How do I reset my password?", 0.0),
+            &response("Use the reset link."),
+        );
+
+        let hit = cache
+            .lookup("base", &request("how do I reset my password", 0.0))
+            .unwrap();
+        assert_eq!(hit.cache_hit, Some(CacheTier::Similar));
+        assert!(cache
+            .lookup(
+                "base",
+                &request("What is the refund policy for orders?", 0.0)
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn oldest_entries_are_evicted_beyond_capacity() {
+        let cache = ResponseCache::new(CacheSettings {
+            max_entries: 2,
+            ..CacheSettings::default()
+        });
+        for prompt in ["a", "b", "c"] {
+            cache.store("base", &request(prompt, 0.0), &response(prompt));
+        }
+        assert!(cache.lookup("base", &request("a", 0.0)).is_none());
+        assert!(cache.lookup("base", &request("c", 0.0)).is_some());
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+}
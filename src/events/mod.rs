@@ -0,0 +1,216 @@
+//! In-process event bus
+//!
+//! Lifecycle changes are published as typed [`Event`]s on a broadcast
+//! channel held by the platform context. Subscribers pick the topics they
+//! care about; the [`webhooks`] service forwards them to external URLs.
+
+pub mod webhooks;
+
+use crate::agents::AgentStatus;
+use crate::audit_logging::AuditSeverity;
+use crate::orchestration::TaskStatus;
+use crate::training::TrainingJobStatus;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Events buffered per subscriber before the slowest one starts missing them.
+const BUS_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+pub enum Topic {
+    #[serde(rename = "agent.status")]
+    AgentStatus,
+    #[serde(rename = "task.finished")]
+    TaskFinished,
+    #[serde(rename = "training.finished")]
+    TrainingFinished,
+    /// High and critical audit events.
+    #[serde(rename = "audit.alert")]
+    AuditAlert,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 4] = [
+        Topic::AgentStatus,
+        Topic::TaskFinished,
+        Topic::TrainingFinished,
+        Topic::AuditAlert,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::AgentStatus => "agent.status",
+            Topic::TaskFinished => "task.finished",
+            Topic::TrainingFinished => "training.finished",
+            Topic::AuditAlert => "audit.alert",
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventPayload {
+    AgentStatusChanged {
+        agent_id: String,
+        name: String,
+        from: AgentStatus,
+        to: AgentStatus,
+    },
+    TaskFinished {
+        task_id: String,
+        task_type: String,
+        status: TaskStatus,
+        error: Option<String>,
+    },
+    TrainingFinished {
+        job_id: String,
+        status: TrainingJobStatus,
+        model_id: Option<String>,
+        error: Option<String>,
+    },
+    AuditAlert {
+        audit_id: String,
+        event_type: String,
+        action: String,
+        resource: String,
+        user_id: Option<String>,
+        severity: AuditSeverity,
+    },
+}
+
+impl EventPayload {
+    pub fn topic(&self) -> Topic {
+        match self {
+            EventPayload::AgentStatusChanged { .. } => Topic::AgentStatus,
+            EventPayload::TaskFinished { .. } => Topic::TaskFinished,
+            EventPayload::TrainingFinished { .. } => Topic::TrainingFinished,
+            EventPayload::AuditAlert { .. } => Topic::AuditAlert,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub id: String,
+    pub timestamp: u64,
+    pub topic: Topic,
+    /// Tenant the event belongs to; `None` for platform-wide events.
+    pub tenant: Option<String>,
+    pub payload: EventPayload,
+}
+
+/// Broadcast channel shared through the platform context.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("subscribers", &self.sender.receiver_count())
+            .finish()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(BUS_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publishes `payload`; events nobody subscribes to are dropped.
+    pub fn publish(&self, tenant: Option<String>, payload: EventPayload) -> Event {
+        let event = Event {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp: crate::utils::timestamp_now(),
+            topic: payload.topic(),
+            tenant,
+            payload,
+        };
+        let _ = self.sender.send(event.clone());
+        event
+    }
+
+    /// Receives events on `topics` published from now on; every topic when empty.
+    pub fn subscribe(&self, topics: &[Topic]) -> EventSubscription {
+        EventSubscription {
+            receiver: self.sender.subscribe(),
+            topics: topics.to_vec(),
+        }
+    }
+}
+
+pub struct EventSubscription {
+    receiver: broadcast::Receiver<Event>,
+    topics: Vec<Topic>,
+}
+
+impl EventSubscription {
+    /// The next matching event, or `None` once the bus is gone. Events
+    /// missed by a lagging subscriber are skipped with a warning.
+    pub async fn recv(&mut self) -> Option<Event> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) if self.topics.is_empty() || self.topics.contains(&event.topic) => {
+                    return Some(event)
+                }
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "event subscriber lagged; events dropped");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribers_only_see_their_topics() {
+        let bus = EventBus::new();
+        let mut tasks = bus.subscribe(&[Topic::TaskFinished]);
+        let mut everything = bus.subscribe(&[]);
+
+        bus.publish(
+            None,
+            EventPayload::AgentStatusChanged {
+                agent_id: "a1".into(),
+                name: "support".into(),
+                from: AgentStatus::Idle,
+                to: AgentStatus::Maintenance,
+            },
+        );
+        let finished = bus.publish(
+            Some("search".into()),
+            EventPayload::TaskFinished {
+                task_id: "t1".into(),
+                task_type: "chat".into(),
+                status: TaskStatus::Completed,
+                error: None,
+            },
+        );
+
+        assert_eq!(tasks.recv().await.unwrap(), finished);
+        assert_eq!(everything.recv().await.unwrap().topic, Topic::AgentStatus);
+        assert_eq!(everything.recv().await.unwrap().topic, Topic::TaskFinished);
+    }
+}
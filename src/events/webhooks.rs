@@ -0,0 +1,556 @@
+//! Webhook delivery of platform events
+//!
+//! Subscriptions are registered through the `/webhooks` API and stored with
+//! the other platform state. Each matching event is POSTed as JSON, signed
+//! with the subscription's secret:
+//!
+//! `X-Chimera-Signature: sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`
+//!
+//! where the timestamp is sent in `X-Chimera-Timestamp`. Failed deliveries
+//! are retried with exponential backoff and every outcome is kept in an
+//! in-memory delivery log. Redirects are not followed, and URLs pointing at
+//! loopback, private or link-local addresses are refused at registration
+//! unless an admin allows them. Redirects are not followed, and URLs pointing at
+//! loopback, private or link-local addresses are refused at registration
+//! unless an admin allows them.
+
+use super::{Event, Topic};
+use crate::platform::service::ServiceRegistration;
+use crate::storage::WebhookRepository;
+use anyhow::{bail, Result};
+use parking_lot::{Mutex, RwLock};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// How long in-flight deliveries may finish their current attempt at shutdown.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookSettings {
+    pub enabled: bool,
+    /// Attempts per delivery, including the first.
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles on each further retry.
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Per-attempt request timeout.
+    pub timeout_ms: u64,
+    /// Delivery records kept for `/webhooks/{id}/deliveries`.
+    pub delivery_log_size: usize,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: 5,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 60_000,
+            timeout_ms: 5_000,
+            delivery_log_size: 1_000,
+        }
+    }
+}
+
+impl WebhookSettings {
+    /// Wait after failed attempt number `attempt` (1-based).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(20);
+        Duration::from_millis(
+            self.initial_backoff_ms
+                .saturating_mul(factor)
+                .min(self.max_backoff_ms),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Topics delivered to this webhook; every topic when empty.
+    pub topics: Vec<Topic>,
+    /// Only events of this tenant are delivered; all events when `None`.
+    pub tenant: Option<String>,
+    /// HMAC key for the signature; only returned when the webhook is created.
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    pub created_at: u64,
+}
+
+impl Webhook {
+    pub fn new(url: &str, topics: Vec<Topic>, tenant: Option<String>) -> Result<Self> {
+        let parsed = reqwest::Url::parse(url)?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            bail!(
+                "webhook URL must use http or https, not {}",
+                parsed.scheme()
+            );
+        }
+
+        let mut secret = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut secret)
+            .map_err(|_| anyhow::anyhow!("system random number generator failed"))?;
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: url.to_string(),
+            topics,
+            tenant,
+            secret: format!("whsec_{}", hex(&secret)),
+            created_at: crate::utils::timestamp_now(),
+        })
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.topics.is_empty() || self.topics.contains(&event.topic))
+            && self
+                .tenant
+                .as_ref()
+                .is_none_or(|tenant| event.tenant.as_ref() == Some(tenant))
+    }
+}
+
+/// Whether `ip` is only reachable from inside the deployment: loopback,
+/// private, link-local or unspecified.
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_internal(IpAddr::V4(ip)),
+            None => {
+                ip.is_loopback()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local()
+                    || ip.is_unspecified()
+            }
+        },
+    }
+}
+
+/// Fails if `url` names or resolves to an internal address. Names that do
+/// not resolve yet are let through; their deliveries fail until they do.
+async fn check_public(url: &reqwest::Url) -> Result<()> {
+    let Some(host) = url.host_str() else {
+        bail!("webhook URL has no host");
+    };
+    let port = url.port_or_known_default().unwrap_or_default();
+    let addresses: Vec<IpAddr> = match host.trim_matches(['[', ']']).parse() {
+        Ok(ip) => vec![ip],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map(|addresses| addresses.map(|address| address.ip()).collect())
+            .unwrap_or_default(),
+    };
+    if let Some(ip) = addresses.into_iter().find(|ip| is_internal(*ip)) {
+        bail!(
+            "webhook URL points at internal address {}; only admins may allow that",
+            ip
+        );
+    }
+    Ok(())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Value of `X-Chimera-Signature` for `body` sent at `timestamp`.
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(timestamp.to_string().as_bytes());
+    context.update(b".");
+    context.update(body);
+    format!("sha256={}", hex(context.sign().as_ref()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Delivered,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeliveryRecord {
+    pub webhook_id: String,
+    pub event_id: String,
+    pub topic: Topic,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// HTTP status of the last attempt, if it got a response.
+    pub response_status: Option<u16>,
+    pub error: Option<String>,
+    pub completed_at: u64,
+}
+
+/// Webhook subscriptions and their delivery log.
+#[derive(Clone)]
+pub struct WebhookRegistry {
+    settings: Arc<RwLock<WebhookSettings>>,
+    repository: Arc<dyn WebhookRepository>,
+    deliveries: Arc<Mutex<VecDeque<DeliveryRecord>>>,
+}
+
+impl fmt::Debug for WebhookRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookRegistry")
+            .field("settings", &*self.settings.read())
+            .field("deliveries", &self.deliveries.lock().len())
+            .finish()
+    }
+}
+
+impl WebhookRegistry {
+    pub fn new(settings: WebhookSettings, repository: Arc<dyn WebhookRepository>) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            repository,
+            deliveries: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    pub fn reconfigure(&self, settings: WebhookSettings) {
+        *self.settings.write() = settings;
+    }
+
+    pub fn settings(&self) -> WebhookSettings {
+        self.settings.read().clone()
+    }
+
+    /// Stores a new subscription; the returned webhook carries its secret.
+    /// Internal targets are refused unless `allow_internal` is set.
+    pub async fn register(
+        &self,
+        url: &str,
+        topics: Vec<Topic>,
+        tenant: Option<String>,
+        allow_internal: bool,
+    ) -> Result<Webhook> {
+        let webhook = Webhook::new(url, topics, tenant)?;
+        if !allow_internal {
+            check_public(&reqwest::Url::parse(&webhook.url)?).await?;
+        }
+        self.repository.save_webhook(&webhook).await?;
+        Ok(webhook)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Webhook>> {
+        self.repository.load_webhook(id).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<Webhook>> {
+        self.repository.load_webhooks().await
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<bool> {
+        self.repository.delete_webhook(id).await
+    }
+
+    fn record(&self, record: DeliveryRecord) {
+        let limit = self.settings.read().delivery_log_size;
+        let mut deliveries = self.deliveries.lock();
+        deliveries.push_back(record);
+        while deliveries.len() > limit {
+            deliveries.pop_front();
+        }
+    }
+
+    /// The newest `limit` deliveries to `webhook_id`, newest first.
+    pub fn deliveries(&self, webhook_id: &str, limit: usize) -> Vec<DeliveryRecord> {
+        self.deliveries
+            .lock()
+            .iter()
+            .rev()
+            .filter(|record| record.webhook_id == webhook_id)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+/// POSTs `event` to `webhook` until it is accepted or the attempts run out.
+async fn deliver(
+    client: reqwest::Client,
+    registry: WebhookRegistry,
+    webhook: Webhook,
+    event: Event,
+    shutdown: CancellationToken,
+) {
+    let settings = registry.settings();
+    let body = serde_json::to_vec(&event).expect("events serialize to JSON");
+    let mut attempts = 0;
+
+    let (status, response_status, error) = loop {
+        attempts += 1;
+        let timestamp = crate::utils::timestamp_now();
+        let outcome = client
+            .post(&webhook.url)
+            .timeout(Duration::from_millis(settings.timeout_ms))
+            .header("content-type", "application/json")
+            .header("x-chimera-event", event.topic.as_str())
+            .header("x-chimera-delivery", &event.id)
+            .header("x-chimera-timestamp", timestamp.to_string())
+            .header(
+                "x-chimera-signature",
+                sign(&webhook.secret, timestamp, &body),
+            )
+            .body(body.clone())
+            .send()
+            .await;
+        let (response_status, error) = match outcome {
+            Ok(response) if response.status().is_success() => {
+                break (
+                    DeliveryStatus::Delivered,
+                    Some(response.status().as_u16()),
+                    None,
+                );
+            }
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                format!("HTTP {}", response.status()),
+            ),
+            Err(err) => (None, err.to_string()),
+        };
+        if attempts >= settings.max_attempts {
+            break (DeliveryStatus::Failed, response_status, Some(error));
+        }
+        // Retries are abandoned at shutdown rather than holding the process up.
+        tokio::select! {
+            _ = shutdown.cancelled() => break (DeliveryStatus::Failed, response_status, Some(error)),
+            _ = tokio::time::sleep(settings.backoff(attempts)) => {}
+        }
+    };
+
+    match status {
+        DeliveryStatus::Delivered => {
+            debug!(webhook = %webhook.id, event = %event.id, attempts, "webhook delivered")
+        }
+        DeliveryStatus::Failed => warn!(
+            webhook = %webhook.id,
+            event = %event.id,
+            attempts,
+            error = error.as_deref().unwrap_or_default(),
+            "webhook delivery failed"
+        ),
+    }
+    registry.record(DeliveryRecord {
+        webhook_id: webhook.id,
+        event_id: event.id,
+        topic: event.topic,
+        status,
+        attempts,
+        response_status,
+        error,
+        completed_at: crate::utils::timestamp_now(),
+    });
+}
+
+/// Forwards events from the bus to every matching webhook.
+pub fn webhook_service() -> ServiceRegistration {
+    ServiceRegistration::new(
+        "webhooks",
+        Arc::new(|context, token| {
+            // Subscribe before spawning so events published during boot are kept.
+            let mut events = context.events().subscribe(&[]);
+            tokio::spawn(async move {
+                let registry = context.webhooks();
+                // A redirect could send the signed event somewhere internal.
+                let client = reqwest::Client::builder()
+                    .redirect(reqwest::redirect::Policy::none())
+                    .build()?;
+                let mut deliveries = JoinSet::new();
+
+                loop {
+                    // Events already published are dispatched before stopping.
+                    let event = tokio::select! {
+                        biased;
+                        event = events.recv() => match event {
+                            Some(event) => event,
+                            None => break,
+                        },
+                        Some(_) = deliveries.join_next(), if !deliveries.is_empty() => continue,
+                        _ = token.cancelled() => break,
+                    };
+                    if !registry.settings().enabled {
+                        continue;
+                    }
+                    let webhooks = match registry.list().await {
+                        Ok(webhooks) => webhooks,
+                        Err(err) => {
+                            warn!(error = %format!("{:#}", err), "failed to load webhooks");
+                            continue;
+                        }
+                    };
+                    for webhook in webhooks.into_iter().filter(|w| w.matches(&event)) {
+                        deliveries.spawn(deliver(
+                            client.clone(),
+                            registry.clone(),
+                            webhook,
+                            event.clone(),
+                            token.clone(),
+                        ));
+                    }
+                }
+
+                if !deliveries.is_empty() {
+                    info!(pending = deliveries.len(), "waiting for webhook deliveries");
+                    let drained = async { while deliveries.join_next().await.is_some() {} };
+                    if tokio::time::timeout(SHUTDOWN_GRACE, drained).await.is_err() {
+                        deliveries.abort_all();
+                    }
+                }
+                Ok(())
+            })
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{EventBus, EventPayload};
+    use crate::orchestration::TaskStatus;
+    use crate::storage::InMemoryRepository;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::Router;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let settings = WebhookSettings {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 350,
+            ..WebhookSettings::default()
+        };
+        assert_eq!(settings.backoff(1), Duration::from_millis(100));
+        assert_eq!(settings.backoff(2), Duration::from_millis(200));
+        assert_eq!(settings.backoff(3), Duration::from_millis(350));
+        assert_eq!(settings.backoff(60), Duration::from_millis(350));
+    }
+
+    #[tokio::test]
+    async fn internal_targets_need_to_be_allowed() {
+        let registry = WebhookRegistry::new(
+            WebhookSettings::default(),
+            Arc::new(InMemoryRepository::new()),
+        );
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://10.1.2.3/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[fe80::1]/hook",
+            "http://[::ffff:192.168.0.1]/hook",
+        ] {
+            assert!(
+                registry.register(url, vec![], None, false).await.is_err(),
+                "{}",
+                url
+            );
+        }
+        registry
+            .register("http://93.184.215.14/hook", vec![], None, false)
+            .await
+            .unwrap();
+        registry
+            .register("http://127.0.0.1:8080/hook", vec![], None, true)
+            .await
+            .unwrap();
+        assert_eq!(registry.list().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_and_signed() {
+        let received = Arc::new(Mutex::new(Vec::<(HeaderMap, Vec<u8>)>::new()));
+        let sink = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: axum::body::Bytes| {
+                let sink = sink.clone();
+                async move {
+                    let mut received = sink.lock();
+                    received.push((headers, body.to_vec()));
+                    // Reject the first attempt to exercise the retry.
+                    if received.len() == 1 {
+                        axum::http::StatusCode::BAD_GATEWAY
+                    } else {
+                        axum::http::StatusCode::NO_CONTENT
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let registry = WebhookRegistry::new(
+            WebhookSettings {
+                initial_backoff_ms: 10,
+                ..WebhookSettings::default()
+            },
+            Arc::new(InMemoryRepository::new()),
+        );
+        let webhook = registry
+            .register(&url, vec![Topic::TaskFinished], Some("search".into()), true)
+            .await
+            .unwrap();
+        let event = EventBus::new().publish(
+            Some("search".into()),
+            EventPayload::TaskFinished {
+                task_id: "t1".into(),
+                task_type: "chat".into(),
+                status: TaskStatus::Completed,
+                error: None,
+            },
+        );
+        assert!(webhook.matches(&event));
+        assert!(!webhook.matches(&Event {
+            tenant: Some("ads".into()),
+            ..event.clone()
+        }));
+
+        deliver(
+            reqwest::Client::new(),
+            registry.clone(),
+            webhook.clone(),
+            event,
+            CancellationToken::new(),
+        )
+        .await;
+
+        let log = registry.deliveries(&webhook.id, 10);
+        assert_eq!(log[0].status, DeliveryStatus::Delivered);
+        assert_eq!(log[0].attempts, 2);
+        let received = received.lock();
+        let (headers, body) = &received[1];
+        let timestamp: u64 = headers["x-chimera-timestamp"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            headers["x-chimera-signature"].to_str().unwrap(),
+            sign(&webhook.secret, timestamp, body)
+        );
+    }
+}
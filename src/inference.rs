@@ -4,13 +4,30 @@
 //! the rest of the platform exercising request/response flows without
 //! requiring heavyweight ML dependencies.
 
+use crate::cache::{CacheMode, CacheTier, ResponseCache};
+use crate::metering::{ApproximateTokenizer, TokenUsage, Tokenizer};
+use crate::prompts::TemplateRef;
+use crate::utils::{validate_schema, ValidationError};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct InferenceEngine {
     model_name: Option<String>,
+    decoder: Arc<dyn Decoder>,
+    tokenizer: Arc<dyn Tokenizer>,
+    cache: Option<(ResponseCache, CacheMode)>,
+}
+
+impl Default for InferenceEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,19 +37,169 @@ pub struct InferenceRequest {
     pub temperature: f32,
     pub top_p: f32,
     pub repetition_penalty: f32,
+    /// Template the prompt was rendered from, echoed into the response.
+    #[serde(default)]
+    pub template: Option<TemplateRef>,
+    /// Requires the output to be JSON matching a schema.
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+    /// Tenant the request is made for, used for accounting.
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseFormat {
+    /// JSON Schema the output must satisfy.
+    pub schema: Value,
+    /// Re-prompts allowed after the first invalid output.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+impl ResponseFormat {
+    pub fn new(schema: Value) -> Self {
+        Self {
+            schema,
+            max_retries: default_max_retries(),
+        }
+    }
+}
+
+fn default_max_retries() -> u32 {
+    2
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InferenceResponse {
     pub text: String,
+    /// Prompt plus completion tokens; see `usage` for the split.
     pub tokens_used: usize,
+    #[serde(default)]
+    pub usage: TokenUsage,
+    /// Name of the model that produced the text.
+    #[serde(default)]
+    pub model: String,
     pub processing_time_ms: u64,
     pub confidence: f32,
+    pub template: Option<TemplateRef>,
+    /// Parsed output when the request carried a `response_format`.
+    #[serde(default)]
+    pub structured: Option<Value>,
+    /// Decoding attempts it took, including re-prompts.
+    #[serde(default = "default_attempts")]
+    pub attempts: u32,
+    /// Set when the response was served from the cache.
+    #[serde(default)]
+    pub cache_hit: Option<CacheTier>,
+}
+
+fn default_attempts() -> u32 {
+    1
+}
+
+#[derive(Debug)]
+pub enum InferenceError {
+    ModelNotLoaded,
+    InvalidSchema(String),
+    Decoding(String),
+    /// Every attempt produced output that failed the response format.
+    StructuredOutput {
+        attempts: u32,
+        errors: Vec<ValidationError>,
+        output: String,
+    },
+}
+
+impl fmt::Display for InferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InferenceError::ModelNotLoaded => write!(f, "model not loaded"),
+            InferenceError::InvalidSchema(reason) => {
+                write!(f, "invalid response schema: {}", reason)
+            }
+            InferenceError::Decoding(reason) => write!(f, "decoding failed: {}", reason),
+            InferenceError::StructuredOutput {
+                attempts, errors, ..
+            } => write!(
+                f,
+                "output did not match the response schema after {} attempts: {}",
+                attempts,
+                describe(errors)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InferenceError {}
+
+/// Text generation backend behind the engine.
+///
+/// Backends that can constrain sampling to a grammar derived from
+/// `request.response_format` report it through `constrains_output`; the
+/// engine then skips the schema instructions but still validates the result.
+#[async_trait]
+pub trait Decoder: Send + Sync + fmt::Debug {
+    async fn decode(
+        &self,
+        model: &str,
+        prompt: &str,
+        request: &InferenceRequest,
+    ) -> Result<String, InferenceError>;
+
+    fn constrains_output(&self) -> bool {
+        false
+    }
+}
+
+/// Stand-in backend: echoes the prompt, or emits a minimal instance of the
+/// requested schema.
+#[derive(Debug, Default)]
+pub struct MockDecoder;
+
+#[async_trait]
+impl Decoder for MockDecoder {
+    async fn decode(
+        &self,
+        model: &str,
+        prompt: &str,
+        request: &InferenceRequest,
+    ) -> Result<String, InferenceError> {
+        Ok(match &request.response_format {
+            Some(format) => sample_instance(&format.schema).to_string(),
+            None => format!("[{model}] Responding to: {prompt}"),
+        })
+    }
+
+    fn constrains_output(&self) -> bool {
+        true
+    }
 }
 
 impl InferenceEngine {
     pub fn new() -> Self {
-        Self { model_name: None }
+        Self {
+            model_name: None,
+            decoder: Arc::new(MockDecoder),
+            tokenizer: Arc::new(ApproximateTokenizer),
+            cache: None,
+        }
+    }
+
+    pub fn with_decoder(mut self, decoder: Arc<dyn Decoder>) -> Self {
+        self.decoder = decoder;
+        self
+    }
+
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Serves repeated requests from `cache`, subject to the agent's `mode`.
+    pub fn with_cache(mut self, cache: ResponseCache, mode: CacheMode) -> Self {
+        self.cache = Some((cache, mode));
+        self
     }
 
     pub fn load_model<P: AsRef<Path>>(
@@ -53,31 +220,204 @@ impl InferenceEngine {
     pub async fn generate(
         &self,
         request: InferenceRequest,
-    ) -> Result<InferenceResponse, Box<dyn std::error::Error>> {
+    ) -> Result<InferenceResponse, InferenceError> {
         let model_name = self
             .model_name
             .as_ref()
-            .ok_or_else(|| "model not loaded".to_string())?;
+            .ok_or(InferenceError::ModelNotLoaded)?;
 
-        let start = Instant::now();
-        let prompt_token_count = request.prompt.split_whitespace().count().max(1);
-        let simulated_output_tokens = request.max_tokens.min(128);
+        let cache = self
+            .cache
+            .as_ref()
+            .filter(|(cache, mode)| cache.is_eligible(*mode, &request))
+            .map(|(cache, _)| cache);
+        if let Some(hit) = cache.and_then(|cache| cache.lookup(model_name, &request)) {
+            return Ok(hit);
+        }
 
-        let response_text = format!(
-            "[{model}] Responding to: {prompt}",
-            model = model_name,
-            prompt = request.prompt
-        );
+        let start = Instant::now();
+        let (text, structured, attempts, usage) = match &request.response_format {
+            None => {
+                let text = self
+                    .decoder
+                    .decode(model_name, &request.prompt, &request)
+                    .await?;
+                let usage = self.usage(&request.prompt, &text);
+                (text, None, 1, usage)
+            }
+            Some(format) => {
+                let (text, value, attempts, usage) = self
+                    .generate_structured(model_name, &request, format)
+                    .await?;
+                (text, Some(value), attempts, usage)
+            }
+        };
 
         let processing_time_ms = start.elapsed().as_millis() as u64;
         let confidence = estimate_confidence(&request);
 
-        Ok(InferenceResponse {
-            text: response_text,
-            tokens_used: prompt_token_count + simulated_output_tokens,
+        let response = InferenceResponse {
+            text,
+            tokens_used: usage.total(),
+            usage,
+            model: model_name.clone(),
             processing_time_ms,
             confidence,
-        })
+            template: request.template.clone(),
+            structured,
+            attempts,
+            cache_hit: None,
+        };
+        if let Some(cache) = cache {
+            cache.store(model_name, &request, &response);
+        }
+        Ok(response)
+    }
+
+    fn usage(&self, prompt: &str, completion: &str) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.tokenizer.count(prompt),
+            completion_tokens: self.tokenizer.count(completion),
+        }
+    }
+
+    /// Decodes until the output parses and validates, feeding the errors back
+    /// into the prompt between attempts. Usage covers every attempt.
+    async fn generate_structured(
+        &self,
+        model_name: &str,
+        request: &InferenceRequest,
+        format: &ResponseFormat,
+    ) -> Result<(String, Value, u32, TokenUsage), InferenceError> {
+        if !(format.schema.is_object() || format.schema.is_boolean()) {
+            return Err(InferenceError::InvalidSchema(
+                "schema must be a JSON object".to_string(),
+            ));
+        }
+
+        let mut prompt = request.prompt.clone();
+        if !self.decoder.constrains_output() {
+            prompt.push_str(&format!(
+                "\n\nRespond with only a JSON value matching this JSON Schema:\n{}",
+                format.schema
+            ));
+        }
+
+        let max_attempts = format.max_retries + 1;
+        let mut attempt = 0;
+        let mut usage = TokenUsage::default();
+        loop {
+            attempt += 1;
+            let output = self.decoder.decode(model_name, &prompt, request).await?;
+            usage += self.usage(&prompt, &output);
+            let errors = match parse_json_output(&output) {
+                Ok(value) => match validate_schema(&format.schema, &value) {
+                    Ok(()) => return Ok((output, value, attempt, usage)),
+                    Err(errors) => errors,
+                },
+                Err(error) => vec![error],
+            };
+
+            if attempt >= max_attempts {
+                return Err(InferenceError::StructuredOutput {
+                    attempts: attempt,
+                    errors,
+                    output,
+                });
+            }
+            prompt.push_str(&format!(
+                "\n\nYour previous reply was rejected ({}). Reply again with only the corrected JSON.",
+                describe(&errors)
+            ));
+        }
+    }
+}
+
+/// Parses model output as JSON, tolerating a surrounding Markdown code fence.
+fn parse_json_output(output: &str) -> Result<Value, ValidationError> {
+    let body = output
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim();
+    serde_json::from_str(body).map_err(|e| ValidationError {
+        field: "$".to_string(),
+        message: format!("Invalid JSON: {}", e),
+    })
+}
+
+fn describe(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Smallest value that satisfies the common schema keywords.
+fn sample_instance(schema: &Value) -> Value {
+    if let Some(value) = schema.get("const") {
+        return value.clone();
+    }
+    if let Some(first) = schema
+        .get("enum")
+        .and_then(Value::as_array)
+        .and_then(|v| v.first())
+    {
+        return first.clone();
+    }
+    if let Some(first) = ["anyOf", "oneOf", "allOf"]
+        .iter()
+        .find_map(|k| schema.get(*k).and_then(Value::as_array)?.first())
+    {
+        return sample_instance(first);
+    }
+
+    let kind = match schema.get("type") {
+        Some(Value::String(kind)) => kind.as_str(),
+        Some(Value::Array(kinds)) => kinds.first().and_then(Value::as_str).unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ => "null",
+    };
+    match kind {
+        "object" => {
+            let mut object = serde_json::Map::new();
+            let properties = schema.get("properties");
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                let property = properties.and_then(|p| p.get(name)).unwrap_or(&Value::Null);
+                object.insert(name.to_string(), sample_instance(property));
+            }
+            Value::Object(object)
+        }
+        "array" => {
+            let count = schema.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+            let item = schema
+                .get("items")
+                .map(sample_instance)
+                .unwrap_or(Value::Null);
+            Value::Array(vec![item; count as usize])
+        }
+        "string" => {
+            let length = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0);
+            Value::String("x".repeat(length as usize))
+        }
+        "integer" | "number" => {
+            let minimum = schema.get("minimum").and_then(Value::as_f64).unwrap_or(0.0);
+            if kind == "integer" {
+                Value::from(minimum.ceil() as i64)
+            } else {
+                Value::from(minimum)
+            }
+        }
+        "boolean" => Value::Bool(false),
+        _ => Value::Null,
     }
 }
 
@@ -102,12 +442,112 @@ mod tests {
             temperature: 0.7,
             top_p: 0.9,
             repetition_penalty: 1.0,
+            template: None,
+            response_format: None,
+            tenant: None,
         };
 
         let response = engine.generate(request).await.unwrap();
 
         assert!(response.text.contains("Hello, world"));
-        assert!(response.tokens_used > 0);
+        assert_eq!(response.model, "test-model");
+        assert!(response.usage.prompt_tokens >= 4);
+        assert!(response.usage.completion_tokens > response.usage.prompt_tokens);
+        assert_eq!(response.tokens_used, response.usage.total());
         assert!(response.confidence >= 0.0 && response.confidence <= 1.0);
     }
+
+    /// Returns canned replies in order, like a model that needs correcting.
+    #[derive(Debug)]
+    struct ScriptedDecoder(parking_lot::Mutex<Vec<&'static str>>);
+
+    #[async_trait]
+    impl Decoder for ScriptedDecoder {
+        async fn decode(
+            &self,
+            _model: &str,
+            prompt: &str,
+            _request: &InferenceRequest,
+        ) -> Result<String, InferenceError> {
+            let mut replies = self.0.lock();
+            if replies.len() < 3 {
+                assert!(prompt.contains("$.city: is required"));
+            }
+            Ok(replies.remove(0).to_string())
+        }
+    }
+
+    fn structured_request(max_retries: u32) -> InferenceRequest {
+        InferenceRequest {
+            prompt: "Where?".to_string(),
+            max_tokens: 32,
+            temperature: 0.0,
+            top_p: 1.0,
+            repetition_penalty: 1.0,
+            template: None,
+            response_format: Some(ResponseFormat {
+                schema: serde_json::json!({
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } },
+                    "required": ["city"]
+                }),
+                max_retries,
+            }),
+            tenant: None,
+        }
+    }
+
+    fn scripted_engine() -> InferenceEngine {
+        let decoder = ScriptedDecoder(parking_lot::Mutex::new(vec![
+            "{}",
+            "not json",
+            "```json\n{\"city\": \"Oslo\"}\n```",
+        ]));
+        let mut engine = InferenceEngine::new().with_decoder(Arc::new(decoder));
+        engine.load_model("models/test-model").unwrap();
+        engine
+    }
+
+    #[tokio::test]
+    async fn invalid_output_is_reprompted_until_it_matches() {
+        let response = scripted_engine()
+            .generate(structured_request(2))
+            .await
+            .unwrap();
+
+        assert_eq!(response.attempts, 3);
+        assert_eq!(
+            response.structured,
+            Some(serde_json::json!({ "city": "Oslo" }))
+        );
+        // Every re-prompt is billed, including the growing correction text.
+        assert!(response.usage.prompt_tokens > 3 * ApproximateTokenizer.count("Where?"));
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_return_the_validation_errors() {
+        let err = scripted_engine()
+            .generate(structured_request(1))
+            .await
+            .unwrap_err();
+
+        match err {
+            InferenceError::StructuredOutput {
+                attempts, output, ..
+            } => {
+                assert_eq!(attempts, 2);
+                assert_eq!(output, "not json");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_decoder_emits_schema_conforming_output() {
+        let mut engine = InferenceEngine::new();
+        engine.load_model("models/test-model").unwrap();
+
+        let response = engine.generate(structured_request(0)).await.unwrap();
+        assert_eq!(response.structured, Some(serde_json::json!({ "city": "" })));
+    }
 }
@@ -0,0 +1,745 @@
+//! # Alerting
+//!
+//! Alert lifecycle for the system monitor, modelled on Alertmanager. Observations of a condition
+//! are deduplicated by a fingerprint of their labels into one alert, which moves from pending to
+//! firing once the condition has persisted for `pending_seconds` and resolves only after it has
+//! stayed clear for `resolve_seconds`, so a flapping check does not page on every transition.
+//!
+//! Alerts are grouped by the `group_by` labels and each group is notified as a whole when one of
+//! its alerts fires, resolves or escalates, and again every `repeat_interval_seconds` while it
+//! keeps firing. Silences mute matching alerts for a fixed window, and firing alerts left
+//! unacknowledged for `escalate_after_seconds` have their severity raised. Notifications are
+//! delivered by pluggable [`Notifier`]s; log, webhook and file sinks are built in.
+
+use crate::types::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tracing::{info, warn};
+
+/// A condition reported by a check, to be turned into an alert
+#[derive(Debug, Clone)]
+pub struct AlertObservation {
+    /// Identify the alert; observations with the same labels update the same alert
+    pub labels: BTreeMap<String, String>,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub description: String,
+    pub system_id: Option<SystemId>,
+}
+
+/// Stable hash of a label set (64-bit FNV-1a, hex encoded)
+pub fn fingerprint(labels: &BTreeMap<String, String>) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (key, value) in labels {
+        for byte in key.bytes().chain([0]).chain(value.bytes()).chain([0]) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+fn raise(severity: &AlertSeverity) -> AlertSeverity {
+    match severity {
+        AlertSeverity::Info => AlertSeverity::Warning,
+        AlertSeverity::Warning => AlertSeverity::Error,
+        AlertSeverity::Error | AlertSeverity::Critical => AlertSeverity::Critical,
+    }
+}
+
+/// Alerts of one group, sent together
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertNotification {
+    pub group_key: String,
+    pub group_labels: BTreeMap<String, String>,
+    /// Firing while any alert in the group fires
+    pub status: AlertState,
+    pub alerts: Vec<Alert>,
+    pub sent_at: DateTime<Utc>,
+}
+
+/// Bookkeeping kept next to each alert
+struct Tracked {
+    alert: Alert,
+    /// Severity last observed, before escalation
+    observed_severity: AlertSeverity,
+    /// When the condition was last reported clear, while firing
+    cleared_at: Option<DateTime<Utc>>,
+    escalated_at: Option<DateTime<Utc>>,
+    /// A transition happened that the alert's group has not been told about
+    needs_notify: bool,
+}
+
+impl Tracked {
+    fn promote(&mut self, pending: Duration, now: DateTime<Utc>) {
+        if self.alert.state == AlertState::Pending && now - self.alert.timestamp >= pending {
+            self.alert.state = AlertState::Firing;
+            self.alert.fired_at = Some(now);
+            self.needs_notify = true;
+        }
+    }
+
+    fn severity(&self) -> AlertSeverity {
+        (0..self.alert.escalations).fold(self.observed_severity.clone(), |severity, _| raise(&severity))
+    }
+}
+
+/// Deduplicates, groups, silences and escalates alerts
+pub struct AlertManager {
+    config: AlertingConfig,
+    alerts: HashMap<String, Tracked>,
+    silences: Vec<Silence>,
+    /// Last notification per group
+    groups: HashMap<String, DateTime<Utc>>,
+}
+
+impl AlertManager {
+    pub fn new(config: AlertingConfig) -> Self {
+        Self {
+            config,
+            alerts: HashMap::new(),
+            silences: Vec::new(),
+            groups: HashMap::new(),
+        }
+    }
+
+    fn pending(&self) -> Duration {
+        Duration::seconds(self.config.pending_seconds as i64)
+    }
+
+    /// Record that a condition holds, returning the fingerprint of its alert
+    pub fn observe(&mut self, observation: AlertObservation, now: DateTime<Utc>) -> String {
+        let fingerprint = fingerprint(&observation.labels);
+        let pending = self.pending();
+        match self.alerts.get_mut(&fingerprint) {
+            Some(tracked) if tracked.alert.state != AlertState::Resolved => {
+                tracked.cleared_at = None;
+                tracked.observed_severity = observation.severity;
+                let severity = tracked.severity();
+                if severity != tracked.alert.severity && tracked.alert.state == AlertState::Firing {
+                    tracked.needs_notify = true;
+                }
+                let alert = &mut tracked.alert;
+                alert.severity = severity;
+                alert.title = observation.title;
+                alert.description = observation.description;
+                alert.system_id = observation.system_id;
+                alert.occurrences += 1;
+                tracked.promote(pending, now);
+            }
+            _ => {
+                let mut tracked = Tracked {
+                    alert: Alert {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        severity: observation.severity.clone(),
+                        title: observation.title,
+                        description: observation.description,
+                        system_id: observation.system_id,
+                        timestamp: now,
+                        acknowledged: false,
+                        acknowledged_by: None,
+                        acknowledged_at: None,
+                        labels: observation.labels,
+                        fingerprint: fingerprint.clone(),
+                        state: AlertState::Pending,
+                        fired_at: None,
+                        resolved_at: None,
+                        occurrences: 1,
+                        escalations: 0,
+                        silenced_by: None,
+                    },
+                    observed_severity: observation.severity,
+                    cleared_at: None,
+                    escalated_at: None,
+                    needs_notify: false,
+                };
+                tracked.promote(pending, now);
+                self.alerts.insert(fingerprint.clone(), tracked);
+            }
+        }
+        fingerprint
+    }
+
+    /// Record that the condition behind an alert no longer holds. A pending alert is dropped;
+    /// a firing one resolves once the condition has stayed clear for `resolve_seconds`.
+    pub fn clear(&mut self, fingerprint: &str, now: DateTime<Utc>) -> bool {
+        match self.alerts.get(fingerprint).map(|tracked| tracked.alert.state) {
+            Some(AlertState::Pending) => {
+                self.alerts.remove(fingerprint);
+                true
+            }
+            Some(AlertState::Firing) => {
+                if let Some(tracked) = self.alerts.get_mut(fingerprint) {
+                    tracked.cleared_at.get_or_insert(now);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Acknowledge an alert by id or fingerprint, stopping its escalation
+    pub fn acknowledge(&mut self, alert: &str, acknowledged_by: String, now: DateTime<Utc>) -> bool {
+        let Some(tracked) = self
+            .alerts
+            .values_mut()
+            .find(|tracked| tracked.alert.id == alert || tracked.alert.fingerprint == alert)
+        else {
+            return false;
+        };
+        tracked.alert.acknowledged = true;
+        tracked.alert.acknowledged_by = Some(acknowledged_by);
+        tracked.alert.acknowledged_at = Some(now);
+        true
+    }
+
+    /// Mute notifications for alerts carrying all of `matchers` between `starts_at` and `ends_at`
+    pub fn silence(
+        &mut self,
+        matchers: BTreeMap<String, String>,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        created_by: String,
+        comment: String,
+    ) -> Result<String, DiscoveryError> {
+        if matchers.is_empty() {
+            return Err(DiscoveryError::ValidationError(
+                "A silence needs at least one matcher".to_string(),
+            ));
+        }
+        if ends_at <= starts_at {
+            return Err(DiscoveryError::ValidationError(
+                "A silence must end after it starts".to_string(),
+            ));
+        }
+        let id = uuid::Uuid::new_v4().to_string();
+        info!("Silenced {:?} until {} by {}: {}", matchers, ends_at, created_by, comment);
+        self.silences.push(Silence {
+            id: id.clone(),
+            matchers,
+            starts_at,
+            ends_at,
+            created_by,
+            comment,
+        });
+        Ok(id)
+    }
+
+    /// End a silence early
+    pub fn expire_silence(&mut self, id: &str, now: DateTime<Utc>) -> bool {
+        match self.silences.iter_mut().find(|silence| silence.id == id && silence.ends_at > now) {
+            Some(silence) => {
+                silence.ends_at = now.max(silence.starts_at);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Silences that have not ended yet
+    pub fn silences(&self, now: DateTime<Utc>) -> Vec<&Silence> {
+        self.silences.iter().filter(|silence| silence.ends_at > now).collect()
+    }
+
+    /// Pending and firing alerts, oldest first
+    pub fn alerts(&self) -> Vec<Alert> {
+        let mut alerts: Vec<Alert> = self
+            .alerts
+            .values()
+            .filter(|tracked| tracked.alert.state != AlertState::Resolved)
+            .map(|tracked| tracked.alert.clone())
+            .collect();
+        alerts.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.fingerprint.cmp(&b.fingerprint)));
+        alerts
+    }
+
+    pub fn get(&self, fingerprint: &str) -> Option<&Alert> {
+        self.alerts.get(fingerprint).map(|tracked| &tracked.alert)
+    }
+
+    /// Firing alerts that are not silenced
+    pub fn firing_count(&self) -> usize {
+        self.alerts
+            .values()
+            .filter(|tracked| tracked.alert.state == AlertState::Firing && tracked.alert.silenced_by.is_none())
+            .count()
+    }
+
+    /// Key and labels of the group an alert belongs to
+    fn group_of(&self, alert: &Alert) -> (String, BTreeMap<String, String>) {
+        let labels: BTreeMap<String, String> = self
+            .config
+            .group_by
+            .iter()
+            .map(|label| (label.clone(), alert.labels.get(label).cloned().unwrap_or_default()))
+            .collect();
+        let key = labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(",");
+        (key, labels)
+    }
+
+    /// Advance every alert to `now` and return the group notifications that are due
+    pub fn evaluate(&mut self, now: DateTime<Utc>) -> Vec<AlertNotification> {
+        let pending = self.pending();
+        let resolve = Duration::seconds(self.config.resolve_seconds as i64);
+        let escalate_after = self.config.escalate_after_seconds.map(|seconds| Duration::seconds(seconds as i64));
+        self.silences.retain(|silence| silence.ends_at > now);
+
+        for tracked in self.alerts.values_mut() {
+            tracked.promote(pending, now);
+            if tracked.alert.state != AlertState::Firing {
+                continue;
+            }
+
+            if tracked.cleared_at.is_some_and(|cleared_at| now - cleared_at >= resolve) {
+                tracked.alert.state = AlertState::Resolved;
+                tracked.alert.resolved_at = Some(now);
+                tracked.needs_notify = true;
+                continue;
+            }
+
+            let silenced_by = self
+                .silences
+                .iter()
+                .find(|silence| silence.is_active(now) && silence.matches(&tracked.alert.labels))
+                .map(|silence| silence.id.clone());
+            if tracked.alert.silenced_by.is_some() && silenced_by.is_none() {
+                // Still firing when the silence ended
+                tracked.needs_notify = true;
+            }
+            tracked.alert.silenced_by = silenced_by;
+
+            if let Some(escalate_after) = escalate_after {
+                let since = tracked.escalated_at.or(tracked.alert.fired_at).unwrap_or(now);
+                if !tracked.alert.acknowledged
+                    && tracked.alert.silenced_by.is_none()
+                    && tracked.alert.severity != AlertSeverity::Critical
+                    && now - since >= escalate_after
+                {
+                    tracked.alert.escalations += 1;
+                    tracked.alert.severity = tracked.severity();
+                    tracked.escalated_at = Some(now);
+                    tracked.needs_notify = true;
+                    warn!(
+                        "Escalated unacknowledged alert {} to {:?}",
+                        tracked.alert.title, tracked.alert.severity
+                    );
+                }
+            }
+        }
+
+        let mut groups: BTreeMap<String, (BTreeMap<String, String>, Vec<String>)> = BTreeMap::new();
+        for (fingerprint, tracked) in &self.alerts {
+            if tracked.alert.state == AlertState::Pending || tracked.alert.silenced_by.is_some() {
+                continue;
+            }
+            let (key, labels) = self.group_of(&tracked.alert);
+            groups
+                .entry(key)
+                .or_insert_with(|| (labels, Vec::new()))
+                .1
+                .push(fingerprint.clone());
+        }
+
+        let repeat = Duration::seconds(self.config.repeat_interval_seconds as i64);
+        let mut notifications = Vec::new();
+        for (group_key, (group_labels, mut fingerprints)) in groups {
+            let members: Vec<&Tracked> = fingerprints.iter().map(|fingerprint| &self.alerts[fingerprint]).collect();
+            let changed = members.iter().any(|tracked| tracked.needs_notify);
+            let repeat_due = members
+                .iter()
+                .any(|tracked| tracked.alert.state == AlertState::Firing && !tracked.alert.acknowledged)
+                && self.groups.get(&group_key).is_none_or(|sent| now - *sent >= repeat);
+            if !changed && !repeat_due {
+                continue;
+            }
+
+            fingerprints.sort();
+            let alerts: Vec<Alert> = fingerprints.iter().map(|fingerprint| self.alerts[fingerprint].alert.clone()).collect();
+            let status = if alerts.iter().any(|alert| alert.state == AlertState::Firing) {
+                AlertState::Firing
+            } else {
+                AlertState::Resolved
+            };
+            for fingerprint in &fingerprints {
+                if let Some(tracked) = self.alerts.get_mut(fingerprint) {
+                    tracked.needs_notify = false;
+                }
+            }
+            self.groups.insert(group_key.clone(), now);
+            notifications.push(AlertNotification {
+                group_key,
+                group_labels,
+                status,
+                alerts,
+                sent_at: now,
+            });
+        }
+
+        // Resolved alerts are forgotten once notified, or straight away while silenced
+        self.alerts.retain(|_, tracked| {
+            tracked.alert.state != AlertState::Resolved || (tracked.needs_notify && tracked.alert.silenced_by.is_none())
+        });
+        let live: Vec<String> = self.alerts.values().map(|tracked| self.group_of(&tracked.alert).0).collect();
+        self.groups.retain(|key, _| live.contains(key));
+        notifications
+    }
+}
+
+/// Delivers alert notifications
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str;
+
+    async fn notify(&self, notification: &AlertNotification) -> Result<(), DiscoveryError>;
+}
+
+/// Build the notifier described by `config`
+pub fn build_notifier(config: &NotifierConfig) -> Result<Box<dyn Notifier>, DiscoveryError> {
+    Ok(match config {
+        NotifierConfig::Log => Box::new(LogNotifier),
+        NotifierConfig::Webhook {
+            url,
+            timeout_ms,
+            headers,
+        } => Box::new(WebhookNotifier::new(url.clone(), *timeout_ms, headers.clone())?),
+        NotifierConfig::File { path } => Box::new(FileNotifier::new(path.clone())),
+    })
+}
+
+/// Send every notification through every notifier, returning how many deliveries failed
+pub async fn dispatch(notifiers: &[Box<dyn Notifier>], notifications: &[AlertNotification]) -> usize {
+    let mut failures = 0;
+    for notification in notifications {
+        for notifier in notifiers {
+            if let Err(e) = notifier.notify(notification).await {
+                warn!(
+                    "Notifier {} failed for group {}: {}",
+                    notifier.name(),
+                    notification.group_key,
+                    e
+                );
+                failures += 1;
+            }
+        }
+    }
+    failures
+}
+
+/// Writes notifications to the tracing log
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    fn name(&self) -> &str {
+        "log"
+    }
+
+    async fn notify(&self, notification: &AlertNotification) -> Result<(), DiscoveryError> {
+        for alert in &notification.alerts {
+            match alert.state {
+                AlertState::Resolved => info!("[{}] RESOLVED {}", notification.group_key, alert.title),
+                _ => warn!(
+                    "[{}] FIRING {:?} {}: {}",
+                    notification.group_key, alert.severity, alert.title, alert.description
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// POSTs notifications as JSON
+pub struct WebhookNotifier {
+    url: String,
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String, timeout_ms: u64, headers: HashMap<String, String>) -> Result<Self, DiscoveryError> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_millis(timeout_ms))
+            .build()
+            .map_err(|e| DiscoveryError::ConfigurationError(format!("Webhook notifier {}: {}", url, e)))?;
+        Ok(Self { url, headers, client })
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    async fn notify(&self, notification: &AlertNotification) -> Result<(), DiscoveryError> {
+        let mut request = self.client.post(&self.url).json(notification);
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| DiscoveryError::NetworkError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(DiscoveryError::NetworkError(format!(
+                "{} answered {}",
+                self.url,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Appends notifications to a file, one JSON object per line
+pub struct FileNotifier {
+    path: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    async fn notify(&self, notification: &AlertNotification) -> Result<(), DiscoveryError> {
+        let io_error = |e: std::io::Error| DiscoveryError::MonitorError(format!("{}: {}", self.path.display(), e));
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+        let mut line =
+            serde_json::to_vec(notification).map_err(|e| DiscoveryError::MonitorError(e.to_string()))?;
+        line.push(b'\n');
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(io_error)?;
+        file.write_all(&line).await.map_err(io_error)?;
+        // tokio finishes writes in the background; flush so the line is on disk when this returns
+        file.flush().await.map_err(io_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_760_000_000 + seconds, 0).unwrap()
+    }
+
+    fn config() -> AlertingConfig {
+        AlertingConfig {
+            group_by: vec!["alertname".to_string()],
+            pending_seconds: 60,
+            resolve_seconds: 120,
+            repeat_interval_seconds: 3600,
+            escalate_after_seconds: Some(600),
+            notifiers: Vec::new(),
+        }
+    }
+
+    fn observation(alertname: &str, instance: &str, severity: AlertSeverity) -> AlertObservation {
+        AlertObservation {
+            labels: BTreeMap::from([
+                ("alertname".to_string(), alertname.to_string()),
+                ("instance".to_string(), instance.to_string()),
+            ]),
+            severity,
+            title: format!("{} on {}", alertname, instance),
+            description: "threshold exceeded".to_string(),
+            system_id: Some(instance.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_deduplicates_and_waits_for_pending_period() {
+        let mut manager = AlertManager::new(config());
+        let first = manager.observe(observation("high_cpu", "a", AlertSeverity::Warning), at(0));
+        assert!(manager.evaluate(at(0)).is_empty());
+
+        let second = manager.observe(observation("high_cpu", "a", AlertSeverity::Warning), at(60));
+        assert_eq!(first, second);
+        assert_eq!(manager.alerts().len(), 1);
+        let alert = manager.get(&first).unwrap();
+        assert_eq!((alert.state, alert.occurrences), (AlertState::Firing, 2));
+
+        let notifications = manager.evaluate(at(60));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status, AlertState::Firing);
+        // Repeated observations of a firing alert notify nothing new
+        manager.observe(observation("high_cpu", "a", AlertSeverity::Warning), at(90));
+        assert!(manager.evaluate(at(90)).is_empty());
+
+        // A condition that clears while pending never fires
+        let brief = manager.observe(observation("high_memory", "a", AlertSeverity::Warning), at(100));
+        assert!(manager.clear(&brief, at(130)));
+        assert!(manager.get(&brief).is_none());
+    }
+
+    #[test]
+    fn test_resolution_needs_the_condition_to_stay_clear() {
+        let mut manager = AlertManager::new(AlertingConfig {
+            pending_seconds: 0,
+            ..config()
+        });
+        let key = manager.observe(observation("disk_full", "a", AlertSeverity::Error), at(0));
+        assert_eq!(manager.evaluate(at(0)).len(), 1);
+
+        // Flapping: cleared, then observed again before resolve_seconds passed
+        manager.clear(&key, at(30));
+        assert!(manager.evaluate(at(100)).is_empty());
+        manager.observe(observation("disk_full", "a", AlertSeverity::Error), at(120));
+        assert!(manager.evaluate(at(200)).is_empty());
+        assert_eq!(manager.get(&key).unwrap().state, AlertState::Firing);
+
+        manager.clear(&key, at(240));
+        let notifications = manager.evaluate(at(360));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].status, AlertState::Resolved);
+        assert_eq!(notifications[0].alerts[0].resolved_at, Some(at(360)));
+        assert!(manager.get(&key).is_none());
+        assert!(manager.alerts().is_empty());
+    }
+
+    #[test]
+    fn test_groups_by_labels_and_repeats() {
+        let mut manager = AlertManager::new(AlertingConfig {
+            pending_seconds: 0,
+            escalate_after_seconds: None,
+            ..config()
+        });
+        manager.observe(observation("high_cpu", "a", AlertSeverity::Warning), at(0));
+        manager.observe(observation("high_cpu", "b", AlertSeverity::Warning), at(0));
+        manager.observe(observation("disk_full", "a", AlertSeverity::Error), at(0));
+
+        let notifications = manager.evaluate(at(0));
+        assert_eq!(notifications.len(), 2);
+        let cpu = notifications.iter().find(|n| n.group_key == "alertname=high_cpu").unwrap();
+        assert_eq!(cpu.alerts.len(), 2);
+        assert_eq!(cpu.group_labels["alertname"], "high_cpu");
+
+        // A new member re-notifies its group only, with every member included
+        manager.observe(observation("high_cpu", "c", AlertSeverity::Warning), at(10));
+        let notifications = manager.evaluate(at(10));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].alerts.len(), 3);
+
+        assert!(manager.evaluate(at(1800)).is_empty());
+        assert_eq!(manager.evaluate(at(3600)).len(), 1);
+        assert_eq!(manager.evaluate(at(3610)).len(), 1);
+    }
+
+    #[test]
+    fn test_silences_mute_for_their_window() {
+        let mut manager = AlertManager::new(AlertingConfig {
+            pending_seconds: 0,
+            ..config()
+        });
+        let matchers = BTreeMap::from([("instance".to_string(), "a".to_string())]);
+        assert!(manager
+            .silence(BTreeMap::new(), at(0), at(600), "ops".to_string(), "all".to_string())
+            .is_err());
+        assert!(manager
+            .silence(matchers.clone(), at(600), at(0), "ops".to_string(), "backwards".to_string())
+            .is_err());
+        let id = manager
+            .silence(matchers, at(0), at(600), "ops".to_string(), "maintenance".to_string())
+            .unwrap();
+
+        let muted = manager.observe(observation("high_cpu", "a", AlertSeverity::Critical), at(0));
+        manager.observe(observation("high_cpu", "b", AlertSeverity::Critical), at(0));
+        let notifications = manager.evaluate(at(0));
+        assert_eq!(notifications[0].alerts.len(), 1);
+        assert_eq!(notifications[0].alerts[0].labels["instance"], "b");
+        assert_eq!(manager.get(&muted).unwrap().silenced_by.as_deref(), Some(id.as_str()));
+        assert_eq!(manager.firing_count(), 1);
+        assert_eq!(manager.silences(at(0)).len(), 1);
+
+        // Once the silence ends the still-firing alert is notified
+        assert!(manager.expire_silence(&id, at(300)));
+        let notifications = manager.evaluate(at(300));
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].alerts.len(), 2);
+        assert!(manager.silences(at(300)).is_empty());
+    }
+
+    #[test]
+    fn test_escalates_until_acknowledged() {
+        let mut manager = AlertManager::new(AlertingConfig {
+            pending_seconds: 0,
+            ..config()
+        });
+        let key = manager.observe(observation("high_cpu", "a", AlertSeverity::Warning), at(0));
+        manager.evaluate(at(0));
+
+        let notifications = manager.evaluate(at(600));
+        assert_eq!(notifications.len(), 1);
+        let alert = manager.get(&key).unwrap();
+        assert_eq!((alert.severity.clone(), alert.escalations), (AlertSeverity::Error, 1));
+
+        // Observing the original severity again keeps the escalation
+        manager.observe(observation("high_cpu", "a", AlertSeverity::Warning), at(700));
+        assert_eq!(manager.get(&key).unwrap().severity, AlertSeverity::Error);
+
+        let id = manager.get(&key).unwrap().id.clone();
+        assert!(manager.acknowledge(&id, "oncall".to_string(), at(900)));
+        assert!(manager.evaluate(at(1200)).is_empty());
+        assert_eq!(manager.get(&key).unwrap().severity, AlertSeverity::Error);
+        assert!(!manager.acknowledge("missing", "oncall".to_string(), at(900)));
+    }
+
+    #[tokio::test]
+    async fn test_file_notifier_appends_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts/notifications.jsonl");
+        let notifiers = vec![
+            build_notifier(&NotifierConfig::Log).unwrap(),
+            build_notifier(&NotifierConfig::File { path: path.clone() }).unwrap(),
+        ];
+
+        let mut manager = AlertManager::new(AlertingConfig {
+            pending_seconds: 0,
+            ..config()
+        });
+        manager.observe(observation("high_cpu", "a", AlertSeverity::Warning), at(0));
+        let notifications = manager.evaluate(at(0));
+        assert_eq!(dispatch(&notifiers, &notifications).await, 0);
+        assert_eq!(dispatch(&notifiers, &notifications).await, 0);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["status"], "firing");
+        assert_eq!(lines[0]["alerts"][0]["labels"]["alertname"], "high_cpu");
+    }
+
+    #[test]
+    fn test_fingerprint_depends_on_labels_only() {
+        let labels = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        assert_eq!(
+            fingerprint(&labels(&[("a", "1"), ("b", "2")])),
+            fingerprint(&labels(&[("b", "2"), ("a", "1")]))
+        );
+        assert_ne!(fingerprint(&labels(&[("a", "12")])), fingerprint(&labels(&[("a1", "2")])));
+    }
+}
@@ -4,9 +4,12 @@
 //! system metrics, application logs, network traffic, external APIs, and databases.
 //! It provides a unified interface for data ingestion and preprocessing.
 
+use crate::log_source::ApplicationLogsSource;
+use crate::procfs::{self, CpuTimes, DiskIoCounters, InterfaceCounters, ProcFs};
+use crate::timeseries::TimeSeriesStore;
 use crate::types::*;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -19,6 +22,7 @@ pub struct DataCollector {
     data_sources: Arc<Mutex<HashMap<SourceId, Box<dyn DataSource>>>>,
     data_batches: Arc<Mutex<Vec<DataBatch>>>,
     statistics: Arc<Mutex<CollectionStatistics>>,
+    timeseries: Arc<Mutex<TimeSeriesStore>>,
     is_running: Arc<Mutex<bool>>,
 }
 
@@ -34,12 +38,20 @@ impl DataCollector {
             avg_latency_ms: 0.0,
             quality_score: 1.0,
         }));
+        let timeseries = match &config.timeseries.snapshot_path {
+            Some(path) => TimeSeriesStore::restore(config.timeseries.clone(), path).unwrap_or_else(|e| {
+                warn!("Starting with empty history, cannot restore {}: {}", path.display(), e);
+                TimeSeriesStore::new(config.timeseries.clone())
+            }),
+            None => TimeSeriesStore::new(config.timeseries.clone()),
+        };
 
         let mut collector = Self {
             config,
             data_sources,
             data_batches,
             statistics,
+            timeseries: Arc::new(Mutex::new(timeseries)),
             is_running: Arc::new(Mutex::new(false)),
         };
 
@@ -59,6 +71,7 @@ impl DataCollector {
         let data_sources = self.data_sources.clone();
         let data_batches = self.data_batches.clone();
         let statistics = self.statistics.clone();
+        let timeseries = self.timeseries.clone();
         let is_running = self.is_running.clone();
 
         tokio::spawn(async move {
@@ -77,6 +90,7 @@ impl DataCollector {
                             &data_sources,
                             &data_batches,
                             &statistics,
+                            &timeseries,
                         ).await {
                             error!("Collection cycle failed: {}", e);
                         }
@@ -93,6 +107,13 @@ impl DataCollector {
     pub async fn stop(&mut self) -> Result<(), DiscoveryError> {
         info!("Stopping Data Collector");
         *self.is_running.lock().await = false;
+        if let Some(path) = &self.config.timeseries.snapshot_path {
+            let timeseries = self.timeseries.lock().await;
+            timeseries.snapshot(path).map_err(|e| {
+                DiscoveryError::CollectorError(format!("Cannot write time-series snapshot {}: {}", path.display(), e))
+            })?;
+            info!("Wrote {} series to {}", timeseries.len(), path.display());
+        }
         info!("Data Collector stopped successfully");
         Ok(())
     }
@@ -124,11 +145,16 @@ impl DataCollector {
         })
     }
 
+    /// History of the collected data points
+    pub fn timeseries(&self) -> Arc<Mutex<TimeSeriesStore>> {
+        self.timeseries.clone()
+    }
+
     /// Collect data from all sources
     pub async fn collect_all(&mut self) -> Result<CollectionResult, DiscoveryError> {
         info!("Performing full data collection");
 
-        let mut sources_processed = 0;
+        let mut sources_processed: u32 = 0;
         let mut data_points_collected = 0;
         let mut errors = Vec::new();
         let start_time = std::time::Instant::now();
@@ -141,6 +167,7 @@ impl DataCollector {
                     data_points_collected += data_batch.data_points.len() as u64;
 
                     // Store the batch
+                    self.timeseries.lock().await.ingest(&data_batch);
                     self.data_batches.lock().await.push(data_batch);
 
                     // Update statistics
@@ -149,9 +176,13 @@ impl DataCollector {
                 }
                 Err(e) => {
                     error!("Data source {} failed: {}", source_id, e);
-                    errors.push(CollectionError {
+                    errors.push(CollectionErrorInfo {
                         source_id: source_id.clone(),
-                        error_type: CollectionErrorType::ConnectionError,
+                        error_type: match &e {
+                            CollectionError::DataFormatInvalid(_) => CollectionErrorType::DataFormatError,
+                            CollectionError::ProcessingTimeout(_) => CollectionErrorType::TimeoutError,
+                            _ => CollectionErrorType::ConnectionError,
+                        },
                         message: e.to_string(),
                         timestamp: Utc::now(),
                     });
@@ -159,9 +190,10 @@ impl DataCollector {
             }
         }
 
-        let duration = start_time.elapsed().as_secs_f64() * 1000.0;
-        let success_rate = if sources_processed + errors.len() > 0 {
-            sources_processed as f64 / (sources_processed + errors.len()) as f64
+        let duration = start_time.elapsed().as_millis() as u64;
+        let attempted = sources_processed as usize + errors.len();
+        let success_rate = if attempted > 0 {
+            sources_processed as f64 / attempted as f64
         } else {
             1.0
         };
@@ -180,6 +212,7 @@ impl DataCollector {
         let is_running = *self.is_running.lock().await;
         let sources_count = self.data_sources.lock().await.len();
         let statistics = self.statistics.lock().await;
+        let timeseries = self.timeseries.lock().await;
 
         let status = if is_running && statistics.success_rate > 0.9 {
             ServiceStatus::Healthy
@@ -201,6 +234,8 @@ impl DataCollector {
                 metrics.insert("success_rate".to_string(), statistics.success_rate);
                 metrics.insert("avg_latency_ms".to_string(), statistics.avg_latency_ms);
                 metrics.insert("quality_score".to_string(), statistics.quality_score);
+                metrics.insert("timeseries_series".to_string(), timeseries.len() as f64);
+                metrics.insert("timeseries_dropped_points".to_string(), timeseries.dropped_points() as f64);
                 metrics
             },
         })
@@ -209,8 +244,8 @@ impl DataCollector {
     /// Add a new data source
     pub async fn add_data_source(&self, source: Box<dyn DataSource>) -> Result<(), DiscoveryError> {
         let source_id = source.get_source_id();
-        self.data_sources.lock().await.insert(source_id, source);
         info!("Added data source: {}", source_id);
+        self.data_sources.lock().await.insert(source_id, source);
         Ok(())
     }
 
@@ -224,13 +259,16 @@ impl DataCollector {
     /// Initialize default data sources
     async fn initialize_default_sources(&mut self) -> Result<(), DiscoveryError> {
         // System metrics source
-        self.add_data_source(Box::new(SystemMetricsSource::new())).await?;
+        let proc = ProcFs::new(&self.config.proc_root);
+        self.add_data_source(Box::new(SystemMetricsSource::new(proc.clone(), self.config.top_processes)))
+            .await?;
 
         // Application logs source
-        self.add_data_source(Box::new(ApplicationLogsSource::new())).await?;
+        self.add_data_source(Box::new(ApplicationLogsSource::new(self.config.logs.clone())?))
+            .await?;
 
         // Network traffic source
-        self.add_data_source(Box::new(NetworkTrafficSource::new())).await?;
+        self.add_data_source(Box::new(NetworkTrafficSource::new(proc))).await?;
 
         info!("Initialized {} default data sources", 3);
         Ok(())
@@ -242,6 +280,7 @@ impl DataCollector {
         data_sources: &Arc<Mutex<HashMap<SourceId, Box<dyn DataSource>>>>,
         data_batches: &Arc<Mutex<Vec<DataBatch>>>,
         statistics: &Arc<Mutex<CollectionStatistics>>,
+        timeseries: &Arc<Mutex<TimeSeriesStore>>,
     ) -> Result<(), DiscoveryError> {
         debug!("Starting data collection cycle");
 
@@ -261,6 +300,7 @@ impl DataCollector {
                     total_latency += start_time.elapsed().as_secs_f64() * 1000.0;
 
                     // Store the batch
+                    timeseries.lock().await.ingest(&data_batch);
                     data_batches.lock().await.push(data_batch);
                 }
                 Err(e) => {
@@ -311,71 +351,241 @@ pub trait DataSource: Send + Sync {
     }
 }
 
-/// System metrics data source
-struct SystemMetricsSource {
+/// System metrics data source reading CPU, memory, disks, pressure and processes from `/proc`
+pub struct SystemMetricsSource {
     source_id: SourceId,
-    config: HashMap<String, String>,
+    proc: ProcFs,
+    top_processes: usize,
+    previous: Mutex<Option<HostSample>>,
+}
+
+/// Cumulative counters from one collection, the baseline for the next one's rates
+struct HostSample {
+    taken_at: std::time::Instant,
+    cpu: CpuTimes,
+    disks: HashMap<String, DiskIoCounters>,
+    process_ticks: HashMap<u32, u64>,
+    processes: Vec<ProcessInfo>,
 }
 
 impl SystemMetricsSource {
-    fn new() -> Self {
+    pub fn new(proc: ProcFs, top_processes: usize) -> Self {
         Self {
             source_id: "system-metrics".to_string(),
-            config: HashMap::new(),
+            proc,
+            top_processes,
+            previous: Mutex::new(None),
+        }
+    }
+
+    /// Busiest processes as of the last collection
+    pub async fn top_processes(&self) -> Vec<ProcessInfo> {
+        self.previous
+            .lock()
+            .await
+            .as_ref()
+            .map(|sample| sample.processes.clone())
+            .unwrap_or_default()
+    }
+
+    /// The `top_processes` busiest processes. CPU usage is the share of all CPUs used since
+    /// `previous`, or since the process started on the first collection.
+    fn process_table(
+        &self,
+        cpu: CpuTimes,
+        previous: Option<&HostSample>,
+    ) -> (HashMap<u32, u64>, Vec<ProcessInfo>) {
+        let elapsed_ticks = cpu.total - previous.map_or(0, |sample| sample.cpu.total.min(cpu.total));
+        let page_size = procfs::page_size();
+
+        let mut ticks = HashMap::new();
+        let mut processes = Vec::new();
+        for pid in self.proc.pids().unwrap_or_default() {
+            // Processes may exit between listing and reading.
+            let Some(stat) = self.proc.pid_stat(pid) else {
+                continue;
+            };
+            let before = previous
+                .and_then(|sample| sample.process_ticks.get(&pid).copied())
+                .unwrap_or(0)
+                .min(stat.cpu_ticks);
+            let cpu_usage_percent = if elapsed_ticks == 0 {
+                0.0
+            } else {
+                (stat.cpu_ticks - before) as f64 / elapsed_ticks as f64 * 100.0
+            };
+            ticks.insert(pid, stat.cpu_ticks);
+            processes.push(ProcessInfo {
+                pid,
+                name: stat.comm,
+                cpu_usage_percent,
+                memory_mb: stat.rss_pages * page_size / 1024 / 1024,
+                status: match stat.state {
+                    'R' => ProcessStatus::Running,
+                    'S' | 'D' | 'I' => ProcessStatus::Sleeping,
+                    'T' | 't' => ProcessStatus::Stopped,
+                    'Z' => ProcessStatus::Zombie,
+                    _ => ProcessStatus::Unknown,
+                },
+            });
         }
+
+        processes.sort_by(|a, b| {
+            b.cpu_usage_percent
+                .total_cmp(&a.cpu_usage_percent)
+                .then(b.memory_mb.cmp(&a.memory_mb))
+        });
+        processes.truncate(self.top_processes);
+        (ticks, processes)
     }
 }
 
 #[async_trait]
 impl DataSource for SystemMetricsSource {
     async fn collect_data(&self) -> Result<DataBatch, CollectionError> {
-        let sys = sysinfo::System::new_all();
         let timestamp = Utc::now();
+        let cpu = self.proc.cpu_times().map_err(|e| source_error(&self.source_id, e))?;
+        let meminfo = self.proc.meminfo().map_err(|e| source_error(&self.source_id, e))?;
+        let mut previous = self.previous.lock().await;
 
         let mut data_points = Vec::new();
-
-        // CPU metrics
-        let cpu_usage = sys.global_cpu_info().cpu_usage();
-        data_points.push(DataPoint {
-            metric_name: "cpu_usage_percent".to_string(),
-            value: cpu_usage as f64,
-            unit: "percent".to_string(),
-            tags: {
-                let mut tags = HashMap::new();
-                tags.insert("component".to_string(), "cpu".to_string());
-                tags
-            },
+        let point = |metric_name: &str, value: f64, unit: &str, tags: &[(&str, &str)]| DataPoint {
+            metric_name: metric_name.to_string(),
+            value,
+            unit: unit.to_string(),
+            tags: tags.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
             timestamp,
-        });
+        };
+
+        // CPU metrics, since the previous collection or since boot
+        let (busy, total) = match previous.as_ref() {
+            Some(sample) => (
+                cpu.busy.saturating_sub(sample.cpu.busy),
+                cpu.total.saturating_sub(sample.cpu.total),
+            ),
+            None => (cpu.busy, cpu.total),
+        };
+        if total > 0 {
+            let usage = busy as f64 / total as f64 * 100.0;
+            data_points.push(point("cpu_usage_percent", usage, "percent", &[("component", "cpu")]));
+        }
 
         // Memory metrics
-        let total_memory = sys.total_memory() as f64;
-        let used_memory = sys.used_memory() as f64;
-        let memory_usage = (used_memory / total_memory) * 100.0;
-
-        data_points.push(DataPoint {
-            metric_name: "memory_usage_percent".to_string(),
-            value: memory_usage,
-            unit: "percent".to_string(),
-            tags: {
-                let mut tags = HashMap::new();
-                tags.insert("component".to_string(), "memory".to_string());
-                tags
-            },
-            timestamp,
-        });
+        let total_memory = meminfo.get("MemTotal").copied().unwrap_or(0);
+        // Kernels before 3.14 have no MemAvailable
+        let available_memory = meminfo
+            .get("MemAvailable")
+            .or_else(|| meminfo.get("MemFree"))
+            .copied()
+            .unwrap_or(0);
+        if total_memory > 0 {
+            let used = total_memory.saturating_sub(available_memory) as f64;
+            data_points.push(point(
+                "memory_usage_percent",
+                used / total_memory as f64 * 100.0,
+                "percent",
+                &[("component", "memory")],
+            ));
+            data_points.push(point(
+                "memory_available_bytes",
+                available_memory as f64,
+                "bytes",
+                &[("component", "memory")],
+            ));
+        }
 
-        // Disk metrics (placeholder)
-        data_points.push(DataPoint {
-            metric_name: "disk_usage_percent".to_string(),
-            value: 50.0, // Placeholder
-            unit: "percent".to_string(),
-            tags: {
-                let mut tags = HashMap::new();
-                tags.insert("component".to_string(), "disk".to_string());
-                tags
-            },
-            timestamp,
+        // Disk usage per mounted filesystem
+        for mount in self.proc.mounts().unwrap_or_default() {
+            // Unreachable network mounts and mounts outside our namespace are skipped.
+            let Ok(usage) = procfs::statvfs(&mount.mount_point) else {
+                continue;
+            };
+            if usage.total_bytes == 0 {
+                continue;
+            }
+            let mount_point = mount.mount_point.to_string_lossy();
+            let tags = [
+                ("component", "disk"),
+                ("mount", mount_point.as_ref()),
+                ("device", mount.device.as_str()),
+                ("fstype", mount.fs_type.as_str()),
+            ];
+            data_points.push(point("disk_usage_percent", usage.usage_percent(), "percent", &tags));
+            data_points.push(point("disk_used_bytes", usage.used_bytes() as f64, "bytes", &tags));
+            data_points.push(point("disk_available_bytes", usage.available_bytes as f64, "bytes", &tags));
+        }
+
+        // Disk I/O rates since the previous collection
+        let disks: HashMap<String, DiskIoCounters> = self
+            .proc
+            .disk_stats()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|disk| (disk.device.clone(), disk))
+            .collect();
+        if let Some(sample) = previous.as_ref() {
+            let seconds = sample.taken_at.elapsed().as_secs_f64();
+            for (device, disk) in disks.iter().filter(|_| seconds > 0.0) {
+                let Some(before) = sample.disks.get(device) else {
+                    continue;
+                };
+                let tags = [("component", "disk"), ("device", device.as_str())];
+                let rate = |now: u64, then: u64| now.saturating_sub(then) as f64 / seconds;
+                data_points.push(point(
+                    "disk_read_bytes_per_sec",
+                    rate(disk.sectors_read, before.sectors_read) * procfs::DISKSTATS_SECTOR_BYTES as f64,
+                    "bytes_per_sec",
+                    &tags,
+                ));
+                data_points.push(point(
+                    "disk_write_bytes_per_sec",
+                    rate(disk.sectors_written, before.sectors_written) * procfs::DISKSTATS_SECTOR_BYTES as f64,
+                    "bytes_per_sec",
+                    &tags,
+                ));
+                data_points.push(point(
+                    "disk_io_time_percent",
+                    (rate(disk.io_ticks_ms, before.io_ticks_ms) / 10.0).min(100.0),
+                    "percent",
+                    &tags,
+                ));
+            }
+        }
+
+        // Pressure stall information
+        for resource in ["cpu", "memory", "io"] {
+            let Some(pressure) = self.proc.pressure(resource) else {
+                continue;
+            };
+            let tags = [("component", "pressure"), ("resource", resource)];
+            data_points.push(point("pressure_some_avg10_percent", pressure.some.avg10, "percent", &tags));
+            data_points.push(point("pressure_some_avg60_percent", pressure.some.avg60, "percent", &tags));
+            if let Some(full) = pressure.full {
+                data_points.push(point("pressure_full_avg10_percent", full.avg10, "percent", &tags));
+                data_points.push(point("pressure_full_avg60_percent", full.avg60, "percent", &tags));
+            }
+        }
+
+        // Busiest processes
+        let (process_ticks, processes) = self.process_table(cpu, previous.as_ref());
+        for process in &processes {
+            let pid = process.pid.to_string();
+            let tags = [("component", "process"), ("pid", pid.as_str()), ("process", process.name.as_str())];
+            data_points.push(point("process_cpu_percent", process.cpu_usage_percent, "percent", &tags));
+            data_points.push(point("process_memory_mb", process.memory_mb as f64, "megabytes", &tags));
+        }
+
+        let mut metadata = HashMap::new();
+        if let Ok(table) = serde_json::to_string(&processes) {
+            metadata.insert("top_processes".to_string(), table);
+        }
+
+        *previous = Some(HostSample {
+            taken_at: std::time::Instant::now(),
+            cpu,
+            disks,
+            process_ticks,
+            processes,
         });
 
         Ok(DataBatch {
@@ -383,7 +593,7 @@ impl DataSource for SystemMetricsSource {
             timestamp,
             data_points,
             quality_score: 0.95, // High quality for system metrics
-            metadata: HashMap::new(),
+            metadata,
         })
     }
 
@@ -394,84 +604,34 @@ impl DataSource for SystemMetricsSource {
     fn get_source_type(&self) -> DataSourceType {
         DataSourceType::SystemMetrics
     }
-}
 
-/// Application logs data source
-struct ApplicationLogsSource {
-    source_id: SourceId,
-    config: HashMap<String, String>,
-}
-
-impl ApplicationLogsSource {
-    fn new() -> Self {
-        Self {
-            source_id: "application-logs".to_string(),
-            config: HashMap::new(),
-        }
+    fn is_available(&self) -> bool {
+        self.proc.root().join("stat").exists()
     }
 }
 
-#[async_trait]
-impl DataSource for ApplicationLogsSource {
-    async fn collect_data(&self) -> Result<DataBatch, CollectionError> {
-        // This would read from actual log files or log aggregation systems
-        // For now, return placeholder data
-        let timestamp = Utc::now();
-
-        let data_points = vec![
-            DataPoint {
-                metric_name: "log_entries_count".to_string(),
-                value: 100.0,
-                unit: "count".to_string(),
-                tags: {
-                    let mut tags = HashMap::new();
-                    tags.insert("level".to_string(), "info".to_string());
-                    tags
-                },
-                timestamp,
-            },
-            DataPoint {
-                metric_name: "error_rate".to_string(),
-                value: 0.05,
-                unit: "ratio".to_string(),
-                tags: {
-                    let mut tags = HashMap::new();
-                    tags.insert("level".to_string(), "error".to_string());
-                    tags
-                },
-                timestamp,
-            },
-        ];
-
-        Ok(DataBatch {
-            source_id: self.source_id.clone(),
-            timestamp,
-            data_points,
-            quality_score: 0.90,
-            metadata: HashMap::new(),
-        })
-    }
-
-    fn get_source_id(&self) -> SourceId {
-        self.source_id.clone()
-    }
-
-    fn get_source_type(&self) -> DataSourceType {
-        DataSourceType::ApplicationLogs
+/// Collection error for a source whose files could not be read
+fn source_error(source_id: &SourceId, error: std::io::Error) -> CollectionError {
+    let message = format!("{}: {}", source_id, error);
+    match error.kind() {
+        std::io::ErrorKind::InvalidData => CollectionError::DataFormatInvalid(message),
+        _ => CollectionError::SourceUnavailable(message),
     }
 }
 
-/// Network traffic data source
-struct NetworkTrafficSource {
+/// Network traffic data source reading interface counters and TCP connections from `/proc/net`
+pub struct NetworkTrafficSource {
     source_id: SourceId,
-    config: HashMap<String, String>,
+    proc: ProcFs,
+    previous: Mutex<Option<(std::time::Instant, HashMap<String, InterfaceCounters>)>>,
 }
 
 impl NetworkTrafficSource {
-    fn new() -> Self {
+    pub fn new(proc: ProcFs) -> Self {
         Self {
             source_id: "network-traffic".to_string(),
-            config: HashMap::new(),
+            proc,
+            previous: Mutex::new(None),
         }
     }
 }
@@ -479,51 +639,82 @@ impl NetworkTrafficSource {
 #[async_trait]
 impl DataSource for NetworkTrafficSource {
     async fn collect_data(&self) -> Result<DataBatch, CollectionError> {
-        // This would collect actual network traffic data
-        // For now, return placeholder data
         let timestamp = Utc::now();
+        let interfaces = self.proc.net_dev().map_err(|e| source_error(&self.source_id, e))?;
+        let mut previous = self.previous.lock().await;
 
-        let data_points = vec![
-            DataPoint {
-                metric_name: "bytes_received_per_sec".to_string(),
-                value: 1024.0 * 100.0, // 100 KB/s
-                unit: "bytes_per_sec".to_string(),
-                tags: {
-                    let mut tags = HashMap::new();
-                    tags.insert("direction".to_string(), "inbound".to_string());
-                    tags
-                },
-                timestamp,
-            },
-            DataPoint {
-                metric_name: "bytes_transmitted_per_sec".to_string(),
-                value: 1024.0 * 50.0, // 50 KB/s
-                unit: "bytes_per_sec".to_string(),
-                tags: {
-                    let mut tags = HashMap::new();
-                    tags.insert("direction".to_string(), "outbound".to_string());
-                    tags
-                },
-                timestamp,
-            },
-            DataPoint {
-                metric_name: "active_connections".to_string(),
-                value: 25.0,
-                unit: "count".to_string(),
-                tags: {
-                    let mut tags = HashMap::new();
-                    tags.insert("type".to_string(), "tcp".to_string());
-                    tags
-                },
-                timestamp,
-            },
-        ];
+        let mut data_points = Vec::new();
+        let point = |metric_name: &str, value: f64, unit: &str, tags: &[(&str, &str)]| DataPoint {
+            metric_name: metric_name.to_string(),
+            value,
+            unit: unit.to_string(),
+            tags: tags.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            timestamp,
+        };
+
+        let seconds = previous
+            .as_ref()
+            .map(|(taken_at, _)| taken_at.elapsed().as_secs_f64())
+            .filter(|seconds| *seconds > 0.0);
+        let mut received_per_sec = 0.0;
+        let mut transmitted_per_sec = 0.0;
+        for interface in &interfaces {
+            let tags = [("interface", interface.name.as_str())];
+            data_points.push(point("network_receive_errors_total", interface.rx_errors as f64, "count", &tags));
+            data_points.push(point("network_transmit_errors_total", interface.tx_errors as f64, "count", &tags));
+            data_points.push(point("network_receive_dropped_total", interface.rx_dropped as f64, "count", &tags));
+            data_points.push(point("network_transmit_dropped_total", interface.tx_dropped as f64, "count", &tags));
+
+            let before = previous
+                .as_ref()
+                .and_then(|(_, counters)| counters.get(&interface.name));
+            let (Some(seconds), Some(before)) = (seconds, before) else {
+                continue;
+            };
+            let received = interface.rx_bytes.saturating_sub(before.rx_bytes) as f64 / seconds;
+            let transmitted = interface.tx_bytes.saturating_sub(before.tx_bytes) as f64 / seconds;
+            data_points.push(point("network_receive_bytes_per_sec", received, "bytes_per_sec", &tags));
+            data_points.push(point("network_transmit_bytes_per_sec", transmitted, "bytes_per_sec", &tags));
+            // Loopback traffic never leaves the host.
+            if interface.name != "lo" {
+                received_per_sec += received;
+                transmitted_per_sec += transmitted;
+            }
+        }
+
+        if seconds.is_some() {
+            data_points.push(point(
+                "bytes_received_per_sec",
+                received_per_sec,
+                "bytes_per_sec",
+                &[("direction", "inbound")],
+            ));
+            data_points.push(point(
+                "bytes_transmitted_per_sec",
+                transmitted_per_sec,
+                "bytes_per_sec",
+                &[("direction", "outbound")],
+            ));
+        }
+
+        if let Ok(sockets) = self.proc.tcp_sockets() {
+            let established = sockets.iter().filter(|socket| socket.is_established()).count();
+            data_points.push(point("active_connections", established as f64, "count", &[("type", "tcp")]));
+        }
+
+        *previous = Some((
+            std::time::Instant::now(),
+            interfaces
+                .into_iter()
+                .map(|interface| (interface.name.clone(), interface))
+                .collect(),
+        ));
 
         Ok(DataBatch {
             source_id: self.source_id.clone(),
             timestamp,
             data_points,
-            quality_score: 0.85,
+            quality_score: 0.95,
             metadata: HashMap::new(),
         })
     }
@@ -535,6 +726,10 @@ impl DataSource for NetworkTrafficSource {
     fn get_source_type(&self) -> DataSourceType {
         DataSourceType::NetworkTraffic
     }
+
+    fn is_available(&self) -> bool {
+        self.proc.root().join("net/dev").exists()
+    }
 }
 
 #[cfg(test)]
@@ -550,41 +745,149 @@ mod tests {
 
     #[tokio::test]
     async fn test_system_metrics_source() {
-        let source = SystemMetricsSource::new();
+        let source = SystemMetricsSource::new(ProcFs::default(), 5);
         let result = source.collect_data().await;
         assert!(result.is_ok());
 
         let batch = result.unwrap();
         assert_eq!(batch.source_id, "system-metrics");
-        assert_eq!(batch.source_type, DataSourceType::SystemMetrics);
+        assert_eq!(source.get_source_type(), DataSourceType::SystemMetrics);
         assert!(!batch.data_points.is_empty());
         assert!(batch.quality_score > 0.9);
     }
 
     #[tokio::test]
     async fn test_application_logs_source() {
-        let source = ApplicationLogsSource::new();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "{\"level\":\"info\",\"msg\":\"started\"}\n").unwrap();
+        let source = ApplicationLogsSource::new(LogSourceConfig {
+            files: vec![LogFileConfig {
+                path,
+                name: None,
+                format: LogFormat::Json,
+                pattern: None,
+                numeric_fields: Vec::new(),
+                from_beginning: true,
+            }],
+            ..LogSourceConfig::default()
+        })
+        .unwrap();
         let result = source.collect_data().await;
         assert!(result.is_ok());
 
         let batch = result.unwrap();
         assert_eq!(batch.source_id, "application-logs");
-        assert_eq!(batch.source_type, DataSourceType::ApplicationLogs);
+        assert_eq!(source.get_source_type(), DataSourceType::ApplicationLogs);
         assert!(!batch.data_points.is_empty());
     }
 
     #[tokio::test]
     async fn test_network_traffic_source() {
-        let source = NetworkTrafficSource::new();
+        let source = NetworkTrafficSource::new(ProcFs::default());
         let result = source.collect_data().await;
         assert!(result.is_ok());
 
         let batch = result.unwrap();
         assert_eq!(batch.source_id, "network-traffic");
-        assert_eq!(batch.source_type, DataSourceType::NetworkTraffic);
+        assert_eq!(source.get_source_type(), DataSourceType::NetworkTraffic);
         assert!(!batch.data_points.is_empty());
     }
 
+    fn fixture_proc() -> ProcFs {
+        ProcFs::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/proc"))
+    }
+
+    fn values<'a>(batch: &'a DataBatch, metric: &str) -> Vec<&'a DataPoint> {
+        batch
+            .data_points
+            .iter()
+            .filter(|point| point.metric_name == metric)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_system_metrics_from_fixture_proc() {
+        let source = SystemMetricsSource::new(fixture_proc(), 2);
+        let batch = source.collect_data().await.unwrap();
+
+        let cpu = values(&batch, "cpu_usage_percent")[0].value;
+        assert!((cpu - 321_865.0 / 792_519.0 * 100.0).abs() < 1e-9);
+        assert_eq!(values(&batch, "memory_usage_percent")[0].value, 50.0);
+
+        let memory_pressure: Vec<f64> = values(&batch, "pressure_full_avg10_percent")
+            .iter()
+            .filter(|point| point.tags["resource"] == "memory")
+            .map(|point| point.value)
+            .collect();
+        assert_eq!(memory_pressure, vec![8.1]);
+
+        let processes = source.top_processes().await;
+        let names: Vec<&str> = processes.iter().map(|process| process.name.as_str()).collect();
+        assert_eq!(names, vec!["python3 (worker)", "systemd"]);
+        assert_eq!(processes[0].status, ProcessStatus::Running);
+        assert_eq!(processes[0].memory_mb, 131072 * procfs::page_size() / 1024 / 1024);
+        assert!(batch.metadata["top_processes"].contains("python3 (worker)"));
+        // Disk rates need a previous collection
+        assert!(values(&batch, "disk_read_bytes_per_sec").is_empty());
+
+        let batch = source.collect_data().await.unwrap();
+        // Nothing moved between the two collections of the same fixture
+        assert!(values(&batch, "cpu_usage_percent").is_empty());
+        let reads = values(&batch, "disk_read_bytes_per_sec");
+        assert_eq!(reads.len(), 4);
+        assert!(reads.iter().all(|point| point.value == 0.0));
+        assert_eq!(source.top_processes().await[0].cpu_usage_percent, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_network_traffic_from_fixture_proc() {
+        let source = NetworkTrafficSource::new(fixture_proc());
+        let batch = source.collect_data().await.unwrap();
+
+        assert_eq!(values(&batch, "active_connections")[0].value, 2.0);
+        let dropped: Vec<(String, f64)> = values(&batch, "network_receive_dropped_total")
+            .iter()
+            .map(|point| (point.tags["interface"].clone(), point.value))
+            .collect();
+        assert!(dropped.contains(&("eth0".to_string(), 40.0)));
+        assert!(values(&batch, "bytes_received_per_sec").is_empty());
+
+        let batch = source.collect_data().await.unwrap();
+        assert_eq!(values(&batch, "bytes_received_per_sec")[0].value, 0.0);
+        assert_eq!(values(&batch, "network_transmit_bytes_per_sec").len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_collected_points_are_kept_and_snapshotted() {
+        let dir = tempfile::tempdir().unwrap();
+        let snapshot = dir.path().join("timeseries.json");
+        let config = CollectorConfig {
+            proc_root: fixture_proc().root().to_path_buf(),
+            logs: LogSourceConfig {
+                files: Vec::new(),
+                ..LogSourceConfig::default()
+            },
+            timeseries: TimeSeriesConfig {
+                snapshot_path: Some(snapshot.clone()),
+                ..TimeSeriesConfig::default()
+            },
+            ..CollectorConfig::default()
+        };
+
+        let mut collector = DataCollector::new(config.clone()).await.unwrap();
+        collector.collect_all().await.unwrap();
+        let series = collector.timeseries().lock().await.len();
+        assert!(series > 0);
+        let key = crate::timeseries::SeriesKey::new("active_connections", [("type", "tcp")]);
+        assert_eq!(collector.timeseries().lock().await.latest(&key).unwrap().value, 2.0);
+
+        collector.stop().await.unwrap();
+        assert!(snapshot.exists());
+        let restored = DataCollector::new(config).await.unwrap();
+        assert_eq!(restored.timeseries().lock().await.len(), series);
+    }
+
     #[test]
     fn test_data_source_types() {
         assert_eq!(DataSourceType::SystemMetrics, DataSourceType::SystemMetrics);
@@ -4,14 +4,23 @@
 //! network topology, and available resources in the environment. It provides comprehensive
 //! environmental awareness to support intelligent planning and resource allocation.
 
+use crate::probe_registry::{run_probe, ProbeRegistry, ProbeSchedule, ProbeStats};
+use crate::procfs::{self, ContainerRef, ProcFs};
 use crate::types::*;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use tokio::time::{timeout, Duration};
-use tracing::{debug, error, info, warn};
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinSet;
+use tokio::time::{timeout, Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Longest the scheduler sleeps before checking whether the scanner was stopped
+const SCHEDULER_POLL: Duration = Duration::from_secs(1);
 
 /// Environmental scanner for system discovery
 pub struct EnvironmentalScanner {
@@ -20,7 +29,8 @@ pub struct EnvironmentalScanner {
     network_topology: Arc<Mutex<NetworkTopology>>,
     resource_inventory: Arc<Mutex<ResourceInventory>>,
     discovery_cache: Arc<Mutex<DiscoveryCache>>,
-    probes: Vec<Box<dyn SystemProbe>>,
+    registry: Arc<Mutex<ProbeRegistry>>,
+    probe_results: Arc<Mutex<BTreeMap<String, Vec<DiscoveredSystem>>>>,
     is_running: Arc<Mutex<bool>>,
 }
 
@@ -55,13 +65,37 @@ impl EnvironmentalScanner {
             cache_ttl: Duration::from_secs(config.cache_ttl_seconds),
         }));
 
-        let mut probes: Vec<Box<dyn SystemProbe>> = Vec::new();
-
-        // Add default probes
-        probes.push(Box::new(LocalSystemProbe::new()));
-        probes.push(Box::new(NetworkProbe::new()));
-        probes.push(Box::new(ContainerProbe::new()));
-        probes.push(Box::new(ServiceProbe::new()));
+        let mut registry = ProbeRegistry::new(Duration::from_secs(config.probe_max_backoff_seconds));
+
+        // Add default probes; local reads are cheap, network sweeps follow the scan interval
+        let proc = ProcFs::new(&config.proc_root);
+        let sweep = Duration::from_secs(config.scan_interval_seconds);
+        let defaults: [(&str, Arc<dyn SystemProbe>, ProbeSchedule); 4] = [
+            (
+                "local",
+                Arc::new(LocalSystemProbe::new()),
+                ProbeSchedule::every(Duration::from_secs(60), Duration::from_secs(10)),
+            ),
+            (
+                "network",
+                Arc::new(NetworkProbe::new(config.network.clone())),
+                ProbeSchedule::every(sweep, Duration::from_secs(config.max_scan_duration_seconds))
+                    .with_jitter(sweep / 10),
+            ),
+            (
+                "container",
+                Arc::new(ContainerProbe::new(proc.clone(), config.cgroup_root.clone())),
+                ProbeSchedule::every(Duration::from_secs(60), Duration::from_secs(15)),
+            ),
+            (
+                "service",
+                Arc::new(ServiceProbe::new(proc)),
+                ProbeSchedule::every(Duration::from_secs(60), Duration::from_secs(15)),
+            ),
+        ];
+        for (name, probe, schedule) in defaults {
+            registry.register(name, probe, schedule.with_overrides(config.probes.get(name)))?;
+        }
 
         Ok(Self {
             config,
@@ -69,11 +103,38 @@ impl EnvironmentalScanner {
             network_topology,
             resource_inventory,
             discovery_cache,
-            probes,
+            registry: Arc::new(Mutex::new(registry)),
+            probe_results: Arc::new(Mutex::new(BTreeMap::new())),
             is_running: Arc::new(Mutex::new(false)),
         })
     }
 
+    /// Register an additional probe, scheduled on the scan interval unless `probes.<name>`
+    /// in the configuration says otherwise
+    pub async fn register_probe(
+        &self,
+        name: &str,
+        probe: Arc<dyn SystemProbe>,
+    ) -> Result<(), DiscoveryError> {
+        let schedule = ProbeSchedule::every(
+            Duration::from_secs(self.config.scan_interval_seconds),
+            Duration::from_secs(self.config.max_scan_duration_seconds),
+        )
+        .with_overrides(self.config.probes.get(name));
+        self.registry.lock().await.register(name, probe, schedule)
+    }
+
+    /// Remove a probe along with the systems it discovered
+    pub async fn unregister_probe(&self, name: &str) -> bool {
+        let removed = self.registry.lock().await.unregister(name);
+        if removed {
+            let shared = self.shared();
+            shared.probe_results.lock().await.remove(name);
+            shared.rebuild().await;
+        }
+        removed
+    }
+
     /// Start the environmental scanner
     pub async fn start(&mut self) -> Result<(), DiscoveryError> {
         info!("Starting Environmental Scanner");
@@ -82,38 +143,10 @@ impl EnvironmentalScanner {
         // Perform initial scan
         self.perform_scan().await?;
 
-        // Start periodic scanning
-        let config = self.config.clone();
-        let systems = self.systems.clone();
-        let network_topology = self.network_topology.clone();
-        let resource_inventory = self.resource_inventory.clone();
-        let discovery_cache = self.discovery_cache.clone();
+        // Start per-probe scheduling
+        let shared = self.shared();
         let is_running = self.is_running.clone();
-
-        tokio::spawn(async move {
-            let scan_interval = Duration::from_secs(config.scan_interval_seconds);
-            let mut interval = tokio::time::interval(scan_interval);
-
-            loop {
-                tokio::select! {
-                    _ = interval.tick() => {
-                        if !*is_running.lock().await {
-                            break;
-                        }
-
-                        if let Err(e) = Self::perform_periodic_scan(
-                            &config,
-                            &systems,
-                            &network_topology,
-                            &resource_inventory,
-                            &discovery_cache,
-                        ).await {
-                            error!("Periodic scan failed: {}", e);
-                        }
-                    }
-                }
-            }
-        });
+        tokio::spawn(Self::run_scheduler(shared, is_running));
 
         info!("Environmental Scanner started successfully");
         Ok(())
@@ -157,13 +190,34 @@ impl EnvironmentalScanner {
         })
     }
 
+    /// Run one probe immediately, whether or not it is enabled or due, and return what it found
+    pub async fn scan_now(&self, probe: &str) -> Result<Vec<DiscoveredSystem>, DiscoveryError> {
+        self.shared().run(probe).await
+    }
+
+    /// A system from the last scan, while that scan is younger than the cache TTL
+    pub async fn cached_system(&self, system_id: &SystemId) -> Option<DiscoveredSystem> {
+        self.discovery_cache.lock().await.get_system(system_id).cloned()
+    }
+
+    /// Run history of every registered probe
+    pub async fn probe_stats(&self) -> BTreeMap<String, ProbeStats> {
+        self.registry.lock().await.stats()
+    }
+
     /// Get scanner health status
     pub async fn health_check(&self) -> Result<ComponentHealth, DiscoveryError> {
         let is_running = *self.is_running.lock().await;
         let systems_count = self.systems.lock().await.len();
         let cache_size = self.discovery_cache.lock().await.systems.len();
-
-        let status = if is_running && systems_count > 0 {
+        let probe_stats = self.probe_stats().await;
+        let failing: Vec<&String> = probe_stats
+            .iter()
+            .filter(|(_, stats)| stats.consecutive_failures > 0)
+            .map(|(name, _)| name)
+            .collect();
+
+        let status = if is_running && systems_count > 0 && failing.is_empty() {
             ServiceStatus::Healthy
         } else if is_running {
             ServiceStatus::Degraded
@@ -175,12 +229,24 @@ impl EnvironmentalScanner {
             name: "environmental-scanner".to_string(),
             status,
             check_duration_ms: 0, // Will be set by actual health check timing
-            error_message: None,
+            error_message: if failing.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "failing probes: {}",
+                    failing.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")
+                ))
+            },
             metrics: {
                 let mut metrics = HashMap::new();
                 metrics.insert("systems_discovered".to_string(), systems_count as f64);
                 metrics.insert("cache_size".to_string(), cache_size as f64);
-                metrics.insert("probes_count".to_string(), self.probes.len() as f64);
+                metrics.insert("probes_count".to_string(), probe_stats.len() as f64);
+                metrics.insert("probes_failing".to_string(), failing.len() as f64);
+                for (name, stats) in &probe_stats {
+                    metrics.insert(format!("probe_{}_success_rate", name), stats.success_rate());
+                    metrics.insert(format!("probe_{}_latency_ms", name), stats.average_latency_ms);
+                }
                 metrics
             },
         })
@@ -188,110 +254,65 @@ impl EnvironmentalScanner {
 
     /// Perform the actual scanning operation
     async fn perform_scan(&mut self) -> Result<(), DiscoveryError> {
-        debug!("Starting environmental scan with {} probes", self.probes.len());
+        let shared = self.shared();
+        let probes = shared.registry.lock().await.enabled();
+        debug!("Starting environmental scan with {} probes", probes.len());
 
-        let mut new_systems = HashMap::new();
-        let mut total_resources = ResourceAvailability {
-            cpu_cores: 0,
-            memory_mb: 0,
-            disk_gb: 0,
-            network_mbps: 0,
-        };
-
-        // Run all probes concurrently
-        let scan_timeout = Duration::from_secs(self.config.max_scan_duration_seconds);
-        let probe_futures = self.probes.iter().map(|probe| async {
-            timeout(scan_timeout, probe.scan()).await
-        });
-
-        for (probe_index, result) in probe_futures.enumerate() {
-            match result {
-                Ok(Ok(system_info)) => {
-                    debug!("Probe {} discovered system: {}", probe_index, system_info.name);
-
-                    let system_id = format!("{}-{}", system_info.system_type, system_info.name);
-                    let discovered_system = DiscoveredSystem {
-                        id: system_id.clone(),
-                        name: system_info.name,
-                        system_type: system_info.system_type,
-                        address: system_info.address,
-                        port: system_info.port,
-                        status: SystemStatus::Online,
-                        capabilities: system_info.capabilities,
-                        resources: system_info.resources,
-                        metadata: system_info.metadata,
-                        discovered_at: Utc::now(),
-                        updated_at: Utc::now(),
-                    };
-
-                    new_systems.insert(system_id.clone(), discovered_system);
-
-                    // Update resource totals
-                    if let Some(cpu) = system_info.resources.cpu_cores {
-                        total_resources.cpu_cores += cpu;
-                    }
-                    if let Some(memory) = system_info.resources.memory_mb {
-                        total_resources.memory_mb += memory;
-                    }
-                    if let Some(disk) = system_info.resources.disk_gb {
-                        total_resources.disk_gb += disk;
-                    }
-                    if let Some(network) = system_info.resources.network_mbps {
-                        total_resources.network_mbps += network;
-                    }
-                }
-                Ok(Err(e)) => {
-                    warn!("Probe {} failed: {}", probe_index, e);
-                }
-                Err(_) => {
-                    error!("Probe {} timed out", probe_index);
-                }
-            }
+        for name in probes {
+            // A failed probe is recorded in its stats and keeps its last results
+            let _ = shared.run(&name).await;
         }
 
-        // Update systems
-        *self.systems.lock().await = new_systems;
-
-        // Update resource inventory
-        let mut resource_inventory = self.resource_inventory.lock().await;
-        resource_inventory.total_cpu_cores = total_resources.cpu_cores;
-        resource_inventory.total_memory_mb = total_resources.memory_mb;
-        resource_inventory.total_disk_gb = total_resources.disk_gb;
-        resource_inventory.available = total_resources;
-
-        // Update discovery cache
-        let mut cache = self.discovery_cache.lock().await;
-        cache.systems = self.systems.lock().await.clone();
-        cache.last_scan = Utc::now();
-
         debug!("Environmental scan completed");
         Ok(())
     }
 
-    /// Perform periodic scan (lighter version of full scan)
-    async fn perform_periodic_scan(
-        config: &ScannerConfig,
-        systems: &Arc<Mutex<HashMap<SystemId, DiscoveredSystem>>>,
-        network_topology: &Arc<Mutex<NetworkTopology>>,
-        resource_inventory: &Arc<Mutex<ResourceInventory>>,
-        discovery_cache: &Arc<Mutex<DiscoveryCache>>,
-    ) -> Result<(), DiscoveryError> {
-        debug!("Performing periodic environmental scan");
-
-        // Quick health checks on existing systems
-        let mut systems_map = systems.lock().await;
-        for system in systems_map.values_mut() {
-            // Update system status based on connectivity
-            system.updated_at = Utc::now();
-            // In a real implementation, this would ping the system or check its health endpoint
+    /// Run probes as they fall due until the scanner is stopped
+    async fn run_scheduler(shared: ScanShared, is_running: Arc<Mutex<bool>>) {
+        let mut running = JoinSet::new();
+
+        while *is_running.lock().await {
+            let due = {
+                let mut registry = shared.registry.lock().await;
+                registry
+                    .due(Instant::now())
+                    .into_iter()
+                    .filter_map(|name| registry.begin(&name).map(|run| (name, run)))
+                    .collect::<Vec<_>>()
+            };
+            for (name, (probe, limit)) in due {
+                let shared = shared.clone();
+                running.spawn(async move {
+                    let _ = shared.complete(&name, run_probe(probe.as_ref(), limit).await).await;
+                });
+            }
+
+            let wake = shared
+                .registry
+                .lock()
+                .await
+                .next_due()
+                .map_or(Instant::now() + SCHEDULER_POLL, |due| {
+                    due.min(Instant::now() + SCHEDULER_POLL)
+                });
+            tokio::select! {
+                _ = tokio::time::sleep_until(wake) => {}
+                // A finished run has rescheduled its probe
+                Some(_) = running.join_next(), if !running.is_empty() => {}
+            }
         }
 
-        // Update cache
-        let mut cache = discovery_cache.lock().await;
-        cache.last_scan = Utc::now();
+        running.shutdown().await;
+    }
 
-        debug!("Periodic environmental scan completed");
-        Ok(())
+    fn shared(&self) -> ScanShared {
+        ScanShared {
+            systems: self.systems.clone(),
+            resource_inventory: self.resource_inventory.clone(),
+            discovery_cache: self.discovery_cache.clone(),
+            registry: self.registry.clone(),
+            probe_results: self.probe_results.clone(),
+        }
     }
 
     /// Analyze scan results for issues
@@ -362,11 +383,120 @@ impl DiscoveryCache {
     }
 }
 
+/// The parts of the scanner that probe runs update, shared with the scheduler task
+#[derive(Clone)]
+struct ScanShared {
+    systems: Arc<Mutex<HashMap<SystemId, DiscoveredSystem>>>,
+    resource_inventory: Arc<Mutex<ResourceInventory>>,
+    discovery_cache: Arc<Mutex<DiscoveryCache>>,
+    registry: Arc<Mutex<ProbeRegistry>>,
+    probe_results: Arc<Mutex<BTreeMap<String, Vec<DiscoveredSystem>>>>,
+}
+
+impl ScanShared {
+    /// Run a probe now and merge what it found
+    async fn run(&self, name: &str) -> Result<Vec<DiscoveredSystem>, DiscoveryError> {
+        let (probe, limit) = self
+            .registry
+            .lock()
+            .await
+            .begin(name)
+            .ok_or_else(|| DiscoveryError::ScannerError(format!("Unknown probe: {}", name)))?;
+        self.complete(name, run_probe(probe.as_ref(), limit).await).await
+    }
+
+    /// Record a finished run and replace the probe's systems with the ones it found
+    async fn complete(
+        &self,
+        name: &str,
+        (result, latency): (Result<Vec<SystemInfo>, ScanError>, Duration),
+    ) -> Result<Vec<DiscoveredSystem>, DiscoveryError> {
+        let outcome = result.as_ref().map(Vec::len).map_err(ToString::to_string);
+        self.registry
+            .lock()
+            .await
+            .finish(name, outcome, latency, Instant::now());
+
+        let found = match result {
+            Ok(found) => found,
+            Err(e) => {
+                warn!("Probe {} failed after {:?}: {}", name, latency, e);
+                return Err(DiscoveryError::ScannerError(format!("Probe {} failed: {}", name, e)));
+            }
+        };
+        debug!("Probe {} discovered {} systems in {:?}", name, found.len(), latency);
+
+        let now = Utc::now();
+        let discovered: Vec<DiscoveredSystem> = found
+            .into_iter()
+            .map(|system_info| DiscoveredSystem {
+                id: format!("{:?}-{}", system_info.system_type, system_info.name).to_lowercase(),
+                name: system_info.name,
+                system_type: system_info.system_type,
+                address: system_info.address,
+                port: system_info.port,
+                status: SystemStatus::Online,
+                capabilities: system_info.capabilities,
+                resources: system_info.resources,
+                metadata: system_info.metadata,
+                discovered_at: now,
+                updated_at: now,
+            })
+            .collect();
+
+        self.probe_results
+            .lock()
+            .await
+            .insert(name.to_string(), discovered.clone());
+        self.rebuild().await;
+        Ok(discovered)
+    }
+
+    /// Recompute systems, resource totals and the cache from every probe's latest results
+    async fn rebuild(&self) {
+        let mut new_systems = HashMap::new();
+        let mut total_resources = ResourceAvailability {
+            cpu_cores: 0,
+            memory_mb: 0,
+            disk_gb: 0,
+            network_mbps: 0,
+        };
+
+        for system in self.probe_results.lock().await.values().flatten() {
+            // Containers and services run on hosts that are already counted.
+            if system.system_type == SystemType::Server {
+                let resources = &system.resources;
+                total_resources.cpu_cores += resources.cpu_cores.unwrap_or(0);
+                total_resources.memory_mb += resources.memory_mb.unwrap_or(0);
+                total_resources.disk_gb += resources.disk_gb.unwrap_or(0);
+                total_resources.network_mbps += resources.network_mbps.unwrap_or(0);
+            }
+            new_systems.insert(system.id.clone(), system.clone());
+        }
+
+        // Update resource inventory
+        {
+            let mut resource_inventory = self.resource_inventory.lock().await;
+            resource_inventory.total_cpu_cores = total_resources.cpu_cores;
+            resource_inventory.total_memory_mb = total_resources.memory_mb;
+            resource_inventory.total_disk_gb = total_resources.disk_gb;
+            resource_inventory.available = total_resources;
+        }
+
+        // Update discovery cache
+        let mut cache = self.discovery_cache.lock().await;
+        cache.systems = new_systems.clone();
+        cache.last_scan = Utc::now();
+
+        *self.systems.lock().await = new_systems;
+    }
+}
+
 /// Trait for system probes
 #[async_trait]
 pub trait SystemProbe: Send + Sync {
-    /// Perform the scan operation
-    async fn scan(&self) -> Result<SystemInfo, ScanError>;
+    /// Perform the scan operation, returning every system the probe found
+    async fn scan(&self) -> Result<Vec<SystemInfo>, ScanError>;
 
     /// Get the probe type
     fn get_probe_type(&self) -> ProbeType;
@@ -405,28 +535,22 @@ pub struct SystemInfo {
 }
 
 /// Local system probe for discovering the local machine
-struct LocalSystemProbe {
-    config: HashMap<String, String>,
-}
+#[derive(Default)]
+pub struct LocalSystemProbe;
 
 impl LocalSystemProbe {
-    fn new() -> Self {
-        Self {
-            config: HashMap::new(),
-        }
+    pub fn new() -> Self {
+        Self
     }
 }
 
 #[async_trait]
 impl SystemProbe for LocalSystemProbe {
-    async fn scan(&self) -> Result<SystemInfo, ScanError> {
+    async fn scan(&self) -> Result<Vec<SystemInfo>, ScanError> {
         // Get local system information
         let sys = sysinfo::System::new_all();
 
         let total_memory = sys.total_memory();
-        let used_memory = sys.used_memory();
-        let total_swap = sys.total_swap();
-        let used_swap = sys.used_swap();
 
         let resources = SystemResources {
             cpu_cores: Some(sys.cpus().len() as u32),
@@ -436,8 +560,8 @@ impl SystemProbe for LocalSystemProbe {
             gpu_info: None,
         };
 
-        Ok(SystemInfo {
-            name: sys.host_name().unwrap_or_else(|| "localhost".to_string()),
+        Ok(vec![SystemInfo {
+            name: sysinfo::System::host_name().unwrap_or_else(|| "localhost".to_string()),
             system_type: SystemType::Server,
             address: "127.0.0.1".to_string(),
             port: None,
@@ -448,12 +572,12 @@ impl SystemProbe for LocalSystemProbe {
             resources,
             metadata: {
                 let mut metadata = HashMap::new();
-                metadata.insert("os".to_string(), sys.os_version().unwrap_or_default());
-                metadata.insert("kernel".to_string(), sys.kernel_version().unwrap_or_default());
-                metadata.insert("uptime".to_string(), sys.uptime().to_string());
+                metadata.insert("os".to_string(), sysinfo::System::os_version().unwrap_or_default());
+                metadata.insert("kernel".to_string(), sysinfo::System::kernel_version().unwrap_or_default());
+                metadata.insert("uptime".to_string(), sysinfo::System::uptime().to_string());
                 metadata
             },
-        })
+        }])
     }
 
     fn get_probe_type(&self) -> ProbeType {
@@ -461,51 +585,201 @@ impl SystemProbe for LocalSystemProbe {
     }
 }
 
-/// Network probe for discovering network services
-struct NetworkProbe {
-    config: HashMap<String, String>,
+/// Network probe that finds hosts by TCP connect scanning the configured ranges
+pub struct NetworkProbe {
+    config: NetworkScanConfig,
 }
 
 impl NetworkProbe {
-    fn new() -> Self {
-        Self {
-            config: HashMap::new(),
-        }
+    pub fn new(config: NetworkScanConfig) -> Self {
+        Self { config }
     }
 }
 
 #[async_trait]
 impl SystemProbe for NetworkProbe {
-    async fn scan(&self) -> Result<SystemInfo, ScanError> {
-        // Network scanning implementation would go here
-        // This is a placeholder that would implement actual network discovery
-        Err(ScanError::NetworkTimeout("Network probe not fully implemented".to_string()))
+    async fn scan(&self) -> Result<Vec<SystemInfo>, ScanError> {
+        let hosts = expand_ranges(&self.config.ranges, self.config.max_hosts)?;
+        let connect_timeout = Duration::from_millis(self.config.connect_timeout_ms);
+        let limit = Arc::new(Semaphore::new(self.config.max_concurrent_connections.max(1)));
+
+        let mut attempts = JoinSet::new();
+        for &host in &hosts {
+            for &port in &self.config.ports {
+                let permit = limit
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| ScanError::Cancelled)?;
+                attempts.spawn(async move {
+                    let _permit = permit;
+                    let address = SocketAddr::from((host, port));
+                    let open = matches!(
+                        timeout(connect_timeout, TcpStream::connect(address)).await,
+                        Ok(Ok(_))
+                    );
+                    (host, port, open)
+                });
+            }
+        }
+
+        let mut open_ports: BTreeMap<Ipv4Addr, Vec<u16>> = BTreeMap::new();
+        while let Some(attempt) = attempts.join_next().await {
+            if let Ok((host, port, true)) = attempt {
+                open_ports.entry(host).or_default().push(port);
+            }
+        }
+        debug!(
+            "Network probe found {} responsive hosts out of {}",
+            open_ports.len(),
+            hosts.len()
+        );
+
+        Ok(open_ports
+            .into_iter()
+            .map(|(host, mut ports)| {
+                ports.sort_unstable();
+                let mut metadata = HashMap::new();
+                metadata.insert(
+                    "open_ports".to_string(),
+                    ports.iter().map(u16::to_string).collect::<Vec<_>>().join(","),
+                );
+                SystemInfo {
+                    name: host.to_string(),
+                    system_type: SystemType::Server,
+                    address: host.to_string(),
+                    port: ports.first().copied(),
+                    capabilities: ports.iter().map(|port| format!("tcp/{}", port)).collect(),
+                    resources: SystemResources::default(),
+                    metadata,
+                }
+            })
+            .collect())
     }
 
     fn get_probe_type(&self) -> ProbeType {
         ProbeType::Network
     }
+
+    fn is_enabled(&self) -> bool {
+        !self.config.ranges.is_empty() && !self.config.ports.is_empty()
+    }
 }
 
-/// Container probe for discovering containers
-struct ContainerProbe {
-    config: HashMap<String, String>,
+/// Expand IPv4 CIDR ranges (or single addresses) into host addresses, leaving out
+/// network and broadcast addresses
+fn expand_ranges(ranges: &[String], max_hosts: usize) -> Result<Vec<Ipv4Addr>, ScanError> {
+    let mut hosts = BTreeSet::new();
+    for range in ranges {
+        let invalid = || ScanError::InvalidTarget(range.clone());
+        let (address, prefix) = match range.split_once('/') {
+            Some((address, prefix)) => (address, prefix.parse::<u32>().map_err(|_| invalid())?),
+            None => (range.as_str(), 32),
+        };
+        let address: Ipv4Addr = address.trim().parse().map_err(|_| invalid())?;
+        if prefix > 32 {
+            return Err(invalid());
+        }
+
+        let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+        let network = u32::from(address) & mask;
+        let broadcast = network | !mask;
+        let (first, last) = if prefix >= 31 {
+            (network, broadcast)
+        } else {
+            (network + 1, broadcast - 1)
+        };
+        if (last - first) as usize + hosts.len() >= max_hosts {
+            return Err(ScanError::InvalidTarget(format!(
+                "{} exceeds the limit of {} hosts",
+                range, max_hosts
+            )));
+        }
+        hosts.extend((first..=last).map(Ipv4Addr::from));
+    }
+    Ok(hosts.into_iter().collect())
+}
+
+/// Container probe reading the cgroup v2 hierarchy and `/proc/*/cgroup`
+pub struct ContainerProbe {
+    proc: ProcFs,
+    cgroup_root: PathBuf,
 }
 
 impl ContainerProbe {
-    fn new() -> Self {
-        Self {
-            config: HashMap::new(),
-        }
+    pub fn new(proc: ProcFs, cgroup_root: PathBuf) -> Self {
+        Self { proc, cgroup_root }
     }
 }
 
 #[async_trait]
 impl SystemProbe for ContainerProbe {
-    async fn scan(&self) -> Result<SystemInfo, ScanError> {
-        // Container discovery implementation would go here
-        // This is a placeholder that would implement actual container discovery
-        Err(ScanError::NetworkTimeout("Container probe not fully implemented".to_string()))
+    async fn scan(&self) -> Result<Vec<SystemInfo>, ScanError> {
+        let mut containers: BTreeMap<String, (ContainerRef, Option<PathBuf>, Vec<u32>)> =
+            BTreeMap::new();
+
+        // Hosts without a cgroup v2 mount still show container membership in /proc.
+        if self.cgroup_root.join("cgroup.controllers").exists() {
+            let found = procfs::find_container_cgroups(&self.cgroup_root)
+                .map_err(|e| ScanError::Unavailable(format!("{}: {}", self.cgroup_root.display(), e)))?;
+            for (container, dir) in found {
+                containers.insert(container.id.clone(), (container, Some(dir), Vec::new()));
+            }
+        }
+        for pid in self.proc.pids().unwrap_or_default() {
+            let Some(container) = self
+                .proc
+                .cgroup(pid)
+                .and_then(|path| procfs::container_from_cgroup(&path))
+            else {
+                continue;
+            };
+            containers
+                .entry(container.id.clone())
+                .or_insert_with(|| (container, None, Vec::new()))
+                .2
+                .push(pid);
+        }
+
+        Ok(containers
+            .into_values()
+            .map(|(container, dir, proc_pids)| {
+                let stats = dir.as_deref().map(procfs::read_cgroup_stats).unwrap_or_default();
+                let mut pids = stats.pids.clone();
+                pids.extend(proc_pids);
+                pids.sort_unstable();
+                pids.dedup();
+
+                let mut metadata = HashMap::new();
+                metadata.insert("container_id".to_string(), container.id.clone());
+                metadata.insert("runtime".to_string(), container.runtime.clone());
+                metadata.insert("pids".to_string(), pids.len().to_string());
+                if let Some(process) = pids.first().and_then(|&pid| self.proc.comm(pid)) {
+                    metadata.insert("process".to_string(), process);
+                }
+                if let Some(dir) = &dir {
+                    let cgroup = dir.strip_prefix(&self.cgroup_root).unwrap_or(dir);
+                    metadata.insert("cgroup".to_string(), format!("/{}", cgroup.display()));
+                }
+                if let Some(current) = stats.memory_current {
+                    metadata.insert("memory_current_bytes".to_string(), current.to_string());
+                }
+
+                SystemInfo {
+                    name: container.id[..12].to_string(),
+                    system_type: SystemType::Container,
+                    address: "127.0.0.1".to_string(),
+                    port: None,
+                    capabilities: vec![format!("runtime/{}", container.runtime)],
+                    resources: SystemResources {
+                        cpu_cores: stats.cpu_limit.map(|cores| cores.ceil() as u32),
+                        memory_mb: stats.memory_limit.map(|bytes| bytes / 1024 / 1024),
+                        ..SystemResources::default()
+                    },
+                    metadata,
+                }
+            })
+            .collect())
     }
 
     fn get_probe_type(&self) -> ProbeType {
@@ -513,25 +787,67 @@ impl SystemProbe for ContainerProbe {
     }
 }
 
-/// Service probe for discovering running services
-struct ServiceProbe {
-    config: HashMap<String, String>,
+/// Service probe mapping listening sockets in `/proc/net/tcp{,6}` to their processes
+pub struct ServiceProbe {
+    proc: ProcFs,
 }
 
 impl ServiceProbe {
-    fn new() -> Self {
-        Self {
-            config: HashMap::new(),
-        }
+    pub fn new(proc: ProcFs) -> Self {
+        Self { proc }
     }
 }
 
 #[async_trait]
 impl SystemProbe for ServiceProbe {
-    async fn scan(&self) -> Result<SystemInfo, ScanError> {
-        // Service discovery implementation would go here
-        // This is a placeholder that would implement actual service discovery
-        Err(ScanError::NetworkTimeout("Service probe not fully implemented".to_string()))
+    async fn scan(&self) -> Result<Vec<SystemInfo>, ScanError> {
+        let sockets = self.proc.listening_sockets().map_err(|e| {
+            ScanError::Unavailable(format!("{}/net/tcp: {}", self.proc.root().display(), e))
+        })?;
+        // Sockets of other users' processes cannot be attributed without privileges.
+        let owners = self.proc.socket_owners();
+
+        let mut services: BTreeMap<String, SystemInfo> = BTreeMap::new();
+        for socket in sockets {
+            let pid = owners.get(&socket.inode).copied();
+            let process = pid
+                .and_then(|pid| self.proc.comm(pid))
+                .unwrap_or_else(|| "unknown".to_string());
+            let port = socket.local.port();
+            let name = format!("{}:{}", process, port);
+
+            // Dual-stack services listen on the same port in both tables.
+            let service = services.entry(name.clone()).or_insert_with(|| {
+                let mut metadata = HashMap::new();
+                metadata.insert("process".to_string(), process.clone());
+                metadata.insert("uid".to_string(), socket.uid.to_string());
+                if let Some(pid) = pid {
+                    metadata.insert("pid".to_string(), pid.to_string());
+                    if let Some(container) = self
+                        .proc
+                        .cgroup(pid)
+                        .and_then(|path| procfs::container_from_cgroup(&path))
+                    {
+                        metadata.insert("container_id".to_string(), container.id);
+                    }
+                }
+                SystemInfo {
+                    name,
+                    system_type: SystemType::Service,
+                    address: socket.local.ip().to_string(),
+                    port: Some(port),
+                    capabilities: Vec::new(),
+                    resources: SystemResources::default(),
+                    metadata,
+                }
+            });
+            let protocol = if socket.local.is_ipv4() { "tcp" } else { "tcp6" };
+            if !service.capabilities.iter().any(|c| c == protocol) {
+                service.capabilities.push(protocol.to_string());
+            }
+        }
+
+        Ok(services.into_values().collect())
     }
 
     fn get_probe_type(&self) -> ProbeType {
@@ -542,6 +858,8 @@ impl SystemProbe for ServiceProbe {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
+    use std::path::Path;
 
     #[tokio::test]
     async fn test_environmental_scanner_creation() {
@@ -556,7 +874,7 @@ mod tests {
         let result = probe.scan().await;
         assert!(result.is_ok());
 
-        let system_info = result.unwrap();
+        let system_info = result.unwrap().remove(0);
         assert_eq!(system_info.system_type, SystemType::Server);
         assert!(!system_info.name.is_empty());
         assert_eq!(system_info.address, "127.0.0.1");
@@ -567,13 +885,217 @@ mod tests {
         let local_probe = LocalSystemProbe::new();
         assert!(matches!(local_probe.get_probe_type(), ProbeType::LocalSystem));
 
-        let network_probe = NetworkProbe::new();
+        let network_probe = NetworkProbe::new(NetworkScanConfig::default());
         assert!(matches!(network_probe.get_probe_type(), ProbeType::Network));
+        assert!(!network_probe.is_enabled());
 
-        let container_probe = ContainerProbe::new();
+        let container_probe = ContainerProbe::new(ProcFs::default(), PathBuf::from("/sys/fs/cgroup"));
         assert!(matches!(container_probe.get_probe_type(), ProbeType::Container));
 
-        let service_probe = ServiceProbe::new();
+        let service_probe = ServiceProbe::new(ProcFs::default());
         assert!(matches!(service_probe.get_probe_type(), ProbeType::Service));
     }
-}
\ No newline at end of file
+
+    struct FixedProbe(Vec<&'static str>);
+
+    #[async_trait]
+    impl SystemProbe for FixedProbe {
+        async fn scan(&self) -> Result<Vec<SystemInfo>, ScanError> {
+            Ok(self
+                .0
+                .iter()
+                .map(|name| SystemInfo {
+                    name: name.to_string(),
+                    system_type: SystemType::Server,
+                    address: "10.0.0.1".to_string(),
+                    port: None,
+                    capabilities: Vec::new(),
+                    resources: SystemResources {
+                        cpu_cores: Some(4),
+                        ..SystemResources::default()
+                    },
+                    metadata: HashMap::new(),
+                })
+                .collect())
+        }
+
+        fn get_probe_type(&self) -> ProbeType {
+            ProbeType::Custom("fixed".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_schedules_follow_config() {
+        let mut config = ScannerConfig::default();
+        config.probes.insert(
+            "container".to_string(),
+            ProbeConfig {
+                enabled: Some(false),
+                ..ProbeConfig::default()
+            },
+        );
+        config.probes.insert(
+            "fixed".to_string(),
+            ProbeConfig {
+                interval_seconds: Some(5),
+                ..ProbeConfig::default()
+            },
+        );
+        let scanner = EnvironmentalScanner::new(config).await.unwrap();
+        scanner
+            .register_probe("fixed", Arc::new(FixedProbe(vec!["db-1"])))
+            .await
+            .unwrap();
+
+        let registry = scanner.registry.lock().await;
+        assert!(!registry.schedule("container").unwrap().enabled);
+        // No ranges configured
+        assert!(!registry.schedule("network").unwrap().enabled);
+        assert!(registry.schedule("local").unwrap().enabled);
+        assert_eq!(registry.schedule("fixed").unwrap().interval, Duration::from_secs(5));
+        assert_eq!(registry.schedule("fixed").unwrap().timeout, Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_scan_now_runs_a_single_probe() {
+        let scanner = EnvironmentalScanner::new(ScannerConfig::default()).await.unwrap();
+        scanner
+            .register_probe("fixed", Arc::new(FixedProbe(vec!["db-1", "db-2"])))
+            .await
+            .unwrap();
+
+        let found = scanner.scan_now("fixed").await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert!(scanner.scan_now("missing").await.is_err());
+
+        let state = scanner.get_state().await.unwrap();
+        assert!(state.systems.contains_key("server-db-1"));
+        assert_eq!(state.resource_inventory.total_cpu_cores, 8);
+
+        let stats = scanner.probe_stats().await;
+        assert_eq!(stats["fixed"].runs, 1);
+        assert_eq!(stats["local"].runs, 0);
+
+        assert!(scanner.unregister_probe("fixed").await);
+        assert!(scanner.get_state().await.unwrap().systems.is_empty());
+    }
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    /// A process with a `comm`, a `cgroup` and fds pointing at `sockets`
+    fn fixture_process(proc_root: &Path, pid: u32, comm: &str, cgroup: &str, sockets: &[u64]) {
+        let dir = proc_root.join(pid.to_string());
+        write(&dir.join("comm"), &format!("{}\n", comm));
+        write(&dir.join("cgroup"), &format!("0::{}\n", cgroup));
+        fs::create_dir_all(dir.join("fd")).unwrap();
+        for (fd, inode) in sockets.iter().enumerate() {
+            std::os::unix::fs::symlink(format!("socket:[{}]", inode), dir.join(format!("fd/{}", fd + 3)))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_expand_ranges() {
+        let hosts = expand_ranges(&["10.0.0.0/30".to_string(), "10.0.0.9".to_string()], 16).unwrap();
+        assert_eq!(
+            hosts,
+            vec![
+                Ipv4Addr::new(10, 0, 0, 1),
+                Ipv4Addr::new(10, 0, 0, 2),
+                Ipv4Addr::new(10, 0, 0, 9),
+            ]
+        );
+        assert!(expand_ranges(&["10.0.0.0/16".to_string()], 4096).is_err());
+        assert!(expand_ranges(&["10.0.0.0/33".to_string()], 4096).is_err());
+        assert!(expand_ranges(&["example.com".to_string()], 4096).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_network_probe_finds_open_ports() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = {
+            let unused = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            unused.local_addr().unwrap().port()
+        };
+
+        let probe = NetworkProbe::new(NetworkScanConfig {
+            ranges: vec!["127.0.0.1/32".to_string()],
+            ports: vec![open, closed],
+            max_concurrent_connections: 1,
+            ..NetworkScanConfig::default()
+        });
+        let systems = probe.scan().await.unwrap();
+        assert_eq!(systems.len(), 1);
+        assert_eq!(systems[0].port, Some(open));
+        assert_eq!(systems[0].capabilities, vec![format!("tcp/{}", open)]);
+    }
+
+    #[tokio::test]
+    async fn test_service_probe_against_fixture_proc() {
+        let proc_root = tempfile::tempdir().unwrap();
+        write(
+            &proc_root.path().join("net/tcp"),
+            "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 18211 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1F90 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000  1000        0 40555 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:18EB 00000000:0000 0A 00000000:00000000 00:00000000 00000000   999        0 51000 1 0000000000000000 100 0 0 10 0
+",
+        );
+        write(
+            &proc_root.path().join("net/tcp6"),
+            "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:0016 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 18212 1 0000000000000000 100 0 0 10 0
+",
+        );
+        fixture_process(proc_root.path(), 812, "sshd", "/system.slice/ssh.service", &[18211, 18212]);
+
+        let systems = ServiceProbe::new(ProcFs::new(proc_root.path())).scan().await.unwrap();
+        let names: Vec<&str> = systems.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["sshd:22", "unknown:6379"]);
+        assert_eq!(systems[0].capabilities, vec!["tcp", "tcp6"]);
+        assert_eq!(systems[0].metadata["pid"], "812");
+        assert_eq!(systems[1].address, "127.0.0.1");
+    }
+
+    #[tokio::test]
+    async fn test_container_probe_against_fixture_cgroups() {
+        let proc_root = tempfile::tempdir().unwrap();
+        let cgroup_root = tempfile::tempdir().unwrap();
+        let docker = "d".repeat(64);
+        let podman = "f".repeat(64);
+
+        write(&cgroup_root.path().join("cgroup.controllers"), "cpu memory pids\n");
+        let scope = cgroup_root.path().join(format!("system.slice/docker-{}.scope", docker));
+        write(&scope.join("cgroup.procs"), "4100\n4101\n");
+        write(&scope.join("cpu.max"), "150000 100000\n");
+        write(&scope.join("memory.max"), "536870912\n");
+        write(&scope.join("memory.current"), "104857600\n");
+        write(&cgroup_root.path().join("user.slice/cgroup.procs"), "1\n");
+
+        fixture_process(proc_root.path(), 4100, "nginx", &format!("/system.slice/docker-{}.scope", docker), &[]);
+        fixture_process(proc_root.path(), 5200, "redis-server", &format!("/machine.slice/libpod-{}.scope", podman), &[]);
+        fixture_process(proc_root.path(), 1, "systemd", "/init.scope", &[]);
+
+        let probe = ContainerProbe::new(ProcFs::new(proc_root.path()), cgroup_root.path().to_path_buf());
+        let systems = probe.scan().await.unwrap();
+        assert_eq!(systems.len(), 2);
+
+        let nginx = &systems[0];
+        assert_eq!(nginx.name, "dddddddddddd");
+        assert_eq!(nginx.system_type, SystemType::Container);
+        assert_eq!(nginx.resources.cpu_cores, Some(2));
+        assert_eq!(nginx.resources.memory_mb, Some(512));
+        assert_eq!(nginx.metadata["process"], "nginx");
+        assert_eq!(nginx.metadata["pids"], "2");
+        assert_eq!(nginx.metadata["runtime"], "docker");
+
+        let redis = &systems[1];
+        assert_eq!(redis.metadata["runtime"], "podman");
+        assert_eq!(redis.metadata["process"], "redis-server");
+        assert_eq!(redis.resources.memory_mb, None);
+    }
+}
@@ -6,10 +6,9 @@
 
 use crate::types::*;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::Mutex;
 use tokio::time::{timeout, Duration};
 use tracing::{debug, error, info, warn};
 
@@ -112,7 +111,9 @@ impl IntegrationHub {
                 Duration::from_secs(self.config.layer_timeout_seconds),
                 connection.send_data(data),
             )
-            .await??;
+            .await
+            .map_err(|_| DiscoveryError::TimeoutError(format!("Sending to layer {}", layer_id)))?
+            .map_err(|e| DiscoveryError::IntegrationError(e.to_string()))?;
         } else {
             return Err(DiscoveryError::IntegrationError(
                 format!("No connection available for layer: {}", layer_id)
@@ -131,15 +132,14 @@ impl IntegrationHub {
                 Duration::from_secs(self.config.layer_timeout_seconds),
                 connection.receive_data(),
             )
-            .await??;
+            .await
+            .map_err(|_| DiscoveryError::TimeoutError(format!("Receiving from layer {}", layer_id)))?
+            .map_err(|e| DiscoveryError::IntegrationError(e.to_string()))
         } else {
-            return Err(DiscoveryError::IntegrationError(
+            Err(DiscoveryError::IntegrationError(
                 format!("No connection available for layer: {}", layer_id)
-            ));
+            ))
         }
-
-        // This would return actual data in a real implementation
-        Err(DiscoveryError::IntegrationError("Not implemented".to_string()))
     }
 
     /// Get hub health status
@@ -420,6 +420,7 @@ impl LayerConnection for ExecutionLayerConnection {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::Utc;
 
     #[tokio::test]
     async fn test_integration_hub_creation() {
@@ -432,7 +433,7 @@ mod tests {
     async fn test_event_router() {
         let router = EventRouter::new();
 
-        let system_state = DiscoveryData::SystemStateUpdate(SystemState {
+        let system_state = DiscoveryData::SystemStateUpdate(Box::new(SystemState {
             environmental: EnvironmentalState {
                 systems: HashMap::new(),
                 network_topology: NetworkTopology {
@@ -488,7 +489,7 @@ mod tests {
                 last_collection: Utc::now(),
             },
             timestamp: Utc::now(),
-        });
+        }));
 
         let result = router.route_event(system_state).await;
         assert!(result.is_ok());
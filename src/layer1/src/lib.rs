@@ -35,6 +35,11 @@ pub mod system_monitor;
 pub mod data_collector;
 pub mod integration_hub;
 pub mod metrics;
+pub mod alerting;
+pub mod log_source;
+pub mod probe_registry;
+pub mod procfs;
+pub mod timeseries;
 
 pub use types::*;
 pub use environmental_scanner::*;
@@ -42,6 +47,10 @@ pub use system_monitor::*;
 pub use data_collector::*;
 pub use integration_hub::*;
 pub use metrics::*;
+pub use alerting::{AlertManager, AlertNotification, AlertObservation, Notifier};
+pub use log_source::ApplicationLogsSource;
+pub use probe_registry::{ProbeRegistry, ProbeSchedule, ProbeStats};
+pub use timeseries::{Rollup, Sample, SeriesKey, TimeSeriesStore};
 
 /// Main discovery service that orchestrates all Layer 1 components
 pub struct DiscoveryService {
@@ -69,6 +78,11 @@ impl DiscoveryService {
         })
     }
 
+    /// Configuration the service was created with
+    pub fn config(&self) -> &DiscoveryConfig {
+        &self.config
+    }
+
     /// Start the discovery service and all its components
     pub async fn start(&mut self) -> Result<(), DiscoveryError> {
         tracing::info!("Starting Layer 1 Discovery Service");
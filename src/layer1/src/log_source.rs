@@ -0,0 +1,702 @@
+//! # Log Source
+//!
+//! Follows application log files and turns their lines into data points. Files are tracked by
+//! inode and offset, so both rotation (the path now names a different file) and truncation (the
+//! file shrank below the offset) are noticed, and the rest of a rotated file is still read from
+//! the open handle before moving on. Offsets can be persisted so that a restarted collector
+//! resumes where the previous one stopped.
+//!
+//! Lines are parsed as JSON, logfmt, a configured regular expression or the platform's own
+//! audit events. Every collection reports entry counts per level and the error rate of each
+//! file, along with one `log_entry` point per line carrying the extracted fields as tags. The
+//! time-series store keys those points by source and level only.
+
+use crate::data_collector::DataSource;
+use crate::types::*;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// Levels counted towards the error rate
+const ERROR_LEVELS: &[&str] = &["error", "err", "fatal", "critical", "crit", "alert", "emerg", "panic"];
+
+/// Field names holding the level, message and time of an entry, in order of preference
+const LEVEL_FIELDS: &[&str] = &["level", "lvl", "severity"];
+const MESSAGE_FIELDS: &[&str] = &["msg", "message"];
+const TIMESTAMP_FIELDS: &[&str] = &["timestamp", "time", "ts", "@timestamp"];
+
+/// A parsed log line
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogRecord {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub level: Option<String>,
+    pub message: Option<String>,
+    /// Every extracted field, including the ones level, message and timestamp came from
+    pub fields: BTreeMap<String, String>,
+}
+
+impl LogRecord {
+    /// Build a record, taking level, message and timestamp from well-known field names
+    fn from_fields(fields: BTreeMap<String, String>) -> Self {
+        let find = |names: &[&str]| names.iter().find_map(|name| fields.get(*name).cloned());
+        Self {
+            timestamp: find(TIMESTAMP_FIELDS).and_then(|value| parse_timestamp(&value)),
+            level: find(LEVEL_FIELDS).map(|level| level.to_lowercase()),
+            message: find(MESSAGE_FIELDS),
+            fields,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level
+            .as_deref()
+            .is_some_and(|level| ERROR_LEVELS.contains(&level))
+    }
+}
+
+/// RFC 3339 or unix time in seconds or milliseconds
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    let number: f64 = value.parse().ok()?;
+    // Seconds since the epoch stay below 1e11 until the year 5138.
+    let millis = if number.abs() < 1e11 { number * 1000.0 } else { number };
+    Utc.timestamp_millis_opt(millis as i64).single()
+}
+
+/// Flatten a JSON object into dotted keys
+fn flatten_json(prefix: &str, value: &serde_json::Value, fields: &mut BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_json(&key, value, fields);
+            }
+        }
+        serde_json::Value::String(text) => {
+            fields.insert(prefix.to_string(), text.clone());
+        }
+        serde_json::Value::Null => {}
+        other => {
+            fields.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+/// Parse a line holding one JSON object
+pub fn parse_json_line(line: &str) -> Option<LogRecord> {
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    if !value.is_object() {
+        return None;
+    }
+    let mut fields = BTreeMap::new();
+    flatten_json("", &value, &mut fields);
+    Some(LogRecord::from_fields(fields))
+}
+
+/// Parse a line of `key=value` pairs; values may be double-quoted with backslash escapes and
+/// a bare key is read as `true`
+pub fn parse_logfmt_line(line: &str) -> Option<LogRecord> {
+    let mut fields = BTreeMap::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        if key.is_empty() {
+            if chars.next().is_none() {
+                break;
+            }
+            // A stray `=` without a key
+            continue;
+        }
+
+        if chars.next_if_eq(&'=').is_none() {
+            fields.insert(key, "true".to_string());
+            continue;
+        }
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' => break,
+                    '\\' => match chars.next() {
+                        Some('n') => value.push('\n'),
+                        Some('t') => value.push('\t'),
+                        Some(escaped) => value.push(escaped),
+                        None => break,
+                    },
+                    c => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        fields.insert(key, value);
+    }
+
+    // Free text with no pairs at all is not logfmt.
+    if !line.contains('=') {
+        return None;
+    }
+    Some(LogRecord::from_fields(fields))
+}
+
+/// Parse a line with the named groups of `pattern`
+pub fn parse_regex_line(pattern: &Regex, line: &str) -> Option<LogRecord> {
+    let captures = pattern.captures(line)?;
+    let fields = pattern
+        .capture_names()
+        .flatten()
+        .filter_map(|name| Some((name.to_string(), captures.name(name)?.as_str().to_string())))
+        .collect();
+    Some(LogRecord::from_fields(fields))
+}
+
+/// Parse an audit event written by the platform's audit logger. Severity maps to a level so
+/// that high and critical events count as errors.
+pub fn parse_audit_line(line: &str) -> Option<LogRecord> {
+    let mut record = parse_json_line(line)?;
+    let field = |name: &str| record.fields.get(name).cloned().unwrap_or_default();
+    let level = match field("severity").as_str() {
+        "Critical" => "critical",
+        "High" => "error",
+        "Medium" => "warning",
+        "Low" => "info",
+        _ => return None,
+    };
+    record.message = Some(format!("{} {}: {}", field("action"), field("resource"), field("result")));
+    record.level = Some(level.to_string());
+    Some(record)
+}
+
+/// Read position within a followed file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileOffset {
+    pub inode: u64,
+    pub offset: u64,
+}
+
+/// Follows one file across rotation and truncation
+pub struct LogFollower {
+    path: PathBuf,
+    from_beginning: bool,
+    file: Option<File>,
+    position: Option<FileOffset>,
+}
+
+impl LogFollower {
+    /// Follow `path`, resuming at `saved` if it still describes the file there
+    pub fn new(path: impl Into<PathBuf>, saved: Option<FileOffset>, from_beginning: bool) -> Self {
+        Self {
+            path: path.into(),
+            from_beginning,
+            file: None,
+            position: saved,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Where the next read starts
+    pub fn position(&self) -> Option<FileOffset> {
+        self.position
+    }
+
+    /// Complete lines appended since the previous call, reading at most `max_bytes` from each
+    /// file. A missing file yields no lines; it may be between rotation and recreation.
+    pub fn read_lines(&mut self, max_bytes: u64) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        let current_inode = match fs::metadata(&self.path) {
+            Ok(metadata) => Some(metadata.ino()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+
+        // Rotated: finish the file still open before moving on to the new one
+        let mut rotated = false;
+        if let (Some(file), Some(position)) = (self.file.as_mut(), self.position.as_mut()) {
+            if current_inode != Some(position.inode) {
+                read_from(file, position, max_bytes, true, &mut lines)?;
+                self.file = None;
+                self.position = None;
+                rotated = true;
+            }
+        }
+        if current_inode.is_none() {
+            return Ok(lines);
+        }
+
+        if self.file.is_none() {
+            let file = File::open(&self.path)?;
+            let metadata = file.metadata()?;
+            let offset = match self.position {
+                Some(saved) if saved.inode == metadata.ino() && saved.offset <= metadata.len() => saved.offset,
+                // The file was replaced while nothing was following it.
+                Some(_) => 0,
+                None if rotated || self.from_beginning => 0,
+                None => metadata.len(),
+            };
+            self.position = Some(FileOffset {
+                inode: metadata.ino(),
+                offset,
+            });
+            self.file = Some(file);
+        }
+
+        if let (Some(file), Some(position)) = (self.file.as_mut(), self.position.as_mut()) {
+            if file.metadata()?.len() < position.offset {
+                // Truncated in place, e.g. by copytruncate rotation
+                position.offset = 0;
+            }
+            read_from(file, position, max_bytes, false, &mut lines)?;
+        }
+        Ok(lines)
+    }
+}
+
+/// Read complete lines from `position`, advancing it past them. A trailing line without a
+/// newline is left for the next read unless the file is `finished` or the line alone fills
+/// `max_bytes`.
+fn read_from(
+    file: &mut File,
+    position: &mut FileOffset,
+    max_bytes: u64,
+    finished: bool,
+    lines: &mut Vec<String>,
+) -> io::Result<()> {
+    file.seek(SeekFrom::Start(position.offset))?;
+    let mut buffer = Vec::new();
+    file.take(max_bytes).read_to_end(&mut buffer)?;
+
+    let mut consumed = 0;
+    for line in buffer.split_inclusive(|byte| *byte == b'\n') {
+        let complete = line.ends_with(b"\n");
+        let oversized = consumed == 0 && buffer.len() as u64 >= max_bytes;
+        if !complete && !finished && !oversized {
+            break;
+        }
+        consumed += line.len();
+        let text = String::from_utf8_lossy(line);
+        lines.push(text.trim_end_matches(['\n', '\r']).to_string());
+    }
+    position.offset += consumed as u64;
+    Ok(())
+}
+
+/// Saved offsets keyed by file path; unreadable state starts over
+fn load_offsets(path: &Path) -> HashMap<PathBuf, FileOffset> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable log offsets in {}: {}", path.display(), e);
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+/// Write offsets through a temporary file so a crash never leaves a torn file behind
+fn save_offsets(path: &Path, offsets: &HashMap<PathBuf, FileOffset>) -> io::Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, serde_json::to_vec_pretty(offsets)?)?;
+    fs::rename(&temporary, path)
+}
+
+struct FollowedFile {
+    name: String,
+    format: LogFormat,
+    pattern: Option<Regex>,
+    numeric_fields: Vec<String>,
+    follower: LogFollower,
+}
+
+impl FollowedFile {
+    fn parse(&self, line: &str) -> Option<LogRecord> {
+        match self.format {
+            LogFormat::Json => parse_json_line(line),
+            LogFormat::Logfmt => parse_logfmt_line(line),
+            LogFormat::Regex => parse_regex_line(self.pattern.as_ref()?, line),
+            LogFormat::Audit => parse_audit_line(line),
+        }
+    }
+}
+
+/// Application logs data source following the configured log files
+pub struct ApplicationLogsSource {
+    source_id: SourceId,
+    config: LogSourceConfig,
+    files: Mutex<Vec<FollowedFile>>,
+}
+
+impl ApplicationLogsSource {
+    pub fn new(config: LogSourceConfig) -> Result<Self, DiscoveryError> {
+        let saved = config
+            .offsets_path
+            .as_deref()
+            .map(load_offsets)
+            .unwrap_or_default();
+
+        let mut files = Vec::new();
+        for file in &config.files {
+            let pattern = match (file.format, &file.pattern) {
+                (LogFormat::Regex, Some(pattern)) => Some(Regex::new(pattern).map_err(|e| {
+                    DiscoveryError::ConfigurationError(format!("{}: {}", file.path.display(), e))
+                })?),
+                (LogFormat::Regex, None) => {
+                    return Err(DiscoveryError::ConfigurationError(format!(
+                        "{}: the regex format needs a pattern",
+                        file.path.display()
+                    )))
+                }
+                _ => None,
+            };
+            let name = file.name.clone().unwrap_or_else(|| {
+                file.path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| file.path.display().to_string())
+            });
+            files.push(FollowedFile {
+                name,
+                format: file.format,
+                pattern,
+                numeric_fields: file.numeric_fields.clone(),
+                follower: LogFollower::new(
+                    file.path.clone(),
+                    saved.get(&file.path).copied(),
+                    file.from_beginning,
+                ),
+            });
+        }
+
+        Ok(Self {
+            source_id: "application-logs".to_string(),
+            config,
+            files: Mutex::new(files),
+        })
+    }
+}
+
+#[async_trait]
+impl DataSource for ApplicationLogsSource {
+    async fn collect_data(&self) -> Result<DataBatch, CollectionError> {
+        let timestamp = Utc::now();
+        let mut files = self.files.lock().await;
+
+        let mut data_points = Vec::new();
+        let mut unreadable = Vec::new();
+        let mut parsed_lines = 0u64;
+        let mut failed_lines = 0u64;
+        for file in files.iter_mut() {
+            let lines = match file.follower.read_lines(self.config.max_bytes_per_collection) {
+                Ok(lines) => lines,
+                Err(e) => {
+                    warn!("Cannot read {}: {}", file.follower.path().display(), e);
+                    unreadable.push(format!("{}: {}", file.follower.path().display(), e));
+                    continue;
+                }
+            };
+
+            let source_tag = ("source".to_string(), file.name.clone());
+            let mut levels: BTreeMap<String, u64> = BTreeMap::new();
+            let mut errors = 0u64;
+            let mut failures = 0u64;
+            for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+                let Some(record) = file.parse(line) else {
+                    failures += 1;
+                    continue;
+                };
+                let level = record.level.clone().unwrap_or_else(|| "unknown".to_string());
+                *levels.entry(level.clone()).or_default() += 1;
+                if record.is_error() {
+                    errors += 1;
+                }
+
+                let entry_time = record.timestamp.unwrap_or(timestamp);
+                for field in &file.numeric_fields {
+                    if let Some(value) = record.fields.get(field).and_then(|value| value.parse::<f64>().ok()) {
+                        data_points.push(DataPoint {
+                            metric_name: format!("log_{}", field),
+                            value,
+                            unit: "value".to_string(),
+                            tags: HashMap::from([source_tag.clone(), ("level".to_string(), level.clone())]),
+                            timestamp: entry_time,
+                        });
+                    }
+                }
+
+                let mut tags: HashMap<String, String> = record.fields.into_iter().collect();
+                tags.insert("level".to_string(), level);
+                if let Some(message) = record.message {
+                    tags.insert("message".to_string(), message);
+                }
+                tags.insert(source_tag.0.clone(), source_tag.1.clone());
+                data_points.push(DataPoint {
+                    metric_name: "log_entry".to_string(),
+                    value: 1.0,
+                    unit: "count".to_string(),
+                    tags,
+                    timestamp: entry_time,
+                });
+            }
+
+            let parsed: u64 = levels.values().sum();
+            parsed_lines += parsed;
+            failed_lines += failures;
+            for (level, count) in levels {
+                data_points.push(DataPoint {
+                    metric_name: "log_entries_count".to_string(),
+                    value: count as f64,
+                    unit: "count".to_string(),
+                    tags: HashMap::from([source_tag.clone(), ("level".to_string(), level)]),
+                    timestamp,
+                });
+            }
+            if parsed > 0 {
+                data_points.push(DataPoint {
+                    metric_name: "error_rate".to_string(),
+                    value: errors as f64 / parsed as f64,
+                    unit: "ratio".to_string(),
+                    tags: HashMap::from([source_tag.clone()]),
+                    timestamp,
+                });
+            }
+            if failures > 0 {
+                data_points.push(DataPoint {
+                    metric_name: "log_parse_failures".to_string(),
+                    value: failures as f64,
+                    unit: "count".to_string(),
+                    tags: HashMap::from([source_tag]),
+                    timestamp,
+                });
+            }
+        }
+
+        if let Some(path) = &self.config.offsets_path {
+            let offsets: HashMap<PathBuf, FileOffset> = files
+                .iter()
+                .filter_map(|file| Some((file.follower.path().to_path_buf(), file.follower.position()?)))
+                .collect();
+            if let Err(e) = save_offsets(path, &offsets) {
+                warn!("Cannot save log offsets to {}: {}", path.display(), e);
+            }
+        }
+
+        if !files.is_empty() && unreadable.len() == files.len() {
+            return Err(CollectionError::SourceUnavailable(unreadable.join("; ")));
+        }
+
+        let total_lines = parsed_lines + failed_lines;
+        Ok(DataBatch {
+            source_id: self.source_id.clone(),
+            timestamp,
+            data_points,
+            quality_score: if total_lines == 0 {
+                1.0
+            } else {
+                parsed_lines as f64 / total_lines as f64
+            },
+            metadata: HashMap::from([
+                ("files".to_string(), files.len().to_string()),
+                ("lines_read".to_string(), total_lines.to_string()),
+            ]),
+        })
+    }
+
+    fn get_source_id(&self) -> SourceId {
+        self.source_id.clone()
+    }
+
+    fn get_source_type(&self) -> DataSourceType {
+        DataSourceType::ApplicationLogs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn append(path: &Path, text: &str) {
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_parses_json_and_logfmt() {
+        let record = parse_json_line(
+            r#"{"ts":"2026-10-18T09:00:00Z","level":"ERROR","msg":"upstream timeout","http":{"status":504},"retry":true}"#,
+        )
+        .unwrap();
+        assert_eq!(record.level.as_deref(), Some("error"));
+        assert_eq!(record.message.as_deref(), Some("upstream timeout"));
+        assert_eq!(record.fields["http.status"], "504");
+        assert_eq!(record.fields["retry"], "true");
+        assert_eq!(record.timestamp.unwrap().to_rfc3339(), "2026-10-18T09:00:00+00:00");
+        assert!(record.is_error());
+        assert!(parse_json_line("plain text").is_none());
+
+        let record = parse_logfmt_line(
+            r#"time=1760778000 level=warn msg="disk \"data\" at 91%" duration_ms=12.5 cached"#,
+        )
+        .unwrap();
+        assert_eq!(record.level.as_deref(), Some("warn"));
+        assert_eq!(record.message.as_deref(), Some(r#"disk "data" at 91%"#));
+        assert_eq!(record.fields["duration_ms"], "12.5");
+        assert_eq!(record.fields["cached"], "true");
+        assert_eq!(record.timestamp.unwrap().timestamp(), 1_760_778_000);
+        assert!(!record.is_error());
+        assert!(parse_logfmt_line("just some words").is_none());
+    }
+
+    #[test]
+    fn test_parses_regex_and_audit_lines() {
+        let pattern = Regex::new(r"^(?P<time>\S+) \[(?P<level>\w+)\] (?P<message>.*)$").unwrap();
+        let record = parse_regex_line(&pattern, "1760778000000 [FATAL] out of memory").unwrap();
+        assert_eq!(record.level.as_deref(), Some("fatal"));
+        assert_eq!(record.message.as_deref(), Some("out of memory"));
+        assert_eq!(record.timestamp.unwrap().timestamp(), 1_760_778_000);
+        assert!(parse_regex_line(&pattern, "no match").is_none());
+
+        let record = parse_audit_line(
+            r#"{"id":"1","timestamp":1760778000,"event_type":"admin","user_id":null,"resource":"agent:a1","action":"drain","result":"success","ip_address":null,"user_agent":null,"tenant":"search","metadata":{"reason":"deploy"},"severity":"High"}"#,
+        )
+        .unwrap();
+        assert_eq!(record.level.as_deref(), Some("error"));
+        assert_eq!(record.message.as_deref(), Some("drain agent:a1: success"));
+        assert_eq!(record.fields["metadata.reason"], "deploy");
+        assert_eq!(record.fields["tenant"], "search");
+    }
+
+    #[test]
+    fn test_follower_handles_rotation_and_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        append(&path, "old\n");
+
+        let mut follower = LogFollower::new(&path, None, false);
+        assert!(follower.read_lines(1024).unwrap().is_empty());
+
+        append(&path, "one\ntw");
+        assert_eq!(follower.read_lines(1024).unwrap(), vec!["one"]);
+        append(&path, "o\n");
+        assert_eq!(follower.read_lines(1024).unwrap(), vec!["two"]);
+
+        // Rotation: the rest of the old file is read, then the new file from its start
+        append(&path, "three\n");
+        fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        append(&path, "four\n");
+        assert_eq!(follower.read_lines(1024).unwrap(), vec!["three", "four"]);
+
+        // Truncation in place
+        fs::write(&path, "5\n").unwrap();
+        assert_eq!(follower.read_lines(1024).unwrap(), vec!["5"]);
+
+        // Resuming from a saved offset
+        append(&path, "six\n");
+        let mut resumed = LogFollower::new(&path, follower.position(), false);
+        assert_eq!(resumed.read_lines(1024).unwrap(), vec!["six"]);
+
+        fs::remove_file(&path).unwrap();
+        assert!(resumed.read_lines(1024).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_source_reports_levels_error_rate_and_persists_offsets() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("api.log");
+        let offsets = dir.path().join("state/offsets.json");
+        append(&log, "level=info msg=ready\n");
+
+        let config = LogSourceConfig {
+            files: vec![LogFileConfig {
+                path: log.clone(),
+                name: None,
+                format: LogFormat::Logfmt,
+                pattern: None,
+                numeric_fields: vec!["duration_ms".to_string()],
+                from_beginning: true,
+            }],
+            offsets_path: Some(offsets.clone()),
+            max_bytes_per_collection: 1024,
+        };
+        let source = ApplicationLogsSource::new(config.clone()).unwrap();
+        append(&log, "level=error msg=\"upstream failed\" duration_ms=250\nlevel=info duration_ms=12\nnot logfmt\n");
+
+        let batch = source.collect_data().await.unwrap();
+        let point = |metric: &str, level: Option<&str>| {
+            batch
+                .data_points
+                .iter()
+                .find(|point| {
+                    point.metric_name == metric
+                        && level.is_none_or(|level| point.tags.get("level").map(String::as_str) == Some(level))
+                })
+                .map(|point| point.value)
+        };
+        assert_eq!(point("log_entries_count", Some("info")), Some(2.0));
+        assert_eq!(point("log_entries_count", Some("error")), Some(1.0));
+        assert_eq!(point("error_rate", None), Some(1.0 / 3.0));
+        assert_eq!(point("log_parse_failures", None), Some(1.0));
+        assert_eq!(point("log_duration_ms", Some("error")), Some(250.0));
+        assert_eq!(batch.quality_score, 0.75);
+        let entry = batch
+            .data_points
+            .iter()
+            .find(|point| point.metric_name == "log_entry" && point.tags["level"] == "error")
+            .unwrap();
+        assert_eq!(entry.tags["source"], "api");
+        assert_eq!(entry.tags["message"], "upstream failed");
+        assert!(offsets.exists());
+
+        // A new source resumes after what was already collected
+        append(&log, "level=info msg=again\n");
+        let source = ApplicationLogsSource::new(config).unwrap();
+        let batch = source.collect_data().await.unwrap();
+        assert_eq!(batch.metadata["lines_read"], "1");
+    }
+
+    #[test]
+    fn test_regex_format_needs_a_valid_pattern() {
+        let file = LogFileConfig {
+            path: PathBuf::from("app.log"),
+            name: None,
+            format: LogFormat::Regex,
+            pattern: None,
+            numeric_fields: Vec::new(),
+            from_beginning: false,
+        };
+        let config = |pattern: Option<&str>| LogSourceConfig {
+            files: vec![LogFileConfig {
+                pattern: pattern.map(str::to_string),
+                ..file.clone()
+            }],
+            ..LogSourceConfig::default()
+        };
+        assert!(ApplicationLogsSource::new(config(None)).is_err());
+        assert!(ApplicationLogsSource::new(config(Some("(unclosed"))).is_err());
+        assert!(ApplicationLogsSource::new(config(Some(r"(?P<message>.*)"))).is_ok());
+    }
+}
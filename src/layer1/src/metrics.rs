@@ -5,14 +5,13 @@
 //! for all discovery components.
 
 use crate::types::*;
-use chrono::{DateTime, Utc};
 use lazy_static::lazy_static;
 use prometheus::{
-    register_counter, register_gauge, register_histogram, Counter, Encoder, Gauge, Histogram,
+    register_counter, register_gauge, register_histogram, Counter, Gauge, Histogram,
     Registry, TextEncoder,
 };
 use std::collections::HashMap;
-use tracing::{debug, error, info};
+use tracing::{debug, error, warn};
 
 lazy_static! {
     /// Prometheus registry for Layer 1 metrics
@@ -218,7 +217,9 @@ impl MetricsCollector {
     pub fn get_metrics(&self) -> Result<String, DiscoveryError> {
         let encoder = TextEncoder::new();
         let metric_families = self.registry.gather();
-        let result = encoder.encode_to_string(&metric_families)?;
+        let result = encoder
+            .encode_to_string(&metric_families)
+            .map_err(|e| DiscoveryError::InternalError(e.to_string()))?;
 
         Ok(result)
     }
@@ -280,10 +281,16 @@ impl PerformanceTimer {
         }
     }
 
+    /// Seconds since the timer started
+    pub fn elapsed(&self) -> f64 {
+        self.start_time.elapsed().as_secs_f64()
+    }
+
     /// Stop the timer and return the duration in seconds
     pub fn stop(self) -> f64 {
-        let duration = self.start_time.elapsed();
-        duration.as_secs_f64()
+        let duration = self.elapsed();
+        debug!("{} took {:.3}s", self.operation_name, duration);
+        duration
     }
 
     /// Stop the timer and record the duration in a histogram
@@ -312,8 +319,7 @@ impl ScopedMetricsRecorder {
 
 impl Drop for ScopedMetricsRecorder {
     fn drop(&mut self) {
-        let duration = self.timer.stop();
-        self.histogram.observe(duration);
+        self.histogram.observe(self.timer.elapsed());
     }
 }
 
@@ -353,7 +359,7 @@ impl MetricsUtils {
 
         let error_score = 1.0 - error_rate;
 
-        (health_ratio * 0.5 + response_time_score * 0.3 + error_score * 0.2).min(1.0).max(0.0)
+        (health_ratio * 0.5 + response_time_score * 0.3 + error_score * 0.2).clamp(0.0, 1.0)
     }
 
     /// Calculate data quality score
@@ -376,7 +382,7 @@ impl MetricsUtils {
             0.5
         };
 
-        (validity_score * 0.4 + latency_score * 0.3 + completeness_ratio * 0.3).min(1.0).max(0.0)
+        (validity_score * 0.4 + latency_score * 0.3 + completeness_ratio * 0.3).clamp(0.0, 1.0)
     }
 
     /// Format metrics for logging
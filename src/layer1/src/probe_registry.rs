@@ -0,0 +1,415 @@
+//! # Probe Registry
+//!
+//! Named [`SystemProbe`]s with their own schedule. Each probe runs on its own interval
+//! (plus random jitter) under its own timeout, so cheap local probes can run every minute
+//! while network sweeps run far less often. A probe that keeps failing is retried with
+//! exponential backoff, and per-probe run, failure and latency stats are kept for health
+//! reporting.
+
+use crate::environmental_scanner::{SystemInfo, SystemProbe};
+use crate::types::{DiscoveryError, ProbeConfig, ScanError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use tokio::time::{timeout, Duration, Instant};
+
+/// When and for how long a probe runs
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeSchedule {
+    pub enabled: bool,
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub jitter: Duration,
+}
+
+impl ProbeSchedule {
+    /// A schedule running every `interval` with at most `timeout` per run
+    pub fn every(interval: Duration, timeout: Duration) -> Self {
+        Self {
+            enabled: true,
+            interval,
+            timeout,
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Add up to `jitter` to every interval
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Apply the fields set in `overrides`
+    pub fn with_overrides(mut self, overrides: Option<&ProbeConfig>) -> Self {
+        if let Some(overrides) = overrides {
+            self.enabled = overrides.enabled.unwrap_or(self.enabled);
+            if let Some(seconds) = overrides.interval_seconds {
+                self.interval = Duration::from_secs(seconds);
+            }
+            if let Some(seconds) = overrides.timeout_seconds {
+                self.timeout = Duration::from_secs(seconds);
+            }
+            if let Some(seconds) = overrides.jitter_seconds {
+                self.jitter = Duration::from_secs(seconds);
+            }
+        }
+        self
+    }
+}
+
+/// Run history of a probe
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProbeStats {
+    pub runs: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_success: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_latency_ms: u64,
+    pub average_latency_ms: f64,
+    pub systems_found: usize,
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+impl ProbeStats {
+    /// Fraction of runs that succeeded, 1.0 before the first run
+    pub fn success_rate(&self) -> f64 {
+        if self.runs == 0 {
+            1.0
+        } else {
+            self.successes as f64 / self.runs as f64
+        }
+    }
+}
+
+struct RegisteredProbe {
+    probe: Arc<dyn SystemProbe>,
+    schedule: ProbeSchedule,
+    stats: ProbeStats,
+    next_run: Instant,
+    running: bool,
+}
+
+/// Registered probes and when each is next due
+pub struct ProbeRegistry {
+    probes: BTreeMap<String, RegisteredProbe>,
+    max_backoff: Duration,
+    random: RandomState,
+}
+
+impl ProbeRegistry {
+    /// Create an empty registry; failing probes are retried at most `max_backoff` apart
+    pub fn new(max_backoff: Duration) -> Self {
+        Self {
+            probes: BTreeMap::new(),
+            max_backoff,
+            random: RandomState::new(),
+        }
+    }
+
+    /// Register `probe` under `name`, due immediately; probes reporting themselves disabled
+    /// stay off whatever the schedule says
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        probe: Arc<dyn SystemProbe>,
+        mut schedule: ProbeSchedule,
+    ) -> Result<(), DiscoveryError> {
+        let name = name.into();
+        if self.probes.contains_key(&name) {
+            return Err(DiscoveryError::ConfigurationError(format!(
+                "probe {} is already registered",
+                name
+            )));
+        }
+        schedule.enabled &= probe.is_enabled();
+        self.probes.insert(
+            name,
+            RegisteredProbe {
+                probe,
+                schedule,
+                stats: ProbeStats::default(),
+                next_run: Instant::now(),
+                running: false,
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove a probe, returning whether it was registered
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.probes.remove(name).is_some()
+    }
+
+    /// Names of the registered probes
+    pub fn names(&self) -> Vec<String> {
+        self.probes.keys().cloned().collect()
+    }
+
+    /// Number of registered probes
+    pub fn len(&self) -> usize {
+        self.probes.len()
+    }
+
+    /// Whether no probes are registered
+    pub fn is_empty(&self) -> bool {
+        self.probes.is_empty()
+    }
+
+    /// Schedule of a probe
+    pub fn schedule(&self, name: &str) -> Option<&ProbeSchedule> {
+        self.probes.get(name).map(|entry| &entry.schedule)
+    }
+
+    /// Stats of every probe
+    pub fn stats(&self) -> BTreeMap<String, ProbeStats> {
+        self.probes
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.stats.clone()))
+            .collect()
+    }
+
+    /// Enabled probes that are due at `now` and not already running
+    pub fn due(&self, now: Instant) -> Vec<String> {
+        self.probes
+            .iter()
+            .filter(|(_, entry)| entry.schedule.enabled && !entry.running && entry.next_run <= now)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// When the next enabled probe becomes due
+    pub fn next_due(&self) -> Option<Instant> {
+        self.probes
+            .values()
+            .filter(|entry| entry.schedule.enabled && !entry.running)
+            .map(|entry| entry.next_run)
+            .min()
+    }
+
+    /// Enabled probes regardless of when they are due
+    pub fn enabled(&self) -> Vec<String> {
+        self.probes
+            .iter()
+            .filter(|(_, entry)| entry.schedule.enabled)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Mark a probe as running and hand out what is needed to run it
+    pub fn begin(&mut self, name: &str) -> Option<(Arc<dyn SystemProbe>, Duration)> {
+        let entry = self.probes.get_mut(name)?;
+        entry.running = true;
+        Some((entry.probe.clone(), entry.schedule.timeout))
+    }
+
+    /// Record the outcome of a run started with [`ProbeRegistry::begin`] and schedule the next one
+    pub fn finish(
+        &mut self,
+        name: &str,
+        outcome: Result<usize, String>,
+        latency: Duration,
+        now: Instant,
+    ) {
+        let max_backoff = self.max_backoff;
+        let Some(entry) = self.probes.get_mut(name) else {
+            return;
+        };
+        entry.running = false;
+
+        let stats = &mut entry.stats;
+        stats.runs += 1;
+        stats.last_run = Some(Utc::now());
+        stats.last_latency_ms = latency.as_millis() as u64;
+        stats.average_latency_ms += (stats.last_latency_ms as f64 - stats.average_latency_ms) / stats.runs as f64;
+        match outcome {
+            Ok(found) => {
+                stats.successes += 1;
+                stats.consecutive_failures = 0;
+                stats.last_success = stats.last_run;
+                stats.last_error = None;
+                stats.systems_found = found;
+            }
+            Err(error) => {
+                stats.failures += 1;
+                stats.consecutive_failures += 1;
+                stats.last_error = Some(error);
+            }
+        }
+
+        let delay = backoff(entry.schedule.interval, stats.consecutive_failures, max_backoff)
+            + jitter(&self.random, entry.schedule.jitter, name, stats.runs);
+        entry.next_run = now + delay;
+        stats.next_run = chrono::Duration::from_std(delay)
+            .ok()
+            .map(|delay| Utc::now() + delay);
+    }
+}
+
+/// `interval` doubled for every consecutive failure, capped at `max_backoff`
+fn backoff(interval: Duration, consecutive_failures: u32, max_backoff: Duration) -> Duration {
+    if consecutive_failures == 0 {
+        return interval;
+    }
+    let factor = 1u32.checked_shl(consecutive_failures).unwrap_or(u32::MAX);
+    interval
+        .checked_mul(factor)
+        .unwrap_or(max_backoff)
+        .min(max_backoff.max(interval))
+}
+
+/// A random duration up to `max`, different per probe so probes started together drift apart
+fn jitter(random: &RandomState, max: Duration, name: &str, runs: u64) -> Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(random.hash_one((name, runs)) % (max_ms + 1))
+}
+
+/// Run a probe under `limit`, returning what it found and how long it took
+pub async fn run_probe(
+    probe: &dyn SystemProbe,
+    limit: Duration,
+) -> (Result<Vec<SystemInfo>, ScanError>, Duration) {
+    let started = Instant::now();
+    let result = match timeout(limit, probe.scan()).await {
+        Ok(result) => result,
+        Err(_) => Err(ScanError::Timeout(limit.as_millis() as u64)),
+    };
+    (result, started.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environmental_scanner::ProbeType;
+    use async_trait::async_trait;
+
+    struct SleepyProbe(Duration);
+
+    #[async_trait]
+    impl SystemProbe for SleepyProbe {
+        async fn scan(&self) -> Result<Vec<SystemInfo>, ScanError> {
+            tokio::time::sleep(self.0).await;
+            Ok(Vec::new())
+        }
+
+        fn get_probe_type(&self) -> ProbeType {
+            ProbeType::Custom("sleepy".to_string())
+        }
+    }
+
+    fn registry() -> ProbeRegistry {
+        let mut registry = ProbeRegistry::new(Duration::from_secs(600));
+        registry
+            .register(
+                "fast",
+                Arc::new(SleepyProbe(Duration::ZERO)),
+                ProbeSchedule::every(Duration::from_secs(60), Duration::from_secs(5)),
+            )
+            .unwrap();
+        registry
+            .register(
+                "slow",
+                Arc::new(SleepyProbe(Duration::ZERO)),
+                ProbeSchedule::every(Duration::from_secs(900), Duration::from_secs(60)),
+            )
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_overrides_only_replace_set_fields() {
+        let schedule = ProbeSchedule::every(Duration::from_secs(60), Duration::from_secs(5))
+            .with_overrides(Some(&ProbeConfig {
+                interval_seconds: Some(10),
+                jitter_seconds: Some(2),
+                ..ProbeConfig::default()
+            }));
+        assert!(schedule.enabled);
+        assert_eq!(schedule.interval, Duration::from_secs(10));
+        assert_eq!(schedule.timeout, Duration::from_secs(5));
+        assert_eq!(schedule.jitter, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_probes_are_due_on_their_own_interval() {
+        let mut registry = registry();
+        let duplicate = ProbeSchedule::every(Duration::from_secs(1), Duration::from_secs(1));
+        assert!(registry
+            .register("fast", Arc::new(SleepyProbe(Duration::ZERO)), duplicate)
+            .is_err());
+
+        let start = Instant::now();
+        assert_eq!(registry.due(start), vec!["fast", "slow"]);
+        for name in ["fast", "slow"] {
+            registry.begin(name).unwrap();
+            assert!(!registry.due(start).contains(&name.to_string()));
+            registry.finish(name, Ok(1), Duration::from_millis(20), start);
+        }
+
+        assert!(registry.due(start + Duration::from_secs(59)).is_empty());
+        assert_eq!(registry.due(start + Duration::from_secs(60)), vec!["fast"]);
+        assert_eq!(registry.next_due(), Some(start + Duration::from_secs(60)));
+        assert_eq!(registry.due(start + Duration::from_secs(900)), vec!["fast", "slow"]);
+
+        let stats = &registry.stats()["fast"];
+        assert_eq!((stats.runs, stats.successes, stats.systems_found), (1, 1, 1));
+        assert_eq!(stats.last_latency_ms, 20);
+    }
+
+    #[test]
+    fn test_failing_probes_back_off() {
+        let mut registry = registry();
+        registry.unregister("slow");
+        let start = Instant::now();
+        for failures in 1..=5u32 {
+            registry.begin("fast").unwrap();
+            registry.finish("fast", Err("refused".to_string()), Duration::ZERO, start);
+            let expected = Duration::from_secs(60 * 2u64.pow(failures)).min(Duration::from_secs(600));
+            assert_eq!(registry.next_due(), Some(start + expected));
+        }
+        let stats = &registry.stats()["fast"];
+        assert_eq!(stats.consecutive_failures, 5);
+        assert_eq!(stats.success_rate(), 0.0);
+        assert_eq!(stats.last_error.as_deref(), Some("refused"));
+
+        registry.begin("fast").unwrap();
+        registry.finish("fast", Ok(0), Duration::ZERO, start);
+        assert_eq!(registry.stats()["fast"].consecutive_failures, 0);
+        assert_eq!(registry.next_due(), Some(start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_bounds() {
+        let random = RandomState::new();
+        for runs in 0..100 {
+            assert!(jitter(&random, Duration::from_secs(3), "local", runs) <= Duration::from_secs(3));
+        }
+        assert_eq!(jitter(&random, Duration::ZERO, "local", 1), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_jitter_differs_between_probes_on_the_same_run() {
+        let random = RandomState::new();
+        let delays: std::collections::HashSet<Duration> = ["local", "network", "container", "service"]
+            .iter()
+            .map(|name| jitter(&random, Duration::from_secs(60), name, 1))
+            .collect();
+        assert!(delays.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_probe_times_out() {
+        let probe = SleepyProbe(Duration::from_secs(5));
+        let (result, latency) = run_probe(&probe, Duration::from_millis(10)).await;
+        assert!(matches!(result, Err(ScanError::Timeout(10))));
+        assert!(latency < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,784 @@
+//! # Procfs
+//!
+//! Readers for the Linux `/proc` and cgroup v2 files that layer 1 discovers and measures
+//! the host from. Every reader is rooted at a configurable directory so that tests, and
+//! scanners running in a container with the host's `/proc` mounted elsewhere, can point
+//! it at a different tree.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+/// `st` value of a listening socket in `/proc/net/tcp`
+const TCP_LISTEN: u8 = 0x0A;
+
+/// `st` value of an established connection in `/proc/net/tcp`
+const TCP_ESTABLISHED: u8 = 0x01;
+
+/// A `/proc` tree
+#[derive(Debug, Clone)]
+pub struct ProcFs {
+    root: PathBuf,
+}
+
+impl ProcFs {
+    /// Read the tree rooted at `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Root of the tree
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Read a file relative to the root
+    pub fn read(&self, relative: impl AsRef<Path>) -> io::Result<String> {
+        fs::read_to_string(self.root.join(relative))
+    }
+
+    /// Ids of every process in the tree
+    pub fn pids(&self) -> io::Result<Vec<u32>> {
+        let mut pids: Vec<u32> = fs::read_dir(&self.root)?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect();
+        pids.sort_unstable();
+        Ok(pids)
+    }
+
+    /// Command name of a process
+    pub fn comm(&self, pid: u32) -> Option<String> {
+        self.read(format!("{}/comm", pid))
+            .ok()
+            .map(|comm| comm.trim().to_string())
+            .filter(|comm| !comm.is_empty())
+    }
+
+    /// Unified (cgroup v2) cgroup path of a process
+    pub fn cgroup(&self, pid: u32) -> Option<String> {
+        parse_cgroup(&self.read(format!("{}/cgroup", pid)).ok()?)
+    }
+
+    /// Socket inodes held open by a process, from its `fd` symlinks
+    pub fn socket_inodes(&self, pid: u32) -> Vec<u64> {
+        let Ok(entries) = fs::read_dir(self.root.join(format!("{}/fd", pid))) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| fs::read_link(entry.path()).ok())
+            .filter_map(|target| parse_socket_link(target.to_str()?))
+            .collect()
+    }
+
+    /// Owning process of every socket inode visible in the tree
+    pub fn socket_owners(&self) -> HashMap<u64, u32> {
+        let mut owners = HashMap::new();
+        for pid in self.pids().unwrap_or_default() {
+            for inode in self.socket_inodes(pid) {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+        owners
+    }
+
+    /// Listening TCP sockets from `net/tcp` and `net/tcp6`
+    pub fn listening_sockets(&self) -> io::Result<Vec<SocketEntry>> {
+        let mut sockets = self.tcp_sockets()?;
+        sockets.retain(|socket| socket.state == TCP_LISTEN);
+        Ok(sockets)
+    }
+
+    /// Aggregate CPU time from `stat`
+    pub fn cpu_times(&self) -> io::Result<CpuTimes> {
+        parse_stat_cpu(&self.read("stat")?).ok_or_else(|| malformed("stat"))
+    }
+
+    /// `meminfo` values in bytes
+    pub fn meminfo(&self) -> io::Result<HashMap<String, u64>> {
+        Ok(parse_meminfo(&self.read("meminfo")?))
+    }
+
+    /// Mounted block-device and network filesystems from `mounts`
+    pub fn mounts(&self) -> io::Result<Vec<MountEntry>> {
+        Ok(parse_mounts(&self.read("mounts")?))
+    }
+
+    /// Per-device I/O counters from `diskstats`
+    pub fn disk_stats(&self) -> io::Result<Vec<DiskIoCounters>> {
+        Ok(parse_diskstats(&self.read("diskstats")?))
+    }
+
+    /// Per-interface counters from `net/dev`
+    pub fn net_dev(&self) -> io::Result<Vec<InterfaceCounters>> {
+        Ok(parse_net_dev(&self.read("net/dev")?))
+    }
+
+    /// TCP sockets from `net/tcp` and `net/tcp6`
+    pub fn tcp_sockets(&self) -> io::Result<Vec<SocketEntry>> {
+        let mut sockets = parse_net_tcp(&self.read("net/tcp")?);
+        // Hosts with IPv6 disabled have no tcp6 table.
+        if let Ok(tcp6) = self.read("net/tcp6") {
+            sockets.extend(parse_net_tcp(&tcp6));
+        }
+        Ok(sockets)
+    }
+
+    /// Pressure stall information for `cpu`, `memory` or `io`; kernels without PSI have none
+    pub fn pressure(&self, resource: &str) -> Option<Pressure> {
+        parse_pressure(&self.read(format!("pressure/{}", resource)).ok()?)
+    }
+
+    /// Scheduling and memory counters of a process
+    pub fn pid_stat(&self, pid: u32) -> Option<PidStat> {
+        parse_pid_stat(&self.read(format!("{}/stat", pid)).ok()?)
+    }
+}
+
+impl Default for ProcFs {
+    fn default() -> Self {
+        Self::new("/proc")
+    }
+}
+
+/// One row of `/proc/net/tcp` or `/proc/net/tcp6`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketEntry {
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub state: u8,
+    pub uid: u32,
+    pub inode: u64,
+}
+
+impl SocketEntry {
+    pub fn is_listening(&self) -> bool {
+        self.state == TCP_LISTEN
+    }
+
+    pub fn is_established(&self) -> bool {
+        self.state == TCP_ESTABLISHED
+    }
+}
+
+/// Parse `/proc/net/tcp` or `/proc/net/tcp6`, skipping malformed rows
+pub fn parse_net_tcp(contents: &str) -> Vec<SocketEntry> {
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 {
+                return None;
+            }
+            Some(SocketEntry {
+                local: parse_socket_addr(fields[1])?,
+                remote: parse_socket_addr(fields[2])?,
+                state: u8::from_str_radix(fields[3], 16).ok()?,
+                uid: fields[7].parse().ok()?,
+                inode: fields[9].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse a `0100007F:1F90` style address. The address is printed as native-endian
+/// 32-bit words, so the bytes are recovered with the host's byte order.
+fn parse_socket_addr(field: &str) -> Option<SocketAddr> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let ip = match address.len() {
+        8 => IpAddr::V4(Ipv4Addr::from(
+            u32::from_str_radix(address, 16).ok()?.to_ne_bytes(),
+        )),
+        32 => {
+            let mut octets = [0u8; 16];
+            for (index, chunk) in octets.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(&address[index * 8..index * 8 + 8], 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+/// Inode of a `socket:[12345]` fd link target
+fn parse_socket_link(target: &str) -> Option<u64> {
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// The unified hierarchy path (`0::/...`) of a `/proc/<pid>/cgroup` file
+pub fn parse_cgroup(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim().to_string())
+}
+
+/// A container recognized from its cgroup path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerRef {
+    pub runtime: String,
+    pub id: String,
+}
+
+/// Recognize the container a cgroup path belongs to. Covers the systemd driver
+/// (`docker-<id>.scope`, `cri-containerd-<id>.scope`, `crio-<id>.scope`,
+/// `libpod-<id>.scope`) and the cgroupfs driver (`/docker/<id>`, `/kubepods/.../<id>`).
+pub fn container_from_cgroup(path: &str) -> Option<ContainerRef> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    for (index, segment) in segments.iter().enumerate().rev() {
+        if let Some(unit) = segment.strip_suffix(".scope") {
+            for (prefix, runtime) in [
+                ("docker-", "docker"),
+                ("cri-containerd-", "containerd"),
+                ("crio-", "cri-o"),
+                ("libpod-", "podman"),
+            ] {
+                if let Some(id) = unit.strip_prefix(prefix).filter(|id| is_container_id(id)) {
+                    return Some(ContainerRef {
+                        runtime: runtime.to_string(),
+                        id: id.to_string(),
+                    });
+                }
+            }
+        }
+        if is_container_id(segment) && index > 0 {
+            let runtime = if segments[..index].iter().any(|s| s.starts_with("kubepods")) {
+                "kubernetes"
+            } else {
+                segments[index - 1]
+            };
+            return Some(ContainerRef {
+                runtime: runtime.to_string(),
+                id: segment.to_string(),
+            });
+        }
+    }
+    None
+}
+
+fn is_container_id(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Limits and usage read from a cgroup v2 directory
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CgroupStats {
+    pub pids: Vec<u32>,
+    /// `cpu.max` quota divided by its period; `None` when unlimited
+    pub cpu_limit: Option<f64>,
+    /// `memory.max` in bytes; `None` when unlimited
+    pub memory_limit: Option<u64>,
+    pub memory_current: Option<u64>,
+}
+
+/// Read the stats of the cgroup at `dir`
+pub fn read_cgroup_stats(dir: &Path) -> CgroupStats {
+    let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+    CgroupStats {
+        pids: read("cgroup.procs")
+            .map(|procs| procs.lines().filter_map(|pid| pid.trim().parse().ok()).collect())
+            .unwrap_or_default(),
+        cpu_limit: read("cpu.max").and_then(|max| parse_cpu_max(&max)),
+        memory_limit: read("memory.max").and_then(|max| max.trim().parse().ok()),
+        memory_current: read("memory.current").and_then(|current| current.trim().parse().ok()),
+    }
+}
+
+/// Cores allowed by a `cpu.max` value such as `200000 100000`
+fn parse_cpu_max(contents: &str) -> Option<f64> {
+    let mut fields = contents.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next().unwrap_or("100000").parse().ok()?;
+    (period > 0.0).then(|| quota / period)
+}
+
+/// Every directory of a cgroup v2 hierarchy that belongs to a container
+pub fn find_container_cgroups(root: &Path) -> io::Result<Vec<(ContainerRef, PathBuf)>> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)?.filter_map(|entry| entry.ok()) {
+            if !entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false) {
+                continue;
+            }
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            // A container's own sub-cgroups belong to it, so they are not descended into.
+            match container_from_cgroup(&relative.to_string_lossy()) {
+                Some(container) => found.push((container, path)),
+                None => pending.push(path),
+            }
+        }
+    }
+    found.sort_by(|a, b| a.0.id.cmp(&b.0.id));
+    Ok(found)
+}
+
+fn malformed(file: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("malformed {}", file))
+}
+
+/// Filesystem types that hold no user data
+const VIRTUAL_FILESYSTEMS: &[&str] = &[
+    "autofs", "binfmt_misc", "bpf", "cgroup", "cgroup2", "configfs", "debugfs", "devpts",
+    "devtmpfs", "efivarfs", "fusectl", "hugetlbfs", "mqueue", "nsfs", "overlay", "proc",
+    "pstore", "ramfs", "rpc_pipefs", "securityfs", "squashfs", "sysfs", "tmpfs", "tracefs",
+];
+
+/// Busy and total CPU time in clock ticks, summed over every CPU
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+/// Parse the aggregate `cpu` line of `/proc/stat`. Guest time is already part of user time.
+pub fn parse_stat_cpu(contents: &str) -> Option<CpuTimes> {
+    let line = contents.lines().find(|line| line.starts_with("cpu "))?;
+    let ticks: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    if ticks.len() < 4 {
+        return None;
+    }
+    let total: u64 = ticks.iter().sum();
+    let idle = ticks[3] + ticks.get(4).copied().unwrap_or(0);
+    Some(CpuTimes {
+        busy: total - idle,
+        total,
+    })
+}
+
+/// Parse `/proc/meminfo` into bytes keyed by field name
+pub fn parse_meminfo(contents: &str) -> HashMap<String, u64> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            let mut fields = value.split_whitespace();
+            let amount: u64 = fields.next()?.parse().ok()?;
+            let bytes = match fields.next() {
+                Some("kB") => amount * 1024,
+                _ => amount,
+            };
+            Some((name.to_string(), bytes))
+        })
+        .collect()
+}
+
+/// One row of `/proc/mounts`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+}
+
+/// Parse `/proc/mounts`, leaving out virtual filesystems and repeated (bind) mounts of a device
+pub fn parse_mounts(contents: &str) -> Vec<MountEntry> {
+    let mut seen = std::collections::HashSet::new();
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(MountEntry {
+                device: unescape_mount_field(fields.next()?),
+                mount_point: PathBuf::from(unescape_mount_field(fields.next()?)),
+                fs_type: fields.next()?.to_string(),
+            })
+        })
+        .filter(|mount| !VIRTUAL_FILESYSTEMS.contains(&mount.fs_type.as_str()))
+        .filter(|mount| seen.insert(mount.device.clone()))
+        .collect()
+}
+
+/// Undo the octal escaping (`\040` for a space) used in `/proc/mounts`
+fn unescape_mount_field(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let code = rest.get(index + 1..index + 4);
+        match code.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Capacity of a mounted filesystem
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FsUsage {
+    pub total_bytes: u64,
+    pub free_bytes: u64,
+    /// Free space usable by unprivileged users
+    pub available_bytes: u64,
+}
+
+impl FsUsage {
+    pub fn used_bytes(&self) -> u64 {
+        self.total_bytes.saturating_sub(self.free_bytes)
+    }
+
+    /// Used share of the space available to users, as `df` reports it
+    pub fn usage_percent(&self) -> f64 {
+        let usable = self.used_bytes() + self.available_bytes;
+        if usable == 0 {
+            0.0
+        } else {
+            self.used_bytes() as f64 / usable as f64 * 100.0
+        }
+    }
+}
+
+/// `statvfs` of the filesystem mounted at `path`
+pub fn statvfs(path: &Path) -> io::Result<FsUsage> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stats` is only read after the call succeeded.
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stats.assume_init()
+    };
+    let fragment = stats.f_frsize as u64;
+    Ok(FsUsage {
+        total_bytes: stats.f_blocks as u64 * fragment,
+        free_bytes: stats.f_bfree as u64 * fragment,
+        available_bytes: stats.f_bavail as u64 * fragment,
+    })
+}
+
+/// Cumulative I/O counters of a block device from `/proc/diskstats`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskIoCounters {
+    pub device: String,
+    pub reads_completed: u64,
+    pub sectors_read: u64,
+    pub writes_completed: u64,
+    pub sectors_written: u64,
+    /// Milliseconds spent with I/O in flight
+    pub io_ticks_ms: u64,
+}
+
+/// Bytes per `/proc/diskstats` sector, whatever the device's real sector size
+pub const DISKSTATS_SECTOR_BYTES: u64 = 512;
+
+/// Parse `/proc/diskstats`, leaving out loop and ram devices
+pub fn parse_diskstats(contents: &str) -> Vec<DiskIoCounters> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 13 {
+                return None;
+            }
+            let field = |index: usize| fields[index].parse::<u64>().ok();
+            Some(DiskIoCounters {
+                device: fields[2].to_string(),
+                reads_completed: field(3)?,
+                sectors_read: field(5)?,
+                writes_completed: field(7)?,
+                sectors_written: field(9)?,
+                io_ticks_ms: field(12)?,
+            })
+        })
+        .filter(|disk| !disk.device.starts_with("loop") && !disk.device.starts_with("ram"))
+        .collect()
+}
+
+/// Cumulative counters of a network interface from `/proc/net/dev`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InterfaceCounters {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_dropped: u64,
+}
+
+/// Parse `/proc/net/dev`
+pub fn parse_net_dev(contents: &str) -> Vec<InterfaceCounters> {
+    contents
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .map(|field| field.parse().ok())
+                .collect::<Option<_>>()?;
+            if counters.len() < 12 {
+                return None;
+            }
+            Some(InterfaceCounters {
+                name: name.trim().to_string(),
+                rx_bytes: counters[0],
+                rx_packets: counters[1],
+                rx_errors: counters[2],
+                rx_dropped: counters[3],
+                tx_bytes: counters[8],
+                tx_packets: counters[9],
+                tx_errors: counters[10],
+                tx_dropped: counters[11],
+            })
+        })
+        .collect()
+}
+
+/// One line of a `/proc/pressure` file; averages are percentages of wall time
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PressureStall {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
+    pub total_us: u64,
+}
+
+/// Pressure stall information of one resource
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    /// Time some tasks were stalled
+    pub some: PressureStall,
+    /// Time all non-idle tasks were stalled; the CPU has none at the system level
+    pub full: Option<PressureStall>,
+}
+
+/// Parse a `/proc/pressure/{cpu,memory,io}` file
+pub fn parse_pressure(contents: &str) -> Option<Pressure> {
+    let mut some = None;
+    let mut full = None;
+    for line in contents.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        let mut stall = PressureStall::default();
+        for field in fields {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "avg10" => stall.avg10 = value.parse().ok()?,
+                "avg60" => stall.avg60 = value.parse().ok()?,
+                "avg300" => stall.avg300 = value.parse().ok()?,
+                "total" => stall.total_us = value.parse().ok()?,
+                _ => {}
+            }
+        }
+        match kind {
+            "some" => some = Some(stall),
+            "full" => full = Some(stall),
+            _ => {}
+        }
+    }
+    Some(Pressure { some: some?, full })
+}
+
+/// Fields of `/proc/<pid>/stat` used for the process table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PidStat {
+    pub pid: u32,
+    pub comm: String,
+    pub state: char,
+    /// User plus system time in clock ticks
+    pub cpu_ticks: u64,
+    pub rss_pages: u64,
+}
+
+/// Parse `/proc/<pid>/stat`. The command name may itself contain spaces and parentheses,
+/// so the fields after it are found from the last `)`.
+pub fn parse_pid_stat(contents: &str) -> Option<PidStat> {
+    let open = contents.find('(')?;
+    let close = contents.rfind(')')?;
+    let fields: Vec<&str> = contents.get(close + 1..)?.split_whitespace().collect();
+    if fields.len() < 22 {
+        return None;
+    }
+    let utime: u64 = fields[11].parse().ok()?;
+    let stime: u64 = fields[12].parse().ok()?;
+    Some(PidStat {
+        pid: contents[..open].trim().parse().ok()?,
+        comm: contents.get(open + 1..close)?.to_string(),
+        state: fields[0].chars().next()?,
+        cpu_ticks: utime + stime,
+        rss_pages: fields[21].parse().ok()?,
+    })
+}
+
+/// Size of a memory page in bytes
+pub fn page_size() -> u64 {
+    // SAFETY: sysconf has no preconditions.
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as u64
+    } else {
+        4096
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NET_TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 40123 1 0000000000000000 100 0 0 10 0
+   1: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 18211 1 0000000000000000 100 0 0 10 0
+   2: 0100007F:1F90 0100007F:D2F0 01 00000000:00000000 00:00000000 00000000  1000        0 40555 1 0000000000000000 20 4 30 10 -1
+";
+
+    const NET_TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:0050 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000    33        0 27001 1 0000000000000000 100 0 0 10 0
+";
+
+    #[test]
+    fn parses_ipv4_and_ipv6_socket_tables() {
+        let sockets = parse_net_tcp(NET_TCP);
+        assert_eq!(sockets.len(), 3);
+        assert_eq!(sockets[0].local, "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(sockets[1].local, "0.0.0.0:22".parse().unwrap());
+        assert_eq!(sockets[1].inode, 18211);
+        assert!(!sockets[2].is_listening());
+
+        let sockets = parse_net_tcp(NET_TCP6);
+        assert_eq!(sockets[0].local, "[::1]:80".parse().unwrap());
+        assert_eq!(sockets[0].uid, 33);
+    }
+
+    #[test]
+    fn recognizes_container_cgroups() {
+        let id = "a".repeat(64);
+        let docker = container_from_cgroup(&format!("/system.slice/docker-{}.scope", id)).unwrap();
+        assert_eq!(docker.runtime, "docker");
+        assert_eq!(docker.id, id);
+
+        let kube = container_from_cgroup(&format!(
+            "/kubepods.slice/kubepods-burstable.slice/kubepods-pod1.slice/cri-containerd-{}.scope",
+            id
+        ))
+        .unwrap();
+        assert_eq!(kube.runtime, "containerd");
+
+        let cgroupfs = container_from_cgroup(&format!("/kubepods/besteffort/pod1/{}", id)).unwrap();
+        assert_eq!(cgroupfs.runtime, "kubernetes");
+        assert_eq!(container_from_cgroup(&format!("/docker/{}", id)).unwrap().runtime, "docker");
+
+        assert!(container_from_cgroup("/user.slice/user-1000.slice/session-2.scope").is_none());
+        assert_eq!(parse_cgroup("0::/system.slice/sshd.service\n").unwrap(), "/system.slice/sshd.service");
+    }
+
+    #[test]
+    fn parses_cpu_max() {
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2.0));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+    }
+
+    fn fixture(name: &str) -> String {
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc").join(name))
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_cpu_and_memory() {
+        let cpu = parse_stat_cpu(&fixture("stat")).unwrap();
+        assert_eq!(cpu.total, 282837 + 120 + 30991 + 468509 + 2145 + 12 + 7905);
+        assert_eq!(cpu.busy, cpu.total - 468509 - 2145);
+
+        let meminfo = parse_meminfo(&fixture("meminfo"));
+        assert_eq!(meminfo["MemTotal"], 16384000 * 1024);
+        assert_eq!(meminfo["MemAvailable"], 8192000 * 1024);
+        assert_eq!(meminfo["HugePages_Total"], 0);
+    }
+
+    #[test]
+    fn parses_mounts_without_virtual_filesystems() {
+        let mounts = parse_mounts(&fixture("mounts"));
+        let points: Vec<&Path> = mounts.iter().map(|mount| mount.mount_point.as_path()).collect();
+        assert_eq!(
+            points,
+            vec![
+                Path::new("/"),
+                Path::new("/boot/efi"),
+                Path::new("/mnt/model store"),
+                Path::new("/mnt/datasets"),
+            ]
+        );
+        assert_eq!(mounts[3].fs_type, "nfs4");
+        assert_eq!(unescape_mount_field(r"a\\b\x"), r"a\\b\x");
+    }
+
+    #[test]
+    fn reads_filesystem_usage() {
+        let usage = statvfs(Path::new("/")).unwrap();
+        assert!(usage.total_bytes > 0);
+        assert!(usage.used_bytes() <= usage.total_bytes);
+        assert!((0.0..=100.0).contains(&usage.usage_percent()));
+    }
+
+    #[test]
+    fn parses_diskstats_and_net_dev() {
+        let disks = parse_diskstats(&fixture("diskstats"));
+        let names: Vec<&str> = disks.iter().map(|disk| disk.device.as_str()).collect();
+        assert_eq!(names, vec!["nvme0n1", "nvme0n1p1", "nvme0n1p2", "sdb"]);
+        assert_eq!(disks[0].sectors_read, 9843022);
+        assert_eq!(disks[0].sectors_written, 22871344);
+        assert_eq!(disks[0].io_ticks_ms, 302112);
+
+        let interfaces = parse_net_dev(&fixture("net/dev"));
+        assert_eq!(interfaces.len(), 3);
+        let eth0 = &interfaces[1];
+        assert_eq!(eth0.name, "eth0");
+        assert_eq!((eth0.rx_bytes, eth0.rx_errors, eth0.rx_dropped), (9823412231, 12, 40));
+        assert_eq!((eth0.tx_bytes, eth0.tx_dropped), (1203344812, 3));
+        assert_eq!(interfaces[2].name, "docker0");
+    }
+
+    #[test]
+    fn parses_pressure() {
+        let memory = parse_pressure(&fixture("pressure/memory")).unwrap();
+        assert_eq!(memory.some.avg10, 12.5);
+        assert_eq!(memory.full.unwrap().avg60, 3.0);
+        assert_eq!(memory.some.total_us, 9120331);
+
+        let cpu = parse_pressure("some avg10=0.27 avg60=2.39 avg300=4.41 total=338803129\n").unwrap();
+        assert_eq!(cpu.full, None);
+        assert!(parse_pressure("").is_none());
+    }
+
+    #[test]
+    fn parses_pid_stat() {
+        let stat = parse_pid_stat(&fixture("4100/stat")).unwrap();
+        assert_eq!(stat.pid, 4100);
+        assert_eq!(stat.comm, "python3 (worker)");
+        assert_eq!(stat.state, 'R');
+        assert_eq!(stat.cpu_ticks, 91203 + 8120);
+        assert_eq!(stat.rss_pages, 131072);
+        assert!(parse_pid_stat("12 (truncated) S 1 2").is_none());
+    }
+}
@@ -4,10 +4,11 @@
 //! capabilities for discovered systems. It tracks system performance metrics, detects anomalies,
 //! and generates alerts for system issues.
 
+use crate::alerting::{self, AlertManager, AlertObservation, Notifier};
 use crate::types::*;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use std::collections::HashMap;
+use chrono::Utc;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::time::{interval, Duration};
@@ -18,7 +19,8 @@ pub struct SystemMonitor {
     config: MonitorConfig,
     health_checks: Arc<Mutex<Vec<Box<dyn HealthCheck>>>>,
     performance_metrics: Arc<Mutex<PerformanceMetrics>>,
-    alerts: Arc<Mutex<Vec<Alert>>>,
+    alerts: Arc<Mutex<AlertManager>>,
+    notifiers: Arc<Vec<Box<dyn Notifier>>>,
     is_running: Arc<Mutex<bool>>,
 }
 
@@ -38,13 +40,20 @@ impl SystemMonitor {
             },
             processes: Vec::new(),
         }));
-        let alerts = Arc::new(Mutex::new(Vec::new()));
+        let alerts = Arc::new(Mutex::new(AlertManager::new(config.alerting.clone())));
+        let notifiers = config
+            .alerting
+            .notifiers
+            .iter()
+            .map(alerting::build_notifier)
+            .collect::<Result<Vec<_>, _>>()?;
 
         let mut monitor = Self {
             config,
             health_checks,
             performance_metrics,
             alerts,
+            notifiers: Arc::new(notifiers),
             is_running: Arc::new(Mutex::new(false)),
         };
 
@@ -64,6 +73,7 @@ impl SystemMonitor {
         let health_checks = self.health_checks.clone();
         let performance_metrics = self.performance_metrics.clone();
         let alerts = self.alerts.clone();
+        let notifiers = self.notifiers.clone();
         let is_running = self.is_running.clone();
 
         tokio::spawn(async move {
@@ -82,6 +92,7 @@ impl SystemMonitor {
                             &health_checks,
                             &performance_metrics,
                             &alerts,
+                            &notifiers,
                         ).await {
                             error!("Health check cycle failed: {}", e);
                         }
@@ -110,14 +121,17 @@ impl SystemMonitor {
 
         let mut checks_map = HashMap::new();
         for check in health_checks.iter() {
-            let result = check.check_health().await?;
+            let result = check
+                .check_health()
+                .await
+                .map_err(|e| DiscoveryError::MonitorError(e.to_string()))?;
             checks_map.insert(check.get_check_id(), result);
         }
 
         Ok(MonitoringState {
             health_checks: checks_map,
             performance_metrics: performance_metrics.clone(),
-            alerts: alerts.clone(),
+            alerts: alerts.alerts(),
             last_update: Utc::now(),
         })
     }
@@ -198,7 +212,7 @@ impl SystemMonitor {
     pub async fn health_check(&self) -> Result<ComponentHealth, DiscoveryError> {
         let is_running = *self.is_running.lock().await;
         let health_checks_count = self.health_checks.lock().await.len();
-        let alerts_count = self.alerts.lock().await.len();
+        let alerts_count = self.alerts.lock().await.firing_count();
 
         let status = if is_running && alerts_count == 0 {
             ServiceStatus::Healthy
@@ -232,11 +246,12 @@ impl SystemMonitor {
     /// Remove a health check by ID
     pub async fn remove_health_check(&self, check_id: &CheckId) -> Result<(), DiscoveryError> {
         let mut checks = self.health_checks.lock().await;
-        checks.retain(|check| check.get_check_id() != check_id);
+        checks.retain(|check| check.get_check_id() != *check_id);
         Ok(())
     }
 
-    /// Generate alert for system issue
+    /// Generate alert for system issue. Repeated calls with the same title and system update
+    /// one alert instead of creating new ones.
     pub async fn generate_alert(
         &self,
         severity: AlertSeverity,
@@ -244,38 +259,80 @@ impl SystemMonitor {
         description: String,
         system_id: Option<SystemId>,
     ) -> Result<(), DiscoveryError> {
-        let alert = Alert {
-            id: uuid::Uuid::new_v4().to_string(),
-            severity,
-            title,
-            description,
-            system_id,
-            timestamp: Utc::now(),
-            acknowledged: false,
-            acknowledged_by: None,
-            acknowledged_at: None,
+        let mut labels = BTreeMap::from([("alertname".to_string(), title.clone())]);
+        if let Some(system_id) = &system_id {
+            labels.insert("system_id".to_string(), system_id.clone());
+        }
+
+        let now = Utc::now();
+        let notifications = {
+            let mut alerts = self.alerts.lock().await;
+            alerts.observe(
+                AlertObservation {
+                    labels,
+                    severity,
+                    title: title.clone(),
+                    description: description.clone(),
+                    system_id,
+                },
+                now,
+            );
+            alerts.evaluate(now)
         };
+        alerting::dispatch(&self.notifiers, &notifications).await;
+        info!("Generated alert: {} - {}", title, description);
+        Ok(())
+    }
 
-        self.alerts.lock().await.push(alert);
-        info!("Generated alert: {} - {}", alert.title, alert.description);
+    /// Clear the condition behind an alert; it resolves once it has stayed clear for
+    /// `resolve_seconds`
+    pub async fn clear_alert(&self, fingerprint: &str) -> Result<(), DiscoveryError> {
+        self.alerts.lock().await.clear(fingerprint, Utc::now());
         Ok(())
     }
 
-    /// Acknowledge an alert
+    /// Acknowledge an alert by id or fingerprint
     pub async fn acknowledge_alert(
         &self,
         alert_id: &str,
         acknowledged_by: String,
     ) -> Result<(), DiscoveryError> {
-        let mut alerts = self.alerts.lock().await;
-        if let Some(alert) = alerts.iter_mut().find(|a| a.id == alert_id) {
-            alert.acknowledged = true;
-            alert.acknowledged_by = Some(acknowledged_by);
-            alert.acknowledged_at = Some(Utc::now());
-        }
+        self.alerts
+            .lock()
+            .await
+            .acknowledge(alert_id, acknowledged_by, Utc::now());
         Ok(())
     }
 
+    /// Mute notifications for alerts carrying all of `matchers` for `duration`
+    pub async fn silence_alerts(
+        &self,
+        matchers: BTreeMap<String, String>,
+        duration: Duration,
+        created_by: String,
+        comment: String,
+    ) -> Result<String, DiscoveryError> {
+        let now = Utc::now();
+        let ends_at = now
+            + chrono::Duration::from_std(duration)
+                .map_err(|e| DiscoveryError::ValidationError(e.to_string()))?;
+        self.alerts
+            .lock()
+            .await
+            .silence(matchers, now, ends_at, created_by, comment)
+    }
+
+    /// End a silence early
+    pub async fn expire_silence(&self, silence_id: &str) -> Result<(), DiscoveryError> {
+        self.alerts.lock().await.expire_silence(silence_id, Utc::now());
+        Ok(())
+    }
+
+    /// Alert lifecycle state shared with the health check loop
+    pub fn alert_manager(&self) -> Arc<Mutex<AlertManager>> {
+        self.alerts.clone()
+    }
+
     /// Initialize default health checks
     async fn initialize_default_checks(&mut self) -> Result<(), DiscoveryError> {
         // CPU usage check
@@ -302,10 +359,11 @@ impl SystemMonitor {
 
     /// Perform health checks cycle
     async fn perform_health_checks(
-        config: &MonitorConfig,
+        _config: &MonitorConfig,
         health_checks: &Arc<Mutex<Vec<Box<dyn HealthCheck>>>>,
         performance_metrics: &Arc<Mutex<PerformanceMetrics>>,
-        alerts: &Arc<Mutex<Vec<Alert>>>,
+        alerts: &Arc<Mutex<AlertManager>>,
+        notifiers: &Arc<Vec<Box<dyn Notifier>>>,
     ) -> Result<(), DiscoveryError> {
         debug!("Starting health check cycle");
 
@@ -350,19 +408,18 @@ impl SystemMonitor {
                         }
                     }
 
-                    // Generate alerts for critical issues
-                    if health_result.status == HealthStatus::Critical {
-                        let _ = Self::generate_alert_from_health_result(
-                            alerts,
-                            &health_result,
-                            AlertSeverity::Critical,
-                        ).await;
-                    } else if health_result.status == HealthStatus::Warning {
-                        let _ = Self::generate_alert_from_health_result(
-                            alerts,
-                            &health_result,
-                            AlertSeverity::Warning,
-                        ).await;
+                    // Raise alerts for failing checks and clear them for passing ones
+                    let observation = Self::alert_from_health_result(&health_result);
+                    let now = Utc::now();
+                    let mut manager = alerts.lock().await;
+                    match health_result.status {
+                        HealthStatus::Critical | HealthStatus::Warning => {
+                            manager.observe(observation, now);
+                        }
+                        HealthStatus::Healthy => {
+                            manager.clear(&alerting::fingerprint(&observation.labels), now);
+                        }
+                        HealthStatus::Unknown => {}
                     }
                 }
                 Err(e) => {
@@ -379,33 +436,35 @@ impl SystemMonitor {
             metrics.disk_usage_percent = total_disk / checks_performed as f64;
         }
 
+        let notifications = alerts.lock().await.evaluate(Utc::now());
+        let failures = alerting::dispatch(notifiers, &notifications).await;
+        if failures > 0 {
+            warn!("{} alert notifications could not be delivered", failures);
+        }
+
         debug!("Health check cycle completed");
         Ok(())
     }
 
-    /// Generate alert from health check result
-    async fn generate_alert_from_health_result(
-        alerts: &Arc<Mutex<Vec<Alert>>>,
-        health_result: &HealthCheck,
-        severity: AlertSeverity,
-    ) -> Result<(), DiscoveryError> {
-        let alert = Alert {
-            id: uuid::Uuid::new_v4().to_string(),
-            severity,
+    /// Alert observation for a health check result, identified by check and system
+    fn alert_from_health_result(health_result: &HealthCheckResult) -> AlertObservation {
+        AlertObservation {
+            labels: BTreeMap::from([
+                ("alertname".to_string(), "health_check_failed".to_string()),
+                ("check_id".to_string(), health_result.check_id.clone()),
+                ("system_id".to_string(), health_result.system_id.clone()),
+            ]),
+            severity: match health_result.status {
+                HealthStatus::Critical => AlertSeverity::Critical,
+                _ => AlertSeverity::Warning,
+            },
             title: format!("Health check failed: {}", health_result.check_id),
             description: format!(
                 "Health check {} failed with status {:?}",
                 health_result.check_id, health_result.status
             ),
-            system_id: Some("system".to_string()), // Would be populated from actual system
-            timestamp: Utc::now(),
-            acknowledged: false,
-            acknowledged_by: None,
-            acknowledged_at: None,
-        };
-
-        alerts.lock().await.push(alert);
-        Ok(())
+            system_id: Some(health_result.system_id.clone()),
+        }
     }
 }
 
@@ -413,7 +472,7 @@ impl SystemMonitor {
 #[async_trait]
 pub trait HealthCheck: Send + Sync {
     /// Perform the health check
-    async fn check_health(&self) -> Result<HealthCheck, HealthError>;
+    async fn check_health(&self) -> Result<HealthCheckResult, HealthError>;
 
     /// Get the check identifier
     fn get_check_id(&self) -> CheckId;
@@ -431,13 +490,13 @@ pub trait HealthCheck: Send + Sync {
 }
 
 /// CPU usage health check
-struct CpuUsageCheck {
+pub struct CpuUsageCheck {
     threshold: f64,
     check_id: CheckId,
 }
 
 impl CpuUsageCheck {
-    fn new(threshold: f64) -> Self {
+    pub fn new(threshold: f64) -> Self {
         Self {
             threshold,
             check_id: "cpu-usage".to_string(),
@@ -447,10 +506,10 @@ impl CpuUsageCheck {
 
 #[async_trait]
 impl HealthCheck for CpuUsageCheck {
-    async fn check_health(&self) -> Result<HealthCheck, HealthError> {
+    async fn check_health(&self) -> Result<HealthCheckResult, HealthError> {
         // Get CPU usage from system
         let sys = sysinfo::System::new_all();
-        let cpu_usage = sys.global_cpu_info().cpu_usage();
+        let cpu_usage = sys.global_cpu_info().cpu_usage() as f64;
 
         let status = if cpu_usage > self.threshold {
             HealthStatus::Critical
@@ -461,9 +520,9 @@ impl HealthCheck for CpuUsageCheck {
         };
 
         let mut metrics = HashMap::new();
-        metrics.insert("cpu_usage".to_string(), cpu_usage as f64);
+        metrics.insert("cpu_usage".to_string(), cpu_usage);
 
-        Ok(HealthCheck {
+        Ok(HealthCheckResult {
             check_id: self.check_id.clone(),
             system_id: "local".to_string(),
             check_type: HealthCheckType::Performance,
@@ -489,13 +548,13 @@ impl HealthCheck for CpuUsageCheck {
 }
 
 /// Memory usage health check
-struct MemoryUsageCheck {
+pub struct MemoryUsageCheck {
     threshold: f64,
     check_id: CheckId,
 }
 
 impl MemoryUsageCheck {
-    fn new(threshold: f64) -> Self {
+    pub fn new(threshold: f64) -> Self {
         Self {
             threshold,
             check_id: "memory-usage".to_string(),
@@ -505,7 +564,7 @@ impl MemoryUsageCheck {
 
 #[async_trait]
 impl HealthCheck for MemoryUsageCheck {
-    async fn check_health(&self) -> Result<HealthCheck, HealthError> {
+    async fn check_health(&self) -> Result<HealthCheckResult, HealthError> {
         let sys = sysinfo::System::new_all();
         let total_memory = sys.total_memory() as f64;
         let used_memory = sys.used_memory() as f64;
@@ -522,7 +581,7 @@ impl HealthCheck for MemoryUsageCheck {
         let mut metrics = HashMap::new();
         metrics.insert("memory_usage".to_string(), memory_usage);
 
-        Ok(HealthCheck {
+        Ok(HealthCheckResult {
             check_id: self.check_id.clone(),
             system_id: "local".to_string(),
             check_type: HealthCheckType::ResourceUsage,
@@ -548,13 +607,13 @@ impl HealthCheck for MemoryUsageCheck {
 }
 
 /// Disk usage health check
-struct DiskUsageCheck {
+pub struct DiskUsageCheck {
     threshold: f64,
     check_id: CheckId,
 }
 
 impl DiskUsageCheck {
-    fn new(threshold: f64) -> Self {
+    pub fn new(threshold: f64) -> Self {
         Self {
             threshold,
             check_id: "disk-usage".to_string(),
@@ -564,7 +623,7 @@ impl DiskUsageCheck {
 
 #[async_trait]
 impl HealthCheck for DiskUsageCheck {
-    async fn check_health(&self) -> Result<HealthCheck, HealthError> {
+    async fn check_health(&self) -> Result<HealthCheckResult, HealthError> {
         // This would check actual disk usage
         // For now, return a placeholder
         let disk_usage = 50.0; // Placeholder value
@@ -580,7 +639,7 @@ impl HealthCheck for DiskUsageCheck {
         let mut metrics = HashMap::new();
         metrics.insert("disk_usage".to_string(), disk_usage);
 
-        Ok(HealthCheck {
+        Ok(HealthCheckResult {
             check_id: self.check_id.clone(),
             system_id: "local".to_string(),
             check_type: HealthCheckType::ResourceUsage,
@@ -606,12 +665,18 @@ impl HealthCheck for DiskUsageCheck {
 }
 
 /// Network connectivity health check
-struct NetworkConnectivityCheck {
+pub struct NetworkConnectivityCheck {
     check_id: CheckId,
 }
 
+impl Default for NetworkConnectivityCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NetworkConnectivityCheck {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             check_id: "network-connectivity".to_string(),
         }
@@ -620,7 +685,7 @@ impl NetworkConnectivityCheck {
 
 #[async_trait]
 impl HealthCheck for NetworkConnectivityCheck {
-    async fn check_health(&self) -> Result<HealthCheck, HealthError> {
+    async fn check_health(&self) -> Result<HealthCheckResult, HealthError> {
         // Check basic network connectivity
         // This would ping a reliable external service or check local network interfaces
         let status = HealthStatus::Healthy; // Placeholder
@@ -628,7 +693,7 @@ impl HealthCheck for NetworkConnectivityCheck {
         let mut metrics = HashMap::new();
         metrics.insert("connectivity".to_string(), 1.0);
 
-        Ok(HealthCheck {
+        Ok(HealthCheckResult {
             check_id: self.check_id.clone(),
             system_id: "local".to_string(),
             check_type: HealthCheckType::Connectivity,
@@ -0,0 +1,530 @@
+//! # Time Series
+//!
+//! In-memory history for collected data points. Each series is identified by a metric name and
+//! its labels and keeps a ring of raw samples plus one ring of min/max/avg/count buckets per
+//! configured rollup tier (1 minute and 1 hour by default), so that short ranges can be read at
+//! full resolution and long ranges from the coarser tiers. Rings have a fixed capacity and drop
+//! their oldest entries first.
+//!
+//! The store can be written to a JSON snapshot and restored from it, which the data collector
+//! does on stop and start when `snapshot_path` is configured.
+
+use crate::types::*;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Metrics recorded once per event, whose other tags describe that one event (a log line's
+/// message, time, ids) and would otherwise make every event a series of its own, with the only
+/// labels their series are keyed by
+const EVENT_LABELS: &[(&str, &[&str])] = &[("log_entry", &["source", "level"])];
+
+/// Identity of a series
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SeriesKey {
+    pub metric: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+impl SeriesKey {
+    pub fn new<K, V>(metric: impl Into<String>, labels: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            metric: metric.into(),
+            labels: labels.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+        }
+    }
+
+    /// The series a data point belongs to, labelled by its tags. Event metrics keep only their
+    /// bounded labels, see [`EVENT_LABELS`].
+    pub fn from_point(point: &DataPoint) -> Self {
+        match EVENT_LABELS.iter().find(|(metric, _)| *metric == point.metric_name) {
+            Some((_, labels)) => Self::new(
+                point.metric_name.clone(),
+                point.tags.iter().filter(|(name, _)| labels.contains(&name.as_str())),
+            ),
+            None => Self::new(point.metric_name.clone(), point.tags.clone()),
+        }
+    }
+
+    /// Whether every label in `labels` is present with the same value
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        labels.iter().all(|(k, v)| self.labels.get(k) == Some(v))
+    }
+}
+
+/// A raw sample
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Aggregate of the samples falling into one bucket of a rollup tier
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rollup {
+    /// Start of the bucket
+    pub start: DateTime<Utc>,
+    pub min: f64,
+    pub max: f64,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Rollup {
+    fn new(start: DateTime<Utc>, value: f64) -> Self {
+        Self {
+            start,
+            min: value,
+            max: value,
+            sum: value,
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn avg(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+/// Fixed-capacity ring of rollup buckets for one tier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RollupRing {
+    resolution_seconds: u64,
+    capacity: usize,
+    buckets: VecDeque<Rollup>,
+}
+
+impl RollupRing {
+    fn new(tier: &RollupTierConfig) -> Self {
+        Self {
+            resolution_seconds: tier.resolution_seconds.max(1),
+            capacity: tier.capacity,
+            buckets: VecDeque::new(),
+        }
+    }
+
+    fn bucket_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let resolution = self.resolution_seconds as i64;
+        let start = timestamp.timestamp().div_euclid(resolution) * resolution;
+        DateTime::from_timestamp(start, 0).unwrap_or(timestamp)
+    }
+
+    fn add(&mut self, timestamp: DateTime<Utc>, value: f64) {
+        let start = self.bucket_start(timestamp);
+        // Samples mostly arrive in order, so the last bucket is checked first.
+        if self.buckets.back().is_some_and(|last| last.start > start) {
+            let index = self.buckets.partition_point(|bucket| bucket.start < start);
+            let full = self.buckets.len() >= self.capacity;
+            match self.buckets.get_mut(index) {
+                Some(bucket) if bucket.start == start => bucket.add(value),
+                // Older than everything kept while the ring is full
+                _ if index == 0 && full => {}
+                _ => self.buckets.insert(index, Rollup::new(start, value)),
+            }
+        } else if let Some(last) = self.buckets.back_mut().filter(|last| last.start == start) {
+            last.add(value);
+        } else {
+            self.buckets.push_back(Rollup::new(start, value));
+        }
+        while self.buckets.len() > self.capacity {
+            self.buckets.pop_front();
+        }
+    }
+}
+
+/// Raw samples and rollups of one series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Series {
+    raw: VecDeque<Sample>,
+    rollups: Vec<RollupRing>,
+}
+
+impl Series {
+    fn new(config: &TimeSeriesConfig) -> Self {
+        Self {
+            raw: VecDeque::new(),
+            rollups: config.rollups.iter().map(RollupRing::new).collect(),
+        }
+    }
+
+    fn add(&mut self, capacity: usize, sample: Sample) {
+        if self.raw.back().is_none_or(|last| last.timestamp <= sample.timestamp) {
+            self.raw.push_back(sample);
+        } else {
+            let index = self.raw.partition_point(|kept| kept.timestamp <= sample.timestamp);
+            self.raw.insert(index, sample);
+        }
+        while self.raw.len() > capacity {
+            self.raw.pop_front();
+        }
+        for ring in &mut self.rollups {
+            ring.add(sample.timestamp, sample.value);
+        }
+    }
+
+    /// Fit a restored series to the current configuration
+    fn conform(mut self, config: &TimeSeriesConfig) -> Self {
+        while self.raw.len() > config.raw_capacity {
+            self.raw.pop_front();
+        }
+        let mut restored = std::mem::take(&mut self.rollups);
+        self.rollups = config
+            .rollups
+            .iter()
+            .map(|tier| {
+                let mut ring = RollupRing::new(tier);
+                if let Some(old) = restored
+                    .iter_mut()
+                    .find(|old| old.resolution_seconds == ring.resolution_seconds)
+                {
+                    ring.buckets = std::mem::take(&mut old.buckets);
+                    while ring.buckets.len() > ring.capacity {
+                        ring.buckets.pop_front();
+                    }
+                }
+                ring
+            })
+            .collect();
+        self
+    }
+}
+
+/// On-disk form of the store; JSON objects need string keys, so series are listed
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    taken_at: DateTime<Utc>,
+    series: Vec<(SeriesKey, Series)>,
+}
+
+/// Ring-buffer store of collected data points keyed by metric and labels
+pub struct TimeSeriesStore {
+    config: TimeSeriesConfig,
+    series: HashMap<SeriesKey, Series>,
+    dropped_points: u64,
+}
+
+impl TimeSeriesStore {
+    pub fn new(config: TimeSeriesConfig) -> Self {
+        Self {
+            config,
+            series: HashMap::new(),
+            dropped_points: 0,
+        }
+    }
+
+    /// Restore a store from `path`, trimming it to `config`. A missing file gives an empty store.
+    pub fn restore(config: TimeSeriesConfig, path: &Path) -> io::Result<Self> {
+        let mut store = Self::new(config);
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(store),
+            Err(e) => return Err(e),
+        };
+        let snapshot: Snapshot = serde_json::from_slice(&contents)?;
+        for (key, series) in snapshot.series.into_iter().take(store.config.max_series) {
+            store.series.insert(key, series.conform(&store.config));
+        }
+        Ok(store)
+    }
+
+    /// Write the store to `path`, through a temporary file so a crash never leaves a torn snapshot
+    pub fn snapshot(&self, path: &Path) -> io::Result<()> {
+        let snapshot = Snapshot {
+            taken_at: Utc::now(),
+            series: self
+                .series
+                .iter()
+                .map(|(key, series)| (key.clone(), series.clone()))
+                .collect(),
+        };
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_vec(&snapshot)?)?;
+        fs::rename(&temporary, path)
+    }
+
+    /// Record a sample; returns false when it was dropped because the store holds `max_series`
+    pub fn insert(&mut self, key: SeriesKey, timestamp: DateTime<Utc>, value: f64) -> bool {
+        if !value.is_finite() {
+            self.dropped_points += 1;
+            return false;
+        }
+        if !self.series.contains_key(&key) && self.series.len() >= self.config.max_series {
+            self.dropped_points += 1;
+            return false;
+        }
+        let config = &self.config;
+        let series = self.series.entry(key).or_insert_with(|| Series::new(config));
+        series.add(config.raw_capacity, Sample { timestamp, value });
+        true
+    }
+
+    /// Record every data point of a batch, returning how many were kept
+    pub fn ingest(&mut self, batch: &DataBatch) -> usize {
+        batch
+            .data_points
+            .iter()
+            .filter(|point| self.insert(SeriesKey::from_point(point), point.timestamp, point.value))
+            .count()
+    }
+
+    /// All series, in key order
+    pub fn keys(&self) -> Vec<&SeriesKey> {
+        let mut keys: Vec<&SeriesKey> = self.series.keys().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Series of `metric` carrying all of `labels`, in key order
+    pub fn find(&self, metric: &str, labels: &BTreeMap<String, String>) -> Vec<&SeriesKey> {
+        let mut keys: Vec<&SeriesKey> = self
+            .series
+            .keys()
+            .filter(|key| key.metric == metric && key.matches(labels))
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Most recent raw sample of a series
+    pub fn latest(&self, key: &SeriesKey) -> Option<Sample> {
+        self.series.get(key)?.raw.back().copied()
+    }
+
+    /// Raw samples with `start <= timestamp < end`, oldest first
+    pub fn range(&self, key: &SeriesKey, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Sample> {
+        self.series
+            .get(key)
+            .map(|series| {
+                series
+                    .raw
+                    .iter()
+                    .filter(|sample| sample.timestamp >= start && sample.timestamp < end)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Buckets of the tier with `resolution_seconds` starting in `start..end`, oldest first;
+    /// `None` when no such tier is configured
+    pub fn rollups(
+        &self,
+        key: &SeriesKey,
+        resolution_seconds: u64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Option<Vec<Rollup>> {
+        if !self
+            .config
+            .rollups
+            .iter()
+            .any(|tier| tier.resolution_seconds.max(1) == resolution_seconds)
+        {
+            return None;
+        }
+        let Some(series) = self.series.get(key) else {
+            return Some(Vec::new());
+        };
+        let ring = series
+            .rollups
+            .iter()
+            .find(|ring| ring.resolution_seconds == resolution_seconds)?;
+        Some(
+            ring.buckets
+                .iter()
+                .filter(|bucket| bucket.start >= start && bucket.start < end)
+                .copied()
+                .collect(),
+        )
+    }
+
+    /// Number of series held
+    pub fn len(&self) -> usize {
+        self.series.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// Points refused since the store was created, for exceeding `max_series` or not being finite
+    pub fn dropped_points(&self) -> u64 {
+        self.dropped_points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_760_000_000 + seconds, 0).unwrap()
+    }
+
+    fn config(raw_capacity: usize) -> TimeSeriesConfig {
+        TimeSeriesConfig {
+            raw_capacity,
+            ..TimeSeriesConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_raw_ring_and_range_queries() {
+        let mut store = TimeSeriesStore::new(config(3));
+        let key = SeriesKey::new("cpu_usage", [("host", "a")]);
+        for (offset, value) in [(0, 1.0), (30, 2.0), (90, 4.0), (60, 3.0)] {
+            assert!(store.insert(key.clone(), at(offset), value));
+        }
+
+        // The oldest sample fell out of the ring and the late one was placed in order
+        let values: Vec<f64> = store
+            .range(&key, at(0), at(120))
+            .iter()
+            .map(|sample| sample.value)
+            .collect();
+        assert_eq!(values, vec![2.0, 3.0, 4.0]);
+        assert_eq!(store.range(&key, at(60), at(90)).len(), 1);
+        assert_eq!(store.latest(&key).unwrap().value, 4.0);
+        assert!(store.range(&SeriesKey::new("cpu_usage", [("host", "b")]), at(0), at(120)).is_empty());
+    }
+
+    #[test]
+    fn test_rollup_tiers() {
+        let mut store = TimeSeriesStore::new(config(2));
+        let key = SeriesKey::new("memory_usage", Vec::<(String, String)>::new());
+        for (offset, value) in [(0, 10.0), (20, 30.0), (30, 20.0), (70, 5.0), (3650, 7.0)] {
+            store.insert(key.clone(), at(offset), value);
+        }
+
+        let minutes = store.rollups(&key, 60, at(-3600), at(7200)).unwrap();
+        assert_eq!(minutes.len(), 3);
+        // 1_760_000_000 is 20 seconds into its minute, so 0, 20 and 30 share the first bucket.
+        assert_eq!(minutes[0].start, at(-20));
+        assert_eq!((minutes[0].min, minutes[0].max, minutes[0].count), (10.0, 30.0, 3));
+        assert_eq!(minutes[0].avg(), 20.0);
+
+        let hours = store.rollups(&key, 3600, at(-3600), at(7200)).unwrap();
+        assert_eq!(hours.iter().map(|bucket| bucket.count).sum::<u64>(), 5);
+        assert!(store.rollups(&key, 300, at(0), at(7200)).is_none());
+    }
+
+    #[test]
+    fn test_ingest_limits_series_and_finds_by_labels() {
+        let mut store = TimeSeriesStore::new(TimeSeriesConfig {
+            max_series: 2,
+            ..TimeSeriesConfig::default()
+        });
+        let point = |interface: &str, value: f64| DataPoint {
+            metric_name: "interface_rx_bytes_per_sec".to_string(),
+            value,
+            unit: "bytes/s".to_string(),
+            tags: HashMap::from([("interface".to_string(), interface.to_string())]),
+            timestamp: at(0),
+        };
+        let batch = DataBatch {
+            source_id: "network-traffic".to_string(),
+            timestamp: at(0),
+            data_points: vec![point("eth0", 1.0), point("eth1", 2.0), point("eth2", 3.0), point("eth0", f64::NAN)],
+            quality_score: 1.0,
+            metadata: HashMap::new(),
+        };
+
+        assert_eq!(store.ingest(&batch), 2);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.dropped_points(), 2);
+        let eth1 = BTreeMap::from([("interface".to_string(), "eth1".to_string())]);
+        let found = store.find("interface_rx_bytes_per_sec", &eth1);
+        assert_eq!(found.len(), 1);
+        assert_eq!(store.latest(found[0]).unwrap().value, 2.0);
+        assert_eq!(store.find("interface_rx_bytes_per_sec", &BTreeMap::new()).len(), 2);
+    }
+
+    #[test]
+    fn test_event_points_share_a_series_per_source_and_level() {
+        let mut store = TimeSeriesStore::new(TimeSeriesConfig {
+            max_series: 2,
+            ..TimeSeriesConfig::default()
+        });
+        let entry = |level: &str, message: &str, offset: i64| DataPoint {
+            metric_name: "log_entry".to_string(),
+            value: 1.0,
+            unit: "count".to_string(),
+            tags: HashMap::from([
+                ("source".to_string(), "audit".to_string()),
+                ("level".to_string(), level.to_string()),
+                ("message".to_string(), message.to_string()),
+                ("audit_id".to_string(), offset.to_string()),
+            ]),
+            timestamp: at(offset),
+        };
+        let batch = DataBatch {
+            source_id: "application-logs".to_string(),
+            timestamp: at(0),
+            data_points: (0..100).map(|offset| entry("info", &format!("request {}", offset), offset)).collect(),
+            quality_score: 1.0,
+            metadata: HashMap::new(),
+        };
+
+        assert_eq!(store.ingest(&batch), 100);
+        assert_eq!(store.len(), 1);
+        let key = SeriesKey::new("log_entry", [("source", "audit"), ("level", "info")]);
+        assert_eq!(store.range(&key, at(0), at(100)).len(), 100);
+
+        // Other sources still find room in the store
+        let cpu = SeriesKey::new("cpu_usage", [("host", "a")]);
+        assert!(store.insert(cpu, at(0), 1.0));
+        assert_eq!(store.dropped_points(), 0);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/timeseries.json");
+        let key = SeriesKey::new("error_rate", [("source", "api")]);
+
+        let mut store = TimeSeriesStore::new(config(10));
+        for offset in 0..5 {
+            store.insert(key.clone(), at(offset * 60), offset as f64);
+        }
+        store.snapshot(&path).unwrap();
+
+        // Restoring into a smaller configuration keeps the newest entries
+        let restored = TimeSeriesStore::restore(
+            TimeSeriesConfig {
+                raw_capacity: 2,
+                rollups: vec![RollupTierConfig {
+                    resolution_seconds: 60,
+                    capacity: 3,
+                }],
+                ..TimeSeriesConfig::default()
+            },
+            &path,
+        )
+        .unwrap();
+        let samples = restored.range(&key, at(0), at(0) + Duration::hours(1));
+        assert_eq!(samples.iter().map(|sample| sample.value).collect::<Vec<_>>(), vec![3.0, 4.0]);
+        assert_eq!(restored.rollups(&key, 60, at(-60), at(3600)).unwrap().len(), 3);
+        assert!(restored.rollups(&key, 3600, at(0), at(3600)).is_none());
+
+        let missing = TimeSeriesStore::restore(TimeSeriesConfig::default(), &dir.path().join("none.json")).unwrap();
+        assert!(missing.is_empty());
+    }
+}
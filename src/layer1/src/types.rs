@@ -5,7 +5,8 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Unique identifier for discovered systems
@@ -18,7 +19,7 @@ pub type SourceId = String;
 pub type CheckId = String;
 
 /// Discovery service configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DiscoveryConfig {
     /// Environmental scanner configuration
     pub scanner: ScannerConfig,
@@ -30,17 +31,6 @@ pub struct DiscoveryConfig {
     pub integration: IntegrationConfig,
 }
 
-impl Default for DiscoveryConfig {
-    fn default() -> Self {
-        Self {
-            scanner: ScannerConfig::default(),
-            monitor: MonitorConfig::default(),
-            collector: CollectorConfig::default(),
-            integration: IntegrationConfig::default(),
-        }
-    }
-}
-
 /// Environmental scanner configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScannerConfig {
@@ -54,6 +44,21 @@ pub struct ScannerConfig {
     pub cache_ttl_seconds: u64,
     /// Enable deep scanning (more thorough but slower)
     pub deep_scan_enabled: bool,
+    /// TCP connect scanning of remote hosts
+    #[serde(default)]
+    pub network: NetworkScanConfig,
+    /// Root of the proc filesystem read by the service and container probes
+    #[serde(default = "default_proc_root")]
+    pub proc_root: PathBuf,
+    /// Root of the cgroup v2 hierarchy read by the container probe
+    #[serde(default = "default_cgroup_root")]
+    pub cgroup_root: PathBuf,
+    /// Per-probe schedule overrides keyed by probe name (`local`, `network`, `container`, `service`)
+    #[serde(default)]
+    pub probes: HashMap<String, ProbeConfig>,
+    /// Upper bound on the retry delay of a failing probe in seconds
+    #[serde(default = "default_probe_max_backoff")]
+    pub probe_max_backoff_seconds: u64,
 }
 
 impl Default for ScannerConfig {
@@ -64,6 +69,65 @@ impl Default for ScannerConfig {
             network_timeout_seconds: 10,
             cache_ttl_seconds: 3600, // 1 hour
             deep_scan_enabled: false,
+            network: NetworkScanConfig::default(),
+            proc_root: default_proc_root(),
+            cgroup_root: default_cgroup_root(),
+            probes: HashMap::new(),
+            probe_max_backoff_seconds: default_probe_max_backoff(),
+        }
+    }
+}
+
+fn default_proc_root() -> PathBuf {
+    PathBuf::from("/proc")
+}
+
+fn default_cgroup_root() -> PathBuf {
+    PathBuf::from("/sys/fs/cgroup")
+}
+
+fn default_probe_max_backoff() -> u64 {
+    3600 // 1 hour
+}
+
+/// Schedule overrides for a single probe; unset fields keep the probe's defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProbeConfig {
+    /// Run the probe on its schedule
+    pub enabled: Option<bool>,
+    /// Seconds between runs
+    pub interval_seconds: Option<u64>,
+    /// Seconds a run may take before it counts as failed
+    pub timeout_seconds: Option<u64>,
+    /// Up to this many seconds are added at random to each interval
+    pub jitter_seconds: Option<u64>,
+}
+
+/// Network probe configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkScanConfig {
+    /// IPv4 CIDR ranges to sweep, e.g. `10.0.0.0/24`; nothing is scanned when empty
+    pub ranges: Vec<String>,
+    /// TCP ports tried on every host
+    pub ports: Vec<u16>,
+    /// Connection attempts in flight at once
+    pub max_concurrent_connections: usize,
+    /// Per-connection timeout in milliseconds
+    pub connect_timeout_ms: u64,
+    /// Refuse ranges that expand to more hosts than this
+    pub max_hosts: usize,
+}
+
+impl Default for NetworkScanConfig {
+    fn default() -> Self {
+        Self {
+            ranges: Vec::new(),
+            ports: vec![22, 80, 443, 5432, 6379, 8080, 9090],
+            max_concurrent_connections: 256,
+            connect_timeout_ms: 500,
+            max_hosts: 4096,
         }
     }
 }
@@ -81,6 +145,9 @@ pub struct MonitorConfig {
     pub disk_alert_threshold: f64,
     /// Enable real-time monitoring
     pub real_time_enabled: bool,
+    /// Alert deduplication, grouping, escalation and notification
+    #[serde(default)]
+    pub alerting: AlertingConfig,
 }
 
 impl Default for MonitorConfig {
@@ -91,10 +158,65 @@ impl Default for MonitorConfig {
             memory_alert_threshold: 85.0,
             disk_alert_threshold: 90.0,
             real_time_enabled: true,
+            alerting: AlertingConfig::default(),
+        }
+    }
+}
+
+/// Alert lifecycle configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertingConfig {
+    /// Labels whose values put alerts in the same notification group
+    pub group_by: Vec<String>,
+    /// Seconds a condition must persist before its alert fires
+    pub pending_seconds: u64,
+    /// Seconds a condition must stay clear before its firing alert resolves
+    pub resolve_seconds: u64,
+    /// Seconds before a group that is still firing is notified again
+    pub repeat_interval_seconds: u64,
+    /// Seconds a firing alert may stay unacknowledged before its severity is raised, repeatedly;
+    /// alerts never escalate when unset
+    pub escalate_after_seconds: Option<u64>,
+    /// Where notifications are sent
+    pub notifiers: Vec<NotifierConfig>,
+}
+
+impl Default for AlertingConfig {
+    fn default() -> Self {
+        Self {
+            group_by: vec!["alertname".to_string()],
+            pending_seconds: 0,
+            resolve_seconds: 120, // two default check intervals
+            repeat_interval_seconds: 3600, // 1 hour
+            escalate_after_seconds: Some(1800), // 30 minutes
+            notifiers: vec![NotifierConfig::Log],
         }
     }
 }
 
+/// A notification sink
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierConfig {
+    /// Write notifications to the tracing log
+    Log,
+    /// POST notifications as JSON
+    Webhook {
+        url: String,
+        #[serde(default = "default_webhook_timeout_ms")]
+        timeout_ms: u64,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Append notifications to a file as JSON lines
+    File { path: PathBuf },
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
 /// Data collector configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CollectorConfig {
@@ -108,6 +230,18 @@ pub struct CollectorConfig {
     pub compression_enabled: bool,
     /// External API timeout in seconds
     pub api_timeout_seconds: u64,
+    /// Root of the proc filesystem read by the system metrics and network traffic sources
+    #[serde(default = "default_proc_root")]
+    pub proc_root: PathBuf,
+    /// Number of processes, by CPU usage, reported by the system metrics source
+    #[serde(default = "default_top_processes")]
+    pub top_processes: usize,
+    /// Log files followed by the application logs source
+    #[serde(default)]
+    pub logs: LogSourceConfig,
+    /// History kept for collected data points
+    #[serde(default)]
+    pub timeseries: TimeSeriesConfig,
 }
 
 impl Default for CollectorConfig {
@@ -118,10 +252,126 @@ impl Default for CollectorConfig {
             retention_hours: 168, // 7 days
             compression_enabled: true,
             api_timeout_seconds: 30,
+            proc_root: default_proc_root(),
+            top_processes: default_top_processes(),
+            logs: LogSourceConfig::default(),
+            timeseries: TimeSeriesConfig::default(),
         }
     }
 }
 
+fn default_top_processes() -> usize {
+    10
+}
+
+/// Time-series store configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeSeriesConfig {
+    /// Raw samples kept per series
+    pub raw_capacity: usize,
+    /// Rollup tiers, each fed from the raw samples
+    pub rollups: Vec<RollupTierConfig>,
+    /// Most series held; points for further series are dropped
+    pub max_series: usize,
+    /// File the store is restored from on start and written to on stop; nothing is kept when unset
+    pub snapshot_path: Option<PathBuf>,
+}
+
+impl Default for TimeSeriesConfig {
+    fn default() -> Self {
+        Self {
+            raw_capacity: 720, // 6 hours at the default collection interval
+            rollups: vec![
+                RollupTierConfig {
+                    resolution_seconds: 60,
+                    capacity: 1440, // 1 day
+                },
+                RollupTierConfig {
+                    resolution_seconds: 3600,
+                    capacity: 720, // 30 days
+                },
+            ],
+            max_series: 10_000,
+            snapshot_path: None,
+        }
+    }
+}
+
+/// A rollup tier of min/max/avg/count buckets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RollupTierConfig {
+    /// Bucket width
+    pub resolution_seconds: u64,
+    /// Buckets kept per series
+    pub capacity: usize,
+}
+
+/// Application logs source configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSourceConfig {
+    /// Files to follow
+    pub files: Vec<LogFileConfig>,
+    /// File keeping read offsets across restarts; offsets are not kept when unset
+    pub offsets_path: Option<PathBuf>,
+    /// Most bytes read from one file per collection
+    pub max_bytes_per_collection: u64,
+}
+
+impl Default for LogSourceConfig {
+    fn default() -> Self {
+        Self {
+            // The platform's own audit trail
+            files: vec![LogFileConfig {
+                path: PathBuf::from("logs/audit.log"),
+                name: Some("audit".to_string()),
+                format: LogFormat::Audit,
+                pattern: None,
+                numeric_fields: Vec::new(),
+                from_beginning: false,
+            }],
+            offsets_path: None,
+            max_bytes_per_collection: 1024 * 1024, // 1 MiB
+        }
+    }
+}
+
+/// A followed log file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFileConfig {
+    pub path: PathBuf,
+    /// `source` tag on the file's data points; the file stem when unset
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Regular expression with named groups, for the `regex` format
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Numeric fields reported as `log_<field>` data points
+    #[serde(default)]
+    pub numeric_fields: Vec<String>,
+    /// Read a file with no saved offset from its start rather than only following new lines
+    #[serde(default)]
+    pub from_beginning: bool,
+}
+
+/// Line formats understood by the application logs source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// One JSON object per line
+    #[default]
+    Json,
+    /// `key=value` pairs
+    Logfmt,
+    /// Named groups of `pattern`
+    Regex,
+    /// The platform's audit log, JSON lines of audit events
+    Audit,
+}
+
 /// Integration hub configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IntegrationConfig {
@@ -198,7 +448,7 @@ pub enum SystemStatus {
 }
 
 /// Resource information for a system
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SystemResources {
     /// CPU cores available
     pub cpu_cores: Option<u32>,
@@ -214,7 +464,7 @@ pub struct SystemResources {
 
 /// System health check result
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HealthCheck {
+pub struct HealthCheckResult {
     /// Check identifier
     pub check_id: CheckId,
     /// System being checked
@@ -397,7 +647,7 @@ pub struct ResourceAvailability {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringState {
     /// Active health checks
-    pub health_checks: HashMap<CheckId, HealthCheck>,
+    pub health_checks: HashMap<CheckId, HealthCheckResult>,
     /// System performance metrics
     pub performance_metrics: PerformanceMetrics,
     /// Active alerts
@@ -480,10 +730,67 @@ pub struct Alert {
     pub acknowledged_by: Option<String>,
     /// Acknowledged timestamp
     pub acknowledged_at: Option<DateTime<Utc>>,
+    /// Labels identifying the alert
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    /// Hash of the labels; repeated observations of the same fingerprint update one alert
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Lifecycle state
+    #[serde(default)]
+    pub state: AlertState,
+    /// When the alert started firing
+    #[serde(default)]
+    pub fired_at: Option<DateTime<Utc>>,
+    /// When the alert resolved
+    #[serde(default)]
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Times the condition was observed
+    #[serde(default)]
+    pub occurrences: u64,
+    /// Times the severity was raised for going unacknowledged
+    #[serde(default)]
+    pub escalations: u32,
+    /// Silence currently muting the alert
+    #[serde(default)]
+    pub silenced_by: Option<String>,
+}
+
+/// Alert lifecycle states
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    /// Observed, but not yet for `pending_seconds`
+    Pending,
+    #[default]
+    Firing,
+    Resolved,
+}
+
+/// A time-boxed silence muting notifications for the alerts it matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Silence {
+    pub id: String,
+    /// Labels an alert must carry, with these values, to be silenced
+    pub matchers: BTreeMap<String, String>,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+    pub created_by: String,
+    pub comment: String,
+}
+
+impl Silence {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.matchers.iter().all(|(k, v)| labels.get(k) == Some(v))
+    }
 }
 
 /// Alert severity levels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum AlertSeverity {
     Info,
     Warning,
@@ -553,9 +860,9 @@ pub enum DiscoveryData {
     /// Full system scan results
     FullScanResult(FullScanResult),
     /// System state update
-    SystemStateUpdate(SystemState),
+    SystemStateUpdate(Box<SystemState>),
     /// Health check results
-    HealthCheckResults(Vec<HealthCheck>),
+    HealthCheckResults(Vec<HealthCheckResult>),
     /// Data collection batch
     DataBatch(DataBatch),
     /// Alert notification
@@ -673,12 +980,12 @@ pub struct CollectionResult {
     /// Success rate (0.0 to 1.0)
     pub success_rate: f64,
     /// Errors encountered
-    pub errors: Vec<CollectionError>,
+    pub errors: Vec<CollectionErrorInfo>,
 }
 
 /// Collection error information
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CollectionError {
+pub struct CollectionErrorInfo {
     /// Source that failed
     pub source_id: SourceId,
     /// Error type
@@ -792,6 +1099,15 @@ pub enum ScanError {
 
     #[error("Scan cancelled")]
     Cancelled,
+
+    #[error("Invalid scan target: {0}")]
+    InvalidTarget(String),
+
+    #[error("Probe unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Probe timed out after {0} ms")]
+    Timeout(u64),
 }
 
 /// Health check error types
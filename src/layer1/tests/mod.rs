@@ -1,8 +0,0 @@
-//! # Layer 1 Tests
-//!
-//! This module contains all tests for Layer 1 (Discovery) components.
-
-pub mod unit_tests;
-pub mod integration_tests;
-pub mod performance_tests;
-pub mod test_utils;
\ No newline at end of file
@@ -3,8 +3,9 @@
 //! Comprehensive unit tests for all Layer 1 (Discovery) components.
 
 use layer1_discovery::*;
+use layer1_discovery::procfs::ProcFs;
 use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use chrono::Utc;
 
 #[cfg(test)]
 mod tests {
@@ -23,7 +24,9 @@ mod tests {
         let service = DiscoveryService::new(config).await.unwrap();
         let health = service.health_check().await.unwrap();
         assert_eq!(health.service, "layer1-discovery");
-        assert!(matches!(health.status, ServiceStatus::Healthy | ServiceStatus::Degraded));
+        assert_eq!(health.components.len(), 4);
+        // Nothing has been started yet, so every component reports itself down
+        assert_eq!(health.status, ServiceStatus::Unhealthy);
     }
 
     #[tokio::test]
@@ -161,13 +164,13 @@ mod tests {
         let local_probe = LocalSystemProbe::new();
         assert!(matches!(local_probe.get_probe_type(), ProbeType::LocalSystem));
 
-        let network_probe = NetworkProbe::new();
+        let network_probe = NetworkProbe::new(NetworkScanConfig::default());
         assert!(matches!(network_probe.get_probe_type(), ProbeType::Network));
 
-        let container_probe = ContainerProbe::new();
+        let container_probe = ContainerProbe::new(ProcFs::default(), std::path::PathBuf::from("/sys/fs/cgroup"));
         assert!(matches!(container_probe.get_probe_type(), ProbeType::Container));
 
-        let service_probe = ServiceProbe::new();
+        let service_probe = ServiceProbe::new(ProcFs::default());
         assert!(matches!(service_probe.get_probe_type(), ProbeType::Service));
     }
 
@@ -177,7 +180,7 @@ mod tests {
         let result = probe.scan().await;
         assert!(result.is_ok());
 
-        let system_info = result.unwrap();
+        let system_info = result.unwrap().remove(0);
         assert_eq!(system_info.system_type, SystemType::Server);
         assert!(!system_info.name.is_empty());
         assert_eq!(system_info.address, "127.0.0.1");
@@ -188,13 +191,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_system_metrics_source() {
-        let source = SystemMetricsSource::new();
+        let source = SystemMetricsSource::new(ProcFs::default(), 10);
         let result = source.collect_data().await;
         assert!(result.is_ok());
 
         let batch = result.unwrap();
         assert_eq!(batch.source_id, "system-metrics");
-        assert_eq!(batch.source_type, DataSourceType::SystemMetrics);
+        assert_eq!(source.get_source_type(), DataSourceType::SystemMetrics);
         assert!(!batch.data_points.is_empty());
         assert!(batch.quality_score > 0.9);
 
@@ -210,13 +213,27 @@ mod tests {
 
     #[tokio::test]
     async fn test_application_logs_source() {
-        let source = ApplicationLogsSource::new();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "level=info msg=started\nlevel=error msg=\"request failed\"\n").unwrap();
+        let source = ApplicationLogsSource::new(LogSourceConfig {
+            files: vec![LogFileConfig {
+                path,
+                name: Some("app".to_string()),
+                format: LogFormat::Logfmt,
+                pattern: None,
+                numeric_fields: Vec::new(),
+                from_beginning: true,
+            }],
+            ..LogSourceConfig::default()
+        })
+        .unwrap();
         let result = source.collect_data().await;
         assert!(result.is_ok());
 
         let batch = result.unwrap();
         assert_eq!(batch.source_id, "application-logs");
-        assert_eq!(batch.source_type, DataSourceType::ApplicationLogs);
+        assert_eq!(source.get_source_type(), DataSourceType::ApplicationLogs);
         assert!(!batch.data_points.is_empty());
         assert!(batch.quality_score > 0.8);
 
@@ -231,13 +248,16 @@ mod tests {
 
     #[tokio::test]
     async fn test_network_traffic_source() {
-        let source = NetworkTrafficSource::new();
+        let source = NetworkTrafficSource::new(ProcFs::default());
+        // Rates need a previous sample to diff against
+        source.collect_data().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
         let result = source.collect_data().await;
         assert!(result.is_ok());
 
         let batch = result.unwrap();
         assert_eq!(batch.source_id, "network-traffic");
-        assert_eq!(batch.source_type, DataSourceType::NetworkTraffic);
+        assert_eq!(source.get_source_type(), DataSourceType::NetworkTraffic);
         assert!(!batch.data_points.is_empty());
         assert!(batch.quality_score > 0.8);
 
@@ -260,7 +280,6 @@ mod tests {
         let health = result.unwrap();
         assert_eq!(health.check_type, HealthCheckType::Performance);
         assert!(health.metrics.contains_key("cpu_usage"));
-        assert!(health.duration_ms >= 0);
         assert!(health.timestamp <= Utc::now());
     }
 
@@ -273,7 +292,6 @@ mod tests {
         let health = result.unwrap();
         assert_eq!(health.check_type, HealthCheckType::ResourceUsage);
         assert!(health.metrics.contains_key("memory_usage"));
-        assert!(health.duration_ms >= 0);
         assert!(health.timestamp <= Utc::now());
     }
 
@@ -286,7 +304,6 @@ mod tests {
         let health = result.unwrap();
         assert_eq!(health.check_type, HealthCheckType::ResourceUsage);
         assert!(health.metrics.contains_key("disk_usage"));
-        assert!(health.duration_ms >= 0);
         assert!(health.timestamp <= Utc::now());
     }
 
@@ -299,7 +316,6 @@ mod tests {
         let health = result.unwrap();
         assert_eq!(health.check_type, HealthCheckType::Connectivity);
         assert!(health.metrics.contains_key("connectivity"));
-        assert!(health.duration_ms >= 0);
         assert!(health.timestamp <= Utc::now());
     }
 
@@ -450,6 +466,14 @@ mod tests {
             acknowledged: false,
             acknowledged_by: None,
             acknowledged_at: None,
+            labels: [("alertname".to_string(), "high_cpu".to_string())].into(),
+            fingerprint: "5b1c0e7a9d3f2e41".to_string(),
+            state: AlertState::Firing,
+            fired_at: Some(Utc::now()),
+            resolved_at: None,
+            occurrences: 1,
+            escalations: 0,
+            silenced_by: None,
         };
 
         assert_eq!(alert.id, "alert-123");
@@ -482,7 +506,7 @@ mod tests {
 
     #[test]
     fn test_health_check_creation() {
-        let health_check = HealthCheck {
+        let health_check = HealthCheckResult {
             check_id: "cpu-check".to_string(),
             system_id: "server1".to_string(),
             check_type: HealthCheckType::Performance,
@@ -5,151 +5,25 @@
 
 pub mod agents;
 pub mod audit_logging;
+pub mod auth;
+pub mod cache;
+pub mod events;
+pub mod guardrails;
 pub mod inference;
+pub mod metering;
 pub mod orchestration;
 pub mod platform;
+pub mod prompts;
 pub mod rate_limiting;
+pub mod routing;
+pub mod sessions;
+pub mod storage;
+pub mod tenancy;
+pub mod tools;
 pub mod training;
 pub mod utils;
 
 pub use platform::{Platform, PlatformConfig, PlatformContext, PlatformRuntime};
-pub mod rate_limiting;
-pub mod training;
-pub mod utils;
-
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
-/// Core configuration for the Chimera platform
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ChimeraConfig {
-    pub agents: HashMap<String, AgentConfig>,
-    pub inference: InferenceConfig,
-    pub training: TrainingConfig,
-    pub monitoring: MonitoringConfig,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AgentConfig {
-    pub agent_type: String,
-    pub model_path: String,
-    pub capabilities: Vec<String>,
-    pub max_concurrent_requests: usize,
-    pub timeout_seconds: u64,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InferenceConfig {
-    pub batch_size: usize,
-    pub max_tokens: usize,
-    pub temperature: f32,
-    pub top_p: f32,
-    pub repetition_penalty: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TrainingConfig {
-    pub base_model: String,
-    pub output_dir: String,
-    pub learning_rate: f32,
-    pub num_epochs: usize,
-    pub save_steps: usize,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MonitoringConfig {
-    pub prometheus_port: u16,
-    pub jaeger_endpoint: String,
-    pub log_level: String,
-}
-
-/// Main platform initialization
-pub async fn init_platform(config: ChimeraConfig) -> Result<Platform, Box<dyn std::error::Error>> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(&config.monitoring.log_level)
-        .init();
-
-    // Initialize rate limiter
-    let rate_limiter = rate_limiting::RateLimiter::new(rate_limiting::RateLimitConfig {
-        default: rate_limiting::RateLimit {
-            requests: 1000,
-            window: std::time::Duration::from_secs(60),
-        },
-        endpoints: HashMap::new(),
-        burst_limit: 100,
-    });
-
-    // Initialize audit logger
-    let audit_logger = audit_logging::AuditLogger::new("logs/audit.log", 90)?;
-
-    Ok(Platform {
-        config,
-        rate_limiter,
-        audit_logger,
-    })
-}
-
-#[derive(Clone)]
-pub struct Platform {
-    pub config: ChimeraConfig,
-    pub rate_limiter: rate_limiting::RateLimiter,
-    pub audit_logger: audit_logging::AuditLogger,
-}
-
-impl Platform {
-    pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
-        tracing::info!("Starting Project Chimera platform");
-
-        // Start monitoring
-        self.start_monitoring().await?;
-
-        // Start agent orchestration
-        self.start_agents().await?;
-
-        tracing::info!("Project Chimera platform started successfully");
-        Ok(())
-    }
-
-    async fn start_monitoring(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // Start Prometheus metrics server
-        let _metrics_handle = tokio::spawn(async move {
-            let app = axum::Router::new().route("/metrics", axum::routing::get(metrics_handler));
-
-            let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", 9090))
-                .await
-                .unwrap();
-
-            axum::serve(listener, app).await.unwrap();
-        });
-
-        Ok(())
-    }
-
-    async fn start_agents(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // Initialize and start all configured agents
-        for (name, _agent_config) in &self.config.agents {
-            tracing::info!("Starting agent: {}", name);
-
-            // Agent initialization logic here
-            // This would create and start individual agent instances
-        }
-
-        Ok(())
-    }
-}
-
-async fn metrics_handler() -> String {
-    // Prometheus metrics handler
-    "# HELP chimera_agents_active Number of active agents
-# TYPE chimera_agents_active gauge
-chimera_agents_active 0
-# HELP chimera_requests_total Total number of requests processed
-# TYPE chimera_requests_total counter
-chimera_requests_total 0
-"
-    .to_string()
-}
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,388 @@
+//! Token usage metering and inference cost
+//!
+//! The inference engine counts prompt and completion tokens with a
+//! [`Tokenizer`]; every generation is then recorded here with the agent,
+//! model, tenant and principal it was made for, priced from the per-model
+//! table in `[metering]`. Records are kept in memory for `retention_hours`
+//! and aggregated into time windows for the `/usage` report.
+
+use parking_lot::{Mutex, RwLock};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+/// Counts the tokens a model would see for a piece of text.
+pub trait Tokenizer: Send + Sync + fmt::Debug {
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Approximates subword tokenizers: each punctuation mark is a token and
+/// words cost one token per four characters.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ApproximateTokenizer;
+
+impl Tokenizer for ApproximateTokenizer {
+    fn count(&self, text: &str) -> usize {
+        let mut tokens = 0;
+        let mut word: usize = 0;
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word += 1;
+                continue;
+            }
+            tokens += word.div_ceil(4);
+            word = 0;
+            if !c.is_whitespace() {
+                tokens += 1;
+            }
+        }
+        tokens + word.div_ceil(4)
+    }
+}
+
+/// One token per whitespace-separated word.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn count(&self, text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenizerKind {
+    #[default]
+    Approximate,
+    Whitespace,
+}
+
+impl TokenizerKind {
+    pub fn build(self) -> Arc<dyn Tokenizer> {
+        match self {
+            TokenizerKind::Approximate => Arc::new(ApproximateTokenizer),
+            TokenizerKind::Whitespace => Arc::new(WhitespaceTokenizer),
+        }
+    }
+}
+
+/// Tokens consumed by one generation, summed over every decoding attempt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> usize {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
+/// Price of a model per thousand tokens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelPrice {
+    pub prompt_per_1k: f64,
+    pub completion_per_1k: f64,
+}
+
+impl ModelPrice {
+    pub fn cost(&self, usage: TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_per_1k
+            + usage.completion_tokens as f64 * self.completion_per_1k)
+            / 1000.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct MeteringSettings {
+    pub tokenizer: TokenizerKind,
+    /// How long usage records are kept for reports.
+    pub retention_hours: u64,
+    /// Oldest records are dropped beyond this many.
+    pub max_records: usize,
+    /// Currency the prices are expressed in; only used as a label.
+    pub currency: String,
+    /// Applied to models without an entry in `prices`.
+    pub default_price: ModelPrice,
+    /// Keyed by model name, i.e. the last component of the agent's `model_path`.
+    pub prices: HashMap<String, ModelPrice>,
+}
+
+impl Default for MeteringSettings {
+    fn default() -> Self {
+        Self {
+            tokenizer: TokenizerKind::default(),
+            retention_hours: 24 * 7,
+            max_records: 100_000,
+            currency: "USD".to_string(),
+            default_price: ModelPrice::default(),
+            prices: HashMap::new(),
+        }
+    }
+}
+
+impl MeteringSettings {
+    pub fn price(&self, model: &str) -> ModelPrice {
+        self.prices
+            .get(model)
+            .copied()
+            .unwrap_or(self.default_price)
+    }
+}
+
+/// One metered generation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageRecord {
+    pub timestamp: u64,
+    pub agent: String,
+    pub model: String,
+    pub tenant: String,
+    pub principal: Option<String>,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageWindow {
+    Minute,
+    Hour,
+    Day,
+    /// One bucket for the whole queried range.
+    #[default]
+    Total,
+}
+
+impl UsageWindow {
+    fn seconds(self) -> Option<u64> {
+        match self {
+            UsageWindow::Minute => Some(60),
+            UsageWindow::Hour => Some(3_600),
+            UsageWindow::Day => Some(86_400),
+            UsageWindow::Total => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageGroup {
+    #[default]
+    Tenant,
+    Agent,
+    Model,
+    Principal,
+}
+
+/// Filters and aggregation for [`UsageMeter::report`]; deserializes from a query string.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct UsageQuery {
+    /// Unix seconds, inclusive.
+    pub since: Option<u64>,
+    /// Unix seconds, exclusive.
+    pub until: Option<u64>,
+    pub window: UsageWindow,
+    pub group_by: UsageGroup,
+    pub tenant: Option<String>,
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    pub fn add(&mut self, record: &UsageRecord) {
+        self.requests += 1;
+        self.prompt_tokens += record.prompt_tokens;
+        self.completion_tokens += record.completion_tokens;
+        self.cost += record.cost;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageBucket {
+    /// Window start in unix seconds; the query's `since` for `total`.
+    pub start: u64,
+    pub key: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageReport {
+    pub currency: String,
+    pub window: UsageWindow,
+    pub group_by: UsageGroup,
+    pub buckets: Vec<UsageBucket>,
+    pub total: UsageTotals,
+}
+
+/// In-memory usage ledger shared by every server in the process.
+#[derive(Clone, Default)]
+pub struct UsageMeter {
+    settings: Arc<RwLock<MeteringSettings>>,
+    records: Arc<Mutex<VecDeque<UsageRecord>>>,
+}
+
+impl UsageMeter {
+    pub fn new(settings: MeteringSettings) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            records: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Swap in new prices and retention; records already priced keep their cost.
+    pub fn reconfigure(&self, settings: MeteringSettings) {
+        *self.settings.write() = settings;
+        self.prune(crate::utils::timestamp_now());
+    }
+
+    pub fn record(
+        &self,
+        agent: &str,
+        model: &str,
+        tenant: &str,
+        principal: Option<String>,
+        usage: TokenUsage,
+    ) -> UsageRecord {
+        let record = UsageRecord {
+            timestamp: crate::utils::timestamp_now(),
+            agent: agent.to_string(),
+            model: model.to_string(),
+            tenant: tenant.to_string(),
+            principal,
+            prompt_tokens: usage.prompt_tokens as u64,
+            completion_tokens: usage.completion_tokens as u64,
+            cost: self.settings.read().price(model).cost(usage),
+        };
+        self.records.lock().push_back(record.clone());
+        self.prune(record.timestamp);
+        record
+    }
+
+    fn prune(&self, now: u64) {
+        let (retention, max_records) = {
+            let settings = self.settings.read();
+            (settings.retention_hours * 3_600, settings.max_records)
+        };
+        let mut records = self.records.lock();
+        while records.len() > max_records
+            || records
+                .front()
+                .is_some_and(|r| now.saturating_sub(r.timestamp) > retention)
+        {
+            records.pop_front();
+        }
+    }
+
+    pub fn report(&self, query: &UsageQuery) -> UsageReport {
+        let mut buckets: BTreeMap<(u64, String), UsageTotals> = BTreeMap::new();
+        let mut total = UsageTotals::default();
+        for record in self.records.lock().iter().filter(|r| {
+            query.since.is_none_or(|since| r.timestamp >= since)
+                && query.until.is_none_or(|until| r.timestamp < until)
+                && query.tenant.as_ref().is_none_or(|t| &r.tenant == t)
+                && query.agent.as_ref().is_none_or(|a| &r.agent == a)
+        }) {
+            let start = match query.window.seconds() {
+                Some(width) => record.timestamp - record.timestamp % width,
+                None => query.since.unwrap_or(0),
+            };
+            let key = match query.group_by {
+                UsageGroup::Tenant => record.tenant.clone(),
+                UsageGroup::Agent => record.agent.clone(),
+                UsageGroup::Model => record.model.clone(),
+                UsageGroup::Principal => record
+                    .principal
+                    .clone()
+                    .unwrap_or_else(|| "anonymous".to_string()),
+            };
+            buckets.entry((start, key)).or_default().add(record);
+            total.add(record);
+        }
+
+        UsageReport {
+            currency: self.settings.read().currency.clone(),
+            window: query.window,
+            group_by: query.group_by,
+            buckets: buckets
+                .into_iter()
+                .map(|((start, key), totals)| UsageBucket { start, key, totals })
+                .collect(),
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approximate_tokenizer_splits_long_words_and_punctuation() {
+        let tokenizer = ApproximateTokenizer;
+        assert_eq!(tokenizer.count(""), 0);
+        assert_eq!(tokenizer.count("Hello, world!"), 6);
+        assert_eq!(tokenizer.count("internationalization"), 5);
+        assert_eq!(WhitespaceTokenizer.count("Hello, world!"), 2);
+    }
+
+    #[test]
+    fn usage_is_priced_and_grouped() {
+        let mut settings = MeteringSettings::default();
+        settings.prices.insert(
+            "mistral-7b".to_string(),
+            ModelPrice {
+                prompt_per_1k: 0.5,
+                completion_per_1k: 1.5,
+            },
+        );
+        let meter = UsageMeter::new(settings);
+        let usage = TokenUsage {
+            prompt_tokens: 1000,
+            completion_tokens: 2000,
+        };
+
+        let record = meter.record("support", "mistral-7b", "search", None, usage);
+        assert!((record.cost - 3.5).abs() < 1e-9);
+        meter.record("support", "mistral-7b", "search", None, usage);
+        meter.record("support", "unpriced", "ads", None, usage);
+
+        let report = meter.report(&UsageQuery::default());
+        assert_eq!(report.buckets.len(), 2);
+        assert_eq!(report.buckets[0].key, "ads");
+        assert_eq!(report.buckets[0].totals.cost, 0.0);
+        assert_eq!(report.buckets[1].totals.requests, 2);
+        assert!((report.total.cost - 7.0).abs() < 1e-9);
+
+        let search = meter.report(&UsageQuery {
+            tenant: Some("search".to_string()),
+            window: UsageWindow::Hour,
+            group_by: UsageGroup::Model,
+            ..UsageQuery::default()
+        });
+        assert_eq!(search.total.requests, 2);
+        assert_eq!(search.buckets[0].start % 3_600, 0);
+    }
+}
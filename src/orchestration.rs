@@ -4,20 +4,27 @@
 //! manages task dependencies, and handles result aggregation.
 
 use crate::agents::{Agent, AgentRegistry, AgentType};
+use crate::events::{EventBus, EventPayload};
 use crate::platform::service::ServiceRegistration;
+use crate::storage::TaskRepository;
+use crate::tenancy::{TenantManager, DEFAULT_TENANT};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
 use std::time::SystemTime;
+use tokio::sync::{mpsc, oneshot, Mutex as AsyncMutex, MutexGuard};
 use tokio::time::Duration;
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub id: String,
     pub task_type: String,
+    pub tenant: String,
     pub input: serde_json::Value,
     pub status: TaskStatus,
     pub assigned_agent: Option<String>,
@@ -27,7 +34,7 @@ pub struct Task {
     pub completed_at: Option<SystemTime>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TaskStatus {
     Pending,
     Assigned,
@@ -37,8 +44,100 @@ pub enum TaskStatus {
     Cancelled,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskError {
+    NotFound(String),
+    /// The task is in a state the operation does not apply to.
+    InvalidState {
+        id: String,
+        status: TaskStatus,
+    },
+    /// No orchestrator is running to answer.
+    Unavailable,
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::NotFound(id) => write!(f, "task {} not found", id),
+            TaskError::InvalidState { id, status } => {
+                write!(f, "task {} is {:?}", id, status)
+            }
+            TaskError::Unavailable => write!(f, "task orchestrator is not running"),
+        }
+    }
+}
+
+impl std::error::Error for TaskError {}
+
+enum TaskCommand {
+    List(oneshot::Sender<Vec<Task>>),
+    Cancel(String, oneshot::Sender<Result<Task, TaskError>>),
+    Retry(String, oneshot::Sender<Result<Task, TaskError>>),
+}
+
+/// Reaches the running orchestrator from outside its service, e.g. the admin API.
+#[derive(Clone)]
+pub struct OrchestratorHandle {
+    sender: mpsc::Sender<TaskCommand>,
+    receiver: Arc<AsyncMutex<mpsc::Receiver<TaskCommand>>>,
+}
+
+impl Default for OrchestratorHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrchestratorHandle {
+    const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel(32);
+        Self {
+            sender,
+            receiver: Arc::new(AsyncMutex::new(receiver)),
+        }
+    }
+
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> TaskCommand,
+    ) -> Result<T, TaskError> {
+        let (reply, response) = oneshot::channel();
+        self.sender
+            .send(command(reply))
+            .await
+            .map_err(|_| TaskError::Unavailable)?;
+        tokio::time::timeout(Self::REPLY_TIMEOUT, response)
+            .await
+            .map_err(|_| TaskError::Unavailable)?
+            .map_err(|_| TaskError::Unavailable)
+    }
+
+    pub async fn list_tasks(&self) -> Result<Vec<Task>, TaskError> {
+        self.request(TaskCommand::List).await
+    }
+
+    pub async fn cancel_task(&self, id: &str) -> Result<Task, TaskError> {
+        self.request(|reply| TaskCommand::Cancel(id.to_string(), reply))
+            .await?
+    }
+
+    pub async fn retry_task(&self, id: &str) -> Result<Task, TaskError> {
+        self.request(|reply| TaskCommand::Retry(id.to_string(), reply))
+            .await?
+    }
+}
+
 pub struct TaskOrchestrator {
     agent_registry: AgentRegistry,
+    tenants: TenantManager,
+    commands: Option<OrchestratorHandle>,
+    repository: Option<Arc<dyn TaskRepository>>,
+    events: Option<EventBus>,
+    /// Tasks changed since they were last saved.
+    dirty: HashSet<String>,
     pending_tasks: HashMap<String, Task>,
     active_tasks: HashMap<String, Task>,
 }
@@ -47,16 +146,69 @@ impl TaskOrchestrator {
     pub fn new(agent_registry: AgentRegistry) -> Self {
         Self {
             agent_registry,
+            tenants: TenantManager::default(),
+            commands: None,
+            repository: None,
+            events: None,
+            dirty: HashSet::new(),
             pending_tasks: HashMap::new(),
             active_tasks: HashMap::new(),
         }
     }
 
+    /// Enforces each tenant's concurrent-task quota when assigning work.
+    pub fn with_tenants(mut self, tenants: TenantManager) -> Self {
+        self.tenants = tenants;
+        self
+    }
+
+    /// Serves list, cancel and retry requests sent through `handle` while running.
+    pub fn with_commands(mut self, handle: OrchestratorHandle) -> Self {
+        self.commands = Some(handle);
+        self
+    }
+
+    /// Restores tasks when `run` starts and saves every change while running.
+    pub fn with_repository(mut self, repository: Arc<dyn TaskRepository>) -> Self {
+        self.repository = Some(repository);
+        self
+    }
+
+    /// Publishes `task.finished` when a task completes, fails or is cancelled.
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish_finished(&self, task: &Task) {
+        if let Some(events) = &self.events {
+            events.publish(
+                Some(task.tenant.clone()),
+                EventPayload::TaskFinished {
+                    task_id: task.id.clone(),
+                    task_type: task.task_type.clone(),
+                    status: task.status.clone(),
+                    error: task.error.clone(),
+                },
+            );
+        }
+    }
+
     pub fn submit_task(&mut self, task_type: String, input: serde_json::Value) -> String {
+        self.submit_task_for(DEFAULT_TENANT, task_type, input)
+    }
+
+    pub fn submit_task_for(
+        &mut self,
+        tenant: &str,
+        task_type: String,
+        input: serde_json::Value,
+    ) -> String {
         let task_id = Uuid::new_v4().to_string();
         let task = Task {
             id: task_id.clone(),
             task_type,
+            tenant: tenant.to_string(),
             input,
             status: TaskStatus::Pending,
             assigned_agent: None,
@@ -67,6 +219,7 @@ impl TaskOrchestrator {
         };
 
         self.pending_tasks.insert(task_id.clone(), task);
+        self.dirty.insert(task_id.clone());
         task_id
     }
 
@@ -76,8 +229,152 @@ impl TaskOrchestrator {
             .or_else(|| self.active_tasks.get(task_id))
     }
 
+    /// Like [`Self::get_task_status`], but hides tasks of other tenants.
+    pub fn get_task_for(&self, tenant: &str, task_id: &str) -> Option<&Task> {
+        self.get_task_status(task_id)
+            .filter(|task| task.tenant == tenant)
+    }
+
+    pub fn list_tasks_for(&self, tenant: &str) -> Vec<&Task> {
+        self.pending_tasks
+            .values()
+            .chain(self.active_tasks.values())
+            .filter(|task| task.tenant == tenant)
+            .collect()
+    }
+
+    /// Every task, pending first, oldest first.
+    pub fn list_tasks(&self) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.pending_tasks.values().collect();
+        tasks.sort_by_key(|task| task.created_at);
+        let mut active: Vec<&Task> = self.active_tasks.values().collect();
+        active.sort_by_key(|task| task.created_at);
+        tasks.extend(active);
+        tasks
+    }
+
+    /// Cancels a pending or running task, releasing its tenant slot.
+    pub fn cancel_task(&mut self, task_id: &str) -> Result<Task, TaskError> {
+        if let Some(mut task) = self.pending_tasks.remove(task_id) {
+            task.status = TaskStatus::Cancelled;
+            task.completed_at = Some(SystemTime::now());
+            self.active_tasks.insert(task.id.clone(), task.clone());
+            self.dirty.insert(task.id.clone());
+            self.publish_finished(&task);
+            return Ok(task);
+        }
+
+        let task = self
+            .active_tasks
+            .get_mut(task_id)
+            .ok_or_else(|| TaskError::NotFound(task_id.to_string()))?;
+        if task.completed_at.is_some() {
+            return Err(TaskError::InvalidState {
+                id: task.id.clone(),
+                status: task.status.clone(),
+            });
+        }
+        self.tenants.finish_task(&task.tenant);
+        task.status = TaskStatus::Cancelled;
+        task.completed_at = Some(SystemTime::now());
+        self.dirty.insert(task.id.clone());
+        let task = task.clone();
+        self.publish_finished(&task);
+        Ok(task)
+    }
+
+    /// Puts a failed or cancelled task back in the queue.
+    pub fn retry_task(&mut self, task_id: &str) -> Result<Task, TaskError> {
+        let status = self
+            .active_tasks
+            .get(task_id)
+            .map(|task| task.status.clone())
+            .ok_or_else(|| TaskError::NotFound(task_id.to_string()))?;
+        if !matches!(status, TaskStatus::Failed | TaskStatus::Cancelled) {
+            return Err(TaskError::InvalidState {
+                id: task_id.to_string(),
+                status,
+            });
+        }
+
+        let mut task = self.active_tasks.remove(task_id).expect("task exists");
+        task.status = TaskStatus::Pending;
+        task.assigned_agent = None;
+        task.result = None;
+        task.error = None;
+        task.completed_at = None;
+        self.pending_tasks.insert(task.id.clone(), task.clone());
+        self.dirty.insert(task.id.clone());
+        Ok(task)
+    }
+
+    fn handle_command(&mut self, command: TaskCommand) {
+        // A dropped reply only means the caller gave up waiting.
+        match command {
+            TaskCommand::List(reply) => {
+                let _ = reply.send(self.list_tasks().into_iter().cloned().collect());
+            }
+            TaskCommand::Cancel(id, reply) => {
+                let _ = reply.send(self.cancel_task(&id));
+            }
+            TaskCommand::Retry(id, reply) => {
+                let _ = reply.send(self.retry_task(&id));
+            }
+        }
+    }
+
+    /// Loads stored tasks. Unfinished ones go back in the queue, since their
+    /// assignments did not survive the restart.
+    async fn restore(&mut self) -> Result<()> {
+        let Some(repository) = self.repository.clone() else {
+            return Ok(());
+        };
+        let mut requeued = 0;
+        for mut task in repository.load_tasks().await? {
+            if self.get_task_status(&task.id).is_some() {
+                continue;
+            }
+            if task.completed_at.is_some() {
+                self.active_tasks.insert(task.id.clone(), task);
+                continue;
+            }
+            task.status = TaskStatus::Pending;
+            task.assigned_agent = None;
+            self.dirty.insert(task.id.clone());
+            self.pending_tasks.insert(task.id.clone(), task);
+            requeued += 1;
+        }
+        if requeued > 0 {
+            info!(tasks = requeued, "requeued unfinished tasks from storage");
+        }
+        Ok(())
+    }
+
+    /// Saves changed tasks; failures are retried on the next call.
+    async fn persist(&mut self) {
+        let Some(repository) = self.repository.clone() else {
+            self.dirty.clear();
+            return;
+        };
+        for id in std::mem::take(&mut self.dirty) {
+            let Some(task) = self.get_task_status(&id).cloned() else {
+                continue;
+            };
+            if let Err(err) = repository.save_task(&task).await {
+                warn!(task = %id, error = %format!("{:#}", err), "failed to persist task");
+                self.dirty.insert(id);
+            }
+        }
+    }
+
     pub async fn run(mut self, shutdown: CancellationToken) -> Result<()> {
+        self.restore().await?;
         let mut interval = tokio::time::interval(Duration::from_millis(250));
+        let handle = self.commands.clone();
+        let mut commands = match &handle {
+            Some(handle) => Some(handle.receiver.lock().await),
+            None => None,
+        };
 
         loop {
             tokio::select! {
@@ -88,20 +385,54 @@ impl TaskOrchestrator {
                 _ = interval.tick() => {
                     self.process_tasks().await?;
                 }
+                Some(command) = next_command(&mut commands) => {
+                    self.handle_command(command);
+                }
             }
+            self.persist().await;
         }
 
+        self.hand_back();
+        self.persist().await;
         Ok(())
     }
 
+    /// Returns unfinished tasks to the queue, so whichever orchestrator
+    /// restores them next reassigns them.
+    fn hand_back(&mut self) {
+        let unfinished: Vec<String> = self
+            .active_tasks
+            .values()
+            .filter(|task| task.completed_at.is_none())
+            .map(|task| task.id.clone())
+            .collect();
+        for id in &unfinished {
+            let mut task = self.active_tasks.remove(id).expect("task exists");
+            self.tenants.finish_task(&task.tenant);
+            task.status = TaskStatus::Pending;
+            task.assigned_agent = None;
+            self.pending_tasks.insert(id.clone(), task);
+            self.dirty.insert(id.clone());
+        }
+        if !unfinished.is_empty() {
+            info!(tasks = unfinished.len(), "handed back unfinished tasks");
+        }
+    }
+
     pub async fn process_tasks(&mut self) -> Result<()> {
         // Move pending tasks to active if agents are available
         let mut tasks_to_activate = Vec::new();
 
         for (task_id, task) in &self.pending_tasks {
-            if let Some(agent) = self.find_suitable_agent(&task.task_type) {
-                tasks_to_activate.push((task_id.clone(), task.clone(), agent.id.clone()));
+            let Some(agent) = self.find_suitable_agent(&task.task_type, &task.tenant) else {
+                continue;
+            };
+            // Over-quota tenants stay pending instead of crowding out the rest.
+            if let Err(err) = self.tenants.start_task(&task.tenant) {
+                debug!(task = %task_id, error = %err, "task held back by tenant quota");
+                continue;
             }
+            tasks_to_activate.push((task_id.clone(), task.clone(), agent.id.clone()));
         }
 
         // Activate tasks
@@ -111,6 +442,7 @@ impl TaskOrchestrator {
 
             self.pending_tasks.remove(&task_id);
             self.active_tasks.insert(task_id.clone(), task);
+            self.dirty.insert(task_id.clone());
 
             info!(task = %task_id, agent = %agent_id, "assigned task to agent");
         }
@@ -118,84 +450,80 @@ impl TaskOrchestrator {
         Ok(())
     }
 
-    fn find_suitable_agent(&self, task_type: &str) -> Option<Agent> {
-        match task_type {
-            "code_generation" => self
-                .agent_registry
-                .get_agents_by_type(AgentType::CodeGeneration)
-                .into_iter()
-                .next(),
-            "data_analysis" => self
-                .agent_registry
-                .get_agents_by_type(AgentType::DataAnalysis)
-                .into_iter()
-                .next(),
-            "creative" => self
-                .agent_registry
-                .get_agents_by_type(AgentType::Creative)
-                .into_iter()
-                .next(),
-            _ => self
-                .agent_registry
-                .get_agents_by_type(AgentType::General)
-                .into_iter()
-                .next(),
-                .agent_manager
-                .get_agents_by_type(&AgentType::CodeGeneration)
-                .first()
-                .copied(),
-            "data_analysis" => self
-                .agent_manager
-                .get_agents_by_type(&AgentType::DataAnalysis)
-                .first()
-                .copied(),
-            "creative" => self
-                .agent_manager
-                .get_agents_by_type(&AgentType::Creative)
-                .first()
-                .copied(),
-            _ => self
-                .agent_manager
-                .get_agents_by_type(&AgentType::General)
-                .first()
-                .copied(),
-        }
+    fn find_suitable_agent(&self, task_type: &str, tenant: &str) -> Option<Agent> {
+        let agent_type = match task_type {
+            "code_generation" => AgentType::CodeGeneration,
+            "data_analysis" => AgentType::DataAnalysis,
+            "creative" => AgentType::Creative,
+            _ => AgentType::General,
+        };
+        self.agent_registry
+            .get_agents_by_type(agent_type)
+            .into_iter()
+            .find(|agent| agent.accepts_work() && agent.serves(tenant))
     }
 
     pub fn complete_task(&mut self, task_id: &str, result: serde_json::Value) {
         if let Some(task) = self.active_tasks.get_mut(task_id) {
+            // A task cancelled while its agent was working keeps its outcome.
+            if task.completed_at.is_some() {
+                return;
+            }
+            self.tenants.finish_task(&task.tenant);
             task.status = TaskStatus::Completed;
             task.result = Some(result);
             task.completed_at = Some(SystemTime::now());
+            self.dirty.insert(task.id.clone());
+            let task = task.clone();
+            self.publish_finished(&task);
         }
     }
 
     pub fn fail_task(&mut self, task_id: &str, error: String) {
         if let Some(task) = self.active_tasks.get_mut(task_id) {
+            if task.completed_at.is_some() {
+                return;
+            }
+            self.tenants.finish_task(&task.tenant);
             task.status = TaskStatus::Failed;
             task.error = Some(error);
             task.completed_at = Some(SystemTime::now());
+            self.dirty.insert(task.id.clone());
+            let task = task.clone();
+            self.publish_finished(&task);
         }
     }
 }
 
-pub fn orchestration_service(registry: AgentRegistry) -> ServiceRegistration {
-    use std::sync::Arc;
+async fn next_command(
+    commands: &mut Option<MutexGuard<'_, mpsc::Receiver<TaskCommand>>>,
+) -> Option<TaskCommand> {
+    match commands {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
 
+pub fn orchestration_service(registry: AgentRegistry) -> ServiceRegistration {
     ServiceRegistration::new(
         "orchestrator",
-        Arc::new(move |_context, token| {
-            let orchestrator = TaskOrchestrator::new(registry.clone());
+        Arc::new(move |context, token| {
+            let orchestrator = TaskOrchestrator::new(registry.clone())
+                .with_tenants(context.tenants())
+                .with_commands(context.orchestrator())
+                .with_repository(context.storage().tasks)
+                .with_events(context.events());
             tokio::spawn(async move { orchestrator.run(token).await })
         }),
     )
+    .depends_on(["agents"])
+    .critical()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agents::{AgentConfig, AgentMetrics};
-    use std::time::SystemTime;
 
     fn create_test_agent(id: &str, agent_type: AgentType) -> Agent {
         let config_type = agent_type.clone();
@@ -205,6 +533,7 @@ mod tests {
             agent_type,
             status: crate::agents::AgentStatus::Idle,
             capabilities: vec!["test".to_string()],
+            tenant: None,
             config: AgentConfig {
                 model_path: "test_model".to_string(),
                 max_tokens: 512,
@@ -214,14 +543,9 @@ mod tests {
                 max_concurrent_requests: 4,
                 capabilities: vec!["test".to_string()],
                 agent_type: config_type,
+                ..AgentConfig::default()
             },
             metrics: AgentMetrics::default(),
-            metrics: AgentMetrics {
-                requests_processed: 0,
-                average_response_time_ms: 0.0,
-                success_rate: 1.0,
-                last_activity: SystemTime::now(),
-            },
         }
     }
 
@@ -239,4 +563,169 @@ mod tests {
         assert!(!task_id.is_empty());
         assert_eq!(orchestrator.pending_tasks.len(), 1);
     }
+
+    #[tokio::test]
+    async fn tenant_task_quota_holds_back_extra_tasks() {
+        use crate::tenancy::{TenancySettings, TenantQuota};
+
+        let registry = AgentRegistry::new();
+        registry.register_agent(create_test_agent("1", AgentType::General));
+        let tenants = TenantManager::new(TenancySettings {
+            default_quota: TenantQuota {
+                concurrent_tasks: Some(1),
+                ..TenantQuota::default()
+            },
+            ..TenancySettings::default()
+        });
+
+        let mut orchestrator = TaskOrchestrator::new(registry).with_tenants(tenants.clone());
+        let first = orchestrator.submit_task_for("search", "chat".into(), serde_json::json!({}));
+        orchestrator.submit_task_for("search", "chat".into(), serde_json::json!({}));
+        orchestrator.submit_task_for("ads", "chat".into(), serde_json::json!({}));
+        orchestrator.process_tasks().await.unwrap();
+
+        assert_eq!(orchestrator.active_tasks.len(), 2);
+        assert_eq!(orchestrator.list_tasks_for("search").len(), 2);
+        assert!(orchestrator.get_task_for("ads", &first).is_none());
+
+        // Either search task may have been assigned first; complete that one.
+        let running = orchestrator
+            .list_tasks_for("search")
+            .into_iter()
+            .find(|task| task.assigned_agent.is_some())
+            .map(|task| task.id.clone())
+            .unwrap();
+        orchestrator.complete_task(&running, serde_json::json!({ "ok": true }));
+        assert_eq!(tenants.usage("search").active_tasks, 0);
+        orchestrator.process_tasks().await.unwrap();
+        assert_eq!(tenants.usage("search").active_tasks, 1);
+    }
+
+    #[tokio::test]
+    async fn late_results_do_not_override_a_cancelled_task() {
+        use crate::events::Topic;
+
+        let registry = AgentRegistry::new();
+        registry.register_agent(create_test_agent("1", AgentType::General));
+        let events = EventBus::new();
+        let mut finished = events.subscribe(&[Topic::TaskFinished]);
+        let mut orchestrator = TaskOrchestrator::new(registry).with_events(events);
+        let completed = orchestrator.submit_task("chat".into(), serde_json::json!({}));
+        let failed = orchestrator.submit_task("chat".into(), serde_json::json!({}));
+        orchestrator.process_tasks().await.unwrap();
+
+        for id in [&completed, &failed] {
+            orchestrator.cancel_task(id).unwrap();
+            assert_eq!(
+                finished.recv().await.unwrap().payload,
+                EventPayload::TaskFinished {
+                    task_id: id.clone(),
+                    task_type: "chat".into(),
+                    status: TaskStatus::Cancelled,
+                    error: None,
+                }
+            );
+        }
+        orchestrator.complete_task(&completed, serde_json::json!({ "ok": true }));
+        orchestrator.fail_task(&failed, "agent crashed".into());
+
+        for id in [&completed, &failed] {
+            let task = orchestrator.get_task_status(id).unwrap();
+            assert_eq!(task.status, TaskStatus::Cancelled);
+            assert!(task.result.is_none() && task.error.is_none());
+        }
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), finished.recv())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn handle_cancels_and_retries_tasks_of_a_running_orchestrator() {
+        let handle = OrchestratorHandle::new();
+        let mut orchestrator =
+            TaskOrchestrator::new(AgentRegistry::new()).with_commands(handle.clone());
+        let id = orchestrator.submit_task("chat".into(), serde_json::json!({}));
+        let token = CancellationToken::new();
+        let running = tokio::spawn(orchestrator.run(token.clone()));
+
+        assert_eq!(handle.list_tasks().await.unwrap().len(), 1);
+        let cancelled = handle.cancel_task(&id).await.unwrap();
+        assert_eq!(cancelled.status, TaskStatus::Cancelled);
+        assert!(matches!(
+            handle.cancel_task(&id).await,
+            Err(TaskError::InvalidState { .. })
+        ));
+        assert_eq!(
+            handle.retry_task(&id).await.unwrap().status,
+            TaskStatus::Pending
+        );
+        assert!(matches!(
+            handle.retry_task("missing").await,
+            Err(TaskError::NotFound(_))
+        ));
+
+        token.cancel();
+        running.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn unfinished_tasks_are_requeued_after_a_restart() {
+        let repository = Arc::new(crate::storage::InMemoryRepository::new());
+        let registry = AgentRegistry::new();
+        registry.register_agent(create_test_agent("1", AgentType::General));
+
+        let mut orchestrator =
+            TaskOrchestrator::new(registry.clone()).with_repository(repository.clone());
+        let running = orchestrator.submit_task("chat".into(), serde_json::json!({}));
+        orchestrator.process_tasks().await.unwrap();
+        let done = orchestrator.submit_task("chat".into(), serde_json::json!({}));
+        orchestrator.process_tasks().await.unwrap();
+        orchestrator.complete_task(&done, serde_json::json!({ "ok": true }));
+        orchestrator.persist().await;
+        assert!(orchestrator.dirty.is_empty());
+
+        let mut restarted = TaskOrchestrator::new(registry).with_repository(repository);
+        restarted.restore().await.unwrap();
+        let requeued = restarted.get_task_status(&running).unwrap();
+        assert_eq!(requeued.status, TaskStatus::Pending);
+        assert!(requeued.assigned_agent.is_none());
+        assert_eq!(
+            restarted.get_task_status(&done).unwrap().status,
+            TaskStatus::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_hands_back_tasks_and_drained_agents_get_none() {
+        let repository = Arc::new(crate::storage::InMemoryRepository::new());
+        let registry = AgentRegistry::new();
+        registry.register_agent(create_test_agent("1", AgentType::General));
+        let tenants = TenantManager::default();
+
+        let mut orchestrator = TaskOrchestrator::new(registry.clone())
+            .with_tenants(tenants.clone())
+            .with_repository(repository.clone());
+        let running = orchestrator.submit_task("chat".into(), serde_json::json!({}));
+        orchestrator.process_tasks().await.unwrap();
+        assert_eq!(tenants.usage(DEFAULT_TENANT).active_tasks, 1);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        orchestrator.run(token).await.unwrap();
+        assert_eq!(tenants.usage(DEFAULT_TENANT).active_tasks, 0);
+        let stored = repository.load_task(&running).await.unwrap().unwrap();
+        assert_eq!(stored.status, TaskStatus::Pending);
+        assert!(stored.assigned_agent.is_none());
+
+        registry.set_status("1", crate::agents::AgentStatus::Offline);
+        let mut restarted = TaskOrchestrator::new(registry).with_repository(repository);
+        restarted.restore().await.unwrap();
+        restarted.process_tasks().await.unwrap();
+        assert_eq!(
+            restarted.get_task_status(&running).unwrap().status,
+            TaskStatus::Pending
+        );
+    }
 }
@@ -0,0 +1,467 @@
+//! Operator API behind the `chimera` CLI
+//!
+//! Served under `/admin` on the telemetry port of every process. Reads need
+//! `ViewStatus`, changes need `Operate` and the audit log needs `Admin`;
+//! every change is written to the audit log. Non-admins only see their own
+//! tenant's agents, tasks and rate-limit buckets, and may only change agents
+//! their tenant owns.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::middleware::from_fn_with_state;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::agents::{Agent, AgentStatus};
+use crate::auth::{self, Permission, Principal, Role};
+use crate::orchestration::TaskError;
+use crate::platform::context::PlatformContext;
+use crate::platform::reload::{reload_file, ConfigReloadError};
+
+pub fn admin_routes() -> Router<PlatformContext> {
+    let reads = Router::new()
+        .route("/admin/agents", get(list_agents))
+        .route("/admin/agents/:key", get(show_agent))
+        .route("/admin/tasks", get(list_tasks))
+        .route("/admin/rate-limits", get(rate_limits))
+        .route_layer(from_fn_with_state(Permission::ViewStatus, auth::require));
+    let changes = Router::new()
+        .route("/admin/agents/:key/pause", post(pause_agent))
+        .route("/admin/agents/:key/resume", post(resume_agent))
+        .route("/admin/tasks/:id/cancel", post(cancel_task))
+        .route("/admin/tasks/:id/retry", post(retry_task))
+        .route("/admin/reload", post(reload))
+        .route("/admin/drain", post(drain))
+        .route_layer(from_fn_with_state(Permission::Operate, auth::require));
+    let audit = Router::new()
+        .route("/admin/audit", get(audit_tail))
+        .route_layer(from_fn_with_state(Permission::Admin, auth::require));
+
+    reads.merge(changes).merge(audit)
+}
+
+pub(crate) fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(serde_json::json!({ "error": message.into() }))).into_response()
+}
+
+pub(crate) fn record(ctx: &PlatformContext, principal: &Principal, action: &str, target: &str) {
+    let user = principal
+        .user_id()
+        .unwrap_or_else(|| "anonymous".to_string());
+    info!(user = %user, action, target, "admin action");
+    if let Err(err) =
+        ctx.audit_logger()
+            .log_admin_action(&user, action, target, principal.ip_string())
+    {
+        error!(?err, "failed to record audit log for admin action");
+    }
+}
+
+/// Shared agents serve every tenant, so any principal may see them.
+fn can_view_agent(principal: &Principal, agent: &Agent) -> bool {
+    principal.role == Role::Admin || agent.serves(&principal.tenant)
+}
+
+/// Changing a shared agent affects every tenant, so only admins may.
+fn can_change_agent(principal: &Principal, agent: &Agent) -> bool {
+    match &agent.tenant {
+        Some(owner) => principal.can_access_tenant(owner),
+        None => principal.role == Role::Admin,
+    }
+}
+
+async fn list_agents(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+) -> impl IntoResponse {
+    let mut agents: Vec<Agent> = ctx
+        .agents()
+        .list_agents()
+        .into_iter()
+        .filter(|agent| can_view_agent(&principal, agent))
+        .collect();
+    agents.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+    Json(agents)
+}
+
+async fn show_agent(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Path(key): Path<String>,
+) -> Response {
+    let agents: Vec<Agent> = ctx
+        .agents()
+        .find(&key)
+        .into_iter()
+        .filter(|agent| can_view_agent(&principal, agent))
+        .collect();
+    if agents.is_empty() {
+        return error_response(StatusCode::NOT_FOUND, format!("agent {} not found", key));
+    }
+    Json(agents).into_response()
+}
+
+fn set_agent_status(
+    ctx: PlatformContext,
+    principal: Principal,
+    key: String,
+    status: AgentStatus,
+    action: &str,
+) -> Response {
+    // Agents of other tenants may share the name, so change the allowed ones by id.
+    let agents: Vec<Agent> = ctx
+        .agents()
+        .find(&key)
+        .into_iter()
+        .filter(|agent| can_change_agent(&principal, agent))
+        .flat_map(|agent| ctx.agents().set_status(&agent.id, status.clone()))
+        .collect();
+    if agents.is_empty() {
+        return error_response(StatusCode::NOT_FOUND, format!("agent {} not found", key));
+    }
+    record(&ctx, &principal, action, &format!("agent:{}", key));
+    Json(agents).into_response()
+}
+
+async fn pause_agent(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Path(key): Path<String>,
+) -> Response {
+    set_agent_status(ctx, principal, key, AgentStatus::Maintenance, "pause_agent")
+}
+
+async fn resume_agent(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Path(key): Path<String>,
+) -> Response {
+    set_agent_status(ctx, principal, key, AgentStatus::Idle, "resume_agent")
+}
+
+fn task_error(err: TaskError) -> Response {
+    let status = match err {
+        TaskError::NotFound(_) => StatusCode::NOT_FOUND,
+        TaskError::InvalidState { .. } => StatusCode::CONFLICT,
+        TaskError::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    error_response(status, err.to_string())
+}
+
+/// Tasks of other tenants look missing to non-admins rather than forbidden.
+async fn check_task_tenant(
+    ctx: &PlatformContext,
+    principal: &Principal,
+    id: &str,
+) -> Result<(), Response> {
+    let tasks = ctx.orchestrator().list_tasks().await.map_err(task_error)?;
+    match tasks.iter().find(|task| task.id == id) {
+        Some(task) if principal.can_access_tenant(&task.tenant) => Ok(()),
+        _ => Err(task_error(TaskError::NotFound(id.to_string()))),
+    }
+}
+
+/// Non-admins only see their own tenant's tasks.
+async fn list_tasks(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+) -> Response {
+    match ctx.orchestrator().list_tasks().await {
+        Ok(tasks) => Json(
+            tasks
+                .into_iter()
+                .filter(|task| principal.can_access_tenant(&task.tenant))
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(err) => task_error(err),
+    }
+}
+
+async fn cancel_task(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = check_task_tenant(&ctx, &principal, &id).await {
+        return response;
+    }
+    match ctx.orchestrator().cancel_task(&id).await {
+        Ok(task) => {
+            record(&ctx, &principal, "cancel_task", &format!("task:{}", id));
+            Json(task).into_response()
+        }
+        Err(err) => task_error(err),
+    }
+}
+
+async fn retry_task(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = check_task_tenant(&ctx, &principal, &id).await {
+        return response;
+    }
+    match ctx.orchestrator().retry_task(&id).await {
+        Ok(task) => {
+            record(&ctx, &principal, "retry_task", &format!("task:{}", id));
+            Json(task).into_response()
+        }
+        Err(err) => task_error(err),
+    }
+}
+
+async fn rate_limits(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+) -> impl IntoResponse {
+    let mut usage = ctx.rate_limiter().snapshot();
+    if principal.role != Role::Admin {
+        usage.retain(|client| client.tenant.as_deref() == Some(principal.tenant.as_str()));
+    }
+    Json(usage)
+}
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    #[serde(default = "default_audit_limit")]
+    limit: usize,
+    since: Option<u64>,
+}
+
+fn default_audit_limit() -> usize {
+    50
+}
+
+async fn audit_tail(
+    State(ctx): State<PlatformContext>,
+    Query(query): Query<AuditQuery>,
+) -> Response {
+    match ctx.audit_logger().recent(query.limit, query.since) {
+        Ok(events) => Json(events).into_response(),
+        Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+async fn reload(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+) -> Response {
+    let Some(path) = ctx.config_path() else {
+        return error_response(
+            StatusCode::CONFLICT,
+            "this process does not watch a configuration file",
+        );
+    };
+    match reload_file(&ctx, &path) {
+        Ok(diff) => {
+            record(
+                &ctx,
+                &principal,
+                "reload_config",
+                &path.display().to_string(),
+            );
+            Json(serde_json::json!({
+                "path": path,
+                "live": diff.live.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            }))
+            .into_response()
+        }
+        Err(err) => {
+            let status = match err.downcast_ref::<ConfigReloadError>() {
+                Some(ConfigReloadError::RestartRequired(_)) => StatusCode::CONFLICT,
+                _ => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+            error_response(status, format!("{:#}", err))
+        }
+    }
+}
+
+/// Flips readiness and stops agents in this process from taking new work.
+async fn drain(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+) -> Response {
+    let started = ctx.begin_drain();
+    if started {
+        record(&ctx, &principal, "drain", "node");
+    }
+    Json(serde_json::json!({
+        "draining": true,
+        "already_draining": !started,
+        "agents": ctx.agents().list_agents().len(),
+        "in_flight": ctx.in_flight().count(),
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::AgentConfig;
+    use crate::agents::AgentRegistry;
+    use crate::auth::AuthMethod;
+    use crate::orchestration::TaskOrchestrator;
+    use crate::platform::test_support::test_context;
+    use crate::rate_limiting::ClientId;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tokio_util::sync::CancellationToken;
+    use tower::Service;
+
+    async fn call(app: &mut Router, method: &str, uri: &str) -> (StatusCode, serde_json::Value) {
+        call_as(app, Principal::anonymous(Role::Admin, None), method, uri).await
+    }
+
+    async fn call_as(
+        app: &mut Router,
+        principal: Principal,
+        method: &str,
+        uri: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        request.extensions_mut().insert(principal);
+        let response = app.call(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn agents_can_be_paused_and_the_node_drained() {
+        let (ctx, _audit) = test_context();
+        ctx.agents().register_from_config(AgentConfig {
+            agent_name: "support".to_string(),
+            ..AgentConfig::default()
+        });
+        let mut app = admin_routes().with_state(ctx.clone());
+
+        let (status, body) = call(&mut app, "POST", "/admin/agents/support/pause").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["status"], "Maintenance");
+        let (status, _) = call(&mut app, "POST", "/admin/agents/missing/pause").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = call(&mut app, "POST", "/admin/reload").await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let (_, body) = call(&mut app, "POST", "/admin/drain").await;
+        assert_eq!(body["already_draining"], false);
+        assert!(ctx.is_draining());
+
+        let (_, body) = call(&mut app, "GET", "/admin/audit?limit=5").await;
+        let actions: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| event["action"].as_str().unwrap())
+            .collect();
+        assert_eq!(actions, ["pause_agent", "drain"]);
+    }
+
+    #[tokio::test]
+    async fn operators_only_reach_agents_and_tasks_of_their_tenant() {
+        let (ctx, _audit) = test_context();
+        for (name, tenant) in [
+            ("shared", None),
+            ("search-bot", Some("search")),
+            ("billing-bot", Some("billing")),
+        ] {
+            ctx.agents().register_from_config(AgentConfig {
+                agent_name: name.to_string(),
+                tenant: tenant.map(str::to_string),
+                ..AgentConfig::default()
+            });
+        }
+        let mut orchestrator =
+            TaskOrchestrator::new(AgentRegistry::new()).with_commands(ctx.orchestrator());
+        let own = orchestrator.submit_task_for("search", "chat".into(), serde_json::json!({}));
+        let other = orchestrator.submit_task_for("billing", "chat".into(), serde_json::json!({}));
+        let token = CancellationToken::new();
+        let running = tokio::spawn(orchestrator.run(token.clone()));
+        let mut app = admin_routes().with_state(ctx.clone());
+        let operator = Principal {
+            id: "search-ops".to_string(),
+            role: Role::Operator,
+            method: AuthMethod::ApiKey,
+            tenant: "search".to_string(),
+            ip: None,
+        };
+
+        let (_, body) = call_as(&mut app, operator.clone(), "GET", "/admin/agents").await;
+        let names: Vec<&str> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|agent| agent["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["search-bot", "shared"]);
+        for (method, uri) in [
+            ("GET", "/admin/agents/billing-bot"),
+            ("POST", "/admin/agents/billing-bot/pause"),
+            ("POST", "/admin/agents/shared/pause"),
+        ] {
+            let (status, _) = call_as(&mut app, operator.clone(), method, uri).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+        }
+        let (status, _) = call_as(
+            &mut app,
+            operator.clone(),
+            "POST",
+            "/admin/agents/search-bot/pause",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = call_as(
+            &mut app,
+            operator.clone(),
+            "POST",
+            &format!("/admin/tasks/{}/cancel", other),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call_as(
+            &mut app,
+            operator.clone(),
+            "POST",
+            &format!("/admin/tasks/{}/cancel", own),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call_as(
+            &mut app,
+            operator.clone(),
+            "POST",
+            &format!("/admin/tasks/{}/retry", own),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&mut app, "POST", &format!("/admin/tasks/{}/cancel", other)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        for (client, tenant) in [("key:search-ops", "search"), ("key:billing-ops", "billing")] {
+            ctx.rate_limiter()
+                .check_tenant_rate_limit(ClientId::Principal(client.into()), tenant, "/predict")
+                .unwrap();
+        }
+        let (_, body) = call_as(&mut app, operator, "GET", "/admin/rate-limits").await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["client"], "key:search-ops");
+        let (_, body) = call(&mut app, "GET", "/admin/rate-limits").await;
+        assert_eq!(body.as_array().unwrap().len(), 2);
+
+        token.cancel();
+        running.await.unwrap().unwrap();
+    }
+}
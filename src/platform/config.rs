@@ -1,15 +1,29 @@
 use anyhow::{Context, Result};
 use config::{Config, Environment, File, FileFormat};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use crate::agents::{AgentConfig, AgentType};
+use crate::auth::AuthSettings;
+use crate::cache::{CacheMode, CacheSettings};
+use crate::events::webhooks::WebhookSettings;
+use crate::guardrails::GuardrailPolicy;
+use crate::metering::MeteringSettings;
+use crate::platform::drain::DrainSettings;
+use crate::platform::validation::{self, ConfigValidationError};
+use crate::prompts::{ChatFormat, PromptSettings};
+use crate::sessions::MemoryPolicy;
+use crate::storage::StorageSettings;
+use crate::tenancy::TenancySettings;
+use crate::tools::ToolSettings;
+use crate::utils::RedactionSettings;
 
 const DEFAULT_CONFIG_PATH: &str = "configs/platform.toml";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct PlatformConfig {
     pub metadata: MetadataSettings,
     pub observability: ObservabilitySettings,
@@ -18,6 +32,17 @@ pub struct PlatformConfig {
     pub agents: HashMap<String, AgentSettings>,
     pub inference: InferenceSettings,
     pub training: TrainingSettings,
+    pub sessions: SessionSettings,
+    pub prompts: PromptSettings,
+    pub tools: ToolSettings,
+    pub redaction: RedactionSettings,
+    pub auth: AuthSettings,
+    pub tenancy: TenancySettings,
+    pub metering: MeteringSettings,
+    pub cache: CacheSettings,
+    pub storage: StorageSettings,
+    pub webhooks: WebhookSettings,
+    pub drain: DrainSettings,
 }
 
 impl Default for PlatformConfig {
@@ -30,6 +55,17 @@ impl Default for PlatformConfig {
             agents: HashMap::new(),
             inference: InferenceSettings::default(),
             training: TrainingSettings::default(),
+            sessions: SessionSettings::default(),
+            prompts: PromptSettings::default(),
+            tools: ToolSettings::default(),
+            redaction: RedactionSettings::default(),
+            auth: AuthSettings::default(),
+            tenancy: TenancySettings::default(),
+            metering: MeteringSettings::default(),
+            cache: CacheSettings::default(),
+            storage: StorageSettings::default(),
+            webhooks: WebhookSettings::default(),
+            drain: DrainSettings::default(),
         }
     }
 }
@@ -40,13 +76,20 @@ impl PlatformConfig {
     }
 
     pub fn load_from_path<P: AsRef<Path>>(path: Option<P>) -> Result<Self> {
+        let default_path = PathBuf::from(DEFAULT_CONFIG_PATH);
+        if default_path.exists() {
+            Self::from_file(&default_path)?;
+        }
+
         let mut builder = Config::builder().add_source(
-            File::from(PathBuf::from(DEFAULT_CONFIG_PATH))
+            File::from(default_path)
                 .format(FileFormat::Toml)
                 .required(false),
         );
 
         if let Some(path) = path {
+            // Parse the file on its own first so errors point at a line and column.
+            Self::from_file(path.as_ref())?;
             builder = builder.add_source(
                 File::from(path.as_ref())
                     .format(FileFormat::Toml)
@@ -56,10 +99,41 @@ impl PlatformConfig {
 
         builder = builder.add_source(Environment::with_prefix("CHIMERA").separator("__"));
 
-        let config = builder.build()?;
-        config
+        let config: Self = builder
+            .build()?
             .try_deserialize()
-            .context("invalid platform configuration")
+            .context("invalid platform configuration (after CHIMERA__* overrides)")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Strictly parses a single TOML file, without defaults files or environment overlays.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::from_toml_str(&contents)
+            .map_err(|err| err.with_origin(path.display().to_string()).into())
+    }
+
+    pub fn from_toml_str(contents: &str) -> Result<Self, ConfigValidationError> {
+        validation::parse_strict(contents)
+    }
+
+    /// Range and syntax checks that deserialization alone does not enforce.
+    pub fn validate(&self) -> Result<(), ConfigValidationError> {
+        let issues = validation::check_ranges(self);
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigValidationError::new(issues))
+        }
+    }
+
+    /// JSON Schema describing every accepted key of `platform.toml`.
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(PlatformConfig))
+            .expect("generated schema serializes to JSON")
     }
 
     pub fn agent_catalog(&self) -> HashMap<String, AgentConfig> {
@@ -70,8 +144,8 @@ impl PlatformConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct MetadataSettings {
     pub name: String,
     pub environment: String,
@@ -90,8 +164,8 @@ impl Default for MetadataSettings {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct ObservabilitySettings {
     pub log_level: String,
     pub metrics_port: u16,
@@ -108,8 +182,8 @@ impl Default for ObservabilitySettings {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct AuditSettings {
     pub log_path: String,
     pub retention_days: u32,
@@ -124,8 +198,8 @@ impl Default for AuditSettings {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct RateLimitingSettings {
     pub default: RateLimitRule,
     pub endpoints: HashMap<String, RateLimitRule>,
@@ -140,8 +214,8 @@ impl Default for RateLimitingSettings {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct RateLimitRule {
     pub requests: u32,
     pub window_seconds: u64,
@@ -158,8 +232,8 @@ impl Default for RateLimitRule {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct AgentSettings {
     pub agent_type: AgentType,
     pub model_path: String,
@@ -168,6 +242,20 @@ pub struct AgentSettings {
     pub system_prompt: String,
     pub capabilities: Vec<String>,
     pub max_concurrent_requests: usize,
+    /// Base URL the router forwards requests to, e.g. `http://localhost:8080`.
+    pub endpoint: Option<String>,
+    pub memory: MemoryPolicy,
+    pub prompt_template: Option<String>,
+    /// Values for the template's own `{{variables}}`.
+    pub prompt_variables: HashMap<String, String>,
+    pub chat_format: ChatFormat,
+    /// Names of the registered tools this agent may call.
+    pub tools: Vec<String>,
+    pub guardrail: GuardrailPolicy,
+    /// Restricts the agent to one tenant; shared when unset.
+    pub tenant: Option<String>,
+    /// `auto` caches temperature-0 requests, `force` all, `off` none.
+    pub cache: CacheMode,
 }
 
 impl Default for AgentSettings {
@@ -180,12 +268,21 @@ impl Default for AgentSettings {
             system_prompt: "You are a helpful assistant.".to_string(),
             capabilities: vec!["text_generation".to_string()],
             max_concurrent_requests: 4,
+            endpoint: None,
+            memory: MemoryPolicy::default(),
+            prompt_template: None,
+            prompt_variables: HashMap::new(),
+            chat_format: ChatFormat::default(),
+            tools: Vec::new(),
+            guardrail: GuardrailPolicy::default(),
+            tenant: None,
+            cache: CacheMode::default(),
         }
     }
 }
 
 impl AgentSettings {
-    fn to_agent_config(&self, name: &str) -> AgentConfig {
+    pub(crate) fn to_agent_config(&self, name: &str) -> AgentConfig {
         AgentConfig {
             model_path: self.model_path.clone(),
             max_tokens: self.max_tokens,
@@ -195,12 +292,21 @@ impl AgentSettings {
             max_concurrent_requests: self.max_concurrent_requests,
             capabilities: self.capabilities.clone(),
             agent_type: self.agent_type.clone(),
+            endpoint: self.endpoint.clone(),
+            memory: self.memory.clone(),
+            prompt_template: self.prompt_template.clone(),
+            prompt_variables: self.prompt_variables.clone(),
+            chat_format: self.chat_format,
+            tools: self.tools.clone(),
+            guardrail: self.guardrail.clone(),
+            tenant: self.tenant.clone(),
+            cache: self.cache,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct InferenceSettings {
     pub batch_size: usize,
     pub max_tokens: usize,
@@ -221,8 +327,8 @@ impl Default for InferenceSettings {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
 pub struct TrainingSettings {
     pub base_model: String,
     pub output_dir: String,
@@ -242,3 +348,27 @@ impl Default for TrainingSettings {
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionBackend {
+    Memory,
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionSettings {
+    pub backend: SessionBackend,
+    /// Database file used when `backend = "sqlite"`.
+    pub sqlite_path: String,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            backend: SessionBackend::Memory,
+            sqlite_path: "data/sessions.db".to_string(),
+        }
+    }
+}
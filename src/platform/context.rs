@@ -1,11 +1,29 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use parking_lot::RwLock;
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
 
-use crate::agents::AgentRegistry;
+use crate::agents::{AgentRegistry, AgentStatus};
 use crate::audit_logging::AuditLogger;
+use crate::auth::Authenticator;
+use crate::cache::ResponseCache;
+use crate::events::webhooks::WebhookRegistry;
+use crate::events::EventBus;
+use crate::metering::UsageMeter;
+use crate::orchestration::OrchestratorHandle;
 use crate::platform::config::PlatformConfig;
+use crate::platform::drain::InFlight;
+use crate::platform::reload::{apply_reload, ConfigDiff, ConfigReloadError};
+use crate::platform::supervisor::ServiceHealthRegistry;
 use crate::rate_limiting::RateLimiter;
+use crate::sessions::SessionManager;
+use crate::storage::Repositories;
+use crate::tenancy::TenantManager;
+use crate::tools::ToolRegistry;
+use crate::utils::Redactor;
 
 #[derive(Clone)]
 pub struct PlatformContext {
@@ -14,25 +32,47 @@ pub struct PlatformContext {
 }
 
 struct SharedState {
-    config: PlatformConfig,
-    audit_logger: Arc<AuditLogger>,
-    rate_limiter: Arc<RateLimiter>,
-    agent_registry: AgentRegistry,
+    config: watch::Sender<Arc<PlatformConfig>>,
+    components: Components,
+    services: ServiceHealthRegistry,
+    /// File the configuration is reloaded from, once a watcher is running.
+    config_path: RwLock<Option<PathBuf>>,
+    draining: AtomicBool,
+    in_flight: InFlight,
+}
+
+/// Shared handles built once at startup and exposed through the context.
+pub(crate) struct Components {
+    pub audit_logger: Arc<AuditLogger>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub agent_registry: AgentRegistry,
+    pub sessions: SessionManager,
+    pub tools: ToolRegistry,
+    pub redactor: Arc<Redactor>,
+    pub auth: Arc<Authenticator>,
+    pub tenants: TenantManager,
+    pub usage: UsageMeter,
+    pub cache: ResponseCache,
+    pub orchestrator: OrchestratorHandle,
+    pub storage: Repositories,
+    pub events: EventBus,
+    pub webhooks: WebhookRegistry,
 }
 
 impl PlatformContext {
     pub(crate) fn new(
         config: PlatformConfig,
-        audit_logger: Arc<AuditLogger>,
-        rate_limiter: Arc<RateLimiter>,
-        agent_registry: AgentRegistry,
+        components: Components,
         shutdown: CancellationToken,
     ) -> Self {
+        let (config, _) = watch::channel(Arc::new(config));
         let shared = SharedState {
             config,
-            audit_logger,
-            rate_limiter,
-            agent_registry,
+            components,
+            services: ServiceHealthRegistry::default(),
+            config_path: RwLock::new(None),
+            draining: AtomicBool::new(false),
+            in_flight: InFlight::default(),
         };
 
         Self {
@@ -41,20 +81,128 @@ impl PlatformContext {
         }
     }
 
-    pub fn config(&self) -> &PlatformConfig {
-        &self.shared.config
+    /// Snapshot of the configuration currently in effect.
+    pub fn config(&self) -> Arc<PlatformConfig> {
+        Arc::clone(&self.shared.config.borrow())
+    }
+
+    /// Receiver that is notified every time a reload is applied.
+    pub fn subscribe_config(&self) -> watch::Receiver<Arc<PlatformConfig>> {
+        self.shared.config.subscribe()
+    }
+
+    /// Validates and applies `candidate`, rejecting it when any change needs a restart.
+    pub fn reload_config(
+        &self,
+        candidate: PlatformConfig,
+    ) -> Result<ConfigDiff, ConfigReloadError> {
+        apply_reload(self, candidate)
+    }
+
+    pub(crate) fn publish_config(&self, config: PlatformConfig) {
+        self.shared.config.send_replace(Arc::new(config));
+    }
+
+    pub fn config_path(&self) -> Option<PathBuf> {
+        self.shared.config_path.read().clone()
+    }
+
+    pub(crate) fn set_config_path(&self, path: PathBuf) {
+        *self.shared.config_path.write() = Some(path);
+    }
+
+    /// Stops the node taking new work and puts its agents into maintenance;
+    /// returns `false` if it was already draining.
+    pub fn begin_drain(&self) -> bool {
+        let started = !self.shared.draining.swap(true, Ordering::SeqCst);
+        let registry = self.agents();
+        for agent in registry.list_agents() {
+            if agent.status != AgentStatus::Offline {
+                registry.set_status(&agent.id, AgentStatus::Maintenance);
+            }
+        }
+        started
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.shared.draining.load(Ordering::SeqCst)
+    }
+
+    /// Requests currently being served by the process's HTTP servers.
+    pub fn in_flight(&self) -> InFlight {
+        self.shared.in_flight.clone()
     }
 
     pub fn audit_logger(&self) -> Arc<AuditLogger> {
-        Arc::clone(&self.shared.audit_logger)
+        Arc::clone(&self.shared.components.audit_logger)
     }
 
     pub fn rate_limiter(&self) -> Arc<RateLimiter> {
-        Arc::clone(&self.shared.rate_limiter)
+        Arc::clone(&self.shared.components.rate_limiter)
     }
 
     pub fn agents(&self) -> AgentRegistry {
-        self.shared.agent_registry.clone()
+        self.shared.components.agent_registry.clone()
+    }
+
+    /// Conversation history shared by every agent hosted in this process.
+    pub fn sessions(&self) -> SessionManager {
+        self.shared.components.sessions.clone()
+    }
+
+    /// Tools agents may call; each agent is limited to the ones it lists.
+    pub fn tools(&self) -> ToolRegistry {
+        self.shared.components.tools.clone()
+    }
+
+    /// PII redaction applied to prompts, completions and audit metadata.
+    pub fn redactor(&self) -> Arc<Redactor> {
+        Arc::clone(&self.shared.components.redactor)
+    }
+
+    /// Resolves request credentials to principals for the HTTP servers.
+    pub fn auth(&self) -> Arc<Authenticator> {
+        Arc::clone(&self.shared.components.auth)
+    }
+
+    /// Per-tenant quotas and usage accounting.
+    pub fn tenants(&self) -> TenantManager {
+        self.shared.components.tenants.clone()
+    }
+
+    /// Metered token usage and cost of every generation.
+    pub fn usage(&self) -> UsageMeter {
+        self.shared.components.usage.clone()
+    }
+
+    /// Inference response cache shared by every hosted agent.
+    pub fn response_cache(&self) -> ResponseCache {
+        self.shared.components.cache.clone()
+    }
+
+    /// Lists, cancels and retries tasks of the running orchestrator.
+    pub fn orchestrator(&self) -> OrchestratorHandle {
+        self.shared.components.orchestrator.clone()
+    }
+
+    /// Persistent agents, tasks, training jobs and model artifacts.
+    pub fn storage(&self) -> Repositories {
+        self.shared.components.storage.clone()
+    }
+
+    /// Lifecycle events published by agents, tasks, training and auditing.
+    pub fn events(&self) -> EventBus {
+        self.shared.components.events.clone()
+    }
+
+    /// Webhook subscriptions fed from [`Self::events`].
+    pub fn webhooks(&self) -> WebhookRegistry {
+        self.shared.components.webhooks.clone()
+    }
+
+    /// Health of every supervised service.
+    pub fn services(&self) -> ServiceHealthRegistry {
+        self.shared.services.clone()
     }
 
     pub fn shutdown_token(&self) -> CancellationToken {
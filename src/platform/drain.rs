@@ -0,0 +1,210 @@
+//! Graceful drain and socket handoff
+//!
+//! A draining process reports unready, refuses new work and gives in-flight
+//! requests and orchestrator tasks until `[drain] deadline_seconds` to
+//! finish before it shuts down. A replacement process can take over the
+//! port either by binding it alongside the old one (`reuse_port`) or by
+//! inheriting the listening socket from a supervisor (`LISTEN_FDS`). In the
+//! latter case the telemetry port is taken over from a second descriptor if
+//! one is passed, and otherwise shared with `SO_REUSEPORT`.
+
+use std::net::SocketAddr;
+use std::os::fd::{FromRawFd, RawFd};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::sync::Notify;
+
+use crate::platform::context::PlatformContext;
+
+/// First descriptor passed under the `LISTEN_FDS` protocol.
+const LISTEN_FDS_START: RawFd = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DrainSettings {
+    /// How long in-flight requests and tasks may run once draining starts;
+    /// unfinished tasks are then handed back to the queue.
+    pub deadline_seconds: u64,
+    /// Bind HTTP ports with `SO_REUSEPORT` so a new process can start
+    /// serving before the old one has drained.
+    pub reuse_port: bool,
+}
+
+impl Default for DrainSettings {
+    fn default() -> Self {
+        Self {
+            deadline_seconds: 30,
+            reuse_port: false,
+        }
+    }
+}
+
+/// Counts requests being served so a drain can wait for them.
+#[derive(Clone, Default)]
+pub struct InFlight {
+    inner: Arc<InFlightState>,
+}
+
+#[derive(Default)]
+struct InFlightState {
+    count: AtomicUsize,
+    idle: Notify,
+}
+
+/// Marks one request in flight until dropped.
+pub struct InFlightGuard {
+    inner: Arc<InFlightState>,
+}
+
+impl InFlight {
+    pub fn start(&self) -> InFlightGuard {
+        self.inner.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.inner.count.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once no request is in flight.
+    pub async fn wait_idle(&self) {
+        loop {
+            let idle = self.inner.idle.notified();
+            if self.count() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if self.inner.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+/// Middleware that counts the request in [`PlatformContext::in_flight`].
+pub async fn track_in_flight(
+    State(ctx): State<PlatformContext>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let _guard = ctx.in_flight().start();
+    next.run(request).await
+}
+
+/// Binds `addr`, sharing the port with other processes when `reuse_port` is set.
+pub fn bind(addr: SocketAddr, reuse_port: bool) -> Result<TcpListener> {
+    let socket = match addr {
+        SocketAddr::V4(_) => TcpSocket::new_v4()?,
+        SocketAddr::V6(_) => TcpSocket::new_v6()?,
+    };
+    socket.set_reuseaddr(true)?;
+    if reuse_port {
+        socket.set_reuseport(true)?;
+    }
+    socket
+        .bind(addr)
+        .with_context(|| format!("failed to bind {}", addr))?;
+    Ok(socket.listen(1024)?)
+}
+
+/// Set once this process serves on a socket handed over by a draining one.
+static HANDOFF: AtomicBool = AtomicBool::new(false);
+
+/// Number of descriptors a supervisor passed under the systemd `LISTEN_FDS`
+/// protocol, if they were meant for this process.
+fn listen_fds() -> Option<u32> {
+    let count: u32 = std::env::var("LISTEN_FDS").ok()?.parse().ok()?;
+    let pid_matches = std::env::var("LISTEN_PID")
+        .ok()
+        .and_then(|pid| pid.parse::<u32>().ok())
+        .is_none_or(|pid| pid == std::process::id());
+    (count > 0 && pid_matches).then_some(count)
+}
+
+/// The descriptor a supervisor passed under the systemd `LISTEN_FDS`
+/// protocol, if it was meant for this process.
+pub fn listen_fd_from_env() -> Option<RawFd> {
+    listen_fds().map(|_| LISTEN_FDS_START)
+}
+
+/// The telemetry socket, passed as the second `LISTEN_FDS` descriptor. Only
+/// returned once, since the listener built from it closes it when dropped.
+pub fn take_telemetry_fd() -> Option<RawFd> {
+    static TAKEN: AtomicBool = AtomicBool::new(false);
+    let passed = listen_fds().is_some_and(|count| count >= 2);
+    (passed && !TAKEN.swap(true, Ordering::SeqCst)).then_some(LISTEN_FDS_START + 1)
+}
+
+/// Records that this process took over its listening socket, e.g. via `--listen-fd`.
+pub fn mark_handoff() {
+    HANDOFF.store(true, Ordering::SeqCst);
+}
+
+/// Whether this process took over from a draining one that may still hold its ports.
+pub fn in_handoff() -> bool {
+    HANDOFF.load(Ordering::SeqCst) || listen_fds().is_some()
+}
+
+/// Takes over a listening socket inherited as descriptor `fd`.
+pub fn inherit(fd: RawFd) -> Result<TcpListener> {
+    // SAFETY: the descriptor was handed to this process to serve on and
+    // nothing else in it takes ownership of it.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    listener
+        .local_addr()
+        .with_context(|| format!("descriptor {} is not a listening socket", fd))?;
+    listener.set_nonblocking(true)?;
+    Ok(TcpListener::from_std(listener)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn wait_idle_resolves_when_the_last_request_finishes() {
+        let in_flight = InFlight::default();
+        let first = in_flight.start();
+        let second = in_flight.start();
+
+        let waiter = tokio::spawn({
+            let in_flight = in_flight.clone();
+            async move { in_flight.wait_idle().await }
+        });
+        drop(first);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+
+        drop(second);
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[tokio::test]
+    async fn reuse_port_lets_two_listeners_share_a_port() {
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let first = bind(addr, true).unwrap();
+        let second = bind(first.local_addr().unwrap(), true).unwrap();
+        assert_eq!(first.local_addr().unwrap(), second.local_addr().unwrap());
+        assert!(bind(first.local_addr().unwrap(), false).is_err());
+    }
+}
@@ -1,10 +1,79 @@
+pub mod admin;
 pub mod config;
 pub mod context;
+pub mod drain;
+pub mod reload;
 pub mod runtime;
 pub mod service;
+pub mod supervisor;
 pub mod telemetry;
+pub mod validation;
+pub mod webhooks;
 
 pub use config::PlatformConfig;
 pub use context::PlatformContext;
+pub use reload::{ConfigDiff, ConfigReloadError};
 pub use runtime::{Platform, PlatformRuntime};
-pub use service::ServiceRegistration;
+pub use service::{RestartMode, RestartPolicy, ServiceRegistration};
+pub use supervisor::{ServiceHealth, ServiceHealthRegistry, ServiceState};
+pub use validation::{ConfigIssue, ConfigValidationError};
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::sync::Arc;
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::agents::AgentRegistry;
+    use crate::audit_logging::AuditLogger;
+    use crate::auth::Authenticator;
+    use crate::cache::ResponseCache;
+    use crate::events::webhooks::WebhookRegistry;
+    use crate::events::EventBus;
+    use crate::metering::UsageMeter;
+    use crate::orchestration::OrchestratorHandle;
+    use crate::platform::context::Components;
+    use crate::platform::{PlatformConfig, PlatformContext};
+    use crate::rate_limiting::RateLimiter;
+    use crate::sessions::{InMemorySessionStore, SessionManager};
+    use crate::storage::Repositories;
+    use crate::tenancy::TenantManager;
+    use crate::tools::ToolRegistry;
+    use crate::utils::{RedactionKey, Redactor};
+
+    pub(crate) fn test_context() -> (PlatformContext, tempfile::NamedTempFile) {
+        test_context_with(PlatformConfig::default())
+    }
+
+    /// Context wired to a throwaway audit log; keep the file alive for the test.
+    pub(crate) fn test_context_with(
+        config: PlatformConfig,
+    ) -> (PlatformContext, tempfile::NamedTempFile) {
+        let audit_file = tempfile::NamedTempFile::new().unwrap();
+        let storage = Repositories::in_memory();
+        let audit_logger = AuditLogger::new(audit_file.path().to_str().unwrap(), 1).unwrap();
+        let context = PlatformContext::new(
+            config.clone(),
+            Components {
+                audit_logger: Arc::new(audit_logger),
+                rate_limiter: Arc::new(RateLimiter::from_settings(&config.rate_limiting)),
+                agent_registry: AgentRegistry::from_catalog(config.agent_catalog()),
+                sessions: SessionManager::new(Arc::new(InMemorySessionStore::new())),
+                tools: ToolRegistry::with_builtins(&config.tools).unwrap(),
+                redactor: Arc::new(
+                    Redactor::from_settings(&config.redaction, RedactionKey::generate()).unwrap(),
+                ),
+                auth: Arc::new(Authenticator::from_settings(&config.auth).unwrap()),
+                tenants: TenantManager::new(config.tenancy.clone()),
+                usage: UsageMeter::new(config.metering.clone()),
+                cache: ResponseCache::new(config.cache.clone()),
+                orchestrator: OrchestratorHandle::new(),
+                webhooks: WebhookRegistry::new(config.webhooks.clone(), storage.webhooks.clone()),
+                storage,
+                events: EventBus::new(),
+            },
+            CancellationToken::new(),
+        );
+        (context, audit_file)
+    }
+}
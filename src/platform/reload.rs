@@ -0,0 +1,485 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::platform::config::PlatformConfig;
+use crate::platform::context::PlatformContext;
+use crate::platform::runtime::set_log_level;
+use crate::platform::service::ServiceRegistration;
+use crate::rate_limiting::RateLimitConfig;
+
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Top-level paths that can be swapped in without restarting the platform.
+const LIVE_PATHS: &[&str] = &[
+    "rate_limiting",
+    "observability.log_level",
+    "inference",
+    "prompts",
+    "tenancy",
+    "metering.retention_hours",
+    "metering.max_records",
+    "metering.currency",
+    "metering.default_price",
+    "metering.prices",
+    "cache.enabled",
+    "cache.ttl_seconds",
+    "cache.max_entries",
+    "cache.similarity.enabled",
+    "cache.similarity.threshold",
+    "webhooks",
+    "drain.deadline_seconds",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.before, &self.after) {
+            (None, Some(after)) => write!(f, "+ {} = {}", self.path, after),
+            (Some(before), None) => write!(f, "- {} = {}", self.path, before),
+            (Some(before), Some(after)) => write!(f, "~ {}: {} -> {}", self.path, before, after),
+            (None, None) => write!(f, "  {}", self.path),
+        }
+    }
+}
+
+/// Difference between the running configuration and a reload candidate,
+/// split by whether each change can be applied live.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigDiff {
+    pub live: Vec<ConfigChange>,
+    pub restart_required: Vec<ConfigChange>,
+}
+
+impl ConfigDiff {
+    pub fn between(current: &PlatformConfig, candidate: &PlatformConfig) -> Self {
+        let mut diff = ConfigDiff::default();
+
+        let mut before = to_value(current);
+        let mut after = to_value(candidate);
+        let before_agents = take_object(&mut before, "agents");
+        let after_agents = take_object(&mut after, "agents");
+
+        let mut before_leaves = BTreeMap::new();
+        let mut after_leaves = BTreeMap::new();
+        flatten("", &before, &mut before_leaves);
+        flatten("", &after, &mut after_leaves);
+
+        for change in leaf_changes(&before_leaves, &after_leaves) {
+            if is_live_path(&change.path) {
+                diff.live.push(change);
+            } else {
+                diff.restart_required.push(change);
+            }
+        }
+
+        // Whole agents may come and go; editing an agent that is already loaded needs a restart.
+        let names: std::collections::BTreeSet<&String> =
+            before_agents.keys().chain(after_agents.keys()).collect();
+        for name in names {
+            let path = format!("agents.{}", name);
+            match (before_agents.get(name), after_agents.get(name)) {
+                (Some(old), Some(new)) => {
+                    let mut old_leaves = BTreeMap::new();
+                    let mut new_leaves = BTreeMap::new();
+                    flatten(&path, old, &mut old_leaves);
+                    flatten(&path, new, &mut new_leaves);
+                    diff.restart_required
+                        .extend(leaf_changes(&old_leaves, &new_leaves));
+                }
+                (old, new) => diff.live.push(ConfigChange {
+                    path,
+                    before: old.cloned(),
+                    after: new.cloned(),
+                }),
+            }
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live.is_empty() && self.restart_required.is_empty()
+    }
+
+    fn touches(&self, prefix: &str) -> bool {
+        self.live
+            .iter()
+            .any(|change| path_has_prefix(&change.path, prefix))
+    }
+}
+
+impl fmt::Display for ConfigDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no configuration changes");
+        }
+        if !self.live.is_empty() {
+            writeln!(f, "live changes:")?;
+            for change in &self.live {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        if !self.restart_required.is_empty() {
+            writeln!(f, "changes requiring restart:")?;
+            for change in &self.restart_required {
+                writeln!(f, "  {}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigReloadError {
+    Invalid(anyhow::Error),
+    RestartRequired(ConfigDiff),
+}
+
+impl fmt::Display for ConfigReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigReloadError::Invalid(err) => {
+                write!(f, "rejected invalid configuration: {:#}", err)
+            }
+            ConfigReloadError::RestartRequired(diff) => {
+                write!(
+                    f,
+                    "rejected configuration reload, restart required\n{}",
+                    diff
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigReloadError {}
+
+/// Validates `candidate`, applies its live sections to the running services
+/// and publishes it to config subscribers.
+pub(crate) fn apply_reload(
+    context: &PlatformContext,
+    candidate: PlatformConfig,
+) -> Result<ConfigDiff, ConfigReloadError> {
+    candidate
+        .validate()
+        .map_err(|err| ConfigReloadError::Invalid(err.into()))?;
+
+    let current = context.config();
+    let diff = ConfigDiff::between(&current, &candidate);
+    if !diff.restart_required.is_empty() {
+        return Err(ConfigReloadError::RestartRequired(diff));
+    }
+    if diff.is_empty() {
+        return Ok(diff);
+    }
+
+    if diff.touches("observability.log_level") {
+        set_log_level(&candidate.observability.log_level).map_err(ConfigReloadError::Invalid)?;
+    }
+
+    if diff.touches("rate_limiting") {
+        context
+            .rate_limiter()
+            .reconfigure(RateLimitConfig::from_settings(&candidate.rate_limiting));
+    }
+
+    if diff.touches("tenancy") {
+        context.tenants().reconfigure(candidate.tenancy.clone());
+    }
+
+    if diff.touches("metering") {
+        context.usage().reconfigure(candidate.metering.clone());
+    }
+
+    if diff.touches("webhooks") {
+        context.webhooks().reconfigure(candidate.webhooks.clone());
+    }
+
+    if diff.touches("cache") {
+        context
+            .response_cache()
+            .reconfigure(candidate.cache.clone());
+    }
+
+    let registry = context.agents();
+    for change in diff
+        .live
+        .iter()
+        .filter(|c| path_has_prefix(&c.path, "agents"))
+    {
+        let name = &change.path["agents.".len()..];
+        match candidate.agents.get(name) {
+            Some(settings) => {
+                registry.register_from_config(settings.to_agent_config(name));
+                info!(agent = %name, "agent added by configuration reload");
+            }
+            None => {
+                registry.remove_agents_by_name(name);
+                info!(agent = %name, "agent removed by configuration reload");
+            }
+        }
+    }
+
+    context.publish_config(candidate);
+    Ok(diff)
+}
+
+pub fn config_reload_service(path: PathBuf) -> ServiceRegistration {
+    ServiceRegistration::new(
+        "config-reload",
+        Arc::new(move |context, token: CancellationToken| {
+            let path = path.clone();
+            tokio::spawn(async move { watch_config(context, path, token).await })
+        }),
+    )
+}
+
+async fn watch_config(
+    context: PlatformContext,
+    path: PathBuf,
+    token: CancellationToken,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+    let mut hangup = ReloadSignal::new()?;
+    let mut last_modified = modified_at(&path);
+    context.set_config_path(path.clone());
+
+    info!(path = %path.display(), "watching platform configuration for changes");
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = hangup.recv() => {
+                info!("SIGHUP received, reloading configuration");
+                last_modified = modified_at(&path);
+                reload_from_disk(&context, &path);
+            }
+            _ = interval.tick() => {
+                let modified = modified_at(&path);
+                if modified != last_modified {
+                    last_modified = modified;
+                    reload_from_disk(&context, &path);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn reload_from_disk(context: &PlatformContext, path: &Path) {
+    match reload_file(context, path) {
+        Ok(diff) if diff.is_empty() => info!("configuration unchanged"),
+        Ok(diff) => info!("configuration reloaded\n{}", diff),
+        Err(err) => warn!(path = %path.display(), "{:#}", err),
+    }
+}
+
+/// Loads `path` and applies it, as a file change or SIGHUP would.
+pub fn reload_file(context: &PlatformContext, path: &Path) -> Result<ConfigDiff> {
+    let candidate =
+        PlatformConfig::load_from_path(Some(path)).context("failed to load configuration")?;
+    Ok(apply_reload(context, candidate)?)
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+struct ReloadSignal {
+    #[cfg(unix)]
+    inner: tokio::signal::unix::Signal,
+}
+
+impl ReloadSignal {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            inner: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?,
+        })
+    }
+
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        if self.inner.recv().await.is_some() {
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+fn to_value(config: &PlatformConfig) -> Value {
+    serde_json::to_value(config).unwrap_or(Value::Null)
+}
+
+fn take_object(value: &mut Value, key: &str) -> serde_json::Map<String, Value> {
+    match value.as_object_mut().and_then(|map| map.remove(key)) {
+        Some(Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, child, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.clone());
+        }
+    }
+}
+
+fn leaf_changes(
+    before: &BTreeMap<String, Value>,
+    after: &BTreeMap<String, Value>,
+) -> Vec<ConfigChange> {
+    let paths: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    paths
+        .into_iter()
+        .filter(|path| before.get(*path) != after.get(*path))
+        .map(|path| ConfigChange {
+            path: path.clone(),
+            before: before.get(path).cloned(),
+            after: after.get(path).cloned(),
+        })
+        .collect()
+}
+
+fn is_live_path(path: &str) -> bool {
+    LIVE_PATHS
+        .iter()
+        .any(|prefix| path_has_prefix(path, prefix))
+}
+
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    path == prefix || (path.starts_with(prefix) && path[prefix.len()..].starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::config::AgentSettings;
+    use crate::platform::test_support::test_context;
+
+    #[test]
+    fn identical_configs_have_no_diff() {
+        let config = PlatformConfig::default();
+        assert!(ConfigDiff::between(&config, &config.clone()).is_empty());
+    }
+
+    #[test]
+    fn live_sections_are_classified_as_live() {
+        let current = PlatformConfig::default();
+        let mut candidate = current.clone();
+        candidate.rate_limiting.default.requests = 10;
+        candidate.observability.log_level = "debug".to_string();
+        candidate.inference.top_p = 0.5;
+        candidate
+            .agents
+            .insert("coder".to_string(), AgentSettings::default());
+
+        let diff = ConfigDiff::between(&current, &candidate);
+        assert!(diff.restart_required.is_empty());
+        let paths: Vec<&str> = diff.live.iter().map(|c| c.path.as_str()).collect();
+        assert!(paths.contains(&"rate_limiting.default.requests"));
+        assert!(paths.contains(&"observability.log_level"));
+        assert!(paths.contains(&"inference.top_p"));
+        assert!(paths.contains(&"agents.coder"));
+    }
+
+    #[test]
+    fn restart_sections_are_reported() {
+        let mut current = PlatformConfig::default();
+        current
+            .agents
+            .insert("coder".to_string(), AgentSettings::default());
+        let mut candidate = current.clone();
+        candidate.observability.metrics_port = 9191;
+        candidate.agents.get_mut("coder").unwrap().model_path = "models/other".to_string();
+
+        let diff = ConfigDiff::between(&current, &candidate);
+        let paths: Vec<&str> = diff
+            .restart_required
+            .iter()
+            .map(|c| c.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["observability.metrics_port", "agents.coder.model_path"]
+        );
+        assert!(diff
+            .to_string()
+            .contains("~ observability.metrics_port: 9090 -> 9191"));
+    }
+
+    #[test]
+    fn reload_applies_live_changes_and_notifies_subscribers() {
+        let (context, _audit) = test_context();
+        let mut updates = context.subscribe_config();
+
+        let mut candidate = PlatformConfig::default();
+        candidate.rate_limiting.default.requests = 5;
+        candidate
+            .agents
+            .insert("coder".to_string(), AgentSettings::default());
+
+        let diff = context.reload_config(candidate).unwrap();
+        assert_eq!(diff.live.len(), 2);
+        assert!(updates.has_changed().unwrap());
+        assert_eq!(
+            updates.borrow_and_update().rate_limiting.default.requests,
+            5
+        );
+        assert_eq!(context.rate_limiter().config().default.requests, 5);
+        assert_eq!(context.agents().list_agents().len(), 1);
+    }
+
+    #[test]
+    fn reload_rejects_restart_changes_without_applying() {
+        let (context, _audit) = test_context();
+
+        let mut candidate = PlatformConfig::default();
+        candidate.rate_limiting.default.requests = 5;
+        candidate.audit.log_path = "elsewhere.log".to_string();
+
+        let err = context.reload_config(candidate).unwrap_err();
+        assert!(matches!(err, ConfigReloadError::RestartRequired(_)));
+        assert_eq!(context.config().rate_limiting.default.requests, 1000);
+        assert_eq!(context.rate_limiter().config().default.requests, 1000);
+    }
+
+    #[test]
+    fn reload_rejects_invalid_candidates() {
+        let (context, _audit) = test_context();
+
+        let mut candidate = PlatformConfig::default();
+        candidate.rate_limiting.default.window_seconds = 0;
+
+        let err = context.reload_config(candidate).unwrap_err();
+        assert!(matches!(err, ConfigReloadError::Invalid(_)));
+    }
+}
@@ -1,22 +1,47 @@
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 use anyhow::Result;
 use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
-use tracing::info;
+use tracing::{error, info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
 
-use crate::agents::AgentRegistry;
+use crate::agents::{agent_loader_service, AgentRegistry, AgentStatus};
 use crate::audit_logging::AuditLogger;
+use crate::auth::{AuthSettings, Authenticator};
+use crate::cache::ResponseCache;
+use crate::events::webhooks::{webhook_service, WebhookRegistry};
+use crate::events::EventBus;
+use crate::metering::UsageMeter;
 use crate::orchestration::orchestration_service;
+use crate::orchestration::{OrchestratorHandle, TaskStatus};
 use crate::platform::config::{AuditSettings, PlatformConfig, RateLimitingSettings};
-use crate::platform::context::PlatformContext;
+use crate::platform::context::{Components, PlatformContext};
+use crate::platform::reload::config_reload_service;
 use crate::platform::service::ServiceRegistration;
+use crate::platform::supervisor::supervise;
 use crate::platform::telemetry::telemetry_service;
-use crate::rate_limiting::RateLimiter;
+use crate::rate_limiting::{RateLimitConfig, RateLimiter};
+use crate::sessions::{session_retention_service, SessionManager};
+use crate::storage::Repositories;
+use crate::tenancy::TenantManager;
+use crate::tools::{Tool, ToolRegistry};
+use crate::utils::{RedactionKey, RedactionSettings, Redactor};
+
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often a drain checks whether orchestrator tasks have finished.
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
 
 pub struct Platform {
     config: PlatformConfig,
     services: Vec<ServiceRegistration>,
+    tools: Vec<Arc<dyn Tool>>,
 }
 
 impl Platform {
@@ -24,52 +49,135 @@ impl Platform {
         let mut services = Vec::new();
         services.push(telemetry_service(config.observability.clone()));
 
-        Self { config, services }
+        Self {
+            config,
+            services,
+            tools: Vec::new(),
+        }
     }
 
     pub fn register_service(&mut self, service: ServiceRegistration) {
         self.services.push(service);
     }
 
+    /// Makes `tool` available to agents alongside the built-ins.
+    pub fn register_tool(&mut self, tool: Arc<dyn Tool>) {
+        self.tools.push(tool);
+    }
+
+    /// Reload `path` on change or SIGHUP, applying the sections that are safe to swap live.
+    pub fn watch_config<P: Into<PathBuf>>(&mut self, path: P) {
+        self.services.push(config_reload_service(path.into()));
+    }
+
     pub async fn start(self) -> Result<PlatformRuntime> {
         initialize_logging(&self.config);
 
-        let audit_logger = Arc::new(init_audit_logger(&self.config.audit)?);
+        let redactor = Arc::new(init_redactor(&self.config.redaction)?);
+        let events = EventBus::new();
+        let audit_logger = Arc::new(
+            init_audit_logger(&self.config.audit)?
+                .with_redactor(Arc::clone(&redactor))
+                .with_events(events.clone()),
+        );
         let rate_limiter = Arc::new(RateLimiter::from_settings(&self.config.rate_limiting));
-        let agent_registry = AgentRegistry::from_catalog(self.config.agent_catalog());
+        let agent_registry = AgentRegistry::new().with_events(events.clone());
+        let sessions = SessionManager::from_settings(
+            &self.config.sessions,
+            self.config.storage.migrate_on_start,
+        )?;
+        let tools = ToolRegistry::with_builtins(&self.config.tools)?;
+        let auth = Arc::new(init_auth(&self.config.auth)?);
+        let tenants = TenantManager::new(self.config.tenancy.clone());
+        let usage = UsageMeter::new(self.config.metering.clone());
+        let cache = ResponseCache::new(self.config.cache.clone());
+        let storage = Repositories::from_settings(&self.config.storage)?;
+        let webhooks = WebhookRegistry::new(self.config.webhooks.clone(), storage.webhooks.clone());
+        for tool in self.tools {
+            tools.register(tool);
+        }
 
         let root_token = CancellationToken::new();
         let context = PlatformContext::new(
             self.config.clone(),
-            audit_logger,
-            rate_limiter,
-            agent_registry,
+            Components {
+                audit_logger,
+                rate_limiter,
+                agent_registry,
+                sessions,
+                tools,
+                redactor,
+                auth,
+                tenants,
+                usage,
+                cache,
+                orchestrator: OrchestratorHandle::new(),
+                storage,
+                events,
+                webhooks,
+            },
             root_token.child_token(),
         );
 
-        let mut tasks = Vec::new();
         let mut services = self.services;
+        services.push(agent_loader_service());
         services.push(orchestration_service(context.agents()));
+        services.push(session_retention_service());
+        services.push(webhook_service());
+        let services = order_by_dependencies(services)?;
+
+        let mut runtime = PlatformRuntime {
+            context: context.clone(),
+            cancel_token: root_token.clone(),
+            tasks: Vec::new(),
+        };
 
+        // Start in dependency order, gating each service on its own readiness.
         for service in services {
-            let handle = service.spawn(context.clone(), root_token.child_token());
-            tasks.push((service.name().to_string(), handle));
+            let name = service.name().to_string();
+            let timeout = service.readiness_timeout();
+            let stop = root_token.child_token();
+            let handle = supervise(service, context.clone(), stop.clone(), root_token.clone());
+            runtime.tasks.push(SupervisedService {
+                name: name.clone(),
+                stop,
+                handle,
+            });
+
+            let ready = tokio::time::timeout(timeout, context.services().wait_ready(&name))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(anyhow::anyhow!(
+                        "service {} not ready within {:?}",
+                        name,
+                        timeout
+                    ))
+                });
+            if let Err(err) = ready {
+                error!(service = %name, error = %format!("{:#}", err), "platform boot aborted");
+                if let Err(shutdown_err) = runtime.shutdown().await {
+                    warn!(error = %format!("{:#}", shutdown_err), "errors while unwinding boot");
+                }
+                return Err(err.context("platform failed to start"));
+            }
         }
 
         info!("platform boot completed");
 
-        Ok(PlatformRuntime {
-            context,
-            cancel_token: root_token,
-            tasks,
-        })
+        Ok(runtime)
     }
 }
 
+struct SupervisedService {
+    name: String,
+    stop: CancellationToken,
+    handle: JoinHandle<Result<()>>,
+}
+
 pub struct PlatformRuntime {
     context: PlatformContext,
     cancel_token: CancellationToken,
-    tasks: Vec<(String, JoinHandle<Result<()>>)>,
+    tasks: Vec<SupervisedService>,
 }
 
 impl PlatformRuntime {
@@ -77,30 +185,175 @@ impl PlatformRuntime {
         self.context.clone()
     }
 
+    /// Resolves once shutdown has been requested, including by a failed critical service.
+    pub async fn cancelled(&self) {
+        self.cancel_token.cancelled().await
+    }
+
     pub async fn shutdown(self) -> Result<()> {
-        self.cancel_token.cancel();
+        self.shutdown_with_timeout(DEFAULT_SHUTDOWN_TIMEOUT).await
+    }
+
+    /// Stops taking new work, gives in-flight requests and orchestrator tasks
+    /// until `[drain] deadline_seconds` to finish, marks the process's agents
+    /// offline and shuts down. Tasks still running are handed back to the queue.
+    pub async fn drain(self) -> Result<()> {
+        let context = self.context();
+        let deadline = Duration::from_secs(context.config().drain.deadline_seconds);
+        context.begin_drain();
+        info!(?deadline, "draining");
+
+        if tokio::time::timeout(deadline, wait_for_idle(&context))
+            .await
+            .is_err()
+        {
+            warn!(
+                requests = context.in_flight().count(),
+                "drain deadline passed, handing back unfinished work"
+            );
+        }
 
-        for (name, handle) in self.tasks {
-            match handle.await {
-                Ok(Ok(())) => info!(service = %name, "service shutdown cleanly"),
+        let registry = context.agents();
+        for agent in registry.list_agents() {
+            registry.set_status(&agent.id, AgentStatus::Offline);
+        }
+        self.shutdown().await
+    }
+
+    /// Stops services in reverse dependency order and waits for all of them,
+    /// aborting whatever is still running once `timeout` has elapsed.
+    pub async fn shutdown_with_timeout(self, timeout: Duration) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut failures = Vec::new();
+
+        for SupervisedService {
+            name,
+            stop,
+            mut handle,
+        } in self.tasks.into_iter().rev()
+        {
+            stop.cancel();
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Ok(Ok(()))) => info!(service = %name, "service shutdown cleanly"),
+                Ok(Ok(Err(err))) => {
+                    error!(service = %name, error = %format!("{:#}", err), "service failed");
+                    failures.push(format!("{}: {:#}", name, err));
+                }
                 Ok(Err(err)) => {
-                    return Err(err);
+                    error!(service = %name, error = %err, "service supervisor crashed");
+                    failures.push(format!("{}: {}", name, err));
                 }
-                Err(err) => {
-                    return Err(err.into());
+                Err(_) => {
+                    warn!(service = %name, ?timeout, "service did not stop in time, aborting");
+                    handle.abort();
+                    failures.push(format!("{}: did not stop within {:?}", name, timeout));
                 }
             }
         }
 
-        Ok(())
+        self.cancel_token.cancel();
+
+        if let Some(reason) = self.context.services().escalation() {
+            failures.insert(0, reason);
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "platform shutdown with failures:\n  {}",
+                failures.join("\n  ")
+            ))
+        }
+    }
+}
+
+/// Resolves once no request is being served and no orchestrator task is running.
+async fn wait_for_idle(context: &PlatformContext) {
+    context.in_flight().wait_idle().await;
+    loop {
+        let running = match context.orchestrator().list_tasks().await {
+            Ok(tasks) => tasks
+                .iter()
+                .filter(|task| matches!(task.status, TaskStatus::Assigned | TaskStatus::InProgress))
+                .count(),
+            // Without an orchestrator there is nothing to wait for.
+            Err(_) => 0,
+        };
+        if running == 0 {
+            return;
+        }
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+    }
+}
+
+/// Orders services so each one starts after everything it depends on,
+/// keeping registration order where there is no constraint.
+fn order_by_dependencies(services: Vec<ServiceRegistration>) -> Result<Vec<ServiceRegistration>> {
+    let names: Vec<String> = services.iter().map(|s| s.name().to_string()).collect();
+    for service in &services {
+        for dependency in service.dependencies() {
+            if !names.contains(dependency) {
+                anyhow::bail!(
+                    "service {} depends on unknown service {}",
+                    service.name(),
+                    dependency
+                );
+            }
+        }
+    }
+
+    let mut pending: Vec<Option<ServiceRegistration>> = services.into_iter().map(Some).collect();
+    let mut started: Vec<String> = Vec::new();
+    let mut ordered = Vec::with_capacity(pending.len());
+
+    while ordered.len() < pending.len() {
+        let next = pending.iter().position(|slot| {
+            slot.as_ref().is_some_and(|service| {
+                service
+                    .dependencies()
+                    .iter()
+                    .all(|dependency| started.contains(dependency))
+            })
+        });
+
+        let Some(index) = next else {
+            let blocked: Vec<&str> = pending
+                .iter()
+                .flatten()
+                .map(|service| service.name())
+                .collect();
+            anyhow::bail!("dependency cycle between services: {}", blocked.join(", "));
+        };
+
+        let service = pending[index].take().expect("slot checked above");
+        started.push(service.name().to_string());
+        ordered.push(service);
     }
+
+    Ok(ordered)
 }
 
 fn initialize_logging(config: &PlatformConfig) {
-    let filter = &config.observability.log_level;
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(filter.as_str())
-        .try_init();
+    let filter = EnvFilter::new(config.observability.log_level.as_str());
+    let (filter, handle) = reload::Layer::new(filter);
+    let installed = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .try_init()
+        .is_ok();
+
+    if installed {
+        let _ = LOG_FILTER.set(handle);
+    }
+}
+
+/// Swap the active log filter. A no-op when another subscriber owns the process.
+pub(crate) fn set_log_level(level: &str) -> Result<()> {
+    if let Some(handle) = LOG_FILTER.get() {
+        handle.reload(EnvFilter::try_new(level)?)?;
+    }
+    Ok(())
 }
 
 fn init_audit_logger(settings: &AuditSettings) -> Result<AuditLogger> {
@@ -108,9 +361,36 @@ fn init_audit_logger(settings: &AuditSettings) -> Result<AuditLogger> {
         .map_err(|err| anyhow::anyhow!(err.to_string()))
 }
 
+fn init_redactor(settings: &RedactionSettings) -> Result<Redactor> {
+    let key = RedactionKey::from_env(&settings.key_env);
+    let redactor =
+        Redactor::from_settings(settings, key.clone().unwrap_or_else(RedactionKey::generate))?;
+    if key.is_none() && redactor.uses_key() {
+        warn!(
+            env = %settings.key_env,
+            "no redaction key set; hashes and tokens will change after a restart"
+        );
+    }
+    Ok(redactor)
+}
+
+fn init_auth(settings: &AuthSettings) -> Result<Authenticator> {
+    let auth = Authenticator::from_settings(settings)?;
+    if !auth.is_enabled() {
+        warn!("authentication is disabled; every request runs as an anonymous admin");
+    }
+    Ok(auth)
+}
+
 impl RateLimiter {
     pub fn from_settings(settings: &RateLimitingSettings) -> Self {
-        use crate::rate_limiting::{RateLimit, RateLimitConfig};
+        RateLimiter::new(RateLimitConfig::from_settings(settings))
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_settings(settings: &RateLimitingSettings) -> Self {
+        use crate::rate_limiting::RateLimit;
         use std::time::Duration;
 
         let default = RateLimit {
@@ -132,10 +412,141 @@ impl RateLimiter {
             })
             .collect();
 
-        RateLimiter::new(RateLimitConfig {
+        RateLimitConfig {
             default,
             endpoints,
             burst_limit: settings.default.burst,
-        })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle_service(name: &str) -> ServiceRegistration {
+        ServiceRegistration::new(
+            name,
+            Arc::new(|_ctx, token: CancellationToken| {
+                tokio::spawn(async move {
+                    token.cancelled().await;
+                    Ok(())
+                })
+            }),
+        )
+    }
+
+    fn quiet_config(audit_dir: &tempfile::TempDir) -> PlatformConfig {
+        let mut config = PlatformConfig::default();
+        config.observability.enable_metrics = false;
+        config.audit.log_path = audit_dir.path().join("audit.log").display().to_string();
+        config
+    }
+
+    #[test]
+    fn services_are_ordered_by_dependencies() {
+        let services = vec![
+            idle_service("api").depends_on(["cache", "db"]),
+            idle_service("cache").depends_on(["db"]),
+            idle_service("db"),
+            idle_service("metrics"),
+        ];
+
+        let ordered: Vec<String> = order_by_dependencies(services)
+            .unwrap()
+            .iter()
+            .map(|s| s.name().to_string())
+            .collect();
+        assert_eq!(ordered, vec!["db", "cache", "api", "metrics"]);
+    }
+
+    #[test]
+    fn dependency_cycles_and_unknown_services_are_rejected() {
+        let cycle = vec![
+            idle_service("a").depends_on(["b"]),
+            idle_service("b").depends_on(["a"]),
+        ];
+        let err = order_by_dependencies(cycle).err().unwrap();
+        assert!(err.to_string().contains("cycle"));
+
+        let unknown = vec![idle_service("a").depends_on(["ghost"])];
+        let err = order_by_dependencies(unknown).err().unwrap();
+        assert!(err.to_string().contains("unknown service ghost"));
+    }
+
+    #[tokio::test]
+    async fn boot_fails_when_a_service_never_becomes_ready() {
+        let audit_dir = tempfile::tempdir().unwrap();
+        let mut platform = Platform::new(quiet_config(&audit_dir));
+        platform.register_service(
+            idle_service("never-ready")
+                .with_readiness(Arc::new(|_ctx| Box::pin(std::future::pending())))
+                .with_readiness_timeout(Duration::from_millis(10)),
+        );
+
+        let err = platform.start().await.err().expect("boot should fail");
+        assert!(format!("{:#}", err).contains("never-ready"));
+    }
+
+    #[tokio::test]
+    async fn orchestrator_waits_for_agents_to_load() {
+        let audit_dir = tempfile::tempdir().unwrap();
+        let mut config = quiet_config(&audit_dir);
+        config.agents.insert(
+            "coder".to_string(),
+            crate::platform::config::AgentSettings::default(),
+        );
+
+        let runtime = Platform::new(config).start().await.unwrap();
+        let context = runtime.context();
+
+        assert!(context.services().is_ready());
+        assert_eq!(context.agents().list_agents().len(), 1);
+        runtime.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn shutdown_drains_every_service_and_reports_failures() {
+        let audit_dir = tempfile::tempdir().unwrap();
+        let mut platform = Platform::new(quiet_config(&audit_dir));
+        // Stopped last, so the services registered after it get to drain first.
+        platform.register_service(ServiceRegistration::new(
+            "stuck",
+            Arc::new(|_ctx, _token| {
+                tokio::spawn(async move {
+                    std::future::pending::<()>().await;
+                    Ok(())
+                })
+            }),
+        ));
+        platform.register_service(
+            ServiceRegistration::new(
+                "fails-on-shutdown",
+                Arc::new(|_ctx, token: CancellationToken| {
+                    tokio::spawn(async move {
+                        token.cancelled().await;
+                        anyhow::bail!("flush failed")
+                    })
+                }),
+            )
+            .with_restart_policy(crate::platform::RestartPolicy::never()),
+        );
+
+        let runtime = platform.start().await.unwrap();
+        let context = runtime.context();
+        tokio::task::yield_now().await;
+
+        let err = runtime
+            .shutdown_with_timeout(Duration::from_millis(100))
+            .await
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("fails-on-shutdown: flush failed"));
+        assert!(err.contains("stuck: did not stop"));
+        assert_eq!(
+            context.services().get("orchestrator").unwrap().state,
+            crate::platform::ServiceState::Stopped
+        );
     }
 }
@@ -1,4 +1,7 @@
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use tokio::task::JoinHandle;
@@ -10,9 +13,99 @@ pub type ServiceSpawner = Arc<
     dyn Fn(PlatformContext, CancellationToken) -> JoinHandle<Result<()>> + Send + Sync + 'static,
 >;
 
+pub type ReadinessFuture = Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>;
+
+/// Resolves once a freshly spawned service is able to take work.
+pub type ReadinessProbe = Arc<dyn Fn(PlatformContext) -> ReadinessFuture + Send + Sync + 'static>;
+
+const DEFAULT_READINESS_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartMode {
+    Never,
+    OnFailure,
+    Always,
+}
+
+/// How the supervisor reacts when a service task exits before shutdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Restarts allowed inside `window` before the service is marked failed.
+    pub max_restarts: u32,
+    pub window: Duration,
+}
+
+impl RestartPolicy {
+    pub fn never() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            ..Self::default()
+        }
+    }
+
+    pub fn on_failure() -> Self {
+        Self::default()
+    }
+
+    pub fn always() -> Self {
+        Self {
+            mode: RestartMode::Always,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn with_max_restarts(mut self, max_restarts: u32, window: Duration) -> Self {
+        self.max_restarts = max_restarts;
+        self.window = window;
+        self
+    }
+
+    pub(crate) fn should_restart(&self, failed: bool) -> bool {
+        match self.mode {
+            RestartMode::Never => false,
+            RestartMode::OnFailure => failed,
+            RestartMode::Always => true,
+        }
+    }
+
+    /// Exponential backoff for the `attempt`-th restart inside the current window.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::OnFailure,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            max_restarts: 5,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
 pub struct ServiceRegistration {
     name: String,
     spawner: ServiceSpawner,
+    restart_policy: RestartPolicy,
+    critical: bool,
+    dependencies: Vec<String>,
+    readiness: Option<ReadinessProbe>,
+    readiness_timeout: Duration,
 }
 
 impl ServiceRegistration {
@@ -20,13 +113,74 @@ impl ServiceRegistration {
         Self {
             name: name.into(),
             spawner,
+            restart_policy: RestartPolicy::default(),
+            critical: false,
+            dependencies: Vec::new(),
+            readiness: None,
+            readiness_timeout: DEFAULT_READINESS_TIMEOUT,
         }
     }
 
+    /// Services that must be ready before this one starts, and stop after it.
+    pub fn depends_on<I, S>(mut self, dependencies: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.dependencies
+            .extend(dependencies.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_readiness(mut self, probe: ReadinessProbe) -> Self {
+        self.readiness = Some(probe);
+        self
+    }
+
+    pub fn with_readiness_timeout(mut self, timeout: Duration) -> Self {
+        self.readiness_timeout = timeout;
+        self
+    }
+
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Shut the whole platform down if this service fails for good.
+    pub fn critical(mut self) -> Self {
+        self.critical = true;
+        self
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    pub fn restart_policy(&self) -> &RestartPolicy {
+        &self.restart_policy
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.critical
+    }
+
+    pub fn dependencies(&self) -> &[String] {
+        &self.dependencies
+    }
+
+    pub fn readiness_timeout(&self) -> Duration {
+        self.readiness_timeout
+    }
+
+    /// Services without a probe are ready as soon as they are spawned.
+    pub fn readiness(&self, context: PlatformContext) -> ReadinessFuture {
+        match &self.readiness {
+            Some(probe) => probe(context),
+            None => Box::pin(async { Ok(()) }),
+        }
+    }
+
     pub fn spawn(
         &self,
         context: PlatformContext,
@@ -35,3 +189,28 @@ impl ServiceRegistration {
         (self.spawner)(context, token)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_the_cap() {
+        let policy = RestartPolicy::on_failure()
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(500));
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(40), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn restart_modes() {
+        assert!(!RestartPolicy::never().should_restart(true));
+        assert!(RestartPolicy::on_failure().should_restart(true));
+        assert!(!RestartPolicy::on_failure().should_restart(false));
+        assert!(RestartPolicy::always().should_restart(false));
+    }
+}
@@ -0,0 +1,493 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::Result;
+use parking_lot::RwLock;
+use serde::Serialize;
+use tokio::sync::watch;
+use tokio::task::{JoinError, JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::platform::context::PlatformContext;
+use crate::platform::service::ServiceRegistration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ServiceState {
+    Starting,
+    Running,
+    /// Spawned, but its readiness probe failed or timed out.
+    NotReady,
+    Restarting,
+    Stopped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceHealth {
+    pub name: String,
+    pub state: ServiceState,
+    pub critical: bool,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+impl ServiceHealth {
+    /// Running services are ready; so are one-shot services that completed cleanly.
+    pub fn is_ready(&self) -> bool {
+        matches!(self.state, ServiceState::Running | ServiceState::Stopped)
+    }
+}
+
+/// Live view of every supervised service, shared through `PlatformContext`.
+#[derive(Clone)]
+pub struct ServiceHealthRegistry {
+    services: Arc<RwLock<HashMap<String, ServiceHealth>>>,
+    escalation: Arc<RwLock<Option<String>>>,
+    changes: Arc<watch::Sender<u64>>,
+}
+
+impl Default for ServiceHealthRegistry {
+    fn default() -> Self {
+        Self {
+            services: Arc::default(),
+            escalation: Arc::default(),
+            changes: Arc::new(watch::channel(0).0),
+        }
+    }
+}
+
+impl ServiceHealthRegistry {
+    pub fn get(&self, name: &str) -> Option<ServiceHealth> {
+        self.services.read().get(name).cloned()
+    }
+
+    pub fn list(&self) -> Vec<ServiceHealth> {
+        let mut services: Vec<_> = self.services.read().values().cloned().collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        services
+    }
+
+    /// True while no service has failed for good.
+    pub fn is_healthy(&self) -> bool {
+        self.services
+            .read()
+            .values()
+            .all(|service| service.state != ServiceState::Failed)
+    }
+
+    /// True once every registered service has become ready.
+    pub fn is_ready(&self) -> bool {
+        self.services.read().values().all(ServiceHealth::is_ready)
+    }
+
+    /// Waits until `name` is ready, failing as soon as it can no longer become ready.
+    pub async fn wait_ready(&self, name: &str) -> Result<()> {
+        let mut changes = self.changes.subscribe();
+        loop {
+            let health = self
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("unknown service {}", name))?;
+            match health.state {
+                _ if health.is_ready() => return Ok(()),
+                ServiceState::NotReady | ServiceState::Failed => anyhow::bail!(
+                    "service {} is not ready: {}",
+                    name,
+                    health.last_error.as_deref().unwrap_or("unknown error")
+                ),
+                _ => {}
+            }
+            changes.changed().await?;
+        }
+    }
+
+    /// The failure that forced the platform to shut down, if any.
+    pub fn escalation(&self) -> Option<String> {
+        self.escalation.read().clone()
+    }
+
+    fn register(&self, name: &str, critical: bool) {
+        self.services.write().insert(
+            name.to_string(),
+            ServiceHealth {
+                name: name.to_string(),
+                state: ServiceState::Starting,
+                critical,
+                restarts: 0,
+                last_error: None,
+            },
+        );
+    }
+
+    fn update<F: FnOnce(&mut ServiceHealth)>(&self, name: &str, apply: F) {
+        if let Some(service) = self.services.write().get_mut(name) {
+            apply(service);
+        }
+        self.changes.send_modify(|version| *version += 1);
+    }
+
+    fn set_state(&self, name: &str, state: ServiceState) {
+        self.update(name, |service| service.state = state);
+    }
+
+    fn escalate(&self, reason: String) {
+        let mut escalation = self.escalation.write();
+        if escalation.is_none() {
+            *escalation = Some(reason);
+        }
+    }
+}
+
+/// Runs `service` under its restart policy until `stop` is cancelled or it
+/// fails for good.
+///
+/// A terminal failure of a critical service cancels `root`, taking the whole
+/// platform down with it.
+pub(crate) fn supervise(
+    service: ServiceRegistration,
+    context: PlatformContext,
+    stop: CancellationToken,
+    root: CancellationToken,
+) -> JoinHandle<Result<()>> {
+    let health = context.services();
+    health.register(service.name(), service.is_critical());
+
+    tokio::spawn(async move {
+        let name = service.name().to_string();
+        let policy = service.restart_policy().clone();
+        let mut recent_restarts: VecDeque<Instant> = VecDeque::new();
+
+        loop {
+            health.set_state(&name, ServiceState::Starting);
+            let mut task = AbortOnDrop(service.spawn(context.clone(), stop.child_token()));
+            let failure = run_until_exit(&service, &context, &mut task).await;
+
+            if stop.is_cancelled() {
+                return finish(&health, &name, failure);
+            }
+
+            match &failure {
+                Some(reason) => warn!(service = %name, %reason, "service failed"),
+                None => info!(service = %name, "service exited"),
+            }
+
+            if !policy.should_restart(failure.is_some()) {
+                return terminate(&health, &root, &service, failure);
+            }
+
+            let now = Instant::now();
+            while recent_restarts
+                .front()
+                .is_some_and(|started| now.duration_since(*started) > policy.window)
+            {
+                recent_restarts.pop_front();
+            }
+            if recent_restarts.len() as u32 >= policy.max_restarts {
+                let reason = format!(
+                    "restarted {} times within {:?}; last error: {}",
+                    recent_restarts.len(),
+                    policy.window,
+                    failure.as_deref().unwrap_or("exited cleanly")
+                );
+                return terminate(&health, &root, &service, Some(reason));
+            }
+            recent_restarts.push_back(now);
+
+            let delay = policy.backoff(recent_restarts.len() as u32);
+            health.update(&name, |entry| {
+                entry.state = ServiceState::Restarting;
+                entry.restarts += 1;
+                if failure.is_some() {
+                    entry.last_error = failure.clone();
+                }
+            });
+            info!(service = %name, delay_ms = delay.as_millis() as u64, "restarting service");
+
+            tokio::select! {
+                _ = stop.cancelled() => return finish(&health, &name, failure),
+                _ = tokio::time::sleep(delay) => {}
+            }
+        }
+    })
+}
+
+/// Waits for the task to exit, flipping the service to running once its
+/// readiness probe passes.
+async fn run_until_exit(
+    service: &ServiceRegistration,
+    context: &PlatformContext,
+    task: &mut AbortOnDrop,
+) -> Option<String> {
+    let health = context.services();
+    let timeout = service.readiness_timeout();
+    let probe = service.readiness(context.clone());
+    let readiness = async move {
+        match tokio::time::timeout(timeout, probe).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(err)) => Err(format!("readiness probe failed: {:#}", err)),
+            Err(_) => Err(format!("not ready within {:?}", timeout)),
+        }
+    };
+    tokio::pin!(readiness);
+    let mut probing = true;
+
+    loop {
+        tokio::select! {
+            outcome = &mut task.0 => return describe_exit(outcome),
+            result = &mut readiness, if probing => {
+                probing = false;
+                match result {
+                    Ok(()) => health.set_state(service.name(), ServiceState::Running),
+                    Err(reason) => {
+                        warn!(service = %service.name(), %reason, "service not ready");
+                        health.update(service.name(), |entry| {
+                            entry.state = ServiceState::NotReady;
+                            entry.last_error = Some(reason);
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn finish(health: &ServiceHealthRegistry, name: &str, failure: Option<String>) -> Result<()> {
+    match failure {
+        None => {
+            health.set_state(name, ServiceState::Stopped);
+            Ok(())
+        }
+        Some(reason) => {
+            health.update(name, |entry| {
+                entry.state = ServiceState::Failed;
+                entry.last_error = Some(reason.clone());
+            });
+            Err(anyhow::anyhow!(reason))
+        }
+    }
+}
+
+fn terminate(
+    health: &ServiceHealthRegistry,
+    root: &CancellationToken,
+    service: &ServiceRegistration,
+    failure: Option<String>,
+) -> Result<()> {
+    let name = service.name();
+    let Some(reason) = failure else {
+        health.set_state(name, ServiceState::Stopped);
+        return Ok(());
+    };
+
+    error!(service = %name, %reason, "service failed permanently");
+    if service.is_critical() {
+        error!(service = %name, "critical service failed, shutting down platform");
+        health.escalate(format!("critical service {} failed: {}", name, reason));
+        root.cancel();
+    }
+
+    finish(health, name, Some(reason))
+}
+
+fn describe_exit(outcome: std::result::Result<Result<()>, JoinError>) -> Option<String> {
+    match outcome {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(format!("{:#}", err)),
+        Err(err) if err.is_panic() => {
+            let payload = err.into_panic();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic payload".to_string());
+            Some(format!("panicked: {}", message))
+        }
+        Err(err) => Some(err.to_string()),
+    }
+}
+
+/// Keeps a service task from outliving its supervisor when shutdown times out.
+struct AbortOnDrop(JoinHandle<Result<()>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::service::RestartPolicy;
+    use crate::platform::test_support::test_context;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    fn counting_service(
+        name: &str,
+        attempts: Arc<AtomicU32>,
+        outcome: fn(u32) -> Result<()>,
+    ) -> ServiceRegistration {
+        ServiceRegistration::new(
+            name,
+            Arc::new(move |_ctx, _token| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                tokio::spawn(async move { outcome(attempt) })
+            }),
+        )
+    }
+
+    fn fast_policy(max_restarts: u32) -> RestartPolicy {
+        RestartPolicy::on_failure()
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(4))
+            .with_max_restarts(max_restarts, Duration::from_secs(60))
+    }
+
+    #[tokio::test]
+    async fn failing_service_is_restarted_until_it_succeeds() {
+        let (context, _audit) = test_context();
+        let attempts = Arc::new(AtomicU32::new(0));
+        let service = counting_service("flaky", attempts.clone(), |attempt| {
+            if attempt < 3 {
+                anyhow::bail!("attempt {} failed", attempt)
+            }
+            Ok(())
+        })
+        .with_restart_policy(fast_policy(5));
+
+        let handle = supervise(
+            service,
+            context.clone(),
+            CancellationToken::new(),
+            CancellationToken::new(),
+        );
+        assert!(handle.await.unwrap().is_ok());
+
+        let health = context.services().get("flaky").unwrap();
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(health.restarts, 2);
+        assert_eq!(health.state, ServiceState::Stopped);
+        assert_eq!(health.last_error.as_deref(), Some("attempt 2 failed"));
+    }
+
+    #[tokio::test]
+    async fn critical_service_exhausting_restarts_escalates() {
+        let (context, _audit) = test_context();
+        let root = CancellationToken::new();
+        let attempts = Arc::new(AtomicU32::new(0));
+        let service = counting_service("core", attempts.clone(), |_| {
+            anyhow::bail!("address in use")
+        })
+        .with_restart_policy(fast_policy(2))
+        .critical();
+
+        let handle = supervise(service, context.clone(), root.child_token(), root.clone());
+        assert!(handle.await.unwrap().is_err());
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert!(root.is_cancelled());
+        assert_eq!(
+            context.services().get("core").unwrap().state,
+            ServiceState::Failed
+        );
+        assert!(context.services().escalation().unwrap().contains("core"));
+    }
+
+    #[tokio::test]
+    async fn panics_are_reported_as_failures() {
+        let (context, _audit) = test_context();
+        let service = ServiceRegistration::new(
+            "panicky",
+            Arc::new(|_ctx, _token| tokio::spawn(async { panic!("boom") })),
+        )
+        .with_restart_policy(RestartPolicy::never());
+
+        let handle = supervise(
+            service,
+            context.clone(),
+            CancellationToken::new(),
+            CancellationToken::new(),
+        );
+        let err = handle.await.unwrap().unwrap_err();
+
+        assert_eq!(err.to_string(), "panicked: boom");
+        assert!(!context.services().is_healthy());
+    }
+
+    #[tokio::test]
+    async fn readiness_probe_gates_running_state() {
+        let (context, _audit) = test_context();
+        let (ready_tx, ready_rx) = tokio::sync::oneshot::channel::<()>();
+        let ready_rx = Arc::new(parking_lot::Mutex::new(Some(ready_rx)));
+        let service = ServiceRegistration::new(
+            "gated",
+            Arc::new(|_ctx, token: CancellationToken| {
+                tokio::spawn(async move {
+                    token.cancelled().await;
+                    Ok(())
+                })
+            }),
+        )
+        .with_readiness(Arc::new(move |_ctx| {
+            let ready_rx = ready_rx.lock().take();
+            Box::pin(async move {
+                if let Some(ready_rx) = ready_rx {
+                    ready_rx.await?;
+                }
+                Ok(())
+            })
+        }));
+
+        let stop = CancellationToken::new();
+        let handle = supervise(
+            service,
+            context.clone(),
+            stop.clone(),
+            CancellationToken::new(),
+        );
+        tokio::task::yield_now().await;
+        assert_eq!(
+            context.services().get("gated").unwrap().state,
+            ServiceState::Starting
+        );
+        assert!(!context.services().is_ready());
+
+        ready_tx.send(()).unwrap();
+        context.services().wait_ready("gated").await.unwrap();
+        assert!(context.services().is_ready());
+
+        stop.cancel();
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn readiness_timeout_marks_service_not_ready() {
+        let (context, _audit) = test_context();
+        let service = ServiceRegistration::new(
+            "slow",
+            Arc::new(|_ctx, token: CancellationToken| {
+                tokio::spawn(async move {
+                    token.cancelled().await;
+                    Ok(())
+                })
+            }),
+        )
+        .with_readiness(Arc::new(|_ctx| Box::pin(std::future::pending())))
+        .with_readiness_timeout(Duration::from_millis(10));
+
+        let stop = CancellationToken::new();
+        let handle = supervise(
+            service,
+            context.clone(),
+            stop.clone(),
+            CancellationToken::new(),
+        );
+
+        let err = context.services().wait_ready("slow").await.unwrap_err();
+        assert!(err.to_string().contains("not ready within"));
+
+        stop.cancel();
+        handle.await.unwrap().unwrap();
+    }
+}
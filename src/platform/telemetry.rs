@@ -1,39 +1,97 @@
 use std::net::SocketAddr;
+use std::os::fd::RawFd;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::middleware::from_fn_with_state;
+use axum::response::IntoResponse;
 use axum::routing::get;
-use axum::Router;
+use axum::{Json, Router};
+use tokio::net::TcpListener;
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
 use tracing::info;
 
+use crate::auth::{self, Permission, Principal, Role};
+use crate::metering::UsageQuery;
+use crate::platform::admin::admin_routes;
 use crate::platform::config::ObservabilitySettings;
+use crate::platform::context::PlatformContext;
+use crate::platform::drain;
 use crate::platform::service::ServiceRegistration;
+use crate::platform::webhooks::webhook_routes;
 
 pub fn telemetry_service(settings: ObservabilitySettings) -> ServiceRegistration {
+    let probe_settings = settings.clone();
+    // Set once this run has bound its listener. Connecting to the port instead
+    // would succeed whenever any process holds it.
+    let bound = Arc::new(watch::channel(false).0);
+    let probe_bound = bound.clone();
     ServiceRegistration::new(
         "telemetry",
-        Arc::new(move |_ctx, token: CancellationToken| {
+        Arc::new(move |ctx, token: CancellationToken| {
             let settings = settings.clone();
+            let bound = bound.clone();
+            bound.send_replace(false);
             tokio::spawn(async move {
                 let outcome: Result<()> = async {
                     if !settings.enable_metrics {
                         return Ok(());
                     }
 
-                    let app = Router::new().route("/metrics", get(metrics_handler));
+                    let reuse_port = ctx.config().drain.reuse_port;
+                    let app = Router::new()
+                        .route(
+                            "/metrics",
+                            get(metrics_handler).route_layer(from_fn_with_state(
+                                Permission::ViewStatus,
+                                auth::require,
+                            )),
+                        )
+                        .route(
+                            "/tenants/usage",
+                            get(tenant_usage_handler).route_layer(from_fn_with_state(
+                                Permission::ViewStatus,
+                                auth::require,
+                            )),
+                        )
+                        .route(
+                            "/usage",
+                            get(usage_handler).route_layer(from_fn_with_state(
+                                Permission::ViewStatus,
+                                auth::require,
+                            )),
+                        )
+                        .route("/health", get(health_handler))
+                        .route("/ready", get(ready_handler))
+                        .merge(admin_routes())
+                        .merge(webhook_routes())
+                        .layer(from_fn_with_state(ctx.clone(), auth::authenticate))
+                        .with_state(ctx);
                     let addr = SocketAddr::from(([0, 0, 0, 0], settings.metrics_port));
                     let shutdown = token.clone();
 
-                    let listener = tokio::net::TcpListener::bind(addr).await?;
+                    let listener = telemetry_listener(
+                        addr,
+                        reuse_port,
+                        drain::take_telemetry_fd(),
+                        drain::in_handoff(),
+                    )
+                    .with_context(|| format!("failed to bind telemetry server on {}", addr))?;
+                    bound.send_replace(true);
                     info!(port = settings.metrics_port, "telemetry server started");
 
-                    axum::serve(listener, app)
-                        .with_graceful_shutdown(async move {
-                            shutdown.cancelled().await;
-                            info!("shutting down telemetry server");
-                        })
-                        .await?;
+                    axum::serve(
+                        listener,
+                        app.into_make_service_with_connect_info::<SocketAddr>(),
+                    )
+                    .with_graceful_shutdown(async move {
+                        shutdown.cancelled().await;
+                        info!("shutting down telemetry server");
+                    })
+                    .await?;
 
                     Ok(())
                 }
@@ -43,8 +101,188 @@ pub fn telemetry_service(settings: ObservabilitySettings) -> ServiceRegistration
             })
         }),
     )
+    .with_readiness(Arc::new(move |_ctx| {
+        let enabled = probe_settings.enable_metrics;
+        let mut bound = probe_bound.subscribe();
+        Box::pin(async move {
+            if enabled {
+                bound.wait_for(|bound| *bound).await?;
+            }
+            Ok(())
+        })
+    }))
 }
 
-async fn metrics_handler() -> &'static str {
-    "# HELP chimera_agents_active Number of active agents\n# TYPE chimera_agents_active gauge\nchimera_agents_active 0\n# HELP chimera_requests_total Total number of requests processed\n# TYPE chimera_requests_total counter\nchimera_requests_total 0\n"
+/// During a handoff the draining process still holds the telemetry port, so
+/// serve on the socket it passed along or share the port with it.
+fn telemetry_listener(
+    addr: SocketAddr,
+    reuse_port: bool,
+    inherited: Option<RawFd>,
+    handoff: bool,
+) -> Result<TcpListener> {
+    match inherited {
+        Some(fd) => drain::inherit(fd),
+        None => drain::bind(addr, reuse_port || handoff),
+    }
+}
+
+/// Liveness: fails only once a service has failed for good.
+async fn health_handler(State(ctx): State<PlatformContext>) -> impl IntoResponse {
+    let services = ctx.services();
+    let status = if services.is_healthy() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(serde_json::json!({
+            "healthy": services.is_healthy(),
+            "services": services.list(),
+        })),
+    )
+}
+
+/// Readiness: every service has started and passed its readiness probe, and
+/// the node is not draining.
+async fn ready_handler(State(ctx): State<PlatformContext>) -> impl IntoResponse {
+    let services = ctx.services();
+    let ready = services.is_ready() && !ctx.is_draining();
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let pending: Vec<String> = services
+        .list()
+        .into_iter()
+        .filter(|service| !service.is_ready())
+        .map(|service| service.name)
+        .collect();
+    (
+        status,
+        Json(serde_json::json!({
+            "ready": ready,
+            "draining": ctx.is_draining(),
+            "pending": pending,
+        })),
+    )
+}
+
+/// Admins get every tenant's series; everyone else only their own tenant's,
+/// without the cache series, which add up traffic of all tenants.
+async fn metrics_handler(
+    State(ctx): State<PlatformContext>,
+    axum::Extension(principal): axum::Extension<Principal>,
+) -> String {
+    let mut body = String::from("# HELP chimera_agents_active Number of active agents\n# TYPE chimera_agents_active gauge\nchimera_agents_active 0\n# HELP chimera_requests_total Total number of requests processed\n# TYPE chimera_requests_total counter\nchimera_requests_total 0\n");
+    if principal.role == Role::Admin {
+        body.push_str(&ctx.tenants().export_prometheus(None));
+        body.push_str(&ctx.response_cache().export_prometheus());
+    } else {
+        body.push_str(&ctx.tenants().export_prometheus(Some(&principal.tenant)));
+    }
+    body
+}
+
+/// Per-tenant usage; admins see every tenant, everyone else only their own.
+async fn tenant_usage_handler(
+    State(ctx): State<PlatformContext>,
+    axum::Extension(principal): axum::Extension<Principal>,
+) -> impl IntoResponse {
+    let tenants = ctx.tenants();
+    let usage = if principal.role == Role::Admin {
+        tenants.export()
+    } else {
+        vec![tenants.usage(&principal.tenant)]
+    };
+    Json(serde_json::json!({ "tenants": usage }))
+}
+
+/// Metered inference usage and cost, e.g. `/usage?window=day&group_by=tenant`.
+/// Non-admins only see their own tenant.
+async fn usage_handler(
+    State(ctx): State<PlatformContext>,
+    axum::Extension(principal): axum::Extension<Principal>,
+    Query(mut query): Query<UsageQuery>,
+) -> impl IntoResponse {
+    if principal.role != Role::Admin {
+        query.tenant = Some(principal.tenant.clone());
+    }
+    Json(ctx.usage().report(&query))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::test_support::test_context;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn ready_only_once_its_own_listener_is_bound() {
+        let (context, _audit) = test_context();
+        let holder = std::net::TcpListener::bind("0.0.0.0:0").unwrap();
+        let port = holder.local_addr().unwrap().port();
+        let service = telemetry_service(ObservabilitySettings {
+            metrics_port: port,
+            ..ObservabilitySettings::default()
+        });
+
+        // Someone else answering on the port does not make this service ready.
+        let probe = service.readiness(context.clone());
+        assert!(tokio::time::timeout(Duration::from_millis(100), probe)
+            .await
+            .is_err());
+
+        drop(holder);
+        let token = CancellationToken::new();
+        let running = service.spawn(context.clone(), token.clone());
+        tokio::time::timeout(Duration::from_secs(5), service.readiness(context))
+            .await
+            .unwrap()
+            .unwrap();
+
+        token.cancel();
+        running.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn replacement_takes_over_the_port_during_a_handoff() {
+        use std::os::fd::IntoRawFd;
+
+        let draining = drain::bind("127.0.0.1:0".parse().unwrap(), true).unwrap();
+        let addr = draining.local_addr().unwrap();
+        assert!(telemetry_listener(addr, false, None, false).is_err());
+        let shared = telemetry_listener(addr, false, None, true).unwrap();
+        assert_eq!(shared.local_addr().unwrap(), addr);
+
+        let passed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let passed_addr = passed.local_addr().unwrap();
+        let inherited = telemetry_listener(addr, false, Some(passed.into_raw_fd()), true).unwrap();
+        assert_eq!(inherited.local_addr().unwrap(), passed_addr);
+    }
+
+    #[tokio::test]
+    async fn metrics_only_show_other_tenants_to_admins() {
+        let (context, _audit) = test_context();
+        context.tenants().record_tokens("search", 5);
+        context.tenants().record_tokens("ads", 7);
+        let scrape = |role: Role| {
+            let principal = Principal {
+                tenant: "search".to_string(),
+                ..Principal::anonymous(role, None)
+            };
+            metrics_handler(State(context.clone()), axum::Extension(principal))
+        };
+
+        let own = scrape(Role::ReadOnly).await;
+        assert!(own.contains("chimera_tenant_tokens_total{tenant=\"search\"} 5"));
+        assert!(!own.contains("tenant=\"ads\""));
+        assert!(!own.contains("chimera_cache_hits_total"));
+
+        let all = scrape(Role::Admin).await;
+        assert!(all.contains("chimera_tenant_tokens_total{tenant=\"ads\"} 7"));
+        assert!(all.contains("chimera_cache_hits_total"));
+    }
 }
@@ -0,0 +1,580 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::auth::KeyBackend;
+use crate::platform::config::{PlatformConfig, RateLimitRule, SessionBackend};
+use crate::prompts::PromptLibrary;
+use crate::storage::StorageBackend;
+use crate::utils::BuiltinDetector;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub path: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl ConfigIssue {
+    fn new<P: Into<String>, M: Into<String>>(path: P, message: M) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    fn locate(mut self, source: &str, span: Option<Range<usize>>) -> Self {
+        if let Some(span) = span {
+            let (line, column) = line_column(source, span.start);
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "{}:{}: ", line, column)?;
+        }
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigValidationError {
+    pub origin: Option<String>,
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl ConfigValidationError {
+    pub fn new(issues: Vec<ConfigIssue>) -> Self {
+        Self {
+            origin: None,
+            issues,
+        }
+    }
+
+    pub fn with_origin<O: Into<String>>(mut self, origin: O) -> Self {
+        self.origin = Some(origin.into());
+        self
+    }
+}
+
+impl fmt::Display for ConfigValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.origin {
+            Some(origin) => write!(f, "invalid platform configuration in {}", origin)?,
+            None => write!(f, "invalid platform configuration")?,
+        }
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigValidationError {}
+
+/// Parses `source` rejecting unknown keys, then runs the range checks with
+/// every issue mapped back to its position in the file.
+pub(crate) fn parse_strict(source: &str) -> Result<PlatformConfig, ConfigValidationError> {
+    let config: PlatformConfig = toml::from_str(source).map_err(|err| {
+        let issue = ConfigIssue::new("", err.message().to_string()).locate(source, err.span());
+        ConfigValidationError::new(vec![issue])
+    })?;
+
+    let issues = check_ranges(&config);
+    if issues.is_empty() {
+        return Ok(config);
+    }
+
+    let document = toml_edit::ImDocument::parse(source).ok();
+    let issues = issues
+        .into_iter()
+        .map(|issue| {
+            let span = document
+                .as_ref()
+                .and_then(|doc| span_of(doc.as_table(), &issue.path));
+            issue.locate(source, span)
+        })
+        .collect();
+    Err(ConfigValidationError::new(issues))
+}
+
+pub(crate) fn check_ranges(config: &PlatformConfig) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+
+    if config.metadata.name.trim().is_empty() {
+        issues.push(ConfigIssue::new("metadata.name", "must not be empty"));
+    }
+
+    let observability = &config.observability;
+    if tracing_subscriber::EnvFilter::try_new(&observability.log_level).is_err() {
+        issues.push(ConfigIssue::new(
+            "observability.log_level",
+            format!("{:?} is not a valid log filter", observability.log_level),
+        ));
+    }
+    if observability.enable_metrics && observability.metrics_port == 0 {
+        issues.push(ConfigIssue::new(
+            "observability.metrics_port",
+            "must be between 1 and 65535",
+        ));
+    }
+
+    if config.audit.log_path.trim().is_empty() {
+        issues.push(ConfigIssue::new("audit.log_path", "must not be empty"));
+    }
+
+    check_rate_limit_rule(
+        "rate_limiting.default",
+        &config.rate_limiting.default,
+        &mut issues,
+    );
+    for (endpoint, rule) in &config.rate_limiting.endpoints {
+        check_rate_limit_rule(
+            &format!("rate_limiting.endpoints.{}", endpoint),
+            rule,
+            &mut issues,
+        );
+    }
+
+    for (name, agent) in &config.agents {
+        let prefix = format!("agents.{}", name);
+        check_temperature(&prefix, agent.temperature, &mut issues);
+        if agent.max_tokens == 0 {
+            issues.push(ConfigIssue::new(
+                format!("{}.max_tokens", prefix),
+                "must be greater than 0",
+            ));
+        }
+        if agent.max_concurrent_requests == 0 {
+            issues.push(ConfigIssue::new(
+                format!("{}.max_concurrent_requests", prefix),
+                "must be greater than 0",
+            ));
+        }
+        if agent.model_path.trim().is_empty() {
+            issues.push(ConfigIssue::new(
+                format!("{}.model_path", prefix),
+                "must not be empty",
+            ));
+        }
+        for (field, value) in [
+            ("max_messages", agent.memory.max_messages),
+            ("context_tokens", agent.memory.context_tokens),
+        ] {
+            if value == 0 {
+                issues.push(ConfigIssue::new(
+                    format!("{}.memory.{}", prefix, field),
+                    "must be greater than 0",
+                ));
+            }
+        }
+        let guardrail = &agent.guardrail;
+        if !(0.0..=1.0).contains(&guardrail.flag_threshold)
+            || !(0.0..=1.0).contains(&guardrail.block_threshold)
+            || guardrail.flag_threshold > guardrail.block_threshold
+        {
+            issues.push(ConfigIssue::new(
+                format!("{}.guardrail", prefix),
+                "thresholds must satisfy 0.0 <= flag_threshold <= block_threshold <= 1.0",
+            ));
+        }
+        if agent.tools.iter().any(|tool| tool == "http_get")
+            && config.tools.http_allowlist.is_empty()
+        {
+            issues.push(ConfigIssue::new(
+                format!("{}.tools", prefix),
+                "http_get needs at least one host in tools.http_allowlist",
+            ));
+        }
+        if let Some(endpoint) = &agent.endpoint {
+            if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) {
+                issues.push(ConfigIssue::new(
+                    format!("{}.endpoint", prefix),
+                    "must be an http:// or https:// URL",
+                ));
+            }
+        }
+    }
+
+    match PromptLibrary::from_settings(&config.prompts) {
+        Ok(library) => {
+            for (name, agent) in config.agent_catalog() {
+                if let Err(err) = library.check_agent(&agent) {
+                    issues.push(ConfigIssue::new(
+                        format!("agents.{}.prompt_template", name),
+                        err.to_string(),
+                    ));
+                }
+            }
+        }
+        Err(errors) => {
+            for (path, err) in errors {
+                issues.push(ConfigIssue::new(path, err.to_string()));
+            }
+        }
+    }
+
+    let inference = &config.inference;
+    check_temperature("inference", inference.temperature, &mut issues);
+    if !(inference.top_p > 0.0 && inference.top_p <= 1.0) {
+        issues.push(ConfigIssue::new(
+            "inference.top_p",
+            "must be greater than 0.0 and at most 1.0",
+        ));
+    }
+    if inference.repetition_penalty <= 0.0 {
+        issues.push(ConfigIssue::new(
+            "inference.repetition_penalty",
+            "must be greater than 0.0",
+        ));
+    }
+    if inference.max_tokens == 0 {
+        issues.push(ConfigIssue::new(
+            "inference.max_tokens",
+            "must be greater than 0",
+        ));
+    }
+    if inference.batch_size == 0 {
+        issues.push(ConfigIssue::new(
+            "inference.batch_size",
+            "must be greater than 0",
+        ));
+    }
+
+    if config.sessions.backend == SessionBackend::Sqlite
+        && config.sessions.sqlite_path.trim().is_empty()
+    {
+        issues.push(ConfigIssue::new(
+            "sessions.sqlite_path",
+            "must not be empty when backend is \"sqlite\"",
+        ));
+    }
+    if config.storage.backend == StorageBackend::Sqlite
+        && config.storage.sqlite_path.trim().is_empty()
+    {
+        issues.push(ConfigIssue::new(
+            "storage.sqlite_path",
+            "must not be empty when backend is \"sqlite\"",
+        ));
+    }
+
+    for (field, value) in [
+        ("timeout_ms", config.tools.timeout_ms),
+        ("max_steps", config.tools.max_steps as u64),
+    ] {
+        if value == 0 {
+            issues.push(ConfigIssue::new(
+                format!("tools.{}", field),
+                "must be greater than 0",
+            ));
+        }
+    }
+    if config
+        .tools
+        .http_allowlist
+        .iter()
+        .any(|host| host.trim().is_empty() || host.contains('/'))
+    {
+        issues.push(ConfigIssue::new(
+            "tools.http_allowlist",
+            "entries must be bare host names",
+        ));
+    }
+
+    let redaction = &config.redaction;
+    for (index, custom) in redaction.custom.iter().enumerate() {
+        let prefix = format!("redaction.custom.{}", index);
+        if custom.name.trim().is_empty() {
+            issues.push(ConfigIssue::new(
+                format!("{}.name", prefix),
+                "must not be empty",
+            ));
+        }
+        if let Err(err) = regex::Regex::new(&custom.pattern) {
+            issues.push(ConfigIssue::new(
+                format!("{}.pattern", prefix),
+                format!("invalid regex: {}", err),
+            ));
+        }
+    }
+    for name in redaction.actions.keys() {
+        let known = BuiltinDetector::ALL.iter().any(|d| d.name() == name)
+            || redaction.custom.iter().any(|c| &c.name == name);
+        if !known {
+            issues.push(ConfigIssue::new(
+                format!("redaction.actions.{}", name),
+                "does not name a detector",
+            ));
+        }
+    }
+
+    let auth = &config.auth;
+    let (store_field, store_path) = match auth.backend {
+        KeyBackend::File => ("keys_path", &auth.keys_path),
+        KeyBackend::Sqlite => ("sqlite_path", &auth.sqlite_path),
+    };
+    if store_path.trim().is_empty() {
+        issues.push(ConfigIssue::new(
+            format!("auth.{}", store_field),
+            "must not be empty for the selected backend",
+        ));
+    }
+    if auth.jwt.role_claim.trim().is_empty() {
+        issues.push(ConfigIssue::new("auth.jwt.role_claim", "must not be empty"));
+    }
+    if auth.public_paths.iter().any(|path| !path.starts_with('/')) {
+        issues.push(ConfigIssue::new(
+            "auth.public_paths",
+            "entries must be absolute paths starting with '/'",
+        ));
+    }
+
+    let tenancy = &config.tenancy;
+    for (prefix, quota) in
+        std::iter::once(("tenancy.default_quota".to_string(), &tenancy.default_quota)).chain(
+            tenancy
+                .tenants
+                .iter()
+                .map(|(tenant, quota)| (format!("tenancy.tenants.{}", tenant), quota)),
+        )
+    {
+        for (field, zero) in [
+            ("requests_per_minute", quota.requests_per_minute == Some(0)),
+            ("tokens_per_day", quota.tokens_per_day == Some(0)),
+            ("concurrent_tasks", quota.concurrent_tasks == Some(0)),
+            (
+                "training_gpu_hours_per_day",
+                quota
+                    .training_gpu_hours_per_day
+                    .is_some_and(|hours| hours <= 0.0),
+            ),
+        ] {
+            if zero {
+                issues.push(ConfigIssue::new(
+                    format!("{}.{}", prefix, field),
+                    "must be greater than 0; omit it for no limit",
+                ));
+            }
+        }
+    }
+
+    let metering = &config.metering;
+    if metering.retention_hours == 0 {
+        issues.push(ConfigIssue::new(
+            "metering.retention_hours",
+            "must be greater than 0",
+        ));
+    }
+    if metering.max_records == 0 {
+        issues.push(ConfigIssue::new(
+            "metering.max_records",
+            "must be greater than 0",
+        ));
+    }
+    for (path, price) in std::iter::once((
+        "metering.default_price".to_string(),
+        &metering.default_price,
+    ))
+    .chain(
+        metering
+            .prices
+            .iter()
+            .map(|(model, price)| (format!("metering.prices.{}", model), price)),
+    ) {
+        for (field, value) in [
+            ("prompt_per_1k", price.prompt_per_1k),
+            ("completion_per_1k", price.completion_per_1k),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                issues.push(ConfigIssue::new(
+                    format!("{}.{}", path, field),
+                    "must be a non-negative number",
+                ));
+            }
+        }
+    }
+
+    let cache = &config.cache;
+    for (field, zero) in [
+        ("ttl_seconds", cache.ttl_seconds == 0),
+        ("max_entries", cache.max_entries == 0),
+        ("similarity.dimensions", cache.similarity.dimensions == 0),
+    ] {
+        if zero {
+            issues.push(ConfigIssue::new(
+                format!("cache.{}", field),
+                "must be greater than 0",
+            ));
+        }
+    }
+    if !(cache.similarity.threshold > 0.0 && cache.similarity.threshold <= 1.0) {
+        issues.push(ConfigIssue::new(
+            "cache.similarity.threshold",
+            "must be greater than 0.0 and at most 1.0",
+        ));
+    }
+
+    if config.drain.deadline_seconds == 0 {
+        issues.push(ConfigIssue::new(
+            "drain.deadline_seconds",
+            "must be greater than 0",
+        ));
+    }
+
+    let webhooks = &config.webhooks;
+    for (field, zero) in [
+        ("max_attempts", webhooks.max_attempts == 0),
+        ("timeout_ms", webhooks.timeout_ms == 0),
+        ("delivery_log_size", webhooks.delivery_log_size == 0),
+    ] {
+        if zero {
+            issues.push(ConfigIssue::new(
+                format!("webhooks.{}", field),
+                "must be greater than 0",
+            ));
+        }
+    }
+    if webhooks.initial_backoff_ms > webhooks.max_backoff_ms {
+        issues.push(ConfigIssue::new(
+            "webhooks.initial_backoff_ms",
+            "must not exceed webhooks.max_backoff_ms",
+        ));
+    }
+
+    if config.training.learning_rate <= 0.0 {
+        issues.push(ConfigIssue::new(
+            "training.learning_rate",
+            "must be greater than 0.0",
+        ));
+    }
+    if config.training.num_epochs == 0 {
+        issues.push(ConfigIssue::new(
+            "training.num_epochs",
+            "must be greater than 0",
+        ));
+    }
+
+    issues
+}
+
+fn check_rate_limit_rule(prefix: &str, rule: &RateLimitRule, issues: &mut Vec<ConfigIssue>) {
+    for (field, value) in [
+        ("requests", rule.requests as u64),
+        ("window_seconds", rule.window_seconds),
+        ("burst", rule.burst as u64),
+    ] {
+        if value == 0 {
+            issues.push(ConfigIssue::new(
+                format!("{}.{}", prefix, field),
+                "must be greater than 0",
+            ));
+        }
+    }
+}
+
+fn check_temperature(prefix: &str, temperature: f32, issues: &mut Vec<ConfigIssue>) {
+    if !(0.0..=2.0).contains(&temperature) {
+        issues.push(ConfigIssue::new(
+            format!("{}.temperature", prefix),
+            "must be between 0.0 and 2.0",
+        ));
+    }
+}
+
+/// Span of the deepest key along `path` that is present in the document.
+fn span_of(table: &toml_edit::Table, path: &str) -> Option<Range<usize>> {
+    let mut current: &dyn toml_edit::TableLike = table;
+    let mut span = None;
+
+    for segment in path.split('.') {
+        let Some((key, item)) = current.get_key_value(segment) else {
+            break;
+        };
+        span = key.span().or_else(|| item.span()).or(span);
+        match item.as_table_like() {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+
+    span
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let prefix = &source[..offset.min(source.len())];
+    let line = prefix.matches('\n').count() + 1;
+    let column = prefix
+        .rfind('\n')
+        .map_or(prefix.len(), |idx| prefix.len() - idx - 1)
+        + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn misspelled_section_is_rejected_with_location() {
+        let source = "[metadata]\nname = \"chimera\"\n\n[rate_limiting.defualt]\nrequests = 10\n";
+
+        let err = parse_strict(source).unwrap_err();
+        let issue = &err.issues[0];
+        assert!(issue.message.contains("unknown field `defualt`"));
+        assert_eq!(issue.line, Some(4));
+    }
+
+    #[test]
+    fn out_of_range_values_point_at_their_keys() {
+        let source = "[rate_limiting.default]\nrequests = 10\nwindow_seconds = 0\n\n[inference]\ntemperature = 3.5\n";
+
+        let err = parse_strict(source).unwrap_err();
+        let paths: Vec<&str> = err.issues.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "rate_limiting.default.window_seconds",
+                "inference.temperature"
+            ]
+        );
+        assert_eq!(err.issues[0].line, Some(3));
+        assert_eq!(err.issues[0].column, Some(1));
+        assert_eq!(err.issues[1].line, Some(6));
+    }
+
+    #[test]
+    fn agents_must_reference_renderable_templates() {
+        let source = "[prompts.templates.review]\ntemplate = \"Review {{language}}\"\n\n[agents.critic]\nprompt_template = \"review\"\n";
+
+        let err = parse_strict(source).unwrap_err();
+        assert_eq!(err.issues[0].path, "agents.critic.prompt_template");
+        assert!(err.issues[0]
+            .message
+            .contains("missing variables: language"));
+        assert_eq!(err.issues[0].line, Some(5));
+    }
+
+    #[test]
+    fn shipped_platform_config_is_valid() {
+        let source = include_str!("../../configs/platform.toml");
+        assert!(parse_strict(source).is_ok());
+    }
+
+    #[test]
+    fn schema_disallows_unknown_keys() {
+        let schema = PlatformConfig::json_schema();
+        assert_eq!(schema["additionalProperties"], serde_json::json!(false));
+        assert!(schema["properties"]["rate_limiting"].is_object());
+    }
+}
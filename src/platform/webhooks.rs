@@ -0,0 +1,288 @@
+//! Webhook subscription API
+//!
+//! Served on the telemetry port next to `/admin`. Listing needs
+//! `ViewStatus` and changes need `Operate`. Webhooks without a tenant receive
+//! every tenant's events, so only admins may create them; everyone else
+//! subscribes for their own tenant. Only admins may point a webhook at an
+//! internal address, by setting `allow_internal`.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::middleware::from_fn_with_state;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+
+use crate::auth::{self, Permission, Principal, Role};
+use crate::events::webhooks::Webhook;
+use crate::events::Topic;
+use crate::platform::admin::{error_response, record};
+use crate::platform::context::PlatformContext;
+
+pub fn webhook_routes() -> Router<PlatformContext> {
+    let reads = Router::new()
+        .route("/webhooks", get(list_webhooks))
+        .route("/webhooks/:id/deliveries", get(list_deliveries))
+        .route_layer(from_fn_with_state(Permission::ViewStatus, auth::require));
+    let changes = Router::new()
+        .route("/webhooks", post(create_webhook))
+        .route("/webhooks/:id", delete(delete_webhook))
+        .route_layer(from_fn_with_state(Permission::Operate, auth::require));
+
+    reads.merge(changes)
+}
+
+fn can_access(principal: &Principal, webhook: &Webhook) -> bool {
+    match &webhook.tenant {
+        Some(tenant) => principal.can_access_tenant(tenant),
+        None => principal.role == Role::Admin,
+    }
+}
+
+fn internal_error(err: anyhow::Error) -> Response {
+    error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", err))
+}
+
+/// The webhook `id` if it exists and `principal` may see it.
+async fn find(ctx: &PlatformContext, principal: &Principal, id: &str) -> Result<Webhook, Response> {
+    match ctx.webhooks().get(id).await {
+        Ok(Some(webhook)) if can_access(principal, &webhook) => Ok(webhook),
+        Ok(_) => Err(error_response(
+            StatusCode::NOT_FOUND,
+            format!("webhook {} not found", id),
+        )),
+        Err(err) => Err(internal_error(err)),
+    }
+}
+
+async fn list_webhooks(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+) -> Response {
+    match ctx.webhooks().list().await {
+        Ok(webhooks) => Json(
+            webhooks
+                .into_iter()
+                .filter(|webhook| can_access(&principal, webhook))
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(err) => internal_error(err),
+    }
+}
+
+#[derive(Deserialize)]
+struct CreateWebhook {
+    url: String,
+    /// Every topic when empty.
+    #[serde(default)]
+    topics: Vec<Topic>,
+    tenant: Option<String>,
+    /// Allow loopback, private and link-local targets; admins only.
+    #[serde(default)]
+    allow_internal: bool,
+}
+
+/// Registers a webhook; the response is the only place its secret is shown.
+async fn create_webhook(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Json(request): Json<CreateWebhook>,
+) -> Response {
+    let tenant = match request.tenant {
+        Some(tenant) if principal.can_access_tenant(&tenant) => Some(tenant),
+        Some(tenant) => {
+            return error_response(
+                StatusCode::FORBIDDEN,
+                format!("tenant {} is not accessible", tenant),
+            )
+        }
+        None if principal.role == Role::Admin => None,
+        None => Some(principal.tenant.clone()),
+    };
+    if request.allow_internal && principal.role != Role::Admin {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            "only admins may allow internal webhook targets".to_string(),
+        );
+    }
+
+    match ctx
+        .webhooks()
+        .register(&request.url, request.topics, tenant, request.allow_internal)
+        .await
+    {
+        Ok(webhook) => {
+            record(
+                &ctx,
+                &principal,
+                "create_webhook",
+                &format!("webhook:{}", webhook.id),
+            );
+            let mut body = serde_json::to_value(&webhook).expect("webhooks serialize to JSON");
+            body["secret"] = serde_json::Value::String(webhook.secret.clone());
+            (StatusCode::CREATED, Json(body)).into_response()
+        }
+        Err(err) => error_response(StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}", err)),
+    }
+}
+
+async fn delete_webhook(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(response) = find(&ctx, &principal, &id).await {
+        return response;
+    }
+    match ctx.webhooks().remove(&id).await {
+        Ok(_) => {
+            record(
+                &ctx,
+                &principal,
+                "delete_webhook",
+                &format!("webhook:{}", id),
+            );
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(err) => internal_error(err),
+    }
+}
+
+#[derive(Deserialize)]
+struct DeliveriesQuery {
+    #[serde(default = "default_deliveries_limit")]
+    limit: usize,
+}
+
+fn default_deliveries_limit() -> usize {
+    50
+}
+
+async fn list_deliveries(
+    State(ctx): State<PlatformContext>,
+    Extension(principal): Extension<Principal>,
+    Path(id): Path<String>,
+    Query(query): Query<DeliveriesQuery>,
+) -> Response {
+    if let Err(response) = find(&ctx, &principal, &id).await {
+        return response;
+    }
+    Json(ctx.webhooks().deliveries(&id, query.limit)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::test_support::test_context;
+    use axum::body::Body;
+    use axum::http::{header, Request};
+    use tower::Service;
+
+    async fn call(
+        app: &mut Router,
+        principal: &Principal,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        request.extensions_mut().insert(principal.clone());
+        let response = app.call(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn operators_only_manage_their_own_tenants_webhooks() {
+        let (ctx, _audit) = test_context();
+        let mut app = webhook_routes().with_state(ctx.clone());
+        let admin = Principal::anonymous(Role::Admin, None);
+        let operator = Principal {
+            tenant: "search".to_string(),
+            ..Principal::anonymous(Role::Operator, None)
+        };
+
+        let request = serde_json::json!({
+            "url": "https://ci.example.com/hook",
+            "topics": ["task.finished"],
+        });
+        let (status, body) = call(&mut app, &operator, "POST", "/webhooks", Some(request)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["tenant"], "search");
+        assert!(body["secret"].as_str().unwrap().starts_with("whsec_"));
+        let id = body["id"].as_str().unwrap().to_string();
+
+        let global = serde_json::json!({ "url": "https://chat.example.com/hook" });
+        let (_, body) = call(&mut app, &admin, "POST", "/webhooks", Some(global)).await;
+        assert_eq!(body["tenant"], serde_json::Value::Null);
+        let global_id = body["id"].as_str().unwrap().to_string();
+
+        let (_, body) = call(&mut app, &operator, "GET", "/webhooks", None).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert!(body[0].get("secret").is_none());
+
+        let uri = format!("/webhooks/{}", global_id);
+        let (status, _) = call(&mut app, &operator, "DELETE", &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = call(
+            &mut app,
+            &operator,
+            "DELETE",
+            &format!("/webhooks/{}", id),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(ctx.webhooks().list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn only_admins_register_internal_targets() {
+        let (ctx, _audit) = test_context();
+        let mut app = webhook_routes().with_state(ctx.clone());
+        let admin = Principal::anonymous(Role::Admin, None);
+        let operator = Principal {
+            tenant: "search".to_string(),
+            ..Principal::anonymous(Role::Operator, None)
+        };
+        let metadata = serde_json::json!({ "url": "http://169.254.169.254/latest/meta-data" });
+        let allowed = serde_json::json!({
+            "url": "http://127.0.0.1:8080/hook",
+            "allow_internal": true,
+        });
+
+        for principal in [&operator, &admin] {
+            let (status, _) = call(
+                &mut app,
+                principal,
+                "POST",
+                "/webhooks",
+                Some(metadata.clone()),
+            )
+            .await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let (status, _) = call(
+            &mut app,
+            &operator,
+            "POST",
+            "/webhooks",
+            Some(allowed.clone()),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = call(&mut app, &admin, "POST", "/webhooks", Some(allowed)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(ctx.webhooks().list().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,527 @@
+//! Prompt templates and chat-format renderers
+//!
+//! Templates are plain text with `{{variable}}` placeholders and
+//! `{{> partial}}` includes. Agents pick a template by name from the
+//! `[prompts]` section of the platform configuration; agents without one get
+//! the built-in template for their [`AgentType`].
+
+use crate::agents::{AgentConfig, AgentType};
+use crate::sessions::{Message, Role};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+const MAX_PARTIAL_DEPTH: usize = 8;
+const BUILTIN_VERSION: &str = "builtin-1";
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptSettings {
+    pub templates: HashMap<String, TemplateSettings>,
+    /// Snippets other templates include with `{{> name}}`.
+    pub partials: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateSettings {
+    pub version: String,
+    pub template: String,
+}
+
+impl Default for TemplateSettings {
+    fn default() -> Self {
+        Self {
+            version: "1".to_string(),
+            template: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatFormat {
+    ChatMl,
+    Llama,
+    #[default]
+    Plain,
+}
+
+impl ChatFormat {
+    /// Renders `messages` as a single prompt ending where the assistant speaks next.
+    pub fn render(&self, messages: &[Message]) -> String {
+        match self {
+            ChatFormat::ChatMl => {
+                let mut prompt = String::new();
+                for message in messages {
+                    prompt.push_str(&format!(
+                        "<|im_start|>{}\n{}<|im_end|>\n",
+                        message.role.as_str(),
+                        message.content
+                    ));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+                prompt
+            }
+            ChatFormat::Llama => render_llama(messages),
+            ChatFormat::Plain => {
+                let mut prompt = String::new();
+                for message in messages {
+                    prompt.push_str(&format!("{}: {}\n", message.role.as_str(), message.content));
+                }
+                prompt.push_str("assistant:");
+                prompt
+            }
+        }
+    }
+}
+
+/// Llama-2 instruction format: system text rides inside the first `[INST]` block.
+fn render_llama(messages: &[Message]) -> String {
+    let system: Vec<&str> = messages
+        .iter()
+        .filter(|m| m.role == Role::System)
+        .map(|m| m.content.as_str())
+        .collect();
+    let mut system = if system.is_empty() {
+        None
+    } else {
+        Some(format!("<<SYS>>\n{}\n<</SYS>>\n\n", system.join("\n")))
+    };
+
+    let mut prompt = String::new();
+    let mut open = false;
+    for message in messages.iter().filter(|m| m.role != Role::System) {
+        if message.role == Role::Assistant {
+            if open {
+                prompt.push_str(&format!(" {} </s>", message.content));
+                open = false;
+            }
+            continue;
+        }
+        if open {
+            // Consecutive user/tool turns share one instruction block.
+            prompt.truncate(prompt.len() - " [/INST]".len());
+            prompt.push_str(&format!("\n{} [/INST]", message.content));
+        } else {
+            prompt.push_str(&format!(
+                "<s>[INST] {}{} [/INST]",
+                system.take().unwrap_or_default(),
+                message.content
+            ));
+            open = true;
+        }
+    }
+    prompt
+}
+
+/// Which template produced a prompt; recorded with every inference call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateRef {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    Unterminated { offset: usize },
+    EmptyTag { offset: usize },
+    UnknownTemplate(String),
+    UnknownPartial(String),
+    PartialCycle(String),
+    MissingVariables(Vec<String>),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::Unterminated { offset } => {
+                write!(f, "unterminated `{{{{` at byte {}", offset)
+            }
+            TemplateError::EmptyTag { offset } => write!(f, "empty tag at byte {}", offset),
+            TemplateError::UnknownTemplate(name) => write!(f, "unknown template {:?}", name),
+            TemplateError::UnknownPartial(name) => write!(f, "unknown partial {:?}", name),
+            TemplateError::PartialCycle(name) => {
+                write!(f, "partial {:?} includes itself", name)
+            }
+            TemplateError::MissingVariables(names) => {
+                write!(f, "missing variables: {}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Variable(String),
+    Partial(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub version: String,
+    segments: Vec<Segment>,
+}
+
+impl PromptTemplate {
+    pub fn parse<N: Into<String>, V: Into<String>>(
+        name: N,
+        version: V,
+        source: &str,
+    ) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut rest = source;
+        let mut offset = 0;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            let tag_offset = offset + start;
+            let after = &rest[start + 2..];
+            let end = after
+                .find("}}")
+                .ok_or(TemplateError::Unterminated { offset: tag_offset })?;
+
+            let tag = after[..end].trim();
+            let segment = match tag.strip_prefix('>') {
+                Some(partial) => Segment::Partial(partial.trim().to_string()),
+                None => Segment::Variable(tag.to_string()),
+            };
+            if matches!(&segment, Segment::Variable(n) | Segment::Partial(n) if n.is_empty()) {
+                return Err(TemplateError::EmptyTag { offset: tag_offset });
+            }
+            segments.push(segment);
+
+            let consumed = start + 2 + end + 2;
+            rest = &rest[consumed..];
+            offset += consumed;
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+
+        Ok(Self {
+            name: name.into(),
+            version: version.into(),
+            segments,
+        })
+    }
+
+    pub fn reference(&self) -> TemplateRef {
+        TemplateRef {
+            name: self.name.clone(),
+            version: self.version.clone(),
+        }
+    }
+}
+
+/// A rendered system prompt together with the template that produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedPrompt {
+    pub text: String,
+    pub template: TemplateRef,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PromptLibrary {
+    templates: HashMap<String, PromptTemplate>,
+    partials: HashMap<String, PromptTemplate>,
+}
+
+impl PromptLibrary {
+    /// Parses every configured template, reporting failures by config path.
+    pub fn from_settings(settings: &PromptSettings) -> Result<Self, Vec<(String, TemplateError)>> {
+        let mut library = Self::default();
+        let mut errors = Vec::new();
+
+        for (name, source) in &settings.partials {
+            match PromptTemplate::parse(name, "", source) {
+                Ok(partial) => {
+                    library.partials.insert(name.clone(), partial);
+                }
+                Err(err) => errors.push((format!("prompts.partials.{}", name), err)),
+            }
+        }
+        for (name, template) in &settings.templates {
+            match PromptTemplate::parse(name, &template.version, &template.template) {
+                Ok(parsed) => {
+                    library.templates.insert(name.clone(), parsed);
+                }
+                Err(err) => errors.push((format!("prompts.templates.{}.template", name), err)),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(library)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The agent's configured template, or the built-in one for its type.
+    pub fn template_for(&self, agent: &AgentConfig) -> Result<PromptTemplate, TemplateError> {
+        match &agent.prompt_template {
+            Some(name) => self
+                .templates
+                .get(name)
+                .cloned()
+                .ok_or_else(|| TemplateError::UnknownTemplate(name.clone())),
+            None => Ok(builtin_template(&agent.agent_type)),
+        }
+    }
+
+    /// Variables `template` needs once partials are expanded.
+    pub fn required_variables(
+        &self,
+        template: &PromptTemplate,
+    ) -> Result<BTreeSet<String>, TemplateError> {
+        let mut names = BTreeSet::new();
+        self.walk(template, &mut Vec::new(), &mut |segment| {
+            if let Segment::Variable(name) = segment {
+                names.insert(name.clone());
+            }
+        })?;
+        Ok(names)
+    }
+
+    pub fn render(
+        &self,
+        template: &PromptTemplate,
+        variables: &HashMap<String, String>,
+    ) -> Result<String, TemplateError> {
+        let missing: Vec<String> = self
+            .required_variables(template)?
+            .into_iter()
+            .filter(|name| !variables.contains_key(name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(TemplateError::MissingVariables(missing));
+        }
+
+        let mut output = String::new();
+        self.walk(template, &mut Vec::new(), &mut |segment| match segment {
+            Segment::Text(text) => output.push_str(text),
+            Segment::Variable(name) => output.push_str(&variables[name]),
+            Segment::Partial(_) => {}
+        })?;
+        Ok(output)
+    }
+
+    /// Renders the agent's system prompt from its template and variables.
+    pub fn system_prompt(&self, agent: &AgentConfig) -> Result<RenderedPrompt, TemplateError> {
+        let template = self.template_for(agent)?;
+        let text = self.render(&template, &agent_variables(agent))?;
+        Ok(RenderedPrompt {
+            text,
+            template: template.reference(),
+        })
+    }
+
+    /// Checks that the agent's template exists and every variable it uses is provided.
+    pub fn check_agent(&self, agent: &AgentConfig) -> Result<(), TemplateError> {
+        self.system_prompt(agent).map(|_| ())
+    }
+
+    fn walk<'a>(
+        &'a self,
+        template: &'a PromptTemplate,
+        stack: &mut Vec<&'a str>,
+        visit: &mut dyn FnMut(&'a Segment),
+    ) -> Result<(), TemplateError> {
+        for segment in &template.segments {
+            if let Segment::Partial(name) = segment {
+                if stack.contains(&name.as_str()) || stack.len() >= MAX_PARTIAL_DEPTH {
+                    return Err(TemplateError::PartialCycle(name.clone()));
+                }
+                let partial = self
+                    .partials
+                    .get(name)
+                    .ok_or_else(|| TemplateError::UnknownPartial(name.clone()))?;
+                stack.push(name);
+                self.walk(partial, stack, visit)?;
+                stack.pop();
+            }
+            visit(segment);
+        }
+        Ok(())
+    }
+}
+
+/// Variables every template can use, plus the agent's own `prompt_variables`.
+pub fn agent_variables(agent: &AgentConfig) -> HashMap<String, String> {
+    let mut variables = agent.prompt_variables.clone();
+    variables.insert("system_prompt".into(), agent.system_prompt.clone());
+    variables.insert("agent_name".into(), agent.agent_name.clone());
+    variables.insert("agent_type".into(), format!("{:?}", agent.agent_type));
+    variables.insert("capabilities".into(), agent.capabilities.join(", "));
+    variables
+}
+
+fn builtin_template(agent_type: &AgentType) -> PromptTemplate {
+    let (name, source) = match agent_type {
+        AgentType::General => ("builtin.general", "{{system_prompt}}"),
+        AgentType::CodeGeneration => (
+            "builtin.code_generation",
+            "{{system_prompt}}\nWrite correct, idiomatic code. Explain only the non-obvious choices.",
+        ),
+        AgentType::DataAnalysis => (
+            "builtin.data_analysis",
+            "{{system_prompt}}\nState your assumptions, show the calculations and quantify uncertainty.",
+        ),
+        AgentType::Creative => (
+            "builtin.creative",
+            "{{system_prompt}}\nFavour vivid, original language over safe clichés.",
+        ),
+        AgentType::Technical => (
+            "builtin.technical",
+            "{{system_prompt}}\nBe precise and reference the relevant specifications.",
+        ),
+    };
+    PromptTemplate::parse(name, BUILTIN_VERSION, source).expect("built-in templates parse")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library(templates: &[(&str, &str)], partials: &[(&str, &str)]) -> PromptLibrary {
+        let settings = PromptSettings {
+            templates: templates
+                .iter()
+                .map(|(name, source)| {
+                    (
+                        name.to_string(),
+                        TemplateSettings {
+                            version: "3".into(),
+                            template: source.to_string(),
+                        },
+                    )
+                })
+                .collect(),
+            partials: partials
+                .iter()
+                .map(|(name, source)| (name.to_string(), source.to_string()))
+                .collect(),
+        };
+        PromptLibrary::from_settings(&settings).unwrap()
+    }
+
+    #[test]
+    fn renders_variables_and_partials() {
+        let library = library(
+            &[(
+                "review",
+                "You are {{ agent_name }}, reviewing {{language}}. {{> safety}}",
+            )],
+            &[("safety", "Never run {{language}} code.")],
+        );
+        let agent = AgentConfig {
+            agent_name: "critic".into(),
+            prompt_template: Some("review".into()),
+            prompt_variables: HashMap::from([("language".into(), "Rust".into())]),
+            ..AgentConfig::default()
+        };
+
+        let prompt = library.system_prompt(&agent).unwrap();
+        assert_eq!(
+            prompt.text,
+            "You are critic, reviewing Rust. Never run Rust code."
+        );
+        assert_eq!(
+            prompt.template,
+            TemplateRef {
+                name: "review".into(),
+                version: "3".into()
+            }
+        );
+    }
+
+    #[test]
+    fn missing_variables_and_bad_partials_are_reported() {
+        let library = library(
+            &[
+                ("review", "{{language}} {{> loop}}"),
+                ("lonely", "{{> absent}}"),
+            ],
+            &[("loop", "{{> loop}}")],
+        );
+        let mut agent = AgentConfig {
+            prompt_template: Some("review".into()),
+            ..AgentConfig::default()
+        };
+        assert_eq!(
+            library.check_agent(&agent),
+            Err(TemplateError::PartialCycle("loop".into()))
+        );
+
+        agent.prompt_template = Some("lonely".into());
+        assert_eq!(
+            library.check_agent(&agent),
+            Err(TemplateError::UnknownPartial("absent".into()))
+        );
+
+        let library = self::library(&[("review", "{{language}} for {{audience}}")], &[]);
+        agent.prompt_template = Some("review".into());
+        assert_eq!(
+            library.check_agent(&agent),
+            Err(TemplateError::MissingVariables(vec![
+                "audience".into(),
+                "language".into()
+            ]))
+        );
+    }
+
+    #[test]
+    fn unterminated_tags_fail_to_parse() {
+        assert_eq!(
+            PromptTemplate::parse("t", "1", "Hello {{name"),
+            Err(TemplateError::Unterminated { offset: 6 })
+        );
+        assert!(PromptTemplate::parse("t", "1", "{{ }}").is_err());
+    }
+
+    #[test]
+    fn agent_types_get_distinct_builtin_templates() {
+        let library = PromptLibrary::default();
+        let coder = AgentConfig {
+            agent_type: AgentType::CodeGeneration,
+            ..AgentConfig::default()
+        };
+
+        let prompt = library.system_prompt(&coder).unwrap();
+        assert_eq!(prompt.template.name, "builtin.code_generation");
+        assert!(prompt
+            .text
+            .starts_with("You are a helpful assistant.\nWrite correct"));
+    }
+
+    #[test]
+    fn chat_formats() {
+        let messages = vec![
+            Message::new(Role::System, "Be brief."),
+            Message::new(Role::User, "Hi"),
+            Message::new(Role::Assistant, "Hello"),
+            Message::new(Role::User, "Bye"),
+        ];
+
+        assert_eq!(
+            ChatFormat::ChatMl.render(&messages),
+            "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n\
+             <|im_start|>assistant\nHello<|im_end|>\n<|im_start|>user\nBye<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+        assert_eq!(
+            ChatFormat::Llama.render(&messages),
+            "<s>[INST] <<SYS>>\nBe brief.\n<</SYS>>\n\nHi [/INST] Hello </s><s>[INST] Bye [/INST]"
+        );
+        assert!(ChatFormat::Plain
+            .render(&messages)
+            .ends_with("user: Bye\nassistant:"));
+    }
+}
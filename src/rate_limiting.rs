@@ -1,6 +1,7 @@
+use serde::Serialize;
 use std::collections::HashMap;
 use std::net::IpAddr;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 #[derive(Debug, Clone)]
 pub struct RateLimit {
@@ -15,46 +16,115 @@ pub struct RateLimitConfig {
     pub burst_limit: u32,
 }
 
+/// Identity a bucket is kept for: the caller's principal when authenticated,
+/// otherwise its address.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientId {
+    Ip(IpAddr),
+    Principal(String),
+}
+
+impl std::fmt::Display for ClientId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientId::Ip(ip) => write!(f, "ip:{}", ip),
+            ClientId::Principal(principal) => write!(f, "{}", principal),
+        }
+    }
+}
+
+/// Requests a client has made in its current window, as reported to operators.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClientUsage {
+    pub client: String,
+    /// Tenant of the client's most recent request, when it was known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub requests: usize,
+    pub last_request_secs_ago: Option<u64>,
+}
+
+impl From<IpAddr> for ClientId {
+    fn from(ip: IpAddr) -> Self {
+        ClientId::Ip(ip)
+    }
+}
+
 #[derive(Debug)]
 struct ClientBucket {
     requests: Vec<Instant>,
+    tenant: Option<String>,
     last_burst_reset: Instant,
 }
 
 #[derive(Clone)]
 pub struct RateLimiter {
-    config: RateLimitConfig,
-    clients: Arc<Mutex<HashMap<IpAddr, ClientBucket>>>,
+    config: Arc<RwLock<RateLimitConfig>>,
+    clients: Arc<Mutex<HashMap<ClientId, ClientBucket>>>,
 }
 
 impl RateLimiter {
     pub fn new(config: RateLimitConfig) -> Self {
         Self {
-            config,
+            config: Arc::new(RwLock::new(config)),
             clients: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Swap in new limits without dropping the request history of existing clients.
+    pub fn reconfigure(&self, config: RateLimitConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    pub fn config(&self) -> RateLimitConfig {
+        self.config.read().unwrap().clone()
+    }
+
     pub fn check_rate_limit(
         &self,
-        client_ip: IpAddr,
+        client: impl Into<ClientId>,
+        endpoint: &str,
+    ) -> Result<(), RateLimitError> {
+        self.check(client.into(), None, endpoint)
+    }
+
+    /// Like [`Self::check_rate_limit`], also recording the tenant the client
+    /// acts for so the snapshot can be scoped to it.
+    pub fn check_tenant_rate_limit(
+        &self,
+        client: impl Into<ClientId>,
+        tenant: &str,
+        endpoint: &str,
+    ) -> Result<(), RateLimitError> {
+        self.check(client.into(), Some(tenant), endpoint)
+    }
+
+    fn check(
+        &self,
+        client: ClientId,
+        tenant: Option<&str>,
         endpoint: &str,
     ) -> Result<(), RateLimitError> {
         let mut clients = self.clients.lock().unwrap();
 
-        let bucket = clients.entry(client_ip).or_insert_with(|| ClientBucket {
+        let bucket = clients.entry(client).or_insert_with(|| ClientBucket {
             requests: Vec::new(),
+            tenant: None,
             last_burst_reset: Instant::now(),
         });
+        if let Some(tenant) = tenant {
+            bucket.tenant = Some(tenant.to_string());
+        }
 
         // Clean old requests outside the window
         let limit = self.get_limit_for_endpoint(endpoint);
+        let burst_limit = self.config.read().unwrap().burst_limit;
         let window_start = Instant::now() - limit.window;
 
         bucket.requests.retain(|&time| time > window_start);
 
         // Check burst limit (requests per second)
-        if bucket.requests.len() >= self.config.burst_limit as usize {
+        if bucket.requests.len() >= burst_limit as usize {
             let time_since_last_burst_reset =
                 Instant::now().duration_since(bucket.last_burst_reset);
             if time_since_last_burst_reset < Duration::from_secs(1) {
@@ -76,16 +146,35 @@ impl RateLimiter {
     }
 
     fn get_limit_for_endpoint(&self, endpoint: &str) -> RateLimit {
-        self.config
+        let config = self.config.read().unwrap();
+        config
             .endpoints
             .get(endpoint)
             .cloned()
-            .unwrap_or(self.config.default.clone())
+            .unwrap_or(config.default.clone())
+    }
+
+    /// Every tracked client, busiest first.
+    pub fn snapshot(&self) -> Vec<ClientUsage> {
+        let clients = self.clients.lock().unwrap();
+        let mut usage: Vec<ClientUsage> = clients
+            .iter()
+            .map(|(client, bucket)| ClientUsage {
+                client: client.to_string(),
+                tenant: bucket.tenant.clone(),
+                requests: bucket.requests.len(),
+                last_request_secs_ago: bucket.requests.last().map(|at| at.elapsed().as_secs()),
+            })
+            .collect();
+        usage.sort_by(|a, b| b.requests.cmp(&a.requests).then(a.client.cmp(&b.client)));
+        usage
     }
 
-    pub fn get_client_stats(&self, client_ip: IpAddr) -> Option<usize> {
+    pub fn get_client_stats(&self, client: impl Into<ClientId>) -> Option<usize> {
         let clients = self.clients.lock().unwrap();
-        clients.get(&client_ip).map(|bucket| bucket.requests.len())
+        clients
+            .get(&client.into())
+            .map(|bucket| bucket.requests.len())
     }
 }
 
@@ -132,4 +221,54 @@ mod tests {
         // Should block 11th request
         assert!(limiter.check_rate_limit(client_ip, "/api/test").is_err());
     }
+
+    #[test]
+    fn test_reconfigure_keeps_client_history() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            default: RateLimit {
+                requests: 2,
+                window: Duration::from_secs(60),
+            },
+            endpoints: HashMap::new(),
+            burst_limit: 20,
+        });
+        let client_ip = IpAddr::from([127, 0, 0, 1]);
+
+        assert!(limiter.check_rate_limit(client_ip, "/api/test").is_ok());
+        assert!(limiter.check_rate_limit(client_ip, "/api/test").is_ok());
+        assert!(limiter.check_rate_limit(client_ip, "/api/test").is_err());
+
+        limiter.reconfigure(RateLimitConfig {
+            default: RateLimit {
+                requests: 3,
+                window: Duration::from_secs(60),
+            },
+            endpoints: HashMap::new(),
+            burst_limit: 20,
+        });
+
+        assert_eq!(limiter.get_client_stats(client_ip), Some(2));
+        assert!(limiter.check_rate_limit(client_ip, "/api/test").is_ok());
+        assert!(limiter.check_rate_limit(client_ip, "/api/test").is_err());
+    }
+
+    #[test]
+    fn principals_get_their_own_buckets() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            default: RateLimit {
+                requests: 1,
+                window: Duration::from_secs(60),
+            },
+            endpoints: HashMap::new(),
+            burst_limit: 20,
+        });
+        let client_ip = IpAddr::from([10, 0, 0, 1]);
+        let alice = ClientId::Principal("key:alice".to_string());
+        let bob = ClientId::Principal("key:bob".to_string());
+
+        assert!(limiter.check_rate_limit(alice.clone(), "/api/test").is_ok());
+        assert!(limiter.check_rate_limit(alice, "/api/test").is_err());
+        assert!(limiter.check_rate_limit(bob, "/api/test").is_ok());
+        assert!(limiter.check_rate_limit(client_ip, "/api/test").is_ok());
+    }
 }
@@ -0,0 +1,474 @@
+//! Request routing over Redis Streams
+//!
+//! Clients append requests to a stream; router workers share a consumer group
+//! so every entry is delivered to exactly one worker. Each request is forwarded
+//! to an agent advertising the requested capability and the agent's reply is
+//! stored under `chimera:response:<request_id>` with a TTL.
+
+use crate::agents::AgentRegistry;
+use crate::platform::service::ServiceRegistration;
+use crate::tenancy::DEFAULT_TENANT;
+use anyhow::{Context, Result};
+use redis::aio::ConnectionManager;
+use redis::streams::{StreamReadOptions, StreamReadReply};
+use redis::{AsyncCommands, FromRedisValue, RedisResult, Value};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+pub const DEFAULT_STREAM: &str = "chimera:requests";
+pub const DEFAULT_GROUP: &str = "chimera-routers";
+const DEFAULT_CAPABILITY: &str = "text_generation";
+
+type StreamEntry = (String, HashMap<String, Value>);
+
+#[derive(Debug, Clone)]
+pub struct RouterOptions {
+    pub redis_url: String,
+    pub stream_key: String,
+    pub group: String,
+    pub consumer_prefix: String,
+    pub workers: usize,
+    pub response_ttl: Duration,
+    pub batch_size: usize,
+    pub block: Duration,
+    /// Entries pending this long are assumed abandoned and reclaimed.
+    pub claim_idle: Duration,
+    pub claim_interval: Duration,
+    pub request_timeout: Duration,
+    /// Sent as `X-API-Key` to agents that require authentication.
+    pub api_key: Option<String>,
+}
+
+impl RouterOptions {
+    pub fn new<U: Into<String>>(redis_url: U) -> Self {
+        Self {
+            redis_url: redis_url.into(),
+            stream_key: DEFAULT_STREAM.to_string(),
+            group: DEFAULT_GROUP.to_string(),
+            consumer_prefix: format!("router-{}", std::process::id()),
+            workers: 1,
+            response_ttl: Duration::from_secs(300),
+            batch_size: 16,
+            block: Duration::from_secs(5),
+            claim_idle: Duration::from_secs(60),
+            claim_interval: Duration::from_secs(30),
+            request_timeout: Duration::from_secs(30),
+            api_key: None,
+        }
+    }
+
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    pub fn with_response_ttl(mut self, ttl: Duration) -> Self {
+        self.response_ttl = ttl;
+        self
+    }
+
+    pub fn with_api_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteRequest {
+    pub request_id: String,
+    pub capability: String,
+    pub tenant: String,
+    pub payload: serde_json::Value,
+}
+
+impl RouteRequest {
+    fn from_fields(request_id: String, fields: &HashMap<String, Value>) -> Result<Self, String> {
+        let data = field(fields, "data").ok_or("missing `data` field")?;
+        let payload =
+            serde_json::from_str(&data).map_err(|err| format!("`data` is not JSON: {}", err))?;
+
+        Ok(Self {
+            request_id,
+            capability: field(fields, "capability").unwrap_or_else(|| DEFAULT_CAPABILITY.into()),
+            tenant: field(fields, "tenant").unwrap_or_else(|| DEFAULT_TENANT.into()),
+            payload,
+        })
+    }
+}
+
+pub fn response_key(request_id: &str) -> String {
+    format!("chimera:response:{}", request_id)
+}
+
+pub struct RequestRouter {
+    options: RouterOptions,
+    agents: AgentRegistry,
+    http: reqwest::Client,
+}
+
+impl RequestRouter {
+    pub fn new(options: RouterOptions, agents: AgentRegistry) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(options.request_timeout)
+            .build()
+            .context("failed to build agent HTTP client")?;
+
+        Ok(Self {
+            options,
+            agents,
+            http,
+        })
+    }
+
+    /// Runs `options.workers` consumers until `token` is cancelled.
+    pub async fn run(self: Arc<Self>, token: CancellationToken) -> Result<()> {
+        let mut workers = JoinSet::new();
+        for index in 0..self.options.workers {
+            let router = Arc::clone(&self);
+            let consumer = format!("{}-{}", self.options.consumer_prefix, index);
+            let token = token.clone();
+            workers.spawn(async move { router.run_worker(consumer, token).await });
+        }
+
+        info!(
+            stream = %self.options.stream_key,
+            group = %self.options.group,
+            workers = self.options.workers,
+            "request router started"
+        );
+
+        while let Some(joined) = workers.join_next().await {
+            if let Err(err) = joined.context("router worker panicked").and_then(|res| res) {
+                // Take the remaining workers down with us so the supervisor restarts a full set.
+                token.cancel();
+                workers.shutdown().await;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Forwards `request` to an agent and returns the document to store as its response.
+    pub async fn route(&self, request: &RouteRequest) -> serde_json::Value {
+        let Some(agent) = self
+            .agents
+            .select_for_capability(&request.capability, &request.tenant)
+        else {
+            return failure(
+                &request.request_id,
+                format!("no agent available for capability {:?}", request.capability),
+            );
+        };
+
+        let endpoint = agent.config.endpoint.as_deref().unwrap_or_default();
+        let url = format!("{}/predict", endpoint.trim_end_matches('/'));
+        let started = Instant::now();
+        let outcome = async {
+            let mut forwarded = self.http.post(&url).json(&request.payload);
+            if let Some(key) = &self.options.api_key {
+                forwarded = forwarded.header("x-api-key", key);
+            }
+            let response = forwarded.send().await?;
+            let status = response.status();
+            let body: serde_json::Value = response.json().await?;
+            Ok::<_, reqwest::Error>((status, body))
+        }
+        .await;
+        self.agents.update_activity(&agent.id);
+
+        match outcome {
+            Ok((status, body)) if status.is_success() => json!({
+                "request_id": request.request_id,
+                "status": "completed",
+                "agent": agent.name,
+                "response": body,
+                "latency_ms": started.elapsed().as_millis() as u64,
+                "completed_at": crate::utils::timestamp_now(),
+            }),
+            Ok((status, body)) => {
+                let mut doc = failure(
+                    &request.request_id,
+                    format!("agent {} returned {}", agent.name, status),
+                );
+                doc["response"] = body;
+                doc
+            }
+            Err(err) => failure(
+                &request.request_id,
+                format!("request to agent {} failed: {}", agent.name, err),
+            ),
+        }
+    }
+
+    async fn run_worker(&self, consumer: String, token: CancellationToken) -> Result<()> {
+        let client =
+            redis::Client::open(self.options.redis_url.as_str()).context("invalid Redis URL")?;
+        let mut conn = ConnectionManager::new(client)
+            .await
+            .context("failed to connect to Redis")?;
+        self.ensure_group(&mut conn).await?;
+
+        let read_options = StreamReadOptions::default()
+            .group(&self.options.group, &consumer)
+            .count(self.options.batch_size)
+            .block(self.options.block.as_millis() as usize);
+        let streams = [&self.options.stream_key];
+        let mut next_claim = Instant::now();
+
+        while !token.is_cancelled() {
+            if Instant::now() >= next_claim {
+                if let Err(err) = self.reclaim(&mut conn, &consumer).await {
+                    warn!(%consumer, error = %err, "failed to reclaim pending requests");
+                }
+                next_claim = Instant::now() + self.options.claim_interval;
+            }
+
+            let read = tokio::select! {
+                _ = token.cancelled() => break,
+                read = conn.xread_options::<_, _, StreamReadReply>(&streams, &[">"], &read_options) => read,
+            };
+
+            match read {
+                Ok(reply) => {
+                    for entry in reply.keys.into_iter().flat_map(|key| key.ids) {
+                        self.handle_entry(&mut conn, &entry.id, &entry.map).await?;
+                    }
+                }
+                Err(err) => {
+                    warn!(%consumer, error = %err, "failed to read request stream");
+                    if err.code() == Some("NOGROUP") {
+                        self.ensure_group(&mut conn).await?;
+                        continue;
+                    }
+                    tokio::select! {
+                        _ = token.cancelled() => break,
+                        _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn ensure_group(&self, conn: &mut ConnectionManager) -> Result<()> {
+        let created: RedisResult<()> = conn
+            .xgroup_create_mkstream(&self.options.stream_key, &self.options.group, "0")
+            .await;
+        match created {
+            Err(err) if err.code() != Some("BUSYGROUP") => Err(err).with_context(|| {
+                format!(
+                    "failed to create consumer group {} on {}",
+                    self.options.group, self.options.stream_key
+                )
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Takes over entries other consumers read but never acknowledged.
+    async fn reclaim(&self, conn: &mut ConnectionManager, consumer: &str) -> Result<()> {
+        let mut cursor = "0-0".to_string();
+        loop {
+            let reply: Vec<Value> = redis::cmd("XAUTOCLAIM")
+                .arg(&self.options.stream_key)
+                .arg(&self.options.group)
+                .arg(consumer)
+                .arg(self.options.claim_idle.as_millis() as u64)
+                .arg(&cursor)
+                .arg("COUNT")
+                .arg(self.options.batch_size)
+                .query_async(conn)
+                .await?;
+            let (next, entries) = parse_autoclaim(reply)?;
+
+            for (id, fields) in entries {
+                info!(%consumer, entry = %id, "reclaimed abandoned request");
+                self.handle_entry(conn, &id, &fields).await?;
+            }
+
+            if next == "0-0" {
+                return Ok(());
+            }
+            cursor = next;
+        }
+    }
+
+    async fn handle_entry(
+        &self,
+        conn: &mut ConnectionManager,
+        entry_id: &str,
+        fields: &HashMap<String, Value>,
+    ) -> Result<()> {
+        match field(fields, "request_id") {
+            Some(request_id) => {
+                let response = match RouteRequest::from_fields(request_id.clone(), fields) {
+                    Ok(request) => self.route(&request).await,
+                    Err(reason) => failure(&request_id, reason),
+                };
+                conn.set_ex::<_, _, ()>(
+                    response_key(&request_id),
+                    response.to_string(),
+                    self.options.response_ttl.as_secs().max(1),
+                )
+                .await
+                .context("failed to store response")?;
+            }
+            None => warn!(entry = %entry_id, "dropping request without a request_id"),
+        }
+
+        conn.xack::<_, _, _, ()>(&self.options.stream_key, &self.options.group, &[entry_id])
+            .await
+            .context("failed to acknowledge request")?;
+        Ok(())
+    }
+}
+
+/// Registers the router as a platform service fed by the platform's agent registry.
+pub fn router_service(options: RouterOptions) -> ServiceRegistration {
+    let probe_url = options.redis_url.clone();
+
+    ServiceRegistration::new(
+        "router",
+        Arc::new(move |context, token| {
+            let options = options.clone();
+            tokio::spawn(async move {
+                let router = Arc::new(RequestRouter::new(options, context.agents())?);
+                router.run(token).await
+            })
+        }),
+    )
+    .depends_on(["agents"])
+    .with_readiness(Arc::new(move |_context| {
+        let url = probe_url.clone();
+        Box::pin(async move {
+            let client = redis::Client::open(url.as_str())?;
+            let mut conn = client.get_multiplexed_tokio_connection().await?;
+            redis::cmd("PING").query_async::<_, ()>(&mut conn).await?;
+            Ok(())
+        })
+    }))
+}
+
+fn field(fields: &HashMap<String, Value>, name: &str) -> Option<String> {
+    fields
+        .get(name)
+        .and_then(|value| String::from_redis_value(value).ok())
+}
+
+fn failure(request_id: &str, error: String) -> serde_json::Value {
+    json!({
+        "request_id": request_id,
+        "status": "failed",
+        "error": error,
+        "completed_at": crate::utils::timestamp_now(),
+    })
+}
+
+/// Splits an `XAUTOCLAIM` reply into the next cursor and the claimed entries.
+///
+/// Redis 6.2 reports deleted entries as nils inside the claimed list; those are skipped.
+fn parse_autoclaim(reply: Vec<Value>) -> RedisResult<(String, Vec<StreamEntry>)> {
+    let mut parts = reply.into_iter();
+    let cursor = String::from_redis_value(&parts.next().unwrap_or(Value::Nil))?;
+    let entries = match parts.next() {
+        Some(Value::Bulk(entries)) => entries
+            .iter()
+            .filter(|entry| !matches!(entry, Value::Nil))
+            .map(StreamEntry::from_redis_value)
+            .collect::<RedisResult<_>>()?,
+        _ => Vec::new(),
+    };
+    Ok((cursor, entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::AgentConfig;
+
+    fn data(value: &str) -> Value {
+        Value::Data(value.as_bytes().to_vec())
+    }
+
+    async fn stub_agent() -> String {
+        let app = axum::Router::new().route(
+            "/predict",
+            axum::routing::post(
+                |axum::Json(body): axum::Json<serde_json::Value>| async move {
+                    axum::Json(json!({ "echo": body }))
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn requests_default_to_text_generation() {
+        let fields = HashMap::from([("data".to_string(), data(r#"{"prompt":"hi"}"#))]);
+
+        let request = RouteRequest::from_fields("req-1".into(), &fields).unwrap();
+        assert_eq!(request.capability, "text_generation");
+        assert_eq!(request.payload, json!({ "prompt": "hi" }));
+
+        let fields = HashMap::from([("data".to_string(), data("not json"))]);
+        assert!(RouteRequest::from_fields("req-2".into(), &fields).is_err());
+    }
+
+    #[test]
+    fn autoclaim_replies_skip_deleted_entries() {
+        let entry = Value::Bulk(vec![
+            data("1-0"),
+            Value::Bulk(vec![data("request_id"), data("req-1")]),
+        ]);
+        let reply = vec![
+            data("5-0"),
+            Value::Bulk(vec![entry, Value::Nil]),
+            Value::Bulk(vec![]),
+        ];
+
+        let (cursor, entries) = parse_autoclaim(reply).unwrap();
+        assert_eq!(cursor, "5-0");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "1-0");
+        assert_eq!(field(&entries[0].1, "request_id").as_deref(), Some("req-1"));
+    }
+
+    #[tokio::test]
+    async fn routes_to_an_agent_with_the_requested_capability() {
+        let agents = AgentRegistry::new();
+        agents.register_from_config(AgentConfig {
+            agent_name: "coder".into(),
+            capabilities: vec!["code_gen".into()],
+            endpoint: Some(stub_agent().await),
+            ..AgentConfig::default()
+        });
+        let router = RequestRouter::new(RouterOptions::new("redis://localhost"), agents).unwrap();
+
+        let request = RouteRequest {
+            request_id: "req-1".into(),
+            capability: "code_gen".into(),
+            tenant: DEFAULT_TENANT.into(),
+            payload: json!({ "prompt": "fn main" }),
+        };
+        let response = router.route(&request).await;
+        assert_eq!(response["status"], "completed");
+        assert_eq!(response["agent"], "coder");
+        assert_eq!(response["response"]["echo"]["prompt"], "fn main");
+
+        let unroutable = RouteRequest {
+            capability: "vision".into(),
+            ..request
+        };
+        let response = router.route(&unroutable).await;
+        assert_eq!(response["status"], "failed");
+    }
+}
@@ -0,0 +1,42 @@
+use super::{Session, SessionStore};
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+
+/// Process-local store; sessions are lost on restart.
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn load(&self, id: &str) -> Result<Option<Session>> {
+        Ok(self.sessions.read().get(id).cloned())
+    }
+
+    async fn save(&self, session: &Session) -> Result<()> {
+        self.sessions
+            .write()
+            .insert(session.id.clone(), session.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Ok(self.sessions.write().remove(id).is_some())
+    }
+
+    async fn purge(&self, agent: &str, cutoff: u64) -> Result<usize> {
+        let mut sessions = self.sessions.write();
+        let before = sessions.len();
+        sessions.retain(|_, session| session.agent != agent || session.updated_at >= cutoff);
+        Ok(before - sessions.len())
+    }
+}
@@ -0,0 +1,561 @@
+//! Conversation sessions and context-window assembly
+//!
+//! Sessions keep the message history of a conversation so multi-turn clients
+//! only send the newest message. Histories are capped per agent; messages
+//! falling off the cap are handed to a [`Summarizer`] before being dropped.
+
+mod memory;
+mod sqlite;
+
+pub use memory::InMemorySessionStore;
+pub use sqlite::SqliteSessionStore;
+
+use crate::metering::{ApproximateTokenizer, Tokenizer};
+use crate::platform::config::{SessionBackend, SessionSettings};
+use crate::platform::service::ServiceRegistration;
+use crate::prompts::ChatFormat;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+    pub tokens: usize,
+    pub created_at: u64,
+}
+
+impl Message {
+    pub fn new<C: Into<String>>(role: Role, content: C) -> Self {
+        let content = content.into();
+        Self {
+            role,
+            tokens: estimate_tokens(&content),
+            content,
+            created_at: crate::utils::timestamp_now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub agent: String,
+    pub messages: Vec<Message>,
+    /// Condensed form of messages that no longer fit the history cap.
+    pub summary: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl Session {
+    pub fn new<I: Into<String>, A: Into<String>>(id: I, agent: A) -> Self {
+        let now = crate::utils::timestamp_now();
+        Self {
+            id: id.into(),
+            agent: agent.into(),
+            messages: Vec::new(),
+            summary: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Per-agent limits on how much conversation state is kept and replayed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryPolicy {
+    /// Messages kept per session before the oldest are summarized away.
+    pub max_messages: usize,
+    /// Token budget for the context window sent to the model.
+    pub context_tokens: usize,
+    /// Sessions idle for longer than this are deleted.
+    pub retention_seconds: u64,
+}
+
+impl Default for MemoryPolicy {
+    fn default() -> Self {
+        Self {
+            max_messages: 100,
+            context_tokens: 2048,
+            retention_seconds: 86_400,
+        }
+    }
+}
+
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn load(&self, id: &str) -> Result<Option<Session>>;
+    async fn save(&self, session: &Session) -> Result<()>;
+    async fn delete(&self, id: &str) -> Result<bool>;
+    /// Deletes sessions of `agent` last updated before `cutoff` (unix seconds).
+    async fn purge(&self, agent: &str, cutoff: u64) -> Result<usize>;
+}
+
+/// Folds messages dropped from a session into its running summary.
+pub trait Summarizer: Send + Sync {
+    fn summarize(&self, previous: Option<&str>, dropped: &[Message]) -> Option<String>;
+}
+
+/// Plain truncation: dropped messages are forgotten and the summary is kept as is.
+#[derive(Debug, Default)]
+pub struct NoSummary;
+
+impl Summarizer for NoSummary {
+    fn summarize(&self, previous: Option<&str>, _dropped: &[Message]) -> Option<String> {
+        previous.map(str::to_string)
+    }
+}
+
+/// Messages selected to fit an agent's token budget, oldest first.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ContextWindow {
+    pub messages: Vec<Message>,
+    pub tokens: usize,
+    /// History messages left out because they did not fit.
+    pub truncated: usize,
+}
+
+impl ContextWindow {
+    /// Keeps the system prompt and summary, then as many recent messages as fit
+    /// `budget`. The newest message is always included.
+    pub fn assemble(session: &Session, system_prompt: &str, budget: usize) -> Self {
+        let mut preamble = Vec::new();
+        if !system_prompt.is_empty() {
+            preamble.push(Message::new(Role::System, system_prompt));
+        }
+        if let Some(summary) = &session.summary {
+            preamble.push(Message::new(
+                Role::System,
+                format!("Summary of the earlier conversation: {}", summary),
+            ));
+        }
+
+        let mut tokens: usize = preamble.iter().map(|m| m.tokens).sum();
+        let mut recent = Vec::new();
+        for message in session.messages.iter().rev() {
+            if !recent.is_empty() && tokens + message.tokens > budget {
+                break;
+            }
+            tokens += message.tokens;
+            recent.push(message.clone());
+        }
+
+        let truncated = session.messages.len() - recent.len();
+        recent.reverse();
+        preamble.extend(recent);
+
+        Self {
+            messages: preamble,
+            tokens,
+            truncated,
+        }
+    }
+
+    /// Plain `role: content` transcript ending with the assistant's turn.
+    pub fn to_prompt(&self) -> String {
+        ChatFormat::Plain.render(&self.messages)
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionManager {
+    store: Arc<dyn SessionStore>,
+    summarizer: Arc<dyn Summarizer>,
+    /// One lock per session with a turn in progress, so concurrent turns of
+    /// a conversation don't overwrite each other's load-modify-save.
+    append_locks: Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+}
+
+impl SessionManager {
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self {
+            store,
+            summarizer: Arc::new(NoSummary),
+            append_locks: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Opens the configured store; `migrate` follows `[storage] migrate_on_start`.
+    pub fn from_settings(settings: &SessionSettings, migrate: bool) -> Result<Self> {
+        let store: Arc<dyn SessionStore> = match settings.backend {
+            SessionBackend::Memory => Arc::new(InMemorySessionStore::new()),
+            SessionBackend::Sqlite => {
+                Arc::new(SqliteSessionStore::open(&settings.sqlite_path, migrate)?)
+            }
+        };
+        Ok(Self::new(store))
+    }
+
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = summarizer;
+        self
+    }
+
+    /// Loads `id`, treating sessions past the retention window as gone.
+    pub async fn get(&self, id: &str, policy: &MemoryPolicy) -> Result<Option<Session>> {
+        let session = self.store.load(id).await?;
+        Ok(session.filter(|session| !is_expired(session, policy)))
+    }
+
+    /// Appends `message` to `id`, creating the session for `agent` if needed.
+    pub async fn append(
+        &self,
+        id: &str,
+        agent: &str,
+        policy: &MemoryPolicy,
+        message: Message,
+    ) -> Result<Session> {
+        let mut turn = self.begin_turn(id, agent, policy).await?;
+        turn.push(message);
+        turn.save().await
+    }
+
+    /// Locks `id` for one turn of the conversation and loads it, creating the
+    /// session for `agent` if needed. Other turns and appends wait until the
+    /// returned turn is saved or dropped; a dropped turn changes nothing.
+    pub async fn begin_turn(
+        &self,
+        id: &str,
+        agent: &str,
+        policy: &MemoryPolicy,
+    ) -> Result<SessionTurn> {
+        let lock = self
+            .append_locks
+            .lock()
+            .entry(id.to_string())
+            .or_default()
+            .clone();
+        let guard = lock.clone().lock_owned().await;
+        let mut turn = SessionTurn {
+            id: id.to_string(),
+            manager: self.clone(),
+            policy: policy.clone(),
+            session: Session::new(id, agent),
+            lock,
+            guard: Some(guard),
+        };
+        match self.get(id, policy).await? {
+            Some(session) if session.agent != agent => {
+                bail!("session {} belongs to agent {}", id, session.agent)
+            }
+            Some(session) => turn.session = session,
+            None => {}
+        }
+        Ok(turn)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        self.store.delete(id).await
+    }
+
+    pub async fn purge_expired(&self, agent: &str, policy: &MemoryPolicy) -> Result<usize> {
+        let cutoff = crate::utils::timestamp_now().saturating_sub(policy.retention_seconds);
+        self.store.purge(agent, cutoff).await
+    }
+}
+
+/// A session locked for one turn, see [`SessionManager::begin_turn`].
+pub struct SessionTurn {
+    id: String,
+    manager: SessionManager,
+    policy: MemoryPolicy,
+    session: Session,
+    lock: Arc<tokio::sync::Mutex<()>>,
+    guard: Option<tokio::sync::OwnedMutexGuard<()>>,
+}
+
+impl SessionTurn {
+    /// The session as it will be saved.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Adds `message`, summarizing whatever falls off the history cap.
+    pub fn push(&mut self, message: Message) {
+        let session = &mut self.session;
+        session.messages.push(message);
+        if session.messages.len() > self.policy.max_messages {
+            let overflow = session.messages.len() - self.policy.max_messages;
+            let dropped: Vec<Message> = session.messages.drain(..overflow).collect();
+            session.summary = self
+                .manager
+                .summarizer
+                .summarize(session.summary.as_deref(), &dropped);
+        }
+    }
+
+    /// Stores the session and ends the turn.
+    pub async fn save(mut self) -> Result<Session> {
+        self.session.updated_at = crate::utils::timestamp_now();
+        self.manager.store.save(&self.session).await?;
+        Ok(self.session.clone())
+    }
+}
+
+impl Drop for SessionTurn {
+    fn drop(&mut self) {
+        let mut locks = self.manager.append_locks.lock();
+        drop(self.guard.take());
+        // Forget the lock once no other turn holds or waits on it.
+        if Arc::strong_count(&self.lock) == 2 {
+            locks.remove(&self.id);
+        }
+    }
+}
+
+/// Periodically deletes sessions past their agent's retention window.
+pub fn session_retention_service() -> ServiceRegistration {
+    ServiceRegistration::new(
+        "session-retention",
+        Arc::new(|context, token| {
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(PURGE_INTERVAL);
+                loop {
+                    tokio::select! {
+                        _ = token.cancelled() => return Ok(()),
+                        _ = interval.tick() => {}
+                    }
+
+                    let sessions = context.sessions();
+                    for (name, agent) in context.config().agent_catalog() {
+                        match sessions.purge_expired(&name, &agent.memory).await {
+                            Ok(0) => {}
+                            Ok(purged) => info!(agent = %name, purged, "expired sessions removed"),
+                            Err(err) => warn!(agent = %name, error = %err, "session purge failed"),
+                        }
+                    }
+                }
+            })
+        }),
+    )
+}
+
+/// Token count from the inference engine's default tokenizer.
+pub fn estimate_tokens(text: &str) -> usize {
+    ApproximateTokenizer.count(text).max(1)
+}
+
+fn is_expired(session: &Session, policy: &MemoryPolicy) -> bool {
+    let idle = crate::utils::timestamp_now().saturating_sub(session.updated_at);
+    idle > policy.retention_seconds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CountingSummarizer;
+
+    impl Summarizer for CountingSummarizer {
+        fn summarize(&self, previous: Option<&str>, dropped: &[Message]) -> Option<String> {
+            let before: usize = previous.and_then(|p| p.parse().ok()).unwrap_or(0);
+            Some((before + dropped.len()).to_string())
+        }
+    }
+
+    /// Yields between load and save so unserialized appends interleave.
+    struct YieldingStore(InMemorySessionStore);
+
+    #[async_trait]
+    impl SessionStore for YieldingStore {
+        async fn load(&self, id: &str) -> Result<Option<Session>> {
+            let session = self.0.load(id).await;
+            tokio::task::yield_now().await;
+            session
+        }
+
+        async fn save(&self, session: &Session) -> Result<()> {
+            self.0.save(session).await
+        }
+
+        async fn delete(&self, id: &str) -> Result<bool> {
+            self.0.delete(id).await
+        }
+
+        async fn purge(&self, agent: &str, cutoff: u64) -> Result<usize> {
+            self.0.purge(agent, cutoff).await
+        }
+    }
+
+    fn policy(max_messages: usize) -> MemoryPolicy {
+        MemoryPolicy {
+            max_messages,
+            ..MemoryPolicy::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn history_is_capped_and_overflow_is_summarized() {
+        let manager = SessionManager::new(Arc::new(InMemorySessionStore::new()))
+            .with_summarizer(Arc::new(CountingSummarizer));
+
+        for turn in 0..5 {
+            manager
+                .append(
+                    "s1",
+                    "assistant",
+                    &policy(3),
+                    Message::new(Role::User, format!("turn {}", turn)),
+                )
+                .await
+                .unwrap();
+        }
+
+        let session = manager.get("s1", &policy(3)).await.unwrap().unwrap();
+        assert_eq!(session.messages.len(), 3);
+        assert_eq!(session.messages[0].content, "turn 2");
+        assert_eq!(session.summary.as_deref(), Some("2"));
+    }
+
+    #[tokio::test]
+    async fn concurrent_appends_keep_every_message() {
+        let manager = SessionManager::new(Arc::new(YieldingStore(InMemorySessionStore::new())));
+
+        let appends: Vec<_> = (0..20)
+            .map(|turn| {
+                let manager = manager.clone();
+                tokio::spawn(async move {
+                    manager
+                        .append(
+                            "s1",
+                            "assistant",
+                            &policy(100),
+                            Message::new(Role::User, format!("turn {}", turn)),
+                        )
+                        .await
+                        .unwrap();
+                })
+            })
+            .collect();
+        for append in appends {
+            append.await.unwrap();
+        }
+
+        let session = manager.get("s1", &policy(100)).await.unwrap().unwrap();
+        assert_eq!(session.messages.len(), 20);
+        assert!(manager.append_locks.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn turns_hold_the_session_until_saved_or_dropped() {
+        let manager = SessionManager::new(Arc::new(InMemorySessionStore::new()));
+        let policy = policy(100);
+
+        let mut turn = manager
+            .begin_turn("s1", "assistant", &policy)
+            .await
+            .unwrap();
+        turn.push(Message::new(Role::User, "first"));
+        let append = tokio::spawn({
+            let (manager, policy) = (manager.clone(), policy.clone());
+            async move {
+                manager
+                    .append(
+                        "s1",
+                        "assistant",
+                        &policy,
+                        Message::new(Role::User, "second"),
+                    )
+                    .await
+                    .unwrap()
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!append.is_finished());
+
+        // A failed turn leaves no trace once dropped.
+        drop(turn);
+        let session = append.await.unwrap();
+        let contents: Vec<&str> = session
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(contents, ["second"]);
+
+        let mut turn = manager
+            .begin_turn("s1", "assistant", &policy)
+            .await
+            .unwrap();
+        turn.push(Message::new(Role::User, "question"));
+        turn.push(Message::new(Role::Assistant, "answer"));
+        assert_eq!(turn.save().await.unwrap().messages.len(), 3);
+        assert!(manager.append_locks.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sessions_are_bound_to_their_agent() {
+        let manager = SessionManager::new(Arc::new(InMemorySessionStore::new()));
+        let policy = MemoryPolicy::default();
+
+        manager
+            .append("s1", "coder", &policy, Message::new(Role::User, "hi"))
+            .await
+            .unwrap();
+        let err = manager
+            .append("s1", "writer", &policy, Message::new(Role::User, "hi"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("belongs to agent coder"));
+    }
+
+    #[test]
+    fn context_window_keeps_newest_messages_within_budget() {
+        let mut session = Session::new("s1", "assistant");
+        session.summary = Some("greetings".into());
+        for text in ["one two three", "four five", "six seven eight nine", "ten"] {
+            session.messages.push(Message::new(Role::User, text));
+        }
+
+        // system (4) + summary (13) leaves room for the last two messages (6 + 1) only.
+        let window = ContextWindow::assemble(&session, "be very brief", 24);
+        let contents: Vec<&str> = window.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents.len(), 4);
+        assert_eq!(&contents[2..], ["six seven eight nine", "ten"]);
+        assert_eq!(window.truncated, 2);
+        assert!(window.to_prompt().ends_with("user: ten\nassistant:"));
+    }
+
+    #[test]
+    fn newest_message_is_kept_even_over_budget() {
+        let mut session = Session::new("s1", "assistant");
+        session
+            .messages
+            .push(Message::new(Role::User, "a very long question indeed"));
+
+        let window = ContextWindow::assemble(&session, "", 1);
+        assert_eq!(window.messages.len(), 1);
+        assert_eq!(window.truncated, 0);
+    }
+}
@@ -0,0 +1,183 @@
+use super::{Message, Session, SessionStore};
+use crate::storage::migrations;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Durable store backed by a SQLite file.
+#[derive(Clone)]
+pub struct SqliteSessionStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSessionStore {
+    /// Opens the store at `path`, applying pending migrations when `migrate`
+    /// is set and otherwise refusing an outdated schema.
+    pub fn open<P: AsRef<Path>>(path: P, migrate: bool) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open session database {}", path.display()))?;
+        Self::with_connection(conn, migrate)
+            .with_context(|| format!("session database {} is not usable", path.display()))
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, true)
+    }
+
+    /// Conversations live in the `conversations` table of the platform schema.
+    fn with_connection(mut conn: Connection, migrate: bool) -> Result<Self> {
+        conn.busy_timeout(migrations::BUSY_TIMEOUT)?;
+        if migrate {
+            migrations::migrate(&mut conn)?;
+        } else {
+            migrations::check(&conn)?;
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn blocking<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || f(&conn.lock())).await?
+    }
+}
+
+#[async_trait]
+impl SessionStore for SqliteSessionStore {
+    async fn load(&self, id: &str) -> Result<Option<Session>> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            let row = conn
+                .query_row(
+                    "SELECT agent_id, messages, metadata, created_at, updated_at
+                     FROM conversations WHERE id = ?1",
+                    params![id],
+                    |row| {
+                        Ok((
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, Option<String>>(2)?,
+                            row.get::<_, i64>(3)?,
+                            row.get::<_, i64>(4)?,
+                        ))
+                    },
+                )
+                .optional()?;
+
+            let Some((agent, messages, metadata, created_at, updated_at)) = row else {
+                return Ok(None);
+            };
+            let messages: Vec<Message> = serde_json::from_str(&messages)
+                .with_context(|| format!("corrupt message history for session {}", id))?;
+            let summary = metadata
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                .and_then(|m| m["summary"].as_str().map(str::to_string));
+
+            Ok(Some(Session {
+                id,
+                agent,
+                messages,
+                summary,
+                created_at: created_at as u64,
+                updated_at: updated_at as u64,
+            }))
+        })
+        .await
+    }
+
+    async fn save(&self, session: &Session) -> Result<()> {
+        let session = session.clone();
+        self.blocking(move |conn| {
+            let messages = serde_json::to_string(&session.messages)?;
+            let metadata = serde_json::json!({ "summary": session.summary }).to_string();
+            conn.execute(
+                "INSERT INTO conversations (id, agent_id, messages, metadata, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(id) DO UPDATE SET
+                     messages = excluded.messages,
+                     metadata = excluded.metadata,
+                     updated_at = excluded.updated_at",
+                params![
+                    session.id,
+                    session.agent,
+                    messages,
+                    metadata,
+                    session.created_at as i64,
+                    session.updated_at as i64,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            Ok(conn.execute("DELETE FROM conversations WHERE id = ?1", params![id])? > 0)
+        })
+        .await
+    }
+
+    async fn purge(&self, agent: &str, cutoff: u64) -> Result<usize> {
+        let agent = agent.to_string();
+        self.blocking(move |conn| {
+            Ok(conn.execute(
+                "DELETE FROM conversations WHERE agent_id = ?1 AND updated_at < ?2",
+                params![agent, cutoff.min(i64::MAX as u64) as i64],
+            )?)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sessions::Role;
+
+    #[tokio::test]
+    async fn sessions_round_trip_through_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.db");
+
+        let mut session = Session::new("s1", "assistant");
+        session.messages.push(Message::new(Role::User, "hello"));
+        session.summary = Some("earlier".into());
+        SqliteSessionStore::open(&path, true)
+            .unwrap()
+            .save(&session)
+            .await
+            .unwrap();
+
+        // Reopen to make sure the history survived on disk.
+        let store = SqliteSessionStore::open(&path, false).unwrap();
+        assert_eq!(store.load("s1").await.unwrap(), Some(session));
+        assert_eq!(store.purge("assistant", u64::MAX).await.unwrap(), 1);
+        assert!(store.load("s1").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn outdated_databases_are_refused_without_migrating() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions.db");
+        Connection::open(&path).unwrap();
+
+        assert!(SqliteSessionStore::open(&path, false).is_err());
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(migrations::status(&conn).unwrap().current, 0);
+    }
+}
@@ -0,0 +1,143 @@
+use super::{
+    AgentRepository, ModelArtifactRepository, TaskRepository, TrainingJobRepository,
+    WebhookRepository,
+};
+use crate::agents::Agent;
+use crate::events::webhooks::Webhook;
+use crate::orchestration::Task;
+use crate::training::{ModelArtifact, TrainingJob};
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+
+/// Process-local repositories; everything is lost on restart.
+#[derive(Debug, Default)]
+pub struct InMemoryRepository {
+    agents: RwLock<HashMap<String, Agent>>,
+    tasks: RwLock<HashMap<String, Task>>,
+    training_jobs: RwLock<HashMap<String, TrainingJob>>,
+    artifacts: RwLock<HashMap<String, ModelArtifact>>,
+    webhooks: RwLock<HashMap<String, Webhook>>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AgentRepository for InMemoryRepository {
+    async fn save_agent(&self, agent: &Agent) -> Result<()> {
+        self.agents.write().insert(agent.id.clone(), agent.clone());
+        Ok(())
+    }
+
+    async fn load_agents(&self) -> Result<Vec<Agent>> {
+        Ok(self.agents.read().values().cloned().collect())
+    }
+
+    async fn delete_agent(&self, id: &str) -> Result<bool> {
+        Ok(self.agents.write().remove(id).is_some())
+    }
+}
+
+#[async_trait]
+impl TaskRepository for InMemoryRepository {
+    async fn save_task(&self, task: &Task) -> Result<()> {
+        self.tasks.write().insert(task.id.clone(), task.clone());
+        Ok(())
+    }
+
+    async fn load_task(&self, id: &str) -> Result<Option<Task>> {
+        Ok(self.tasks.read().get(id).cloned())
+    }
+
+    async fn load_tasks(&self) -> Result<Vec<Task>> {
+        let mut tasks: Vec<Task> = self.tasks.read().values().cloned().collect();
+        tasks.sort_by_key(|task| task.created_at);
+        Ok(tasks)
+    }
+}
+
+#[async_trait]
+impl TrainingJobRepository for InMemoryRepository {
+    async fn save_training_job(&self, job: &TrainingJob) -> Result<()> {
+        self.training_jobs
+            .write()
+            .insert(job.id.clone(), job.clone());
+        Ok(())
+    }
+
+    async fn load_training_job(&self, id: &str) -> Result<Option<TrainingJob>> {
+        Ok(self.training_jobs.read().get(id).cloned())
+    }
+
+    async fn list_training_jobs(&self, tenant: Option<&str>) -> Result<Vec<TrainingJob>> {
+        let mut jobs: Vec<TrainingJob> = self
+            .training_jobs
+            .read()
+            .values()
+            .filter(|job| tenant.is_none_or(|tenant| job.tenant == tenant))
+            .cloned()
+            .collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        Ok(jobs)
+    }
+}
+
+#[async_trait]
+impl ModelArtifactRepository for InMemoryRepository {
+    async fn save_artifact(&self, artifact: &ModelArtifact) -> Result<()> {
+        self.artifacts
+            .write()
+            .insert(artifact.id.clone(), artifact.clone());
+        Ok(())
+    }
+
+    async fn load_artifact(&self, id: &str) -> Result<Option<ModelArtifact>> {
+        Ok(self.artifacts.read().get(id).cloned())
+    }
+
+    async fn list_artifacts(&self) -> Result<Vec<ModelArtifact>> {
+        let mut artifacts: Vec<ModelArtifact> = self.artifacts.read().values().cloned().collect();
+        artifacts.sort_by_key(|artifact| std::cmp::Reverse(artifact.created_at));
+        Ok(artifacts)
+    }
+
+    async fn activate_artifact(&self, id: &str) -> Result<bool> {
+        let mut artifacts = self.artifacts.write();
+        let Some(name) = artifacts.get(id).map(|artifact| artifact.name.clone()) else {
+            return Ok(false);
+        };
+        for artifact in artifacts.values_mut().filter(|a| a.name == name) {
+            artifact.is_active = artifact.id == id;
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for InMemoryRepository {
+    async fn save_webhook(&self, webhook: &Webhook) -> Result<()> {
+        self.webhooks
+            .write()
+            .insert(webhook.id.clone(), webhook.clone());
+        Ok(())
+    }
+
+    async fn load_webhook(&self, id: &str) -> Result<Option<Webhook>> {
+        Ok(self.webhooks.read().get(id).cloned())
+    }
+
+    async fn load_webhooks(&self) -> Result<Vec<Webhook>> {
+        let mut webhooks: Vec<Webhook> = self.webhooks.read().values().cloned().collect();
+        webhooks.sort_by_key(|webhook| webhook.created_at);
+        Ok(webhooks)
+    }
+
+    async fn delete_webhook(&self, id: &str) -> Result<bool> {
+        Ok(self.webhooks.write().remove(id).is_some())
+    }
+}
@@ -0,0 +1,248 @@
+//! Embedded schema migrations
+//!
+//! Version 1 is the SQLite equivalent of the Alembic revision in
+//! `src/migrations/versions/001_initial_schema.py`; later versions extend it
+//! with what the Rust platform needs. Applied versions are recorded in
+//! `schema_migrations`.
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::time::Duration;
+
+/// How long a connection waits for another one sharing the file, e.g. the
+/// session store and the platform repositories.
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+/// Timestamps are unix seconds and JSON columns hold serialized text.
+/// Version 1 uses `IF NOT EXISTS` so session databases created before
+/// migrations existed are adopted as they are.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: "
+CREATE TABLE IF NOT EXISTS agents (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    agent_type TEXT NOT NULL,
+    status TEXT DEFAULT 'inactive',
+    config TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    last_seen INTEGER,
+    version TEXT DEFAULT '1.0.0'
+);
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    user_id TEXT,
+    agent_id TEXT NOT NULL,
+    messages TEXT NOT NULL,
+    metadata TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS model_artifacts (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    model_type TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    metadata TEXT,
+    is_active INTEGER DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    version TEXT DEFAULT '1.0.0'
+);
+CREATE TABLE IF NOT EXISTS training_jobs (
+    id TEXT PRIMARY KEY,
+    model_id TEXT REFERENCES model_artifacts (id),
+    status TEXT DEFAULT 'pending',
+    config TEXT NOT NULL,
+    metrics TEXT,
+    error_message TEXT,
+    created_at INTEGER NOT NULL,
+    started_at INTEGER,
+    completed_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS ix_agents_status ON agents (status);
+CREATE INDEX IF NOT EXISTS ix_agents_type ON agents (agent_type);
+CREATE INDEX IF NOT EXISTS ix_conversations_user_id ON conversations (user_id);
+CREATE INDEX IF NOT EXISTS ix_conversations_agent_id ON conversations (agent_id);
+CREATE INDEX IF NOT EXISTS ix_model_artifacts_active ON model_artifacts (is_active);
+CREATE INDEX IF NOT EXISTS ix_training_jobs_status ON training_jobs (status);
+",
+    },
+    Migration {
+        version: 2,
+        name: "tasks_and_tenants",
+        sql: "
+ALTER TABLE agents ADD COLUMN tenant TEXT;
+ALTER TABLE agents ADD COLUMN metrics TEXT;
+ALTER TABLE training_jobs ADD COLUMN tenant TEXT NOT NULL DEFAULT 'default';
+CREATE TABLE tasks (
+    id TEXT PRIMARY KEY,
+    task_type TEXT NOT NULL,
+    tenant TEXT NOT NULL,
+    input TEXT NOT NULL,
+    status TEXT NOT NULL,
+    assigned_agent TEXT,
+    result TEXT,
+    error TEXT,
+    created_at INTEGER NOT NULL,
+    completed_at INTEGER
+);
+CREATE INDEX ix_tasks_status ON tasks (status);
+CREATE INDEX ix_training_jobs_tenant ON training_jobs (tenant);
+",
+    },
+    Migration {
+        version: 3,
+        name: "webhooks",
+        sql: "
+CREATE TABLE webhooks (
+    id TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    topics TEXT NOT NULL,
+    tenant TEXT,
+    secret TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+",
+    },
+];
+
+const TRACKING_TABLE: &str = "
+CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at INTEGER NOT NULL
+);
+";
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SchemaStatus {
+    pub current: u32,
+    pub latest: u32,
+}
+
+impl SchemaStatus {
+    pub fn is_current(&self) -> bool {
+        self.current == self.latest
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &'static Migration> + '_ {
+        MIGRATIONS
+            .iter()
+            .filter(move |migration| migration.version > self.current)
+    }
+}
+
+/// Schema version of `conn`, without changing the database.
+pub fn status(conn: &Connection) -> Result<SchemaStatus> {
+    let tracked = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_migrations'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    let current = if tracked {
+        conn.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )?
+    } else {
+        0
+    };
+    Ok(SchemaStatus {
+        current,
+        latest: latest_version(),
+    })
+}
+
+/// Fails unless `conn` is at exactly the schema this build expects.
+pub fn check(conn: &Connection) -> Result<SchemaStatus> {
+    let status = status(conn)?;
+    if status.current > status.latest {
+        bail!(
+            "database schema version {} is newer than the latest this build knows ({})",
+            status.current,
+            status.latest
+        );
+    }
+    if !status.is_current() {
+        bail!(
+            "database schema is at version {} but {} is required; run `chimera db migrate`",
+            status.current,
+            status.latest
+        );
+    }
+    Ok(status)
+}
+
+/// Applies every pending migration, each in its own transaction, and
+/// returns the versions applied.
+pub fn migrate(conn: &mut Connection) -> Result<Vec<u32>> {
+    conn.execute_batch(TRACKING_TABLE)?;
+    let status = status(conn)?;
+    if status.current > status.latest {
+        check(conn)?;
+    }
+
+    let mut applied = Vec::new();
+    for migration in status.pending() {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql).with_context(|| {
+            format!(
+                "migration {} ({}) failed",
+                migration.version, migration.name
+            )
+        })?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES (?1, ?2, ?3)",
+            params![
+                migration.version,
+                migration.name,
+                crate::utils::timestamp_now() as i64
+            ],
+        )?;
+        tx.commit()?;
+        applied.push(migration.version);
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_apply_once_and_are_checked() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        assert_eq!(status(&conn).unwrap().current, 0);
+        assert!(check(&conn).is_err());
+
+        assert_eq!(migrate(&mut conn).unwrap(), [1, 2, 3]);
+        assert!(migrate(&mut conn).unwrap().is_empty());
+        assert!(check(&conn).unwrap().is_current());
+
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name, applied_at) VALUES (99, 'future', 0)",
+            [],
+        )
+        .unwrap();
+        assert!(migrate(&mut conn).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+//! Persistent platform state
+//!
+//! Agents, orchestrator tasks, training jobs, model artifacts and webhook
+//! subscriptions are saved through the repository traits here, backed by
+//! SQLite or, by default, process memory. Conversations are persisted by the
+//! session store, whose SQLite backend runs the same [`migrations`], so
+//! `[sessions]` and `[storage]` may share one database file.
+
+mod memory;
+pub mod migrations;
+mod sqlite;
+
+pub use crate::sessions::SessionStore as ConversationRepository;
+pub use memory::InMemoryRepository;
+pub use sqlite::SqliteRepository;
+
+use crate::agents::Agent;
+use crate::events::webhooks::Webhook;
+use crate::orchestration::Task;
+use crate::training::{ModelArtifact, TrainingJob};
+use anyhow::Result;
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[async_trait]
+pub trait AgentRepository: Send + Sync {
+    async fn save_agent(&self, agent: &Agent) -> Result<()>;
+    async fn load_agents(&self) -> Result<Vec<Agent>>;
+    async fn delete_agent(&self, id: &str) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait TaskRepository: Send + Sync {
+    async fn save_task(&self, task: &Task) -> Result<()>;
+    async fn load_task(&self, id: &str) -> Result<Option<Task>>;
+    /// Every stored task, oldest first.
+    async fn load_tasks(&self) -> Result<Vec<Task>>;
+}
+
+#[async_trait]
+pub trait TrainingJobRepository: Send + Sync {
+    async fn save_training_job(&self, job: &TrainingJob) -> Result<()>;
+    async fn load_training_job(&self, id: &str) -> Result<Option<TrainingJob>>;
+    /// Jobs of `tenant`, or of every tenant, newest first.
+    async fn list_training_jobs(&self, tenant: Option<&str>) -> Result<Vec<TrainingJob>>;
+}
+
+#[async_trait]
+pub trait ModelArtifactRepository: Send + Sync {
+    async fn save_artifact(&self, artifact: &ModelArtifact) -> Result<()>;
+    async fn load_artifact(&self, id: &str) -> Result<Option<ModelArtifact>>;
+    /// Artifacts newest first.
+    async fn list_artifacts(&self) -> Result<Vec<ModelArtifact>>;
+    /// Marks `id` active and every other artifact with its name inactive.
+    async fn activate_artifact(&self, id: &str) -> Result<bool>;
+}
+
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    async fn save_webhook(&self, webhook: &Webhook) -> Result<()>;
+    async fn load_webhook(&self, id: &str) -> Result<Option<Webhook>>;
+    /// Webhooks oldest first.
+    async fn load_webhooks(&self) -> Result<Vec<Webhook>>;
+    async fn delete_webhook(&self, id: &str) -> Result<bool>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Memory,
+    Sqlite,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    pub backend: StorageBackend,
+    /// Database file used when `backend = "sqlite"`.
+    pub sqlite_path: String,
+    /// Apply pending migrations at startup; otherwise refuse to start until
+    /// `chimera db migrate` has been run.
+    pub migrate_on_start: bool,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Memory,
+            sqlite_path: "data/chimera.db".to_string(),
+            migrate_on_start: true,
+        }
+    }
+}
+
+/// One handle per kind of persisted state, all served by the same backend.
+#[derive(Clone)]
+pub struct Repositories {
+    pub agents: Arc<dyn AgentRepository>,
+    pub tasks: Arc<dyn TaskRepository>,
+    pub training_jobs: Arc<dyn TrainingJobRepository>,
+    pub artifacts: Arc<dyn ModelArtifactRepository>,
+    pub webhooks: Arc<dyn WebhookRepository>,
+}
+
+impl Repositories {
+    pub fn in_memory() -> Self {
+        Self::from_backend(Arc::new(InMemoryRepository::new()))
+    }
+
+    /// Opens the configured backend, migrating or checking its schema first.
+    pub fn from_settings(settings: &StorageSettings) -> Result<Self> {
+        Ok(match settings.backend {
+            StorageBackend::Memory => Self::in_memory(),
+            StorageBackend::Sqlite => Self::from_backend(Arc::new(SqliteRepository::open(
+                &settings.sqlite_path,
+                settings.migrate_on_start,
+            )?)),
+        })
+    }
+
+    fn from_backend<R>(backend: Arc<R>) -> Self
+    where
+        R: AgentRepository
+            + TaskRepository
+            + TrainingJobRepository
+            + ModelArtifactRepository
+            + WebhookRepository
+            + 'static,
+    {
+        Self {
+            agents: backend.clone(),
+            tasks: backend.clone(),
+            training_jobs: backend.clone(),
+            artifacts: backend.clone(),
+            webhooks: backend,
+        }
+    }
+}
+
+impl Default for Repositories {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
@@ -0,0 +1,598 @@
+use super::migrations::{self, SchemaStatus};
+use super::{
+    AgentRepository, ModelArtifactRepository, TaskRepository, TrainingJobRepository,
+    WebhookRepository,
+};
+use crate::agents::{Agent, AgentConfig, AgentMetrics};
+use crate::events::webhooks::Webhook;
+use crate::orchestration::Task;
+use crate::training::{ModelArtifact, TrainingJob};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Durable repositories backed by a SQLite file.
+#[derive(Clone)]
+pub struct SqliteRepository {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteRepository {
+    /// Opens `path`, applying pending migrations when `migrate` is set and
+    /// otherwise failing unless the schema is already current.
+    pub fn open<P: AsRef<Path>>(path: P, migrate: bool) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open database {}", path.display()))?;
+        Self::with_connection(conn, migrate)
+            .with_context(|| format!("database {} is not usable", path.display()))
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?, true)
+    }
+
+    fn with_connection(mut conn: Connection, migrate: bool) -> Result<Self> {
+        conn.busy_timeout(migrations::BUSY_TIMEOUT)?;
+        if migrate {
+            migrations::migrate(&mut conn)?;
+        } else {
+            migrations::check(&conn)?;
+        }
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    pub fn schema_status(&self) -> Result<SchemaStatus> {
+        migrations::status(&self.conn.lock())
+    }
+
+    async fn blocking<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || f(&conn.lock())).await?
+    }
+}
+
+/// Enums are stored by their serde name, e.g. `Maintenance` or `running`.
+fn enum_text<T: Serialize>(value: &T) -> Result<String> {
+    Ok(match serde_json::to_value(value)? {
+        serde_json::Value::String(text) => text,
+        other => other.to_string(),
+    })
+}
+
+fn parse_enum<T: DeserializeOwned>(text: String) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(text.clone()))
+        .with_context(|| format!("unknown value {:?}", text))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn from_json<T: DeserializeOwned>(text: &str) -> Result<T> {
+    Ok(serde_json::from_str(text)?)
+}
+
+fn to_unix(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+fn from_unix(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+const AGENT_COLUMNS: &str = "id, name, agent_type, status, config, tenant, metrics";
+
+fn agent_from_row(row: &Row) -> Result<Agent> {
+    let config: AgentConfig = from_json(&row.get::<_, String>(4)?)?;
+    let metrics = match row.get::<_, Option<String>>(6)? {
+        Some(metrics) => from_json(&metrics)?,
+        None => AgentMetrics::default(),
+    };
+    Ok(Agent {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        agent_type: parse_enum(row.get(2)?)?,
+        status: parse_enum(row.get(3)?)?,
+        capabilities: config.capabilities.clone(),
+        tenant: row.get(5)?,
+        config,
+        metrics,
+    })
+}
+
+#[async_trait]
+impl AgentRepository for SqliteRepository {
+    async fn save_agent(&self, agent: &Agent) -> Result<()> {
+        let agent = agent.clone();
+        self.blocking(move |conn| {
+            let now = crate::utils::timestamp_now() as i64;
+            conn.execute(
+                "INSERT INTO agents (id, name, agent_type, status, config, tenant, metrics,
+                                     created_at, updated_at, last_seen)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9)
+                 ON CONFLICT(id) DO UPDATE SET
+                     name = excluded.name,
+                     agent_type = excluded.agent_type,
+                     status = excluded.status,
+                     config = excluded.config,
+                     tenant = excluded.tenant,
+                     metrics = excluded.metrics,
+                     updated_at = excluded.updated_at,
+                     last_seen = excluded.last_seen",
+                params![
+                    agent.id,
+                    agent.name,
+                    enum_text(&agent.agent_type)?,
+                    enum_text(&agent.status)?,
+                    to_json(&agent.config)?,
+                    agent.tenant,
+                    to_json(&agent.metrics)?,
+                    now,
+                    to_unix(agent.metrics.last_activity),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_agents(&self) -> Result<Vec<Agent>> {
+        self.blocking(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM agents ORDER BY created_at, id",
+                AGENT_COLUMNS
+            ))?;
+            let mut rows = statement.query([])?;
+            let mut agents = Vec::new();
+            while let Some(row) = rows.next()? {
+                agents.push(agent_from_row(row)?);
+            }
+            Ok(agents)
+        })
+        .await
+    }
+
+    async fn delete_agent(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            Ok(conn.execute("DELETE FROM agents WHERE id = ?1", params![id])? > 0)
+        })
+        .await
+    }
+}
+
+const TASK_COLUMNS: &str =
+    "id, task_type, tenant, input, status, assigned_agent, result, error, created_at, completed_at";
+
+fn task_from_row(row: &Row) -> Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        task_type: row.get(1)?,
+        tenant: row.get(2)?,
+        input: from_json(&row.get::<_, String>(3)?)?,
+        status: parse_enum(row.get(4)?)?,
+        assigned_agent: row.get(5)?,
+        result: row
+            .get::<_, Option<String>>(6)?
+            .map(|result| from_json(&result))
+            .transpose()?,
+        error: row.get(7)?,
+        created_at: from_unix(row.get(8)?),
+        completed_at: row.get::<_, Option<i64>>(9)?.map(from_unix),
+    })
+}
+
+#[async_trait]
+impl TaskRepository for SqliteRepository {
+    async fn save_task(&self, task: &Task) -> Result<()> {
+        let task = task.clone();
+        self.blocking(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO tasks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    TASK_COLUMNS
+                ),
+                params![
+                    task.id,
+                    task.task_type,
+                    task.tenant,
+                    to_json(&task.input)?,
+                    enum_text(&task.status)?,
+                    task.assigned_agent,
+                    task.result.as_ref().map(to_json).transpose()?,
+                    task.error,
+                    to_unix(task.created_at),
+                    task.completed_at.map(to_unix),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_task(&self, id: &str) -> Result<Option<Task>> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            let mut statement =
+                conn.prepare(&format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS))?;
+            let mut rows = statement.query(params![id])?;
+            rows.next()?.map(task_from_row).transpose()
+        })
+        .await
+    }
+
+    async fn load_tasks(&self) -> Result<Vec<Task>> {
+        self.blocking(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM tasks ORDER BY created_at, id",
+                TASK_COLUMNS
+            ))?;
+            let mut rows = statement.query([])?;
+            let mut tasks = Vec::new();
+            while let Some(row) = rows.next()? {
+                tasks.push(task_from_row(row)?);
+            }
+            Ok(tasks)
+        })
+        .await
+    }
+}
+
+const JOB_COLUMNS: &str = "id, tenant, model_id, status, config, metrics, error_message,
+                           created_at, started_at, completed_at";
+
+fn job_from_row(row: &Row) -> Result<TrainingJob> {
+    Ok(TrainingJob {
+        id: row.get(0)?,
+        tenant: row.get(1)?,
+        model_id: row.get(2)?,
+        status: parse_enum(row.get(3)?)?,
+        config: from_json(&row.get::<_, String>(4)?)?,
+        metrics: row
+            .get::<_, Option<String>>(5)?
+            .map(|metrics| from_json(&metrics))
+            .transpose()?,
+        error_message: row.get(6)?,
+        created_at: row.get::<_, i64>(7)? as u64,
+        started_at: row.get::<_, Option<i64>>(8)?.map(|at| at as u64),
+        completed_at: row.get::<_, Option<i64>>(9)?.map(|at| at as u64),
+    })
+}
+
+#[async_trait]
+impl TrainingJobRepository for SqliteRepository {
+    async fn save_training_job(&self, job: &TrainingJob) -> Result<()> {
+        let job = job.clone();
+        self.blocking(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO training_jobs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    JOB_COLUMNS
+                ),
+                params![
+                    job.id,
+                    job.tenant,
+                    job.model_id,
+                    enum_text(&job.status)?,
+                    to_json(&job.config)?,
+                    job.metrics.as_ref().map(to_json).transpose()?,
+                    job.error_message,
+                    job.created_at as i64,
+                    job.started_at.map(|at| at as i64),
+                    job.completed_at.map(|at| at as i64),
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_training_job(&self, id: &str) -> Result<Option<TrainingJob>> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM training_jobs WHERE id = ?1",
+                JOB_COLUMNS
+            ))?;
+            let mut rows = statement.query(params![id])?;
+            rows.next()?.map(job_from_row).transpose()
+        })
+        .await
+    }
+
+    async fn list_training_jobs(&self, tenant: Option<&str>) -> Result<Vec<TrainingJob>> {
+        let tenant = tenant.map(str::to_string);
+        self.blocking(move |conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM training_jobs
+                 WHERE ?1 IS NULL OR tenant = ?1
+                 ORDER BY created_at DESC, id",
+                JOB_COLUMNS
+            ))?;
+            let mut rows = statement.query(params![tenant])?;
+            let mut jobs = Vec::new();
+            while let Some(row) = rows.next()? {
+                jobs.push(job_from_row(row)?);
+            }
+            Ok(jobs)
+        })
+        .await
+    }
+}
+
+const ARTIFACT_COLUMNS: &str =
+    "id, name, model_type, file_path, checksum, metadata, is_active, created_at, version";
+
+fn artifact_from_row(row: &Row) -> Result<ModelArtifact> {
+    Ok(ModelArtifact {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        model_type: row.get(2)?,
+        file_path: row.get(3)?,
+        checksum: row.get(4)?,
+        metadata: row
+            .get::<_, Option<String>>(5)?
+            .map(|metadata| from_json(&metadata))
+            .transpose()?,
+        is_active: row.get(6)?,
+        created_at: row.get::<_, i64>(7)? as u64,
+        version: row.get(8)?,
+    })
+}
+
+#[async_trait]
+impl ModelArtifactRepository for SqliteRepository {
+    async fn save_artifact(&self, artifact: &ModelArtifact) -> Result<()> {
+        let artifact = artifact.clone();
+        self.blocking(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO model_artifacts ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    ARTIFACT_COLUMNS
+                ),
+                params![
+                    artifact.id,
+                    artifact.name,
+                    artifact.model_type,
+                    artifact.file_path,
+                    artifact.checksum,
+                    artifact.metadata.as_ref().map(to_json).transpose()?,
+                    artifact.is_active,
+                    artifact.created_at as i64,
+                    artifact.version,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_artifact(&self, id: &str) -> Result<Option<ModelArtifact>> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM model_artifacts WHERE id = ?1",
+                ARTIFACT_COLUMNS
+            ))?;
+            let mut rows = statement.query(params![id])?;
+            rows.next()?.map(artifact_from_row).transpose()
+        })
+        .await
+    }
+
+    async fn list_artifacts(&self) -> Result<Vec<ModelArtifact>> {
+        self.blocking(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM model_artifacts ORDER BY created_at DESC, id",
+                ARTIFACT_COLUMNS
+            ))?;
+            let mut rows = statement.query([])?;
+            let mut artifacts = Vec::new();
+            while let Some(row) = rows.next()? {
+                artifacts.push(artifact_from_row(row)?);
+            }
+            Ok(artifacts)
+        })
+        .await
+    }
+
+    async fn activate_artifact(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            let name: Option<String> = conn
+                .query_row(
+                    "SELECT name FROM model_artifacts WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(name) = name else {
+                return Ok(false);
+            };
+            conn.execute(
+                "UPDATE model_artifacts SET is_active = (id = ?1) WHERE name = ?2",
+                params![id, name],
+            )?;
+            Ok(true)
+        })
+        .await
+    }
+}
+
+const WEBHOOK_COLUMNS: &str = "id, url, topics, tenant, secret, created_at";
+
+fn webhook_from_row(row: &Row) -> Result<Webhook> {
+    Ok(Webhook {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        topics: from_json(&row.get::<_, String>(2)?)?,
+        tenant: row.get(3)?,
+        secret: row.get(4)?,
+        created_at: row.get::<_, i64>(5)? as u64,
+    })
+}
+
+#[async_trait]
+impl WebhookRepository for SqliteRepository {
+    async fn save_webhook(&self, webhook: &Webhook) -> Result<()> {
+        let webhook = webhook.clone();
+        self.blocking(move |conn| {
+            conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO webhooks ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    WEBHOOK_COLUMNS
+                ),
+                params![
+                    webhook.id,
+                    webhook.url,
+                    to_json(&webhook.topics)?,
+                    webhook.tenant,
+                    webhook.secret,
+                    webhook.created_at as i64,
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_webhook(&self, id: &str) -> Result<Option<Webhook>> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM webhooks WHERE id = ?1",
+                WEBHOOK_COLUMNS
+            ))?;
+            let mut rows = statement.query(params![id])?;
+            rows.next()?.map(webhook_from_row).transpose()
+        })
+        .await
+    }
+
+    async fn load_webhooks(&self) -> Result<Vec<Webhook>> {
+        self.blocking(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT {} FROM webhooks ORDER BY created_at, id",
+                WEBHOOK_COLUMNS
+            ))?;
+            let mut rows = statement.query([])?;
+            let mut webhooks = Vec::new();
+            while let Some(row) = rows.next()? {
+                webhooks.push(webhook_from_row(row)?);
+            }
+            Ok(webhooks)
+        })
+        .await
+    }
+
+    async fn delete_webhook(&self, id: &str) -> Result<bool> {
+        let id = id.to_string();
+        self.blocking(move |conn| {
+            Ok(conn.execute("DELETE FROM webhooks WHERE id = ?1", params![id])? > 0)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::AgentRegistry;
+    use crate::orchestration::TaskStatus;
+    use crate::training::TrainingJobStatus;
+
+    #[tokio::test]
+    async fn state_survives_reopening_the_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chimera.db");
+
+        let registry = AgentRegistry::new();
+        let agent_id = registry.register_from_config(AgentConfig::default());
+        let agent = registry.get_agent(&agent_id).unwrap();
+        let task = Task {
+            id: "t1".to_string(),
+            task_type: "chat".to_string(),
+            tenant: "search".to_string(),
+            input: serde_json::json!({ "prompt": "hi" }),
+            status: TaskStatus::Failed,
+            assigned_agent: Some(agent_id.clone()),
+            result: None,
+            error: Some("timeout".to_string()),
+            created_at: from_unix(1_700_000_000),
+            completed_at: Some(from_unix(1_700_000_060)),
+        };
+        let mut job = TrainingJob::new("search", serde_json::json!({ "epochs": 3 }));
+        job.start();
+        let artifact = ModelArtifact {
+            id: "m1".to_string(),
+            name: "adapter".to_string(),
+            model_type: "lora".to_string(),
+            file_path: "out/adapter.safetensors".to_string(),
+            checksum: "abc".to_string(),
+            metadata: None,
+            is_active: false,
+            created_at: 1,
+            version: "1.0.0".to_string(),
+        };
+        {
+            let repo = SqliteRepository::open(&path, true).unwrap();
+            repo.save_agent(&agent).await.unwrap();
+            repo.save_task(&task).await.unwrap();
+            repo.save_training_job(&job).await.unwrap();
+            repo.save_artifact(&artifact).await.unwrap();
+            job.complete("m1", serde_json::json!({ "loss": 0.1 }));
+            repo.save_training_job(&job).await.unwrap();
+        }
+
+        // Without migrate the schema must already be current.
+        let repo = SqliteRepository::open(&path, false).unwrap();
+        let agents = repo.load_agents().await.unwrap();
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].id, agent_id);
+        assert_eq!(agents[0].status, agent.status);
+        assert_eq!(
+            repo.load_tasks().await.unwrap()[0].created_at,
+            task.created_at
+        );
+        assert_eq!(
+            repo.load_task("t1")
+                .await
+                .unwrap()
+                .unwrap()
+                .error
+                .as_deref(),
+            Some("timeout")
+        );
+
+        let jobs = repo.list_training_jobs(Some("search")).await.unwrap();
+        assert_eq!(jobs, [job]);
+        assert_eq!(jobs[0].status, TrainingJobStatus::Completed);
+        assert!(repo
+            .list_training_jobs(Some("ads"))
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(repo.activate_artifact("m1").await.unwrap());
+        assert!(repo.load_artifact("m1").await.unwrap().unwrap().is_active);
+        assert!(!repo.activate_artifact("missing").await.unwrap());
+    }
+}
@@ -0,0 +1,400 @@
+//! Tenants, their quotas and usage accounting
+//!
+//! Every principal belongs to a tenant (`default` unless its API key or
+//! token says otherwise). Requests, inference tokens, orchestrator tasks and
+//! training GPU-hours are counted per tenant and checked against the quota in
+//! `[tenancy]`, so one team cannot starve the others.
+
+use parking_lot::{Mutex, RwLock};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TENANT: &str = "default";
+
+pub(crate) const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Prometheus series name, metric type and the usage field it reports.
+type Series = (&'static str, &'static str, fn(&TenantUsage) -> f64);
+
+/// Limits for one tenant; `None` means unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenantQuota {
+    /// Requests accepted per rolling minute.
+    pub requests_per_minute: Option<u32>,
+    /// Inference tokens per UTC day.
+    pub tokens_per_day: Option<u64>,
+    /// Orchestrator tasks assigned at the same time.
+    pub concurrent_tasks: Option<u32>,
+    /// Training GPU-hours per UTC day.
+    pub training_gpu_hours_per_day: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct TenancySettings {
+    /// Applied to every tenant without an entry of its own.
+    pub default_quota: TenantQuota,
+    pub tenants: HashMap<String, TenantQuota>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuotaError {
+    RequestsPerMinute { tenant: String, limit: u32 },
+    TokensPerDay { tenant: String, limit: u64 },
+    ConcurrentTasks { tenant: String, limit: u32 },
+    GpuHoursPerDay { tenant: String, limit: f64 },
+}
+
+impl std::fmt::Display for QuotaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaError::RequestsPerMinute { tenant, limit } => {
+                write!(
+                    f,
+                    "tenant {} exceeded {} requests per minute",
+                    tenant, limit
+                )
+            }
+            QuotaError::TokensPerDay { tenant, limit } => {
+                write!(f, "tenant {} used its {} tokens for today", tenant, limit)
+            }
+            QuotaError::ConcurrentTasks { tenant, limit } => {
+                write!(f, "tenant {} already runs {} tasks", tenant, limit)
+            }
+            QuotaError::GpuHoursPerDay { tenant, limit } => write!(
+                f,
+                "tenant {} used its {} training GPU-hours for today",
+                tenant, limit
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuotaError {}
+
+/// Accounting for one tenant. Totals are cumulative since startup; the
+/// `_today` fields reset at UTC midnight.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TenantUsage {
+    pub tenant: String,
+    pub requests: u64,
+    pub rejected: u64,
+    pub tokens: u64,
+    pub tokens_today: u64,
+    pub tasks_started: u64,
+    pub active_tasks: u32,
+    pub gpu_hours: f64,
+    pub gpu_hours_today: f64,
+}
+
+struct TenantState {
+    usage: TenantUsage,
+    recent_requests: VecDeque<Instant>,
+    day: u64,
+}
+
+impl TenantState {
+    fn new(tenant: &str) -> Self {
+        Self {
+            usage: TenantUsage {
+                tenant: tenant.to_string(),
+                ..TenantUsage::default()
+            },
+            recent_requests: VecDeque::new(),
+            day: current_day(),
+        }
+    }
+
+    fn roll_day(&mut self) {
+        let today = current_day();
+        if today != self.day {
+            self.day = today;
+            self.usage.tokens_today = 0;
+            self.usage.gpu_hours_today = 0.0;
+        }
+    }
+}
+
+pub(crate) fn current_day() -> u64 {
+    crate::utils::timestamp_now() / SECONDS_PER_DAY
+}
+
+/// Quota enforcement and usage accounting shared by every server in the process.
+#[derive(Clone, Default)]
+pub struct TenantManager {
+    settings: Arc<RwLock<TenancySettings>>,
+    state: Arc<Mutex<HashMap<String, TenantState>>>,
+}
+
+impl TenantManager {
+    pub fn new(settings: TenancySettings) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            state: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Swap in new quotas, keeping the usage counted so far.
+    pub fn reconfigure(&self, settings: TenancySettings) {
+        *self.settings.write() = settings;
+    }
+
+    pub fn quota(&self, tenant: &str) -> TenantQuota {
+        let settings = self.settings.read();
+        settings
+            .tenants
+            .get(tenant)
+            .unwrap_or(&settings.default_quota)
+            .clone()
+    }
+
+    fn with_state<T>(&self, tenant: &str, f: impl FnOnce(&mut TenantState) -> T) -> T {
+        let mut state = self.state.lock();
+        let entry = state
+            .entry(tenant.to_string())
+            .or_insert_with(|| TenantState::new(tenant));
+        entry.roll_day();
+        f(entry)
+    }
+
+    /// Counts a request, refusing it when the tenant is over its request rate
+    /// or has spent its daily tokens.
+    pub fn admit_request(&self, tenant: &str) -> Result<(), QuotaError> {
+        let quota = self.quota(tenant);
+        self.with_state(tenant, |state| {
+            let now = Instant::now();
+            while state
+                .recent_requests
+                .front()
+                .is_some_and(|at| now.duration_since(*at) >= Duration::from_secs(60))
+            {
+                state.recent_requests.pop_front();
+            }
+
+            let refused = if let Some(limit) = quota
+                .requests_per_minute
+                .filter(|limit| state.recent_requests.len() >= *limit as usize)
+            {
+                Some(QuotaError::RequestsPerMinute {
+                    tenant: tenant.to_string(),
+                    limit,
+                })
+            } else {
+                quota
+                    .tokens_per_day
+                    .filter(|limit| state.usage.tokens_today >= *limit)
+                    .map(|limit| QuotaError::TokensPerDay {
+                        tenant: tenant.to_string(),
+                        limit,
+                    })
+            };
+            if let Some(err) = refused {
+                state.usage.rejected += 1;
+                return Err(err);
+            }
+
+            state.recent_requests.push_back(now);
+            state.usage.requests += 1;
+            Ok(())
+        })
+    }
+
+    pub fn record_tokens(&self, tenant: &str, tokens: u64) {
+        self.with_state(tenant, |state| {
+            state.usage.tokens += tokens;
+            state.usage.tokens_today += tokens;
+        });
+    }
+
+    /// Claims a concurrent-task slot; release it with [`Self::finish_task`].
+    pub fn start_task(&self, tenant: &str) -> Result<(), QuotaError> {
+        let quota = self.quota(tenant);
+        self.with_state(tenant, |state| {
+            if let Some(limit) = quota
+                .concurrent_tasks
+                .filter(|limit| state.usage.active_tasks >= *limit)
+            {
+                return Err(QuotaError::ConcurrentTasks {
+                    tenant: tenant.to_string(),
+                    limit,
+                });
+            }
+            state.usage.active_tasks += 1;
+            state.usage.tasks_started += 1;
+            Ok(())
+        })
+    }
+
+    pub fn finish_task(&self, tenant: &str) {
+        self.with_state(tenant, |state| {
+            state.usage.active_tasks = state.usage.active_tasks.saturating_sub(1);
+        });
+    }
+
+    /// Refuses a training run once today's GPU-hours are spent. `used_today` comes
+    /// from the stored training jobs, since each trainer run is its own process.
+    pub fn admit_training(&self, tenant: &str, used_today: f64) -> Result<(), QuotaError> {
+        let quota = self.quota(tenant);
+        self.with_state(tenant, |state| {
+            state.usage.gpu_hours_today = used_today;
+            match quota
+                .training_gpu_hours_per_day
+                .filter(|limit| state.usage.gpu_hours_today >= *limit)
+            {
+                Some(limit) => {
+                    state.usage.rejected += 1;
+                    Err(QuotaError::GpuHoursPerDay {
+                        tenant: tenant.to_string(),
+                        limit,
+                    })
+                }
+                None => Ok(()),
+            }
+        })
+    }
+
+    pub fn record_gpu_hours(&self, tenant: &str, hours: f64) {
+        self.with_state(tenant, |state| {
+            state.usage.gpu_hours += hours;
+            state.usage.gpu_hours_today += hours;
+        });
+    }
+
+    pub fn usage(&self, tenant: &str) -> TenantUsage {
+        self.with_state(tenant, |state| state.usage.clone())
+    }
+
+    /// Usage of every tenant seen so far, sorted by tenant id.
+    pub fn export(&self) -> Vec<TenantUsage> {
+        let mut state = self.state.lock();
+        let mut usage: Vec<TenantUsage> = state
+            .values_mut()
+            .map(|entry| {
+                entry.roll_day();
+                entry.usage.clone()
+            })
+            .collect();
+        usage.sort_by(|a, b| a.tenant.cmp(&b.tenant));
+        usage
+    }
+
+    /// Per-tenant counters in Prometheus text format, for `tenant` only if given.
+    pub fn export_prometheus(&self, tenant: Option<&str>) -> String {
+        let usage = match tenant {
+            Some(tenant) => vec![self.usage(tenant)],
+            None => self.export(),
+        };
+        let mut output = String::new();
+        let series: [Series; 5] = [
+            ("chimera_tenant_requests_total", "counter", |u| {
+                u.requests as f64
+            }),
+            ("chimera_tenant_rejected_total", "counter", |u| {
+                u.rejected as f64
+            }),
+            ("chimera_tenant_tokens_total", "counter", |u| {
+                u.tokens as f64
+            }),
+            ("chimera_tenant_active_tasks", "gauge", |u| {
+                u.active_tasks as f64
+            }),
+            ("chimera_tenant_gpu_hours_total", "counter", |u| u.gpu_hours),
+        ];
+        for (name, kind, value) in series {
+            output.push_str(&format!("# TYPE {} {}\n", name, kind));
+            for tenant in &usage {
+                output.push_str(&format!(
+                    "{}{{tenant=\"{}\"}} {}\n",
+                    name,
+                    tenant.tenant,
+                    value(tenant)
+                ));
+            }
+        }
+        output
+    }
+}
+
+/// Storage key for a per-tenant resource id; the default tenant keeps bare ids.
+pub fn scoped_key(tenant: &str, id: &str) -> String {
+    if tenant == DEFAULT_TENANT {
+        id.to_string()
+    } else {
+        format!("{}/{}", tenant, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> TenantManager {
+        let mut tenants = HashMap::new();
+        tenants.insert(
+            "search".to_string(),
+            TenantQuota {
+                requests_per_minute: Some(2),
+                tokens_per_day: Some(100),
+                concurrent_tasks: Some(1),
+                training_gpu_hours_per_day: Some(1.0),
+            },
+        );
+        TenantManager::new(TenancySettings {
+            default_quota: TenantQuota::default(),
+            tenants,
+        })
+    }
+
+    #[test]
+    fn quotas_are_enforced_per_tenant() {
+        let tenants = manager();
+
+        assert!(tenants.admit_request("search").is_ok());
+        assert!(tenants.admit_request("search").is_ok());
+        assert!(matches!(
+            tenants.admit_request("search"),
+            Err(QuotaError::RequestsPerMinute { limit: 2, .. })
+        ));
+        // A noisy tenant does not affect others.
+        for _ in 0..10 {
+            assert!(tenants.admit_request(DEFAULT_TENANT).is_ok());
+        }
+
+        assert!(tenants.start_task("search").is_ok());
+        assert!(tenants.start_task("search").is_err());
+        tenants.finish_task("search");
+        assert!(tenants.start_task("search").is_ok());
+
+        assert!(tenants.admit_training("search", 0.5).is_ok());
+        assert!(tenants.admit_training("search", 1.5).is_err());
+        assert!(tenants.admit_training(DEFAULT_TENANT, 1.5).is_ok());
+    }
+
+    #[test]
+    fn daily_tokens_block_further_requests() {
+        let tenants = TenantManager::new(TenancySettings {
+            default_quota: TenantQuota {
+                tokens_per_day: Some(50),
+                ..TenantQuota::default()
+            },
+            ..TenancySettings::default()
+        });
+
+        assert!(tenants.admit_request("ads").is_ok());
+        tenants.record_tokens("ads", 60);
+        assert!(matches!(
+            tenants.admit_request("ads"),
+            Err(QuotaError::TokensPerDay { limit: 50, .. })
+        ));
+
+        let usage = tenants.usage("ads");
+        assert_eq!((usage.requests, usage.rejected, usage.tokens), (1, 1, 60));
+        assert!(tenants
+            .export_prometheus(None)
+            .contains("chimera_tenant_tokens_total{tenant=\"ads\"} 60"));
+    }
+}
@@ -0,0 +1,268 @@
+use super::Tool;
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Response bodies longer than this are cut before being handed to the model.
+const MAX_BODY_CHARS: usize = 4096;
+
+/// Evaluates arithmetic expressions: `+ - * / % ^`, parentheses and unary minus.
+#[derive(Debug, Default)]
+pub struct CalculatorTool;
+
+#[async_trait]
+impl Tool for CalculatorTool {
+    fn name(&self) -> &str {
+        "calculator"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluates an arithmetic expression."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "expression": { "type": "string", "minLength": 1 } },
+            "required": ["expression"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn invoke(&self, arguments: Value) -> Result<Value> {
+        let expression = arguments["expression"].as_str().unwrap_or_default();
+        let result = evaluate(expression)?;
+        if !result.is_finite() {
+            bail!("result is not a finite number");
+        }
+        Ok(json!({ "result": result }))
+    }
+}
+
+/// Fetches a URL over HTTP(S), restricted to an allowlist of hosts.
+pub struct HttpGetTool {
+    client: reqwest::Client,
+    allowlist: Vec<String>,
+}
+
+impl HttpGetTool {
+    pub fn new(allowlist: Vec<String>, timeout: Duration) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        Ok(Self { client, allowlist })
+    }
+}
+
+#[async_trait]
+impl Tool for HttpGetTool {
+    fn name(&self) -> &str {
+        "http_get"
+    }
+
+    fn description(&self) -> &str {
+        "Fetches a web page from an allowed host and returns its status and body."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "url": { "type": "string", "minLength": 1 } },
+            "required": ["url"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn invoke(&self, arguments: Value) -> Result<Value> {
+        let url = arguments["url"].as_str().unwrap_or_default();
+        let url = reqwest::Url::parse(url).with_context(|| format!("invalid url {:?}", url))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("unsupported scheme {}", url.scheme());
+        }
+        let host = url.host_str().unwrap_or_default();
+        if !self.allowlist.iter().any(|allowed| allowed == host) {
+            bail!("host {} is not in the allowlist", host);
+        }
+
+        let response = self.client.get(url).send().await?;
+        let status = response.status().as_u16();
+        let body: String = response
+            .text()
+            .await?
+            .chars()
+            .take(MAX_BODY_CHARS)
+            .collect();
+        Ok(json!({ "status": status, "body": body }))
+    }
+}
+
+/// Looks up values from the `[tools.kv]` table.
+#[derive(Debug, Default)]
+pub struct KeyValueTool {
+    entries: HashMap<String, String>,
+}
+
+impl KeyValueTool {
+    pub fn new(entries: HashMap<String, String>) -> Self {
+        Self { entries }
+    }
+}
+
+#[async_trait]
+impl Tool for KeyValueTool {
+    fn name(&self) -> &str {
+        "kv_lookup"
+    }
+
+    fn description(&self) -> &str {
+        "Returns the configured value stored under a key."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": { "key": { "type": "string" } },
+            "required": ["key"],
+            "additionalProperties": false
+        })
+    }
+
+    async fn invoke(&self, arguments: Value) -> Result<Value> {
+        let key = arguments["key"].as_str().unwrap_or_default();
+        let value = self
+            .entries
+            .get(key)
+            .ok_or_else(|| anyhow!("no value stored under {:?}", key))?;
+        Ok(json!({ "key": key, "value": value }))
+    }
+}
+
+fn evaluate(expression: &str) -> Result<f64> {
+    let mut parser = Parser {
+        chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+        pos: 0,
+    };
+    let value = parser.sum()?;
+    if let Some(c) = parser.peek() {
+        bail!("unexpected {:?} at position {}", c, parser.pos);
+    }
+    Ok(value)
+}
+
+/// Recursive-descent parser; `^` binds tightest and is right-associative.
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn sum(&mut self) -> Result<f64> {
+        let mut value = self.product()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.product()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn product(&mut self) -> Result<f64> {
+        let mut value = self.unary()?;
+        while let Some(op @ ('*' | '/' | '%')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            if op != '*' && rhs == 0.0 {
+                bail!("division by zero");
+            }
+            value = match op {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<f64> {
+        match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                Ok(-self.unary()?)
+            }
+            Some('+') => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<f64> {
+        let base = self.atom()?;
+        if self.peek() == Some('^') {
+            self.pos += 1;
+            let exponent = self.unary()?;
+            return Ok(base.powf(exponent));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<f64> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.sum()?;
+                if self.peek() != Some(')') {
+                    bail!("missing ')' at position {}", self.pos);
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let literal: String = self.chars[start..self.pos].iter().collect();
+                literal
+                    .parse()
+                    .map_err(|_| anyhow!("invalid number {:?}", literal))
+            }
+            Some(c) => bail!("unexpected {:?} at position {}", c, self.pos),
+            None => bail!("unexpected end of expression"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculator_respects_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("(1 + 2) * -3").unwrap(), -9.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("7 % 4 / 2").unwrap(), 1.5);
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("2 * (3").is_err());
+        assert!(evaluate("rm -rf").is_err());
+    }
+
+    #[tokio::test]
+    async fn http_get_refuses_hosts_outside_the_allowlist() {
+        let tool = HttpGetTool::new(vec!["example.com".into()], Duration::from_secs(1)).unwrap();
+        let err = tool
+            .invoke(json!({ "url": "http://169.254.169.254/latest/meta-data" }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not in the allowlist"));
+    }
+}
@@ -0,0 +1,430 @@
+//! Tool calling for agents
+//!
+//! Tools expose a name, a JSON Schema for their arguments and an async
+//! `invoke`. The agent loop asks the model for output, runs any tool call it
+//! contains, feeds the result back as a `tool` message and repeats until the
+//! model answers in plain text or the step limit is reached.
+
+mod builtin;
+
+pub use builtin::{CalculatorTool, HttpGetTool, KeyValueTool};
+
+use crate::audit_logging::AuditLogger;
+use crate::sessions::{Message, Role};
+use crate::utils::validate_schema;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::error;
+
+#[async_trait]
+pub trait Tool: Send + Sync {
+    fn name(&self) -> &str;
+    fn description(&self) -> &str;
+    /// JSON Schema the arguments are validated against before `invoke`.
+    fn parameters(&self) -> Value;
+    async fn invoke(&self, arguments: Value) -> Result<Value>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolSettings {
+    pub timeout_ms: u64,
+    /// Model turns allowed per request before the loop gives up.
+    pub max_steps: usize,
+    /// Hosts `http_get` may fetch from; the tool is disabled when empty.
+    pub http_allowlist: Vec<String>,
+    /// Entries served by `kv_lookup`.
+    pub kv: HashMap<String, String>,
+}
+
+impl Default for ToolSettings {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 10_000,
+            max_steps: 5,
+            http_allowlist: Vec::new(),
+            kv: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: Value,
+}
+
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Arc<RwLock<HashMap<String, Arc<dyn Tool>>>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry holding the built-in tools enabled by `settings`.
+    pub fn with_builtins(settings: &ToolSettings) -> Result<Self> {
+        let registry = Self::new();
+        registry.register(Arc::new(CalculatorTool));
+        registry.register(Arc::new(KeyValueTool::new(settings.kv.clone())));
+        if !settings.http_allowlist.is_empty() {
+            registry.register(Arc::new(HttpGetTool::new(
+                settings.http_allowlist.clone(),
+                Duration::from_millis(settings.timeout_ms),
+            )?));
+        }
+        Ok(registry)
+    }
+
+    pub fn register(&self, tool: Arc<dyn Tool>) {
+        self.tools.write().insert(tool.name().to_string(), tool);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.read().get(name).cloned()
+    }
+
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        let mut specs: Vec<ToolSpec> = self
+            .tools
+            .read()
+            .values()
+            .map(|tool| ToolSpec {
+                name: tool.name().to_string(),
+                description: tool.description().to_string(),
+                parameters: tool.parameters(),
+            })
+            .collect();
+        specs.sort_by(|a, b| a.name.cmp(&b.name));
+        specs
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCallRecord {
+    pub name: String,
+    pub arguments: Value,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentLoopOutcome {
+    pub answer: String,
+    pub steps: usize,
+    pub calls: Vec<ToolCallRecord>,
+}
+
+/// Runs tool calls on behalf of one agent, restricted to the tools it may use.
+pub struct ToolExecutor {
+    registry: ToolRegistry,
+    allowed: Vec<String>,
+    agent: String,
+    timeout: Duration,
+    max_steps: usize,
+    audit: Arc<AuditLogger>,
+    user_id: Option<String>,
+}
+
+impl ToolExecutor {
+    pub fn new(
+        registry: ToolRegistry,
+        allowed: Vec<String>,
+        agent: &str,
+        settings: &ToolSettings,
+        audit: Arc<AuditLogger>,
+    ) -> Self {
+        Self {
+            registry,
+            allowed,
+            agent: agent.to_string(),
+            timeout: Duration::from_millis(settings.timeout_ms),
+            max_steps: settings.max_steps,
+            audit,
+            user_id: None,
+        }
+    }
+
+    /// Attributes audited calls to the caller that triggered them.
+    pub fn on_behalf_of(mut self, user_id: Option<String>) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    pub fn specs(&self) -> Vec<ToolSpec> {
+        self.registry
+            .specs()
+            .into_iter()
+            .filter(|spec| self.allowed.contains(&spec.name))
+            .collect()
+    }
+
+    /// System prompt addendum describing the tools and how to call them.
+    pub fn instructions(&self) -> String {
+        let mut text = String::from(
+            "You can call tools. To call one, reply with only \
+             <tool_call>{\"name\": \"...\", \"arguments\": {...}}</tool_call> \
+             and wait for the result. Reply in plain text once you have the answer.\nTools:",
+        );
+        for spec in self.specs() {
+            text.push_str(&format!(
+                "\n- {}: {} Parameters: {}",
+                spec.name, spec.description, spec.parameters
+            ));
+        }
+        text
+    }
+
+    /// Validates, runs and audits a single call. Failures are reported in the record.
+    pub async fn invoke(&self, call: &ToolCall) -> ToolCallRecord {
+        let started = Instant::now();
+        let outcome = self.execute(call).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+
+        let audit_outcome = outcome.as_ref().map(|_| ()).map_err(String::as_str);
+        if let Err(err) = self.audit.log_tool_call(
+            self.user_id.clone(),
+            &self.agent,
+            &call.name,
+            audit_outcome,
+            duration_ms,
+        ) {
+            error!(?err, tool = %call.name, "failed to record audit log for tool call");
+        }
+
+        let (result, error) = match outcome {
+            Ok(value) => (Some(value), None),
+            Err(err) => (None, Some(err)),
+        };
+        ToolCallRecord {
+            name: call.name.clone(),
+            arguments: call.arguments.clone(),
+            result,
+            error,
+            duration_ms,
+        }
+    }
+
+    async fn execute(&self, call: &ToolCall) -> Result<Value, String> {
+        let tool = self
+            .registry
+            .get(&call.name)
+            .filter(|_| self.allowed.contains(&call.name))
+            .ok_or_else(|| format!("unknown tool {:?}", call.name))?;
+
+        validate_schema(&tool.parameters(), &call.arguments).map_err(|errors| {
+            let details: Vec<String> = errors
+                .iter()
+                .map(|e| format!("{}: {}", e.field, e.message))
+                .collect();
+            format!("invalid arguments: {}", details.join("; "))
+        })?;
+
+        match tokio::time::timeout(self.timeout, tool.invoke(call.arguments.clone())).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(err)) => Err(format!("{:#}", err)),
+            Err(_) => Err(format!("timed out after {:?}", self.timeout)),
+        }
+    }
+
+    /// Drives `generate` until it produces an answer instead of a tool call.
+    pub async fn run<F, Fut>(
+        &self,
+        mut messages: Vec<Message>,
+        mut generate: F,
+    ) -> Result<AgentLoopOutcome>
+    where
+        F: FnMut(Vec<Message>) -> Fut,
+        Fut: Future<Output = Result<String>>,
+    {
+        let mut calls = Vec::new();
+        for step in 1..=self.max_steps {
+            let output = generate(messages.clone()).await?;
+            let Some(call) = parse_tool_call(&output) else {
+                return Ok(AgentLoopOutcome {
+                    answer: output,
+                    steps: step,
+                    calls,
+                });
+            };
+
+            let record = self.invoke(&call).await;
+            let feedback = match (&record.result, &record.error) {
+                (Some(result), _) => json!({ "name": call.name, "result": result }),
+                (None, error) => json!({ "name": call.name, "error": error }),
+            };
+            messages.push(Message::new(Role::Assistant, output));
+            messages.push(Message::new(Role::Tool, feedback.to_string()));
+            calls.push(record);
+        }
+
+        bail!("no final answer after {} steps", self.max_steps)
+    }
+}
+
+/// Extracts a tool call from model output: either a `<tool_call>` block or
+/// output that is nothing but the call's JSON (optionally fenced).
+pub fn parse_tool_call(output: &str) -> Option<ToolCall> {
+    let body = match output.find("<tool_call>") {
+        Some(start) => {
+            let rest = &output[start + "<tool_call>".len()..];
+            &rest[..rest.find("</tool_call>")?]
+        }
+        None => output
+            .trim()
+            .trim_start_matches("```json")
+            .trim_start_matches("```")
+            .trim_end_matches("```"),
+    };
+
+    let value: Value = serde_json::from_str(body.trim()).ok()?;
+    let value = match value.get("tool_call") {
+        Some(inner) => inner.clone(),
+        None => value,
+    };
+    let mut call: ToolCall = serde_json::from_value(value).ok()?;
+    if call.arguments.is_null() {
+        call.arguments = json!({});
+    }
+    Some(call)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct SlowTool;
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        fn description(&self) -> &str {
+            "Never finishes in time."
+        }
+
+        fn parameters(&self) -> Value {
+            json!({ "type": "object" })
+        }
+
+        async fn invoke(&self, _arguments: Value) -> Result<Value> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(Value::Null)
+        }
+    }
+
+    fn executor(audit_file: &tempfile::NamedTempFile) -> ToolExecutor {
+        let registry = ToolRegistry::with_builtins(&ToolSettings::default()).unwrap();
+        registry.register(Arc::new(SlowTool));
+        let audit = AuditLogger::new(audit_file.path().to_str().unwrap(), 1).unwrap();
+        let settings = ToolSettings {
+            timeout_ms: 50,
+            max_steps: 3,
+            ..ToolSettings::default()
+        };
+        ToolExecutor::new(
+            registry,
+            vec!["calculator".into(), "slow".into()],
+            "tester",
+            &settings,
+            Arc::new(audit),
+        )
+    }
+
+    #[test]
+    fn parses_tagged_and_bare_tool_calls() {
+        let tagged = "Let me check.\n<tool_call>{\"name\": \"calculator\", \"arguments\": {\"expression\": \"1+1\"}}</tool_call>";
+        assert_eq!(parse_tool_call(tagged).unwrap().name, "calculator");
+
+        let fenced = "```json\n{\"tool_call\": {\"name\": \"kv_lookup\"}}\n```";
+        let call = parse_tool_call(fenced).unwrap();
+        assert_eq!(call.name, "kv_lookup");
+        assert_eq!(call.arguments, json!({}));
+
+        assert!(parse_tool_call("The answer is 4.").is_none());
+    }
+
+    #[tokio::test]
+    async fn loop_feeds_tool_results_back_until_an_answer() {
+        let audit_file = tempfile::NamedTempFile::new().unwrap();
+        let executor = executor(&audit_file);
+
+        let outcome = executor
+            .run(vec![Message::new(Role::User, "What is 6*7?")], |messages| {
+                let last = messages.last().unwrap().clone();
+                async move {
+                    Ok(match last.role {
+                        Role::Tool => format!("It is {}", last.content),
+                        _ => r#"<tool_call>{"name":"calculator","arguments":{"expression":"6*7"}}</tool_call>"#.to_string(),
+                    })
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.steps, 2);
+        assert_eq!(outcome.calls[0].result, Some(json!({ "result": 42.0 })));
+        assert!(outcome.answer.contains("42"));
+
+        let audit = std::fs::read_to_string(audit_file.path()).unwrap();
+        assert!(audit.contains("\"event_type\":\"tool_call\""));
+    }
+
+    #[tokio::test]
+    async fn invalid_arguments_timeouts_and_disallowed_tools_are_reported() {
+        let audit_file = tempfile::NamedTempFile::new().unwrap();
+        let executor = executor(&audit_file);
+
+        let call = |name: &str, arguments: Value| ToolCall {
+            name: name.into(),
+            arguments,
+        };
+        let bad_args = executor
+            .invoke(&call("calculator", json!({ "expression": 7 })))
+            .await;
+        assert!(bad_args.error.unwrap().contains("$.expression"));
+
+        let slow = executor.invoke(&call("slow", json!({}))).await;
+        assert!(slow.error.unwrap().contains("timed out"));
+
+        let not_allowed = executor
+            .invoke(&call("kv_lookup", json!({ "key": "a" })))
+            .await;
+        assert!(not_allowed.error.unwrap().contains("unknown tool"));
+    }
+
+    #[tokio::test]
+    async fn step_limit_stops_endless_tool_use() {
+        let audit_file = tempfile::NamedTempFile::new().unwrap();
+        let executor = executor(&audit_file);
+
+        let err = executor
+            .run(Vec::new(), |_| async {
+                Ok(r#"{"name":"calculator","arguments":{"expression":"1"}}"#.to_string())
+            })
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("after 3 steps"));
+    }
+}
@@ -8,7 +8,7 @@
 //! that the behaviour is simulated.
 
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
@@ -38,6 +38,146 @@ pub struct EpochMetrics {
     pub samples_processed: usize,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrainingJobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A training run as recorded in the `training_jobs` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainingJob {
+    pub id: String,
+    pub tenant: String,
+    /// Artifact the run produced, once it has completed.
+    pub model_id: Option<String>,
+    pub status: TrainingJobStatus,
+    pub config: serde_json::Value,
+    pub metrics: Option<serde_json::Value>,
+    pub error_message: Option<String>,
+    pub created_at: u64,
+    pub started_at: Option<u64>,
+    pub completed_at: Option<u64>,
+}
+
+impl TrainingJob {
+    pub fn new(tenant: &str, config: serde_json::Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            tenant: tenant.to_string(),
+            model_id: None,
+            status: TrainingJobStatus::Pending,
+            config,
+            metrics: None,
+            error_message: None,
+            created_at: crate::utils::timestamp_now(),
+            started_at: None,
+            completed_at: None,
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.status = TrainingJobStatus::Running;
+        self.started_at = Some(crate::utils::timestamp_now());
+    }
+
+    pub fn complete(&mut self, model_id: &str, metrics: serde_json::Value) {
+        self.status = TrainingJobStatus::Completed;
+        self.model_id = Some(model_id.to_string());
+        self.metrics = Some(metrics);
+        self.completed_at = Some(crate::utils::timestamp_now());
+    }
+
+    pub fn fail(&mut self, error: impl Into<String>) {
+        self.status = TrainingJobStatus::Failed;
+        self.error_message = Some(error.into());
+        self.completed_at = Some(crate::utils::timestamp_now());
+    }
+
+    /// Records the GPU time the run used in its metrics, next to anything already there.
+    pub fn record_gpu_hours(&mut self, hours: f64) {
+        let metrics = self.metrics.get_or_insert_with(|| serde_json::json!({}));
+        if let Some(fields) = metrics.as_object_mut() {
+            fields.insert("gpu_hours".to_string(), serde_json::json!(hours));
+        }
+    }
+
+    /// GPU time of a finished run: the recorded hours, else its wall-clock time.
+    pub fn gpu_hours(&self) -> Option<f64> {
+        let completed_at = self.completed_at?;
+        let recorded = self
+            .metrics
+            .as_ref()
+            .and_then(|metrics| metrics.get("gpu_hours"))
+            .and_then(serde_json::Value::as_f64);
+        recorded.or_else(|| {
+            let started_at = self.started_at?;
+            Some(completed_at.saturating_sub(started_at) as f64 / 3600.0)
+        })
+    }
+}
+
+/// GPU-hours of `tenant`'s runs that finished today (UTC), read from storage
+/// so every trainer process sees the runs of the others.
+pub async fn gpu_hours_today(
+    jobs: &dyn crate::storage::TrainingJobRepository,
+    tenant: &str,
+) -> anyhow::Result<f64> {
+    let today = crate::tenancy::current_day();
+    Ok(jobs
+        .list_training_jobs(Some(tenant))
+        .await?
+        .iter()
+        .filter(|job| {
+            job.completed_at
+                .is_some_and(|at| at / crate::tenancy::SECONDS_PER_DAY == today)
+        })
+        .filter_map(TrainingJob::gpu_hours)
+        .sum())
+}
+
+/// A model file produced by training, as recorded in the `model_artifacts` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelArtifact {
+    pub id: String,
+    pub name: String,
+    pub model_type: String,
+    pub file_path: String,
+    /// Hex SHA-256 of the file.
+    pub checksum: String,
+    pub metadata: Option<serde_json::Value>,
+    /// At most one artifact per name is active.
+    pub is_active: bool,
+    pub created_at: u64,
+    pub version: String,
+}
+
+impl ModelArtifact {
+    /// Describes the file at `path`, checksumming its contents.
+    pub async fn from_file(name: &str, model_type: &str, path: &Path) -> std::io::Result<Self> {
+        let contents = tokio::fs::read(path).await?;
+        let digest = ring::digest::digest(&ring::digest::SHA256, &contents);
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            model_type: model_type.to_string(),
+            file_path: path.display().to_string(),
+            checksum: digest
+                .as_ref()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            metadata: None,
+            is_active: false,
+            created_at: crate::utils::timestamp_now(),
+            version: "1.0.0".to_string(),
+        })
+    }
+}
+
 pub struct LoRATrainer {
     config: TrainingConfig,
 }
@@ -144,4 +284,36 @@ mod tests {
         assert!(!summary.metrics.is_empty());
         assert!(summary.output_adapter.exists());
     }
+
+    #[tokio::test]
+    async fn stored_runs_exhaust_the_daily_gpu_quota() {
+        use crate::storage::{InMemoryRepository, TrainingJobRepository};
+        use crate::tenancy::{TenancySettings, TenantManager, TenantQuota};
+
+        let jobs = InMemoryRepository::new();
+        // Each trainer run builds its own manager, so nothing carries over in memory.
+        let new_process = || {
+            TenantManager::new(TenancySettings {
+                default_quota: TenantQuota {
+                    training_gpu_hours_per_day: Some(1.0),
+                    ..TenantQuota::default()
+                },
+                ..TenancySettings::default()
+            })
+        };
+
+        let used = gpu_hours_today(&jobs, "search").await.unwrap();
+        assert!(new_process().admit_training("search", used).is_ok());
+        let mut job = TrainingJob::new("search", serde_json::json!({}));
+        job.start();
+        job.record_gpu_hours(1.25);
+        job.fail("out of memory");
+        jobs.save_training_job(&job).await.unwrap();
+
+        let used = gpu_hours_today(&jobs, "search").await.unwrap();
+        assert_eq!(used, 1.25);
+        assert!(new_process().admit_training("search", used).is_err());
+        let other = gpu_hours_today(&jobs, "ads").await.unwrap();
+        assert!(new_process().admit_training("ads", other).is_ok());
+    }
 }
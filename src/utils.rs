@@ -7,10 +7,12 @@ use std::path::Path;
 
 pub mod config;
 pub mod metrics;
+pub mod schema;
 pub mod validation;
 
 pub use config::*;
 pub use metrics::*;
+pub use schema::validate_schema;
 pub use validation::*;
 
 pub fn generate_id() -> String {
@@ -0,0 +1,251 @@
+//! Minimal JSON Schema validation
+//!
+//! Covers the keywords tool parameters and response formats use: `type`,
+//! `properties`, `required`, `additionalProperties`, `items`, `enum`,
+//! `const`, numeric and length bounds, and `anyOf`/`oneOf`/`allOf`.
+//! Unknown keywords are ignored.
+
+use super::validation::ValidationError;
+use serde_json::Value;
+
+/// Validates `instance` against `schema`, collecting every violation.
+pub fn validate_schema(schema: &Value, instance: &Value) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    check(schema, instance, "$", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check(schema: &Value, instance: &Value, path: &str, errors: &mut Vec<ValidationError>) {
+    let Some(schema) = schema.as_object() else {
+        if schema == &Value::Bool(false) {
+            push(errors, path, "no value is allowed here".to_string());
+        }
+        return;
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| has_type(instance, name)) {
+            push(
+                errors,
+                path,
+                format!(
+                    "expected {}, found {}",
+                    allowed.join(" or "),
+                    type_name(instance)
+                ),
+            );
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(instance) {
+            push(
+                errors,
+                path,
+                format!("must be one of {}", Value::from(options.clone())),
+            );
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != instance {
+            push(errors, path, format!("must equal {}", constant));
+        }
+    }
+
+    match instance {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !map.contains_key(name) {
+                        push(
+                            errors,
+                            &format!("{}.{}", path, name),
+                            "is required".to_string(),
+                        );
+                    }
+                }
+            }
+            for (name, value) in map {
+                let child = format!("{}.{}", path, name);
+                match properties.and_then(|p| p.get(name)) {
+                    Some(property) => check(property, value, &child, errors),
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            push(errors, &child, "is not an allowed property".to_string())
+                        }
+                        Some(extra @ Value::Object(_)) => check(extra, value, &child, errors),
+                        _ => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            bound(schema, "minItems", items.len(), path, errors, |min, n| {
+                n >= min
+            });
+            bound(schema, "maxItems", items.len(), path, errors, |max, n| {
+                n <= max
+            });
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{}[{}]", path, index), errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count();
+            bound(schema, "minLength", length, path, errors, |min, n| n >= min);
+            bound(schema, "maxLength", length, path, errors, |max, n| n <= max);
+        }
+        Value::Number(number) => {
+            let value = number.as_f64().unwrap_or_default();
+            for (keyword, wording) in [
+                ("minimum", "at least"),
+                ("maximum", "at most"),
+                ("exclusiveMinimum", "greater than"),
+                ("exclusiveMaximum", "less than"),
+            ] {
+                let Some(limit) = schema.get(keyword).and_then(Value::as_f64) else {
+                    continue;
+                };
+                let holds = match keyword {
+                    "minimum" => value >= limit,
+                    "maximum" => value <= limit,
+                    "exclusiveMinimum" => value > limit,
+                    _ => value < limit,
+                };
+                if !holds {
+                    push(errors, path, format!("must be {} {}", wording, limit));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+        for sub in all {
+            check(sub, instance, path, errors);
+        }
+    }
+    for (keyword, wanted) in [("anyOf", None), ("oneOf", Some(1))] {
+        if let Some(options) = schema.get(keyword).and_then(Value::as_array) {
+            let matching = options
+                .iter()
+                .filter(|sub| validate_schema(sub, instance).is_ok())
+                .count();
+            let ok = match wanted {
+                Some(exactly) => matching == exactly,
+                None => matching > 0,
+            };
+            if !ok {
+                push(
+                    errors,
+                    path,
+                    format!("matches {} of the {} alternatives", matching, keyword),
+                );
+            }
+        }
+    }
+}
+
+fn bound(
+    schema: &serde_json::Map<String, Value>,
+    keyword: &str,
+    actual: usize,
+    path: &str,
+    errors: &mut Vec<ValidationError>,
+    holds: fn(usize, usize) -> bool,
+) {
+    if let Some(limit) = schema.get(keyword).and_then(Value::as_u64) {
+        if !holds(limit as usize, actual) {
+            push(errors, path, format!("violates {} = {}", keyword, limit));
+        }
+    }
+}
+
+fn has_type(instance: &Value, name: &str) -> bool {
+    match name {
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "boolean" => instance.is_boolean(),
+        "null" => instance.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn push(errors: &mut Vec<ValidationError>, path: &str, message: String) {
+    errors.push(ValidationError {
+        field: path.to_string(),
+        message,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_every_violation_with_its_path() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "city": { "type": "string", "minLength": 2 },
+                "days": { "type": "integer", "minimum": 1, "maximum": 7 },
+                "units": { "enum": ["metric", "imperial"] }
+            },
+            "required": ["city", "days"],
+            "additionalProperties": false
+        });
+
+        let errors =
+            validate_schema(&schema, &json!({ "days": 9, "units": "kelvin", "x": 1 })).unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["$.city", "$.days", "$.units", "$.x"]);
+
+        assert!(validate_schema(&schema, &json!({ "city": "Oslo", "days": 3 })).is_ok());
+    }
+
+    #[test]
+    fn nested_arrays_and_alternatives() {
+        let schema = json!({
+            "type": "array",
+            "items": { "anyOf": [{ "type": "string" }, { "type": "number" }] },
+            "maxItems": 2
+        });
+
+        assert!(validate_schema(&schema, &json!(["a", 1])).is_ok());
+        let errors = validate_schema(&schema, &json!(["a", true, 3])).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].field, "$[1]");
+    }
+}
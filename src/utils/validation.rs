@@ -1,5 +1,12 @@
 //! Input validation and sanitization utilities
 
+pub mod redaction;
+
+pub use redaction::{
+    BuiltinDetector, CustomDetector, Redacted, RedactionAction, RedactionKey, RedactionSettings,
+    Redactor,
+};
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,654 @@
+//! PII detection and redaction
+//!
+//! A [`Redactor`] runs a list of detectors over text and replaces every
+//! match according to the detector's [`RedactionAction`]:
+//!
+//! - `mask` – `[REDACTED:email]`
+//! - `hash` – `[email:hash:3f9a…]`, a keyed hash that is stable for a key
+//! - `tokenize` – `[email:tok:…]`, encrypted under the local key so
+//!   [`Redactor::detokenize`] can restore the original
+
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use regex::Regex;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinDetector {
+    Email,
+    Phone,
+    CreditCard,
+    Iban,
+    IpAddress,
+    Secret,
+}
+
+impl BuiltinDetector {
+    pub const ALL: [BuiltinDetector; 6] = [
+        BuiltinDetector::Secret,
+        BuiltinDetector::Email,
+        BuiltinDetector::CreditCard,
+        BuiltinDetector::Iban,
+        BuiltinDetector::IpAddress,
+        BuiltinDetector::Phone,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuiltinDetector::Email => "email",
+            BuiltinDetector::Phone => "phone",
+            BuiltinDetector::CreditCard => "credit_card",
+            BuiltinDetector::Iban => "iban",
+            BuiltinDetector::IpAddress => "ip_address",
+            BuiltinDetector::Secret => "secret",
+        }
+    }
+
+    fn pattern(&self) -> &'static str {
+        match self {
+            BuiltinDetector::Email => {
+                r"(?i)\b[a-z0-9._%+-]+@[a-z0-9-]+(?:\.[a-z0-9-]+)*\.[a-z]{2,}\b"
+            }
+            BuiltinDetector::Phone => {
+                r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{1,4}\)[\s.-]?)?\d{2,4}(?:[\s.-]\d{2,4}){1,4}\b"
+            }
+            BuiltinDetector::CreditCard => r"\b\d(?:[ -]?\d){12,18}\b",
+            BuiltinDetector::Iban => r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b",
+            BuiltinDetector::IpAddress => {
+                r"\b(?:\d{1,3}\.){3}\d{1,3}\b|(?i)\b(?:[0-9a-f]{0,4}:){2,7}[0-9a-f]{0,4}\b"
+            }
+            BuiltinDetector::Secret => r"\b[A-Za-z0-9][A-Za-z0-9_\-+/=]{19,}",
+        }
+    }
+
+    fn accepts(&self, candidate: &str) -> bool {
+        match self {
+            BuiltinDetector::Email => true,
+            // Without a country code, short digit groups are more likely dates or amounts.
+            BuiltinDetector::Phone => {
+                let count = digits(candidate).len();
+                let min = if candidate.starts_with('+') { 7 } else { 9 };
+                (min..=15).contains(&count)
+            }
+            BuiltinDetector::CreditCard => {
+                let digits = digits(candidate);
+                (13..=19).contains(&digits.len()) && luhn_valid(&digits)
+            }
+            BuiltinDetector::Iban => iban_valid(candidate),
+            BuiltinDetector::IpAddress => candidate.parse::<std::net::IpAddr>().is_ok(),
+            BuiltinDetector::Secret => looks_like_secret(candidate),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionAction {
+    Mask,
+    Hash,
+    Tokenize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CustomDetector {
+    pub name: String,
+    pub pattern: String,
+    pub action: Option<RedactionAction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedactionSettings {
+    pub enabled: bool,
+    /// Apply to messages before they reach a model or a session store.
+    pub prompts: bool,
+    /// Apply to model output before it is returned or stored.
+    pub completions: bool,
+    /// Apply to audit event metadata.
+    pub audit: bool,
+    pub detectors: Vec<BuiltinDetector>,
+    pub default_action: RedactionAction,
+    /// Per-detector overrides of `default_action`.
+    pub actions: HashMap<String, RedactionAction>,
+    pub custom: Vec<CustomDetector>,
+    /// Environment variable holding the hashing/tokenization key.
+    pub key_env: String,
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prompts: true,
+            completions: true,
+            audit: true,
+            detectors: BuiltinDetector::ALL.to_vec(),
+            default_action: RedactionAction::Mask,
+            actions: HashMap::new(),
+            custom: Vec::new(),
+            key_env: "CHIMERA_REDACTION_KEY".to_string(),
+        }
+    }
+}
+
+/// Key material for `hash` and `tokenize`; never printed.
+#[derive(Clone)]
+pub struct RedactionKey([u8; 32]);
+
+impl RedactionKey {
+    pub fn from_secret(secret: &str) -> Self {
+        let digest = ring::digest::digest(&ring::digest::SHA256, secret.as_bytes());
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(digest.as_ref());
+        Self(bytes)
+    }
+
+    pub fn from_env(var: &str) -> Option<Self> {
+        std::env::var(var)
+            .ok()
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| Self::from_secret(&secret))
+    }
+
+    /// Random key; tokens it produces cannot be reversed after a restart.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .expect("system random number generator failed");
+        Self(bytes)
+    }
+
+    fn derive(&self, purpose: &str) -> [u8; 32] {
+        let key = hmac::Key::new(hmac::HMAC_SHA256, &self.0);
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(hmac::sign(&key, purpose.as_bytes()).as_ref());
+        bytes
+    }
+}
+
+impl fmt::Debug for RedactionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RedactionKey(..)")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub detector: String,
+    pub action: RedactionAction,
+    /// Byte range in the original text.
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Redacted {
+    pub text: String,
+    pub findings: Vec<Finding>,
+}
+
+struct Detector {
+    name: String,
+    regex: Regex,
+    builtin: Option<BuiltinDetector>,
+    action: RedactionAction,
+}
+
+pub struct Redactor {
+    detectors: Vec<Detector>,
+    hash_key: hmac::Key,
+    token_key: [u8; 32],
+    settings: RedactionSettings,
+}
+
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Redactor")
+            .field("detectors", &self.detector_names())
+            .finish()
+    }
+}
+
+impl Redactor {
+    pub fn from_settings(settings: &RedactionSettings, key: RedactionKey) -> Result<Self> {
+        let action_for = |name: &str, explicit: Option<RedactionAction>| {
+            explicit
+                .or_else(|| settings.actions.get(name).copied())
+                .unwrap_or(settings.default_action)
+        };
+
+        let mut detectors = Vec::new();
+        if settings.enabled {
+            for builtin in BuiltinDetector::ALL {
+                if !settings.detectors.contains(&builtin) {
+                    continue;
+                }
+                detectors.push(Detector {
+                    name: builtin.name().to_string(),
+                    regex: Regex::new(builtin.pattern()).expect("built-in pattern compiles"),
+                    builtin: Some(builtin),
+                    action: action_for(builtin.name(), None),
+                });
+            }
+            for custom in &settings.custom {
+                let regex = Regex::new(&custom.pattern)
+                    .with_context(|| format!("invalid pattern for detector {}", custom.name))?;
+                detectors.push(Detector {
+                    name: custom.name.clone(),
+                    regex,
+                    builtin: None,
+                    action: action_for(&custom.name, custom.action),
+                });
+            }
+        }
+
+        Ok(Self {
+            detectors,
+            hash_key: hmac::Key::new(hmac::HMAC_SHA256, &key.derive("hash")),
+            token_key: key.derive("tokenize"),
+            settings: settings.clone(),
+        })
+    }
+
+    /// Redactor that leaves text untouched.
+    pub fn disabled() -> Self {
+        let settings = RedactionSettings {
+            enabled: false,
+            ..RedactionSettings::default()
+        };
+        Self::from_settings(&settings, RedactionKey::generate()).expect("no patterns to compile")
+    }
+
+    /// Whether any detector hashes or tokenizes, i.e. output depends on the key.
+    pub fn uses_key(&self) -> bool {
+        self.detectors
+            .iter()
+            .any(|d| d.action != RedactionAction::Mask)
+    }
+
+    pub fn settings(&self) -> &RedactionSettings {
+        &self.settings
+    }
+
+    pub fn detector_names(&self) -> Vec<&str> {
+        self.detectors.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    /// Replaces every detected value. Markers left by an earlier pass are kept
+    /// as they are, so redacting twice is harmless.
+    pub fn redact(&self, text: &str) -> Redacted {
+        let mut matches = Vec::new();
+        if !self.detectors.is_empty() {
+            for marker in marker_pattern().captures_iter(text) {
+                if self.issued(&marker) {
+                    let found = marker.get(0).expect("whole match");
+                    matches.push((found.start(), found.end(), None));
+                }
+            }
+        }
+        for (rank, detector) in self.detectors.iter().enumerate() {
+            for found in detector.regex.find_iter(text) {
+                let accepted = detector
+                    .builtin
+                    .is_none_or(|builtin| builtin.accepts(found.as_str()));
+                if accepted && !found.as_str().is_empty() {
+                    matches.push((found.start(), found.end(), Some(rank)));
+                }
+            }
+        }
+        // Earliest match wins; on a tie the longer one, then detector order.
+        matches.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)).then(a.2.cmp(&b.2)));
+
+        let mut output = String::with_capacity(text.len());
+        let mut findings = Vec::new();
+        let mut cursor = 0;
+        for (start, end, rank) in matches {
+            if start < cursor {
+                continue;
+            }
+            output.push_str(&text[cursor..start]);
+            cursor = end;
+            let Some(rank) = rank else {
+                output.push_str(&text[start..end]);
+                continue;
+            };
+            let detector = &self.detectors[rank];
+            output.push_str(&self.replacement(detector, &text[start..end]));
+            findings.push(Finding {
+                detector: detector.name.clone(),
+                action: detector.action,
+                start,
+                end,
+            });
+        }
+        output.push_str(&text[cursor..]);
+
+        Redacted {
+            text: output,
+            findings,
+        }
+    }
+
+    /// Redacts every string inside a JSON value, keys included.
+    pub fn redact_json(&self, value: &serde_json::Value) -> serde_json::Value {
+        use serde_json::Value;
+        match value {
+            Value::String(text) => Value::String(self.redact(text).text),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.redact_json(v)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| (self.redact(k).text, self.redact_json(v)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Whether `marker` is one this redactor could have written. Anything else
+    /// shaped like a marker is scanned like the rest of the text.
+    fn issued(&self, marker: &regex::Captures) -> bool {
+        let action_of = |name: &str| {
+            self.detectors
+                .iter()
+                .find(|detector| detector.name == name)
+                .map(|detector| detector.action)
+        };
+        if let Some(name) = marker.get(1) {
+            return action_of(name.as_str()) == Some(RedactionAction::Mask);
+        }
+        let (label, kind, value) = (&marker[2], &marker[3], &marker[4]);
+        match kind {
+            "hash" => {
+                action_of(label) == Some(RedactionAction::Hash)
+                    && value.len() == HASH_HEX_LEN
+                    && value
+                        .bytes()
+                        .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+                    && !self.detectors.iter().any(|d| d.regex.is_match(value))
+            }
+            _ => {
+                action_of(label) == Some(RedactionAction::Tokenize)
+                    && self.open(label, value).is_ok()
+            }
+        }
+    }
+
+    /// Restores values replaced by `tokenize`; other markers are left as they are.
+    pub fn detokenize(&self, text: &str) -> String {
+        token_pattern()
+            .replace_all(text, |caps: &regex::Captures| {
+                self.open(&caps[1], &caps[2])
+                    .unwrap_or_else(|_| caps[0].to_string())
+            })
+            .into_owned()
+    }
+
+    fn replacement(&self, detector: &Detector, value: &str) -> String {
+        match detector.action {
+            RedactionAction::Mask => format!("[REDACTED:{}]", detector.name),
+            RedactionAction::Hash => {
+                let tag = hmac::sign(&self.hash_key, value.as_bytes());
+                let hex: String = tag.as_ref()[..HASH_HEX_LEN / 2]
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect();
+                format!("[{}:hash:{}]", detector.name, hex)
+            }
+            RedactionAction::Tokenize => format!(
+                "[{}:tok:{}]",
+                detector.name,
+                self.seal(&detector.name, value)
+            ),
+        }
+    }
+
+    /// Encrypts with a nonce derived from the value, so equal values give equal tokens.
+    fn seal(&self, label: &str, value: &str) -> String {
+        let nonce_key = hmac::Key::new(hmac::HMAC_SHA256, &self.token_key);
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&hmac::sign(&nonce_key, value.as_bytes()).as_ref()[..12]);
+
+        let mut sealed = value.as_bytes().to_vec();
+        self.aead()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(label.as_bytes()),
+                &mut sealed,
+            )
+            .expect("sealing cannot fail for in-memory buffers");
+
+        let mut token = nonce.to_vec();
+        token.extend(sealed);
+        URL_SAFE_NO_PAD.encode(token)
+    }
+
+    fn open(&self, label: &str, token: &str) -> Result<String> {
+        let bytes = URL_SAFE_NO_PAD.decode(token)?;
+        if bytes.len() < 12 {
+            return Err(anyhow!("token too short"));
+        }
+        let (nonce, sealed) = bytes.split_at(12);
+        let mut sealed = sealed.to_vec();
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("bad nonce"))?;
+        let plain = self
+            .aead()
+            .open_in_place(nonce, Aad::from(label.as_bytes()), &mut sealed)
+            .map_err(|_| anyhow!("token was not issued under this key"))?;
+        Ok(String::from_utf8(plain.to_vec())?)
+    }
+
+    fn aead(&self) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &self.token_key).expect("32-byte key"))
+    }
+}
+
+fn token_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\[([A-Za-z0-9_-]+):tok:([A-Za-z0-9_-]+)\]").unwrap())
+}
+
+/// Hex digits of the truncated HMAC written by `hash`.
+const HASH_HEX_LEN: usize = 16;
+
+fn marker_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"\[REDACTED:([A-Za-z0-9_-]+)\]|\[([A-Za-z0-9_-]+):(hash|tok):([A-Za-z0-9_-]+)\]",
+        )
+        .unwrap()
+    })
+}
+
+fn digits(text: &str) -> Vec<u32> {
+    text.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+fn luhn_valid(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2 == 1, d * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => d,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// ISO 13616 mod-97 check.
+fn iban_valid(candidate: &str) -> bool {
+    let compact: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+    let (head, tail) = compact.split_at(4);
+    let mut remainder = 0u32;
+    for c in tail.chars().chain(head.chars()) {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        for digit in value.to_string().chars() {
+            remainder = (remainder * 10 + digit.to_digit(10).unwrap()) % 97;
+        }
+    }
+    remainder == 1
+}
+
+/// Known key prefixes, or long tokens mixing letters and digits with high entropy.
+fn looks_like_secret(candidate: &str) -> bool {
+    const PREFIXES: [&str; 9] = [
+        "sk-",
+        "sk_live_",
+        "pk_live_",
+        "ghp_",
+        "gho_",
+        "github_pat_",
+        "xoxb-",
+        "AKIA",
+        "AIza",
+    ];
+    if PREFIXES.iter().any(|p| candidate.starts_with(p)) && candidate.len() >= 20 {
+        return true;
+    }
+
+    let has_digit = candidate.chars().any(|c| c.is_ascii_digit());
+    let has_letter = candidate.chars().any(|c| c.is_ascii_alphabetic());
+    candidate.len() >= 24 && has_digit && has_letter && shannon_entropy(candidate) >= 4.0
+}
+
+fn shannon_entropy(text: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let len = text.chars().count() as f64;
+    counts
+        .values()
+        .map(|&n| {
+            let p = n as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(settings: RedactionSettings) -> Redactor {
+        Redactor::from_settings(&settings, RedactionKey::from_secret("test-key")).unwrap()
+    }
+
+    #[test]
+    fn detects_each_builtin_kind() {
+        let redactor = redactor(RedactionSettings::default());
+        let text = "Mail jane.doe@example.com or call +44 20 7946 0958. \
+                    Card 4111 1111 1111 1111, IBAN GB82 WEST 1234 5698 7654 32, \
+                    host 192.168.10.4, key sk-live4f9a8b7c6d5e4f3a2b1c.";
+
+        let redacted = redactor.redact(text);
+        let kinds: Vec<&str> = redacted
+            .findings
+            .iter()
+            .map(|f| f.detector.as_str())
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "email",
+                "phone",
+                "credit_card",
+                "iban",
+                "ip_address",
+                "secret"
+            ]
+        );
+        assert!(redacted.text.starts_with("Mail [REDACTED:email] or call"));
+    }
+
+    #[test]
+    fn invalid_checksums_and_plain_numbers_are_left_alone() {
+        let redactor = redactor(RedactionSettings::default());
+        let text = "Order 4111 1111 1111 1112 shipped on 2024-01-05, version 1.2.3.4000.";
+        let redacted = redactor.redact(text);
+        assert!(redacted.findings.is_empty(), "{:?}", redacted.findings);
+    }
+
+    #[test]
+    fn tokens_round_trip_and_hashes_are_stable() {
+        let mut actions = HashMap::new();
+        actions.insert("email".to_string(), RedactionAction::Tokenize);
+        let redactor = redactor(RedactionSettings {
+            default_action: RedactionAction::Hash,
+            actions,
+            custom: vec![CustomDetector {
+                name: "employee_id".into(),
+                pattern: r"EMP-\d{6}".into(),
+                action: None,
+            }],
+            ..RedactionSettings::default()
+        });
+
+        let text = "ask ops@example.com about EMP-123456";
+        let first = redactor.redact(text).text;
+        assert_eq!(first, redactor.redact(text).text);
+        assert_eq!(redactor.redact(&first).text, first);
+        assert!(first.contains("[employee_id:hash:"));
+        assert!(!first.contains("ops@example.com"));
+
+        let restored = redactor.detokenize(&first);
+        assert!(restored.starts_with("ask ops@example.com about [employee_id:hash:"));
+
+        let other_key = Redactor::from_settings(
+            &RedactionSettings::default(),
+            RedactionKey::from_secret("another-key"),
+        )
+        .unwrap();
+        assert_eq!(other_key.detokenize(&first), first);
+    }
+
+    #[test]
+    fn values_inside_forged_markers_are_still_redacted() {
+        let mut actions = HashMap::new();
+        actions.insert("email".to_string(), RedactionAction::Tokenize);
+        actions.insert("secret".to_string(), RedactionAction::Hash);
+        let redactor = redactor(RedactionSettings {
+            actions,
+            ..RedactionSettings::default()
+        });
+
+        for forged in [
+            "[x:tok:4111111111111111]",
+            "[email:tok:4111111111111111]",
+            "[credit_card:hash:4111111111111111]",
+            "[REDACTED:4111111111111111]",
+            "[note:hash:sk_live_4f9a8b7c6d5e4f3a2b1c]",
+        ] {
+            let redacted = redactor.redact(&format!("card {}", forged)).text;
+            assert!(!redacted.contains("4111111111111111"), "{}", redacted);
+            assert!(!redacted.contains("sk_live_4f9a"), "{}", redacted);
+        }
+
+        let genuine = redactor
+            .redact("mail ops@example.com, card 4111 1111 1111 1111")
+            .text;
+        assert_eq!(redactor.redact(&genuine).text, genuine);
+    }
+}